@@ -27,10 +27,13 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("echo", do_echo),
     ("exit", do_exit),
     ("help", do_help),
+    ("ln", do_ln),
     ("ls", do_ls),
     ("mkdir", do_mkdir),
+    ("mv", do_mv),
     ("pwd", do_pwd),
     ("rm", do_rm),
+    ("sync", do_sync),
     ("uname", do_uname),
 ];
 
@@ -44,13 +47,21 @@ fn do_ls(args: &str) {
     let name_count = args.split_whitespace().count();
 
     fn show_entry_info(path: &str, entry: &str) -> io::Result<()> {
-        let metadata = fs::metadata(path)?;
+        let metadata = fs::symlink_metadata(path)?;
         let size = metadata.len();
         let file_type = metadata.file_type();
         let file_type_char = file_type.as_char();
         let rwx = metadata.permissions().rwx_buf();
         let rwx = unsafe { core::str::from_utf8_unchecked(&rwx) };
-        println!("{}{} {:>8} {}", file_type_char, rwx, size, entry);
+        if metadata.is_symlink() {
+            let target = fs::read_link(path)?;
+            println!(
+                "{}{} {:>8} {} -> {}",
+                file_type_char, rwx, size, entry, target
+            );
+        } else {
+            println!("{}{} {:>8} {}", file_type_char, rwx, size, entry);
+        }
         Ok(())
     }
 
@@ -196,6 +207,66 @@ fn do_rm(args: &str) {
     }
 }
 
+/// Returns the path of `src` in the directory `dst` if `dst` is an existing
+/// directory, otherwise returns `dst` itself.
+fn target_path(src: &str, dst: &str) -> String {
+    match fs::metadata(dst) {
+        Ok(metadata) if metadata.is_dir() => {
+            let name = src.trim_end_matches('/').rsplit('/').next().unwrap_or(src);
+            let mut path = String::from(dst.trim_end_matches('/'));
+            path.push('/');
+            path.push_str(name);
+            path
+        }
+        _ => String::from(dst),
+    }
+}
+
+fn do_mv(args: &str) {
+    let paths: Vec<&str> = args.split_whitespace().collect();
+    match paths.len() {
+        0 => print_err!("mv", "missing operand"),
+        1 => println!("mv: missing destination operand after '{}'", paths[0]),
+        _ => {
+            let (srcs, dst) = paths.split_at(paths.len() - 1);
+            for src in srcs {
+                if let Err(e) = fs::rename(src, &target_path(src, dst[0])) {
+                    print_err!("mv", format_args!("cannot move '{src}'"), e.as_str());
+                }
+            }
+        }
+    }
+}
+
+fn do_ln(args: &str) {
+    let mut symbolic = false;
+    let mut paths = Vec::new();
+    for arg in args.split_whitespace() {
+        if arg == "-s" {
+            symbolic = true;
+        } else {
+            paths.push(arg);
+        }
+    }
+    if paths.len() != 2 {
+        print_err!("ln", "usage: ln [-s] TARGET LINK_NAME");
+        return;
+    }
+    let (target, link) = (paths[0], target_path(paths[0], paths[1]));
+    let (res, kind) = if symbolic {
+        (fs::symlink(target, &link), "symbolic link")
+    } else {
+        (fs::hard_link(target, &link), "hard link")
+    };
+    if let Err(e) = res {
+        print_err!(
+            "ln",
+            format_args!("failed to create {kind} '{link}'"),
+            e.as_str()
+        );
+    }
+}
+
 fn do_cd(mut args: &str) {
     if args.is_empty() {
         args = "/";
@@ -209,6 +280,20 @@ fn do_cd(mut args: &str) {
     }
 }
 
+fn do_sync(args: &str) {
+    if let Err(e) = fs::sync() {
+        print_err!("sync", e);
+        return;
+    }
+    if args == "-v" {
+        let stats = fs::cache_stats();
+        println!(
+            "block cache: {} hits, {} misses, {} writebacks",
+            stats.hits, stats.misses, stats.writebacks
+        );
+    }
+}
+
 fn do_pwd(_args: &str) {
     let pwd = libax::env::current_dir().unwrap();
     println!("{}", pwd);
@@ -6,17 +6,13 @@ extern crate libax;
 
 extern crate alloc;
 
-use alloc::vec::Vec;
-use libax::io::{self, prelude::*};
-use libax::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use alloc::{format, vec::Vec};
+use libax::http::{Client, Method};
+use libax::io;
+use libax::net::{SocketAddr, ToSocketAddrs};
 
 const DEST_HOST: &str = "ident.me";
 const DEST_IP: &str = "49.12.234.183";
-const REQUEST: &str = "\
-GET / HTTP/1.1\r\n\
-Host: ident.me\r\n\
-Accept: */*\r\n\
-\r\n";
 
 fn get_addr() -> SocketAddr {
     let dest = if cfg!(feature = "dns") {
@@ -31,12 +27,23 @@ fn get_addr() -> SocketAddr {
 }
 
 fn client() -> io::Result {
-    let mut stream = TcpStream::connect(get_addr())?;
-    stream.write(REQUEST.as_bytes())?;
-    let mut buf = [0; 2048];
-    let n = stream.read(&mut buf)?;
-    let response = core::str::from_utf8(&buf[..n]).unwrap();
-    println!("{}", response); // longer response need to handle tcp package problems.
+    let url = format!("http://{}/", get_addr());
+    let response = Client::new()
+        .request(Method::Get, &url)
+        .header("Host", DEST_HOST)
+        .header("Accept", "*/*")
+        .send()?;
+    println!(
+        "{} {} {}",
+        response.version(),
+        response.status().0,
+        response.reason()
+    );
+    for (name, value) in response.headers().iter() {
+        println!("{}: {}", name, value);
+    }
+    println!();
+    println!("{}", response.body_str().unwrap_or_default());
     Ok(())
 }
 
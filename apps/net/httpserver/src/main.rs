@@ -11,28 +11,10 @@
 
 #[macro_use]
 extern crate libax;
-extern crate alloc;
 
-use core::str::FromStr;
+use libax::http::{Method, Request, Response, Router, Server, StatusCode};
 
-use libax::io::{self, prelude::*};
-use libax::net::{IpAddr, TcpListener, TcpStream};
-use libax::thread;
-
-const LOCAL_IP: &str = "10.0.2.15";
-const LOCAL_PORT: u16 = 5555;
-
-macro_rules! header {
-    () => {
-        "\
-HTTP/1.1 200 OK\r\n\
-Content-Type: text/html\r\n\
-Content-Length: {}\r\n\
-Connection: close\r\n\
-\r\n\
-{}"
-    };
-}
+const LOCAL_ADDR: &str = "10.0.2.15:5555";
 
 const CONTENT: &str = r#"<html>
 <head>
@@ -50,39 +32,21 @@ const CONTENT: &str = r#"<html>
 </html>
 "#;
 
-fn http_server(mut stream: TcpStream) -> io::Result {
-    let mut buf = [0u8; 1024];
-    stream.read(&mut buf)?;
-
-    let reponse = alloc::format!(header!(), CONTENT.len(), CONTENT);
-    stream.write_all(reponse.as_bytes())?;
-
-    Ok(())
-}
-
-fn accept_loop() -> io::Result {
-    let (addr, port) = (IpAddr::from_str(LOCAL_IP).unwrap(), LOCAL_PORT);
-    let mut listener = TcpListener::bind((addr, port).into())?;
-    println!("listen on: http://{}/", listener.local_addr().unwrap());
-
-    let mut i = 0;
-    loop {
-        match listener.accept() {
-            Ok((stream, addr)) => {
-                info!("new client {}: {}", i, addr);
-                thread::spawn(move || match http_server(stream) {
-                    Err(e) => error!("client connection error: {:?}", e),
-                    Ok(()) => info!("client {} closed successfully", i),
-                });
-            }
-            Err(e) => return Err(e),
-        }
-        i += 1;
-    }
+fn serve() -> libax::io::Result {
+    let mut router = Router::new();
+    router.route(Method::Get, "/", |_req: &Request| {
+        Response::new(StatusCode::OK)
+            .header("Content-Type", "text/html")
+            .body(CONTENT)
+    });
+
+    let server = Server::bind(LOCAL_ADDR)?;
+    println!("listen on: http://{}/", server.local_addr().unwrap());
+    server.serve(router)
 }
 
 #[no_mangle]
 fn main() {
     println!("Hello, ArceOS HTTP server!");
-    accept_loop().expect("test HTTP server failed");
+    serve().expect("test HTTP server failed");
 }
@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate libax;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use libax::thread;
+
+const NUM_TASKS: usize = 8;
+const NUM_ROUNDS: usize = 100;
+
+#[no_mangle]
+fn main() {
+    // Task stacks are mapped in the kernel address space, so creating and
+    // freeing them on different CPUs makes some CPUs wait for its lock, while
+    // the lock holder shoots down the TLB entries of the freed stacks on them.
+    let tasks = (0..NUM_TASKS)
+        .map(|i| {
+            thread::spawn(move || {
+                let sum: usize = (0..NUM_ROUNDS)
+                    .map(|j| thread::spawn(move || i * j).join().unwrap())
+                    .sum();
+                println!("task {} finished", i);
+                sum
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (i, t) in tasks.into_iter().enumerate() {
+        assert_eq!(t.join().unwrap(), i * NUM_ROUNDS * (NUM_ROUNDS - 1) / 2);
+    }
+    println!("TLB shootdown tests run OK!");
+}
@@ -0,0 +1,317 @@
+//! Buddy allocation in page-granularity.
+
+use crate::{AllocError, AllocResult, BaseAllocator, PageAllocator};
+
+/// The maximum order of free blocks, i.e., a free block contains at most
+/// `2^MAX_ORDER` pages.
+const MAX_ORDER: usize = 30;
+
+/// The maximum number of free blocks that can be tracked. It limits the
+/// fragmentation, rather than the size of memory.
+const MAX_FREE_BLOCKS: usize = 4096;
+
+/// The maximum number of memory zones, i.e., regions added by `init` or
+/// `add_memory`.
+const MAX_ZONES: usize = 16;
+
+/// A free block of `2^order` pages, starting from the page frame number `pfn`.
+///
+/// Blocks are ordered by their orders first, then their addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct FreeBlock {
+    order: usize,
+    pfn: usize,
+}
+
+impl FreeBlock {
+    const EMPTY: Self = Self { order: 0, pfn: 0 };
+}
+
+/// A contiguous memory region managed by [`BuddyPageAllocator`].
+///
+/// Blocks are never merged across zones, so each zone keeps its own
+/// accounting.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryZone {
+    /// The start address of the zone (aligned to the page size).
+    pub start: usize,
+    /// The total number of pages in the zone.
+    pub total_pages: usize,
+    /// The number of free pages in the zone.
+    pub free_pages: usize,
+}
+
+impl MemoryZone {
+    const EMPTY: Self = Self {
+        start: 0,
+        total_pages: 0,
+        free_pages: 0,
+    };
+}
+
+/// Fragmentation statistics of a [`BuddyPageAllocator`].
+#[derive(Debug, Clone, Copy)]
+pub struct BuddyStats {
+    /// The number of free blocks of each order, where a block of order `i`
+    /// contains `2^i` pages.
+    pub free_blocks: [usize; MAX_ORDER + 1],
+    /// The number of free pages.
+    pub free_pages: usize,
+    /// The number of pages in the largest free block.
+    pub largest_free_block: usize,
+}
+
+impl BuddyStats {
+    /// Returns the percentage of free pages that are not in the largest free
+    /// block, which is 0 if there is no fragmentation (or no free memory).
+    pub fn fragmentation(&self) -> usize {
+        ((self.free_pages - self.largest_free_block) * 100)
+            .checked_div(self.free_pages)
+            .unwrap_or(0)
+    }
+}
+
+/// A page-granularity memory allocator based on the buddy system.
+///
+/// Free blocks are recorded in a fixed-size table inside the allocator, so it
+/// never accesses the managed memory, and the size of memory is only bounded
+/// by the address space. Each region added by [`init`] or [`add_memory`] forms
+/// a [`MemoryZone`], and up to 16 zones are supported.
+///
+/// If the free blocks become too fragmented to be recorded, allocations that
+/// require splitting fail with [`AllocError::NoMemory`], and deallocated pages
+/// that cannot be recorded are leaked (still counted as used).
+///
+/// The `PAGE_SIZE` must be a power of two.
+///
+/// [`init`]: BaseAllocator::init
+/// [`add_memory`]: BaseAllocator::add_memory
+pub struct BuddyPageAllocator<const PAGE_SIZE: usize> {
+    blocks: [FreeBlock; MAX_FREE_BLOCKS],
+    num_blocks: usize,
+    zones: [MemoryZone; MAX_ZONES],
+    num_zones: usize,
+    total_pages: usize,
+    used_pages: usize,
+}
+
+impl<const PAGE_SIZE: usize> BuddyPageAllocator<PAGE_SIZE> {
+    /// Creates a new empty `BuddyPageAllocator`.
+    pub const fn new() -> Self {
+        Self {
+            blocks: [FreeBlock::EMPTY; MAX_FREE_BLOCKS],
+            num_blocks: 0,
+            zones: [MemoryZone::EMPTY; MAX_ZONES],
+            num_zones: 0,
+            total_pages: 0,
+            used_pages: 0,
+        }
+    }
+
+    /// Returns the memory zones managed by the allocator.
+    pub fn zones(&self) -> &[MemoryZone] {
+        &self.zones[..self.num_zones]
+    }
+
+    /// Returns the fragmentation statistics of free memory.
+    pub fn stats(&self) -> BuddyStats {
+        let mut stats = BuddyStats {
+            free_blocks: [0; MAX_ORDER + 1],
+            free_pages: 0,
+            largest_free_block: 0,
+        };
+        for b in self.free_blocks() {
+            stats.free_blocks[b.order] += 1;
+            stats.free_pages += 1 << b.order;
+        }
+        if let Some(b) = self.free_blocks().last() {
+            stats.largest_free_block = 1 << b.order;
+        }
+        stats
+    }
+
+    fn free_blocks(&self) -> &[FreeBlock] {
+        &self.blocks[..self.num_blocks]
+    }
+
+    fn zone_index(&self, pfn: usize) -> Option<usize> {
+        self.zones().iter().position(|z| {
+            let start = z.start / PAGE_SIZE;
+            (start..start + z.total_pages).contains(&pfn)
+        })
+    }
+
+    fn insert_block(&mut self, idx: usize, block: FreeBlock) {
+        debug_assert!(self.num_blocks < MAX_FREE_BLOCKS);
+        self.blocks.copy_within(idx..self.num_blocks, idx + 1);
+        self.blocks[idx] = block;
+        self.num_blocks += 1;
+    }
+
+    fn remove_block(&mut self, idx: usize) -> FreeBlock {
+        let block = self.blocks[idx];
+        self.blocks.copy_within(idx + 1..self.num_blocks, idx);
+        self.num_blocks -= 1;
+        block
+    }
+
+    /// Frees a block of `2^order` pages in the zone `zone`, and merges it with
+    /// its buddies as much as possible.
+    ///
+    /// Returns `false` if the block cannot be recorded.
+    fn free_block(&mut self, zone: usize, mut pfn: usize, mut order: usize) -> bool {
+        let zone_start = self.zones[zone].start / PAGE_SIZE;
+        let zone_end = zone_start + self.zones[zone].total_pages;
+        while order < MAX_ORDER {
+            let merged = pfn & !(1 << order);
+            if merged < zone_start || merged + (2 << order) > zone_end {
+                break;
+            }
+            let buddy = FreeBlock {
+                order,
+                pfn: pfn ^ (1 << order),
+            };
+            match self.free_blocks().binary_search(&buddy) {
+                Ok(idx) => self.remove_block(idx),
+                Err(_) => break,
+            };
+            pfn = merged;
+            order += 1;
+        }
+        if self.num_blocks == MAX_FREE_BLOCKS {
+            return false;
+        }
+        let block = FreeBlock { order, pfn };
+        let idx = self.free_blocks().binary_search(&block).unwrap_err();
+        self.insert_block(idx, block);
+        true
+    }
+
+    /// Frees `num_pages` pages starting from `pfn` in the zone `zone`, by
+    /// splitting them into maximal aligned blocks.
+    ///
+    /// Returns the number of pages that cannot be recorded.
+    fn free_range(&mut self, zone: usize, mut pfn: usize, mut num_pages: usize) -> usize {
+        let mut leaked = 0;
+        while num_pages > 0 {
+            let order = (pfn.trailing_zeros() as usize)
+                .min(num_pages.ilog2() as usize)
+                .min(MAX_ORDER);
+            if !self.free_block(zone, pfn, order) {
+                leaked += 1 << order;
+            }
+            pfn += 1 << order;
+            num_pages -= 1 << order;
+        }
+        leaked
+    }
+}
+
+impl<const PAGE_SIZE: usize> Default for BuddyPageAllocator<PAGE_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PAGE_SIZE: usize> BaseAllocator for BuddyPageAllocator<PAGE_SIZE> {
+    fn init(&mut self, start: usize, size: usize) {
+        assert!(PAGE_SIZE.is_power_of_two());
+        self.add_memory(start, size).unwrap();
+    }
+
+    fn add_memory(&mut self, start: usize, size: usize) -> AllocResult {
+        let end = super::align_down(start + size, PAGE_SIZE);
+        let start = super::align_up(start, PAGE_SIZE);
+        if start >= end {
+            return Ok(());
+        }
+        if self
+            .zones()
+            .iter()
+            .any(|z| start < z.start + z.total_pages * PAGE_SIZE && z.start < end)
+        {
+            return Err(AllocError::MemoryOverlap);
+        }
+        if self.num_zones == MAX_ZONES {
+            return Err(AllocError::NoMemory);
+        }
+
+        let num_pages = (end - start) / PAGE_SIZE;
+        let zone = self.num_zones;
+        self.zones[zone] = MemoryZone {
+            start,
+            total_pages: num_pages,
+            free_pages: num_pages,
+        };
+        self.num_zones += 1;
+        let leaked = self.free_range(zone, start / PAGE_SIZE, num_pages);
+        self.zones[zone].free_pages -= leaked;
+        self.total_pages += num_pages;
+        self.used_pages += leaked;
+        Ok(())
+    }
+}
+
+impl<const PAGE_SIZE: usize> PageAllocator for BuddyPageAllocator<PAGE_SIZE> {
+    const PAGE_SIZE: usize = PAGE_SIZE;
+
+    fn alloc_pages(&mut self, num_pages: usize, align_pow2: usize) -> AllocResult<usize> {
+        if !align_pow2.is_multiple_of(PAGE_SIZE) || !align_pow2.is_power_of_two() || num_pages == 0
+        {
+            return Err(AllocError::InvalidParam);
+        }
+        // blocks of order `n` are aligned to `2^n` pages
+        let order = (num_pages.next_power_of_two().trailing_zeros() as usize)
+            .max((align_pow2 / PAGE_SIZE).trailing_zeros() as usize);
+        if order > MAX_ORDER {
+            return Err(AllocError::NoMemory);
+        }
+        let idx = self.free_blocks().partition_point(|b| b.order < order);
+        if idx == self.num_blocks {
+            return Err(AllocError::NoMemory);
+        }
+        // splitting the block and freeing its tail add at most `2 * MAX_ORDER`
+        // blocks
+        if self.num_blocks + 2 * MAX_ORDER > MAX_FREE_BLOCKS {
+            return Err(AllocError::NoMemory);
+        }
+
+        let block = self.remove_block(idx);
+        for o in order..block.order {
+            let buddy = FreeBlock {
+                order: o,
+                pfn: block.pfn + (1 << o),
+            };
+            let idx = self.free_blocks().binary_search(&buddy).unwrap_err();
+            self.insert_block(idx, buddy);
+        }
+        let zone = self.zone_index(block.pfn).unwrap();
+        let leaked = self.free_range(zone, block.pfn + num_pages, (1 << order) - num_pages);
+        debug_assert_eq!(leaked, 0);
+        self.zones[zone].free_pages -= num_pages;
+        self.used_pages += num_pages;
+        Ok(block.pfn * PAGE_SIZE)
+    }
+
+    fn dealloc_pages(&mut self, pos: usize, num_pages: usize) {
+        let pfn = pos / PAGE_SIZE;
+        let Some(zone) = self.zone_index(pfn) else {
+            return;
+        };
+        let leaked = self.free_range(zone, pfn, num_pages);
+        self.zones[zone].free_pages += num_pages - leaked;
+        self.used_pages -= num_pages - leaked;
+    }
+
+    fn total_pages(&self) -> usize {
+        self.total_pages
+    }
+
+    fn used_pages(&self) -> usize {
+        self.used_pages
+    }
+
+    fn available_pages(&self) -> usize {
+        self.total_pages - self.used_pages
+    }
+}
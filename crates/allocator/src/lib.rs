@@ -5,7 +5,7 @@
 //! - [`ByteAllocator`]: Byte-granularity memory allocator. (e.g.,
 //!   [`BuddyByteAllocator`], [`SlabByteAllocator`])
 //! - [`PageAllocator`]: Page-granularity memory allocator. (e.g.,
-//!   [`BitmapPageAllocator`])
+//!   [`BitmapPageAllocator`], [`BuddyPageAllocator`])
 //! - [`IdAllocator`]: Used to allocate unique IDs.
 
 #![no_std]
@@ -13,10 +13,15 @@
 
 mod bitmap;
 mod buddy;
+mod buddy_page;
 mod slab;
 
+#[cfg(test)]
+mod tests;
+
 pub use bitmap::BitmapPageAllocator;
 pub use buddy::BuddyByteAllocator;
+pub use buddy_page::{BuddyPageAllocator, BuddyStats, MemoryZone};
 pub use slab::SlabByteAllocator;
 
 /// The error type used for allocation.
@@ -0,0 +1,105 @@
+use crate::{AllocError, BaseAllocator, BuddyPageAllocator, PageAllocator};
+
+const PAGE_SIZE: usize = 0x1000;
+const SIZE_1G: usize = 0x4000_0000;
+
+type Allocator = BuddyPageAllocator<PAGE_SIZE>;
+
+#[test]
+fn test_buddy_page_large_memory() {
+    let mut a = Allocator::new();
+    // 6 GiB, starting from an address that is not aligned to 1 GiB
+    a.init(0xffff_8000_0020_0000, 6 * SIZE_1G);
+    a.add_memory(0xffff_8010_0000_0000, 64 * SIZE_1G).unwrap();
+    assert_eq!(a.total_pages(), 70 * SIZE_1G / PAGE_SIZE);
+    assert_eq!(a.available_pages(), a.total_pages());
+    assert_eq!(a.zones().len(), 2);
+
+    let pos = a.alloc_pages(SIZE_1G / PAGE_SIZE, SIZE_1G).unwrap();
+    assert_eq!(pos % SIZE_1G, 0);
+    assert_eq!(a.used_pages(), SIZE_1G / PAGE_SIZE);
+    a.dealloc_pages(pos, SIZE_1G / PAGE_SIZE);
+    assert_eq!(a.used_pages(), 0);
+}
+
+#[test]
+fn test_buddy_page_alloc_dealloc() {
+    let mut a = Allocator::new();
+    let base = 0x8000_0000;
+    a.init(base, 1024 * PAGE_SIZE);
+    let before = a.stats();
+    assert_eq!(before.free_pages, 1024);
+    assert_eq!(before.largest_free_block, 1024);
+    assert_eq!(before.fragmentation(), 0);
+
+    // non-power-of-two counts only take the requested pages
+    let p1 = a.alloc_pages(3, PAGE_SIZE).unwrap();
+    let p2 = a.alloc_pages(1, PAGE_SIZE).unwrap();
+    let p3 = a.alloc_pages(5, 16 * PAGE_SIZE).unwrap();
+    assert_eq!(p3 % (16 * PAGE_SIZE), 0);
+    assert_eq!(a.used_pages(), 9);
+    assert_eq!(a.zones()[0].free_pages, 1024 - 9);
+    assert!(a.stats().fragmentation() > 0);
+
+    // partial deallocation is allowed
+    a.dealloc_pages(p3 + 2 * PAGE_SIZE, 3);
+    a.dealloc_pages(p3, 2);
+    a.dealloc_pages(p2, 1);
+    a.dealloc_pages(p1, 3);
+    assert_eq!(a.used_pages(), 0);
+    let after = a.stats();
+    assert_eq!(after.free_blocks, before.free_blocks);
+    assert_eq!(after.largest_free_block, 1024);
+}
+
+#[test]
+fn test_buddy_page_errors() {
+    let mut a = Allocator::new();
+    a.init(0x8000_0000, 16 * PAGE_SIZE);
+    assert!(matches!(
+        a.add_memory(0x8000_0000 + 8 * PAGE_SIZE, 16 * PAGE_SIZE),
+        Err(AllocError::MemoryOverlap)
+    ));
+    assert!(matches!(
+        a.alloc_pages(1, PAGE_SIZE / 2),
+        Err(AllocError::InvalidParam)
+    ));
+    assert!(matches!(
+        a.alloc_pages(0, PAGE_SIZE),
+        Err(AllocError::InvalidParam)
+    ));
+    assert!(matches!(
+        a.alloc_pages(17, PAGE_SIZE),
+        Err(AllocError::NoMemory)
+    ));
+
+    // blocks are not merged across zones
+    a.add_memory(0x8000_0000 + 16 * PAGE_SIZE, 16 * PAGE_SIZE)
+        .unwrap();
+    assert!(matches!(
+        a.alloc_pages(32, PAGE_SIZE),
+        Err(AllocError::NoMemory)
+    ));
+    let p = a.alloc_pages(16, PAGE_SIZE).unwrap();
+    assert_eq!(a.zones().iter().filter(|z| z.free_pages == 0).count(), 1);
+    a.dealloc_pages(p, 16);
+    assert_eq!(a.available_pages(), 32);
+}
+
+#[test]
+fn test_buddy_page_fragmentation() {
+    let mut a = Allocator::new();
+    a.init(0x8000_0000, 4096 * PAGE_SIZE);
+    let pages: [usize; 256] = core::array::from_fn(|_| a.alloc_pages(1, PAGE_SIZE).unwrap());
+    for &p in pages.iter().step_by(2) {
+        a.dealloc_pages(p, 1);
+    }
+    let stats = a.stats();
+    assert_eq!(stats.free_blocks[0], 128);
+    assert_eq!(stats.free_pages, 4096 - 128);
+    for &p in pages.iter().skip(1).step_by(2) {
+        a.dealloc_pages(p, 1);
+    }
+    assert_eq!(a.stats().free_blocks[0], 0);
+    assert_eq!(a.stats().fragmentation(), 0);
+}
@@ -4,7 +4,7 @@
 
 use core::ptr::NonNull;
 
-use crate::{TriggerMode, GIC_MAX_IRQ, SPI_RANGE};
+use crate::{TriggerMode, GIC_MAX_IRQ, SGI_RANGE, SPI_RANGE};
 use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::register_structs;
 use tock_registers::registers::{ReadOnly, ReadWrite, WriteOnly};
@@ -177,6 +177,16 @@ impl GicDistributor {
         }
     }
 
+    /// Sends a software generated interrupt (SGI) to the given CPU interface.
+    pub fn send_sgi(&mut self, cpu_id: usize, vector: usize) {
+        if cpu_id >= self.cpu_num() || !SGI_RANGE.contains(&vector) {
+            return;
+        }
+        // TargetListFilter = 0b00, forward to the CPUs in CPUTargetList
+        let target_list = 1 << cpu_id;
+        self.regs().SGIR.set(target_list << 16 | vector as u32);
+    }
+
     /// Initializes the GIC distributor.
     ///
     /// It disables all interrupts, sets the target of all SPIs to CPU 0,
@@ -36,8 +36,12 @@ pub enum AxError {
     BadState,
     /// The connection was refused by the remote server,
     ConnectionRefused,
+    /// Cross-device or cross-filesystem (hard) link or rename.
+    CrossesDevices,
     /// A non-empty directory was specified where an empty directory was expected.
     DirectoryNotEmpty,
+    /// Loop in the filesystem, often too many levels of symbolic links.
+    FilesystemLoop,
     /// Data not valid for the operation were encountered.
     ///
     /// Unlike [`InvalidInput`], this typically means that the operation
@@ -65,10 +69,14 @@ pub enum AxError {
     NotFound,
     /// The operation lacked the necessary privileges to complete.
     PermissionDenied,
+    /// The filesystem or storage medium is read-only, but a write operation was attempted.
+    ReadOnlyFilesystem,
     /// Device or resource is busy.
     ResourceBusy,
     /// The underlying storage (typically, a filesystem) is full.
     StorageFull,
+    /// The I/O operation's timeout expired, causing it to be canceled.
+    TimedOut,
     /// An error returned when an operation could not be completed because an
     /// "end of file" was reached prematurely.
     UnexpectedEof,
@@ -201,7 +209,9 @@ impl From<AxError> for LinuxError {
             AlreadyExists => LinuxError::EEXIST,
             BadAddress | BadState => LinuxError::EFAULT,
             ConnectionRefused => LinuxError::ECONNREFUSED,
+            CrossesDevices => LinuxError::EXDEV,
             DirectoryNotEmpty => LinuxError::ENOTEMPTY,
+            FilesystemLoop => LinuxError::ELOOP,
             InvalidInput | InvalidData => LinuxError::EINVAL,
             Io => LinuxError::EIO,
             IsADirectory => LinuxError::EISDIR,
@@ -210,8 +220,10 @@ impl From<AxError> for LinuxError {
             NotConnected => LinuxError::ENOTCONN,
             NotFound => LinuxError::ENOENT,
             PermissionDenied => LinuxError::EACCES,
+            ReadOnlyFilesystem => LinuxError::EROFS,
             ResourceBusy => LinuxError::EBUSY,
             StorageFull => LinuxError::ENOSPC,
+            TimedOut => LinuxError::ETIMEDOUT,
             Unsupported => LinuxError::ENOSYS,
             UnexpectedEof | WriteZero => LinuxError::EIO,
             WouldBlock => LinuxError::EAGAIN,
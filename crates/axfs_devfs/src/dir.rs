@@ -1,7 +1,7 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-use axfs_vfs::{VfsError, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeTimes, VfsResult};
 use spin::RwLock;
 
 /// The directory node in the device filesystem.
@@ -10,6 +10,7 @@ use spin::RwLock;
 pub struct DirNode {
     parent: RwLock<Weak<dyn VfsNodeOps>>,
     children: RwLock<BTreeMap<&'static str, VfsNodeRef>>,
+    times: RwLock<VfsNodeTimes>,
 }
 
 impl DirNode {
@@ -18,6 +19,7 @@ impl DirNode {
         Arc::new(Self {
             parent: RwLock::new(parent),
             children: RwLock::new(BTreeMap::new()),
+            times: RwLock::new(VfsNodeTimes::now()),
         })
     }
 
@@ -30,18 +32,20 @@ impl DirNode {
         let parent = self.clone() as VfsNodeRef;
         let node = Self::new(Some(&parent));
         self.children.write().insert(name, node.clone());
+        self.times.write().touch_modified();
         node
     }
 
     /// Add a node to this directory.
     pub fn add(&self, name: &'static str, node: VfsNodeRef) {
         self.children.write().insert(name, node);
+        self.times.write().touch_modified();
     }
 }
 
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        Ok(VfsNodeAttr::new_dir(4096, 0).with_times(*self.times.read()))
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
@@ -0,0 +1,82 @@
+use axfs_vfs::VfsResult;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeTimes, VfsNodeType};
+
+/// A framebuffer device behaves like `/dev/fb0`.
+///
+/// Reads and writes access the pixels in the framebuffer memory directly, and
+/// the screen is flushed after each write. The memory can also be mapped by
+/// [`FrameBufferDev::base_vaddr`] and [`FrameBufferDev::size`].
+pub struct FrameBufferDev {
+    base_vaddr: usize,
+    size: usize,
+    flush: fn(),
+}
+
+impl FrameBufferDev {
+    /// Create a new framebuffer device of `size` bytes at `base_vaddr`.
+    /// `flush` is called to show the framebuffer on the screen after writes.
+    ///
+    /// # Safety
+    ///
+    /// The memory region must be valid and accessible during the lifetime of
+    /// the device.
+    pub const unsafe fn new(base_vaddr: usize, size: usize, flush: fn()) -> Self {
+        Self {
+            base_vaddr,
+            size,
+            flush,
+        }
+    }
+
+    /// The base virtual address of the framebuffer memory.
+    pub const fn base_vaddr(&self) -> usize {
+        self.base_vaddr
+    }
+
+    /// The size of the framebuffer memory in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the range of the framebuffer memory at `offset` with at most
+    /// `len` bytes.
+    fn range(&self, offset: u64, len: usize) -> (*mut u8, usize) {
+        let offset = offset.min(self.size as u64) as usize;
+        let len = len.min(self.size - offset);
+        ((self.base_vaddr + offset) as *mut u8, len)
+    }
+}
+
+impl VfsNodeOps for FrameBufferDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            self.size as u64,
+            0,
+        )
+        .with_times(VfsNodeTimes::now()))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let (ptr, len) = self.range(offset, buf.len());
+        unsafe { core::ptr::copy_nonoverlapping(ptr, buf.as_mut_ptr(), len) };
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let (ptr, len) = self.range(offset, buf.len());
+        if len == 0 && !buf.is_empty() {
+            return Err(VfsError::StorageFull);
+        }
+        unsafe { core::ptr::copy_nonoverlapping(buf.as_ptr(), ptr, len) };
+        (self.flush)();
+        Ok(len)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -7,14 +7,18 @@
 extern crate alloc;
 
 mod dir;
+mod framebuffer;
 mod null;
+mod random;
 mod zero;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
+pub use self::framebuffer::FrameBufferDev;
 pub use self::null::NullDev;
+pub use self::random::RandomDev;
 pub use self::zero::ZeroDev;
 
 use alloc::sync::Arc;
@@ -0,0 +1,77 @@
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeTimes, VfsNodeType, VfsResult};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The increment of the SplitMix64 generator (the golden ratio).
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A random device behaves like `/dev/random` or `/dev/urandom`.
+///
+/// Reads never block, and return bytes from a [SplitMix64] generator seeded
+/// at creation. Writes mix the data into the state of the generator.
+///
+/// **Note**: the bytes are NOT cryptographically secure.
+///
+/// [SplitMix64]: https://prng.di.unimi.it/splitmix64.c
+pub struct RandomDev {
+    state: AtomicU64,
+}
+
+impl RandomDev {
+    /// Create a new random device with the given seed.
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    fn next_u64(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl Default for RandomDev {
+    fn default() -> Self {
+        Self::new(0xa2ce_a2ce)
+    }
+}
+
+impl VfsNodeOps for RandomDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        // the device has no persistent state, all timestamps are the current time
+        Ok(
+            VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0)
+                .with_times(VfsNodeTimes::now()),
+        )
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        for chunk in buf.chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            self.state
+                .fetch_xor(u64::from_le_bytes(bytes), Ordering::Relaxed);
+            self.next_u64(); // diffuse the written bytes
+        }
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeOps, VfsNodeType, VfsResult};
 
 use crate::*;
 
@@ -113,3 +114,73 @@ fn test_devfs() {
     test_devfs_ops(&devfs).unwrap();
     test_get_parent(&devfs).unwrap();
 }
+
+fn fake_clock() -> Duration {
+    Duration::from_secs(1000)
+}
+
+#[test]
+fn test_devfs_times() {
+    axfs_vfs::set_time_source(fake_clock);
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    let root = devfs.root_dir();
+    let attr = root.get_attr().unwrap();
+    assert_eq!(attr.mtime(), Duration::from_secs(1000));
+    assert_eq!(attr.ctime(), Duration::from_secs(1000));
+
+    let node = root.lookup("null").unwrap();
+    assert_eq!(node.get_attr().unwrap().atime(), Duration::from_secs(1000));
+    assert_eq!(
+        node.set_times(None, Some(Duration::ZERO)).err(),
+        Some(VfsError::Unsupported)
+    );
+}
+
+#[test]
+fn test_random_dev() {
+    let dev = RandomDev::new(42);
+    let (mut buf1, mut buf2) = ([0; 13], [0; 13]);
+    assert_eq!(dev.read_at(0, &mut buf1).unwrap(), 13);
+    assert_eq!(dev.read_at(0, &mut buf2).unwrap(), 13);
+    assert_ne!(buf1, buf2);
+    assert_ne!(buf1, [0; 13]);
+
+    // the same seed gives the same bytes, unless data is written
+    let (dev1, dev2) = (RandomDev::new(42), RandomDev::new(42));
+    dev1.read_at(0, &mut buf1).unwrap();
+    dev2.read_at(0, &mut buf2).unwrap();
+    assert_eq!(buf1, buf2);
+    assert_eq!(dev1.write_at(0, b"entropy").unwrap(), 7);
+    dev1.read_at(0, &mut buf1).unwrap();
+    dev2.read_at(0, &mut buf2).unwrap();
+    assert_ne!(buf1, buf2);
+}
+
+static FLUSHED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn fake_flush() {
+    FLUSHED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+}
+
+#[test]
+fn test_framebuffer_dev() {
+    let mut mem = vec![0u8; 16];
+    let dev = unsafe { FrameBufferDev::new(mem.as_mut_ptr() as usize, mem.len(), fake_flush) };
+    assert_eq!(dev.get_attr().unwrap().size(), 16);
+
+    assert_eq!(dev.write_at(4, &[1, 2, 3, 4]).unwrap(), 4);
+    assert_eq!(FLUSHED.load(std::sync::atomic::Ordering::Relaxed), 1);
+    assert_eq!(dev.write_at(14, &[5, 6, 7]).unwrap(), 2);
+    assert_eq!(dev.write_at(16, &[8]).err(), Some(VfsError::StorageFull));
+
+    let mut buf = [0xff; 20];
+    assert_eq!(dev.read_at(0, &mut buf).unwrap(), 16);
+    assert_eq!(
+        &buf[..16],
+        &[0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 5, 6]
+    );
+    assert_eq!(dev.read_at(16, &mut buf).unwrap(), 0);
+    assert_eq!(mem[5], 2);
+}
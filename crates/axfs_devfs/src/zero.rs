@@ -1,4 +1,4 @@
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeTimes, VfsNodeType, VfsResult};
 
 /// A zero device behaves like `/dev/zero`.
 ///
@@ -7,12 +7,11 @@ pub struct ZeroDev;
 
 impl VfsNodeOps for ZeroDev {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new(
-            VfsNodePerm::default_file(),
-            VfsNodeType::CharDevice,
-            0,
-            0,
-        ))
+        // the device has no state, all timestamps are the current time
+        Ok(
+            VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0)
+                .with_times(VfsNodeTimes::now()),
+        )
     }
 
     fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
@@ -0,0 +1,151 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsError, VfsNodeTimes, VfsResult};
+use spin::RwLock;
+
+/// The directory node in the process information filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct DirNode {
+    this: Weak<DirNode>,
+    parent: RwLock<Weak<dyn VfsNodeOps>>,
+    children: RwLock<BTreeMap<&'static str, VfsNodeRef>>,
+    times: RwLock<VfsNodeTimes>,
+}
+
+impl DirNode {
+    pub(super) fn new(parent: Option<&VfsNodeRef>) -> Arc<Self> {
+        let parent = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            parent: RwLock::new(parent),
+            children: RwLock::new(BTreeMap::new()),
+            times: RwLock::new(VfsNodeTimes::now()),
+        })
+    }
+
+    pub(super) fn set_parent(&self, parent: Option<&VfsNodeRef>) {
+        *self.parent.write() = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+    }
+
+    /// Create a subdirectory at this directory, or returns the existing one
+    /// with the same name.
+    pub fn mkdir(self: &Arc<Self>, name: &'static str) -> Arc<Self> {
+        if let Some(dir) = self.children.read().get(name).and_then(|node| {
+            let dir = node.as_any().downcast_ref::<Self>()?;
+            dir.this.upgrade()
+        }) {
+            return dir;
+        }
+        let parent = self.clone() as VfsNodeRef;
+        let node = Self::new(Some(&parent));
+        self.children.write().insert(name, node.clone());
+        self.times.write().touch_modified();
+        node
+    }
+
+    /// Add a node to this directory.
+    pub fn add(&self, name: &'static str, node: VfsNodeRef) {
+        self.children.write().insert(name, node);
+        self.times.write().touch_modified();
+    }
+}
+
+impl VfsNodeOps for DirNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new_dir(4096, 0).with_times(*self.times.read()))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().upgrade()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        let (name, rest) = split_path(path);
+        let node = match name {
+            "" | "." => Ok(self.clone() as VfsNodeRef),
+            ".." => self.parent().ok_or(VfsError::NotFound),
+            _ => self
+                .children
+                .read()
+                .get(name)
+                .cloned()
+                .ok_or(VfsError::NotFound),
+        }?;
+
+        if let Some(rest) = rest {
+            node.lookup(rest)
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let children = self.children.read();
+        let mut children = children.iter().skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some((name, node)) = children.next() {
+                        *ent = VfsDirEntry::new(name, node.get_attr().unwrap().file_type());
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        log::debug!("create {:?} at procfs: {}", ty, path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.create(rest, ty),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
+                _ => self
+                    .children
+                    .read()
+                    .get(name)
+                    .ok_or(VfsError::NotFound)?
+                    .create(rest, ty),
+            }
+        } else if name.is_empty() || name == "." || name == ".." {
+            Ok(()) // already exists
+        } else {
+            Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+        }
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        log::debug!("remove at procfs: {}", path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.remove(rest),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
+                _ => self
+                    .children
+                    .read()
+                    .get(name)
+                    .ok_or(VfsError::NotFound)?
+                    .remove(rest),
+            }
+        } else {
+            Err(VfsError::PermissionDenied) // do not support to remove nodes dynamically
+        }
+    }
+
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let trimmed_path = path.trim_start_matches('/');
+    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
+        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+    })
+}
@@ -0,0 +1,57 @@
+use alloc::{boxed::Box, string::String};
+use axfs_vfs::VfsResult;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeTimes, VfsNodeType};
+use spin::Mutex;
+
+/// A read-only file whose contents are generated when it is read.
+///
+/// The contents are generated on reads at offset 0, and later reads at other
+/// offsets return the same snapshot, so a file read sequentially is
+/// consistent. Like files in Linux procfs, its size is reported as 0, so it
+/// should be read until the end (e.g., by `read_to_string`).
+pub struct ProcFile {
+    generate: Box<dyn Fn() -> String + Send + Sync>,
+    snapshot: Mutex<String>,
+}
+
+impl ProcFile {
+    /// Create a new file with the function to generate its contents.
+    pub fn new<F>(generate: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Self {
+            generate: Box::new(generate),
+            snapshot: Mutex::new(String::new()),
+        }
+    }
+}
+
+impl VfsNodeOps for ProcFile {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        // the contents are generated on reads, all timestamps are the current time
+        let perm = VfsNodePerm::from_bits_truncate(0o444);
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, 0, 0).with_times(VfsNodeTimes::now()))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut contents = self.snapshot.lock();
+        if offset == 0 {
+            *contents = (self.generate)();
+        }
+        let start = contents.len().min(offset as usize);
+        let len = buf.len().min(contents.len() - start);
+        buf[..len].copy_from_slice(&contents.as_bytes()[start..start + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
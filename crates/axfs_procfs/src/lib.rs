@@ -0,0 +1,88 @@
+//! Process information filesystem used by [ArceOS](https://github.com/rcore-os/arceos).
+//!
+//! It is usually mounted on `/proc`, and contains read-only files whose
+//! contents are generated dynamically when read, to inspect the kernel state.
+//!
+//! The implementation is based on [`axfs_vfs`].
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod dir;
+mod file;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::dir::DirNode;
+pub use self::file::ProcFile;
+
+use alloc::{string::String, sync::Arc};
+use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use spin::once::Once;
+
+/// A process information filesystem that implements [`axfs_vfs::VfsOps`].
+pub struct ProcFileSystem {
+    parent: Once<VfsNodeRef>,
+    root: Arc<DirNode>,
+}
+
+impl ProcFileSystem {
+    /// Create a new instance.
+    pub fn new() -> Self {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new(None),
+        }
+    }
+
+    /// Create a subdirectory at the root directory, or returns the existing
+    /// one.
+    pub fn mkdir(&self, name: &'static str) -> Arc<DirNode> {
+        self.root.mkdir(name)
+    }
+
+    /// Add a node to the root directory.
+    ///
+    /// The node must implement [`axfs_vfs::VfsNodeOps`], and be wrapped in [`Arc`].
+    pub fn add(&self, name: &'static str, node: VfsNodeRef) {
+        self.root.add(name, node);
+    }
+
+    /// Add a [`ProcFile`] generated by `generate` at `path`, relative to the
+    /// root directory. Missing parent directories are created.
+    pub fn add_file<F>(&self, path: &'static str, generate: F)
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        let path = path.trim_matches('/');
+        let (dir_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let mut dir = self.root.clone();
+        for name in dir_path.split('/').filter(|s| !s.is_empty()) {
+            dir = dir.mkdir(name);
+        }
+        dir.add(name, Arc::new(ProcFile::new(generate)));
+    }
+}
+
+impl VfsOps for ProcFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        if let Some(parent) = mount_point.parent() {
+            self.root.set_parent(Some(self.parent.call_once(|| parent)));
+        } else {
+            self.root.set_parent(None);
+        }
+        Ok(())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
+
+impl Default for ProcFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+
+use crate::*;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn read_all(procfs: &ProcFileSystem, path: &str) -> VfsResult<String> {
+    let node = procfs.root_dir().lookup(path)?;
+    let mut contents = Vec::new();
+    let mut buf = [0; 4];
+    loop {
+        let len = node.read_at(contents.len() as _, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        contents.extend_from_slice(&buf[..len]);
+    }
+    Ok(String::from_utf8(contents).unwrap())
+}
+
+fn test_procfs_ops(procfs: &ProcFileSystem) -> VfsResult {
+    let root = procfs.root_dir();
+    assert!(root.get_attr()?.is_dir());
+    assert_eq!(root.clone().lookup("foo").err(), Some(VfsError::NotFound));
+
+    let node = root.clone().lookup("///version")?;
+    let attr = node.get_attr()?;
+    assert_eq!(attr.file_type(), VfsNodeType::File);
+    assert_eq!(attr.size(), 0);
+    assert_eq!(attr.perm().bits(), 0o444);
+    assert_eq!(read_all(procfs, "version")?, "ArceOS 0.1.0\n");
+    let mut buf = [0; 32];
+    assert_eq!(node.read_at(0, &mut buf[..7])?, 7);
+    assert_eq!(node.read_at(7, &mut buf)?, 6);
+    assert_eq!(&buf[..6], b"0.1.0\n");
+    assert_eq!(node.read_at(100, &mut buf)?, 0);
+    assert_eq!(
+        node.write_at(0, &buf).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(node.truncate(0).err(), Some(VfsError::PermissionDenied));
+
+    // contents are generated on each read
+    assert_eq!(read_all(procfs, "counter")?, "1\n");
+    assert_eq!(read_all(procfs, "./counter")?, "2\n");
+
+    let net = root.clone().lookup("net")?;
+    assert!(net.get_attr()?.is_dir());
+    assert_eq!(read_all(procfs, "net/tcp")?, "tcp\n");
+    assert_eq!(read_all(procfs, "net/../net//udp")?, "udp\n");
+    assert!(Arc::ptr_eq(&net.parent().unwrap(), &root));
+
+    assert_eq!(
+        root.create("new", VfsNodeType::File).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(
+        root.remove("version").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_procfs() {
+    // .
+    // ├── counter
+    // ├── net
+    // │   ├── tcp
+    // │   └── udp
+    // └── version
+
+    let procfs = ProcFileSystem::new();
+    procfs.add_file("version", || "ArceOS 0.1.0\n".into());
+    procfs.add_file("/counter", || {
+        format!("{}\n", COUNTER.fetch_add(1, Ordering::Relaxed) + 1)
+    });
+    procfs.add_file("net/tcp", || "tcp\n".into());
+    procfs.add_file("net/udp", || "udp\n".into()); // reuse the directory
+
+    test_procfs_ops(&procfs).unwrap();
+}
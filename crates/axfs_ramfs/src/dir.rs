@@ -1,12 +1,14 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
 use alloc::{string::String, vec::Vec};
+use core::time::Duration;
 
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-use axfs_vfs::{VfsError, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeTimes, VfsResult};
 use spin::RwLock;
 
 use crate::file::FileNode;
+use crate::symlink::SymlinkNode;
 
 /// The directory node in the RAM filesystem.
 ///
@@ -15,6 +17,7 @@ pub struct DirNode {
     this: Weak<DirNode>,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
     children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    times: RwLock<VfsNodeTimes>,
 }
 
 impl DirNode {
@@ -23,6 +26,7 @@ impl DirNode {
             this: this.clone(),
             parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
             children: RwLock::new(BTreeMap::new()),
+            times: RwLock::new(VfsNodeTimes::now()),
         })
     }
 
@@ -52,6 +56,19 @@ impl DirNode {
             _ => return Err(VfsError::Unsupported),
         };
         self.children.write().insert(name.into(), node);
+        self.times.write().touch_modified();
+        Ok(())
+    }
+
+    /// Creates a new symbolic link with the given name in this directory,
+    /// pointing to `target`.
+    pub fn create_symlink(&self, name: &str, target: &str) -> VfsResult {
+        let mut children = self.children.write();
+        if children.contains_key(name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        children.insert(name.into(), Arc::new(SymlinkNode::new(target)));
+        self.times.write().touch_modified();
         Ok(())
     }
 
@@ -65,13 +82,58 @@ impl DirNode {
             }
         }
         children.remove(name);
+        self.times.write().touch_modified();
         Ok(())
     }
+
+    /// Looks up the directory containing the last component of `path`, and
+    /// returns it with the name of the last component.
+    ///
+    /// Returns [`CrossesDevices`](VfsError::CrossesDevices) if the directory is
+    /// not in this filesystem.
+    fn lookup_parent<'a>(&self, path: &'a str) -> VfsResult<(Arc<DirNode>, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let this = self.this.upgrade().ok_or(VfsError::NotFound)?;
+        if dir_path.trim_matches('/').is_empty() {
+            return Ok((this, name));
+        }
+        let node = this.lookup(dir_path)?;
+        match node.as_any().downcast_ref::<DirNode>() {
+            Some(dir) => Ok((dir.this.upgrade().ok_or(VfsError::NotFound)?, name)),
+            None if node.get_attr()?.is_dir() => Err(VfsError::CrossesDevices),
+            None => Err(VfsError::NotADirectory),
+        }
+    }
+
+    /// Whether this directory is `dir` or one of its ancestors.
+    fn is_ancestor_of(&self, dir: &DirNode) -> bool {
+        if core::ptr::eq(self, dir) {
+            return true;
+        }
+        let mut parent = dir.parent();
+        while let Some(node) = parent {
+            match node.as_any().downcast_ref::<DirNode>() {
+                Some(d) if core::ptr::eq(self, d) => return true,
+                Some(d) => parent = d.parent(),
+                None => return false,
+            }
+        }
+        false
+    }
 }
 
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        Ok(VfsNodeAttr::new_dir(4096, 0).with_times(*self.times.read()))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        self.times.write().set(atime, mtime);
+        Ok(())
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
@@ -165,6 +227,82 @@ impl VfsNodeOps for DirNode {
         }
     }
 
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        log::debug!("rename at ramfs: {} -> {}", src_path, dst_path);
+        let (src_dir, src_name) = self.lookup_parent(src_path)?;
+        let (dst_dir, dst_name) = self.lookup_parent(dst_path)?;
+        let node = src_dir
+            .children
+            .read()
+            .get(src_name)
+            .cloned()
+            .ok_or(VfsError::NotFound)?;
+        let src_is_dir = node.as_any().downcast_ref::<DirNode>();
+
+        if let Some(dir) = src_is_dir {
+            // cannot move a directory into itself
+            if dir.is_ancestor_of(&dst_dir) {
+                return Err(VfsError::InvalidInput);
+            }
+        }
+        if let Some(old) = dst_dir.children.read().get(dst_name) {
+            if Arc::ptr_eq(old, &node) {
+                return Ok(()); // the same node, do nothing
+            }
+            match (src_is_dir, old.as_any().downcast_ref::<DirNode>()) {
+                (Some(_), Some(old_dir)) if !old_dir.children.read().is_empty() => {
+                    return Err(VfsError::DirectoryNotEmpty)
+                }
+                (Some(_), None) => return Err(VfsError::NotADirectory),
+                (None, Some(_)) => return Err(VfsError::IsADirectory),
+                _ => {}
+            }
+        }
+
+        src_dir.children.write().remove(src_name);
+        dst_dir
+            .children
+            .write()
+            .insert(dst_name.into(), node.clone());
+        if let Some(dir) = src_is_dir {
+            dir.set_parent(Some(&(dst_dir.clone() as VfsNodeRef)));
+        }
+        src_dir.times.write().touch_modified();
+        dst_dir.times.write().touch_modified();
+        Ok(())
+    }
+
+    fn link(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        log::debug!("link at ramfs: {} -> {}", dst_path, src_path);
+        let (src_dir, src_name) = self.lookup_parent(src_path)?;
+        let (dst_dir, dst_name) = self.lookup_parent(dst_path)?;
+        let node = src_dir
+            .children
+            .read()
+            .get(src_name)
+            .cloned()
+            .ok_or(VfsError::NotFound)?;
+        if node.as_any().is::<DirNode>() {
+            return Err(VfsError::PermissionDenied); // hard links to directories are not allowed
+        }
+        let mut children = dst_dir.children.write();
+        if children.contains_key(dst_name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        children.insert(dst_name.into(), node);
+        dst_dir.times.write().touch_modified();
+        Ok(())
+    }
+
+    fn symlink(&self, target: &str, path: &str) -> VfsResult {
+        log::debug!("symlink at ramfs: {} -> {}", path, target);
+        if target.is_empty() {
+            return Err(VfsError::NotFound);
+        }
+        let (dir, name) = self.lookup_parent(path)?;
+        dir.create_symlink(name, target)
+    }
+
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
@@ -1,5 +1,6 @@
 use alloc::vec::Vec;
-use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodeTimes, VfsResult};
+use core::time::Duration;
 use spin::RwLock;
 
 /// The file node in the RAM filesystem.
@@ -7,19 +8,27 @@ use spin::RwLock;
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct FileNode {
     content: RwLock<Vec<u8>>,
+    times: RwLock<VfsNodeTimes>,
 }
 
 impl FileNode {
-    pub(super) const fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             content: RwLock::new(Vec::new()),
+            times: RwLock::new(VfsNodeTimes::now()),
         }
     }
 }
 
 impl VfsNodeOps for FileNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_file(self.content.read().len() as _, 0))
+        let attr = VfsNodeAttr::new_file(self.content.read().len() as _, 0);
+        Ok(attr.with_times(*self.times.read()))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        self.times.write().set(atime, mtime);
+        Ok(())
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
@@ -29,6 +38,7 @@ impl VfsNodeOps for FileNode {
         } else {
             content.resize(size as _, 0);
         }
+        self.times.write().touch_modified();
         Ok(())
     }
 
@@ -38,6 +48,7 @@ impl VfsNodeOps for FileNode {
         let end = content.len().min(offset as usize + buf.len());
         let src = &content[start..end];
         buf[..src.len()].copy_from_slice(src);
+        self.times.write().touch_accessed();
         Ok(src.len())
     }
 
@@ -49,6 +60,7 @@ impl VfsNodeOps for FileNode {
         }
         let dst = &mut content[offset..offset + buf.len()];
         dst.copy_from_slice(&buf[..dst.len()]);
+        self.times.write().touch_modified();
         Ok(buf.len())
     }
 
@@ -0,0 +1,47 @@
+use alloc::string::String;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodeTimes, VfsResult};
+use core::time::Duration;
+use spin::RwLock;
+
+/// The symbolic link node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct SymlinkNode {
+    target: String,
+    times: RwLock<VfsNodeTimes>,
+}
+
+impl SymlinkNode {
+    pub(super) fn new(target: &str) -> Self {
+        Self {
+            target: target.into(),
+            times: RwLock::new(VfsNodeTimes::now()),
+        }
+    }
+
+    /// Returns the target path of the symbolic link.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let attr = VfsNodeAttr::new_symlink(self.target.len() as _, 0);
+        Ok(attr.with_times(*self.times.read()))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        self.times.write().set(atime, mtime);
+        Ok(())
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = buf.len().min(self.target.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        self.times.write().touch_accessed();
+        Ok(len)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
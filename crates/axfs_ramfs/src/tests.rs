@@ -1,4 +1,6 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
 
@@ -134,3 +136,184 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+static FAKE_NOW: AtomicU64 = AtomicU64::new(0);
+
+fn fake_clock() -> Duration {
+    Duration::from_secs(FAKE_NOW.load(Ordering::SeqCst))
+}
+
+#[test]
+fn test_ramfs_times() {
+    axfs_vfs::set_time_source(fake_clock);
+    FAKE_NOW.store(100, Ordering::SeqCst);
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let node = root.clone().lookup("f1").unwrap();
+    let attr = node.get_attr().unwrap();
+    assert_eq!(attr.atime(), Duration::from_secs(100));
+    assert_eq!(attr.mtime(), Duration::from_secs(100));
+    assert_eq!(attr.ctime(), Duration::from_secs(100));
+    assert_eq!(root.get_attr().unwrap().mtime(), Duration::from_secs(100));
+
+    FAKE_NOW.store(200, Ordering::SeqCst);
+    node.write_at(0, b"hello").unwrap();
+    let attr = node.get_attr().unwrap();
+    assert_eq!(attr.atime(), Duration::from_secs(100));
+    assert_eq!(attr.mtime(), Duration::from_secs(200));
+
+    FAKE_NOW.store(300, Ordering::SeqCst);
+    node.read_at(0, &mut [0; 5]).unwrap();
+    let attr = node.get_attr().unwrap();
+    assert_eq!(attr.atime(), Duration::from_secs(300));
+    assert_eq!(attr.mtime(), Duration::from_secs(200));
+
+    node.set_times(None, Some(Duration::from_secs(42))).unwrap();
+    let attr = node.get_attr().unwrap();
+    assert_eq!(attr.atime(), Duration::from_secs(300));
+    assert_eq!(attr.mtime(), Duration::from_secs(42));
+    assert_eq!(attr.ctime(), Duration::from_secs(300));
+
+    FAKE_NOW.store(400, Ordering::SeqCst);
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    assert_eq!(root.get_attr().unwrap().mtime(), Duration::from_secs(400));
+    root.remove("f1").unwrap();
+}
+
+#[test]
+fn test_ramfs_rename_link() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/bar", VfsNodeType::Dir).unwrap();
+    root.create("empty", VfsNodeType::Dir).unwrap();
+    root.clone()
+        .lookup("f1")
+        .unwrap()
+        .write_at(0, b"f1")
+        .unwrap();
+
+    // rename in the same directory, and replace an existing file
+    let f1 = root.clone().lookup("f1").unwrap();
+    assert_eq!(root.rename("f1", "f3"), Ok(()));
+    assert_eq!(root.clone().lookup("f1").err(), Some(VfsError::NotFound));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f3").unwrap(), &f1));
+    assert_eq!(root.rename("f3", "f2"), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f2").unwrap(), &f1));
+    assert_eq!(root.rename("f2", "./f2"), Ok(()));
+
+    // move across directories
+    assert_eq!(root.rename("f2", "foo/bar/f4"), Ok(()));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("foo/bar/f4").unwrap(),
+        &f1
+    ));
+    let foo = root.clone().lookup("foo").unwrap();
+    assert_eq!(foo.rename("bar", "../bar"), Ok(()));
+    let bar = root.clone().lookup("bar").unwrap();
+    assert!(Arc::ptr_eq(&bar.parent().unwrap(), &root));
+    assert!(Arc::ptr_eq(&bar.clone().lookup("f4").unwrap(), &f1));
+
+    // error cases
+    assert_eq!(root.rename("f1", "f5").err(), Some(VfsError::NotFound));
+    assert_eq!(
+        root.rename("bar", "bar/baz").err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(root.rename("bar", ".").err(), Some(VfsError::InvalidInput));
+    assert_eq!(
+        root.rename("bar/f4", "foo").err(),
+        Some(VfsError::IsADirectory)
+    );
+    assert_eq!(
+        root.rename("foo", "bar/f4").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("foo", "bar").err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(root.rename("foo", "empty"), Ok(()));
+    assert!(root.clone().lookup("foo").is_err());
+
+    // hard links
+    assert_eq!(root.link("bar/f4", "f5"), Ok(()));
+    assert!(Arc::ptr_eq(&root.clone().lookup("f5").unwrap(), &f1));
+    assert_eq!(
+        root.link("bar/f4", "f5").err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.link("bar", "bar2").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(root.remove("bar/f4"), Ok(()));
+    let mut buf = [0; 2];
+    assert_eq!(
+        root.clone().lookup("f5").unwrap().read_at(0, &mut buf),
+        Ok(2)
+    );
+    assert_eq!(&buf, b"f1");
+}
+
+#[test]
+fn test_ramfs_symlink() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+
+    assert_eq!(root.symlink("foo/f1", "l1"), Ok(()));
+    assert_eq!(root.symlink("../l1", "foo/l2"), Ok(()));
+    assert_eq!(root.symlink("not_exist", "l3"), Ok(())); // dangling link
+    assert_eq!(
+        root.symlink("f1", "l1").err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(root.symlink("", "l4").err(), Some(VfsError::NotFound));
+    assert_eq!(
+        root.symlink("f1", "foo/f1/l4").err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    // links are not followed by the filesystem itself
+    let l1 = root.clone().lookup("l1").unwrap();
+    let attr = l1.get_attr().unwrap();
+    assert!(attr.is_symlink());
+    assert_eq!(attr.file_type(), VfsNodeType::SymLink);
+    assert_eq!(attr.size(), 6);
+    assert_eq!(attr.perm().bits(), 0o777);
+    assert_eq!(l1.clone().lookup("f1").err(), Some(VfsError::NotADirectory));
+    let mut buf = [0; 16];
+    assert_eq!(l1.readlink(&mut buf), Ok(6));
+    assert_eq!(&buf[..6], b"foo/f1");
+    assert_eq!(l1.readlink(&mut buf[..3]), Ok(3)); // truncated
+    assert_eq!(&buf[..3], b"foo");
+    assert_eq!(l1.read_at(0, &mut buf).err(), Some(VfsError::InvalidInput));
+
+    let l2 = root.clone().lookup("foo/l2").unwrap();
+    assert_eq!(l2.readlink(&mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"../l1");
+    assert_eq!(
+        root.clone()
+            .lookup("foo/f1")
+            .unwrap()
+            .readlink(&mut buf)
+            .err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(root.readlink(&mut buf).err(), Some(VfsError::InvalidInput));
+
+    let mut entries = ramfs.root_dir_node().get_entries();
+    entries.sort();
+    assert_eq!(entries, ["foo", "l1", "l3"]);
+
+    // removing a link does not affect the target
+    assert_eq!(root.remove("l1"), Ok(()));
+    assert!(root.clone().lookup("foo/f1").is_ok());
+    assert_eq!(root.clone().lookup("l1").err(), Some(VfsError::NotFound));
+}
@@ -1,8 +1,8 @@
 //! Virtual filesystem interfaces used by [ArceOS](https://github.com/rcore-os/arceos).
 //!
-//! A filesystem is a set of files and directories (symbol links are not
-//! supported currently), collectively referred to as **nodes**, which are
-//! conceptually similar to [inodes] in Linux. A file system needs to implement
+//! A filesystem is a set of files, directories and symbolic links, collectively
+//! referred to as **nodes**, which are conceptually similar to [inodes] in
+//! Linux. A file system needs to implement
 //! the [`VfsOps`] trait, its files and directories need to implement the
 //! [`VfsNodeOps`] trait.
 //!
@@ -22,14 +22,19 @@
 //! | [`open()`](VfsNodeOps::open) | Do something when the node is opened | both |
 //! | [`release()`](VfsNodeOps::release) | Do something when the node is closed | both |
 //! | [`get_attr()`](VfsNodeOps::get_attr) | Get the attributes of the node | both |
+//! | [`set_times()`](VfsNodeOps::set_times) | Set the access and modification time | both |
 //! | [`read_at()`](VfsNodeOps::read_at) | Read data from the file | file |
 //! | [`write_at()`](VfsNodeOps::write_at) | Write data to the file | file |
 //! | [`fsync()`](VfsNodeOps::fsync) | Synchronize the file data to disk | file |
 //! | [`truncate()`](VfsNodeOps::truncate) | Truncate the file | file |
+//! | [`readlink()`](VfsNodeOps::readlink) | Read the target of the symbolic link | symbolic link |
 //! | [`parent()`](VfsNodeOps::parent) | Get the parent directory | directory |
 //! | [`lookup()`](VfsNodeOps::lookup) | Lookup the node with the given path | directory |
 //! | [`create()`](VfsNodeOps::create) | Create a new node with the given path | directory |
 //! | [`remove()`](VfsNodeOps::remove) | Remove the node with the given path | directory |
+//! | [`rename()`](VfsNodeOps::rename) | Rename or move a node | directory |
+//! | [`link()`](VfsNodeOps::link) | Create a hard link to a node | directory |
+//! | [`symlink()`](VfsNodeOps::symlink) | Create a symbolic link | directory |
 //! | [`read_dir()`](VfsNodeOps::read_dir) | Read directory entries | directory |
 //!
 //! [inodes]: https://en.wikipedia.org/wiki/Inode
@@ -40,13 +45,16 @@ extern crate alloc;
 
 mod macros;
 mod structs;
+mod time;
 
 pub mod path;
 
 use alloc::sync::Arc;
 use axerrno::{ax_err, AxError, AxResult};
+use core::time::Duration;
 
 pub use self::structs::{FileSystemInfo, VfsDirEntry, VfsNodeAttr, VfsNodePerm, VfsNodeType};
+pub use self::time::{current_time, set_time_source, VfsNodeTimes};
 
 /// A wrapper of [`Arc<dyn VfsNodeOps>`].
 pub type VfsNodeRef = Arc<dyn VfsNodeOps>;
@@ -100,6 +108,21 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Get the inode number of the node, which identifies the file in the
+    /// filesystem, even if the file is looked up multiple times.
+    ///
+    /// Return `None` if the filesystem always returns the same node for the
+    /// same file, then the node itself identifies the file.
+    fn ino(&self) -> Option<u64> {
+        None
+    }
+
+    /// Set the access and modification time of the node, leaving those given
+    /// `None` unchanged.
+    fn set_times(&self, _atime: Option<Duration>, _mtime: Option<Duration>) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
     // file operations:
 
     /// Read data from the file at the given offset.
@@ -122,6 +145,16 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(InvalidInput)
     }
 
+    // symbolic link operations:
+
+    /// Read the target path of the symbolic link into `buf`.
+    ///
+    /// Return the number of bytes read, the target is truncated if `buf` is
+    /// too small. The length of the target is the size of the node.
+    fn readlink(&self, _buf: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(InvalidInput)
+    }
+
     // directory operations:
 
     /// Get the parent directory of this directory.
@@ -150,6 +183,29 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Rename or move the node at `src_path` to `dst_path`, both relative to
+    /// this directory and in the same filesystem.
+    ///
+    /// If `dst_path` already exists, it is replaced atomically. In this case,
+    /// a directory can only replace an empty directory and a non-directory
+    /// can only replace a non-directory.
+    fn rename(&self, _src_path: &str, _dst_path: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a hard link at `dst_path` to the node at `src_path`, both
+    /// relative to this directory and in the same filesystem.
+    fn link(&self, _src_path: &str, _dst_path: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a symbolic link at `path` in the directory, pointing to `target`.
+    ///
+    /// The `target` is stored as is and not checked for existence.
+    fn symlink(&self, _target: &str, _path: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
     /// Read directory entries into `dirents`, starting from `start_idx`.
     fn read_dir(&self, _start_idx: usize, _dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
         ax_err!(Unsupported)
@@ -50,6 +50,18 @@ macro_rules! impl_vfs_non_dir_default {
             $crate::__priv::ax_err!(NotADirectory)
         }
 
+        fn rename(&self, _src_path: &str, _dst_path: &str) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
+        fn link(&self, _src_path: &str, _dst_path: &str) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
+        fn symlink(&self, _target: &str, _path: &str) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
         fn read_dir(
             &self,
             _start_idx: usize,
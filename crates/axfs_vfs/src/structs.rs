@@ -1,3 +1,7 @@
+use core::time::Duration;
+
+use crate::VfsNodeTimes;
+
 /// Filesystem attributes.
 ///
 /// Currently not used.
@@ -16,6 +20,8 @@ pub struct VfsNodeAttr {
     size: u64,
     /// Number of 512B blocks allocated.
     blocks: u64,
+    /// Access, modification and status change time.
+    times: VfsNodeTimes,
 }
 
 bitflags::bitflags! {
@@ -87,6 +93,14 @@ impl VfsNodePerm {
         Self::from_bits_truncate(0o755)
     }
 
+    /// Returns the default permission for a symbolic link.
+    ///
+    /// The default permission is `0o777` (everyone can read, write and execute),
+    /// as the permission of the target is used when following the link.
+    pub const fn default_symlink() -> Self {
+        Self::from_bits_truncate(0o777)
+    }
+
     /// Returns a 9-bytes string representation of the permission.
     ///
     /// For example, `0o755` is represented as `rwxr-xr-x`.
@@ -149,6 +163,11 @@ impl VfsNodeType {
         matches!(self, Self::Dir)
     }
 
+    /// Whether the node is a symbolic link.
+    pub const fn is_symlink(self) -> bool {
+        matches!(self, Self::SymLink)
+    }
+
     /// Returns a character representation of the node type.
     ///
     /// For example, `d` for directory, `-` for regular file, etc.
@@ -174,6 +193,7 @@ impl VfsNodeAttr {
             ty,
             size,
             blocks,
+            times: VfsNodeTimes::ZERO,
         }
     }
 
@@ -184,6 +204,7 @@ impl VfsNodeAttr {
             ty: VfsNodeType::File,
             size,
             blocks,
+            times: VfsNodeTimes::ZERO,
         }
     }
 
@@ -195,9 +216,28 @@ impl VfsNodeAttr {
             ty: VfsNodeType::Dir,
             size,
             blocks,
+            times: VfsNodeTimes::ZERO,
+        }
+    }
+
+    /// Creates a new `VfsNodeAttr` for a symbolic link, with the default
+    /// symbolic link permission. The size is the length of the target path.
+    pub const fn new_symlink(size: u64, blocks: u64) -> Self {
+        Self {
+            mode: VfsNodePerm::default_symlink(),
+            ty: VfsNodeType::SymLink,
+            size,
+            blocks,
+            times: VfsNodeTimes::ZERO,
         }
     }
 
+    /// Returns a copy of `self` with the timestamps set to `times`.
+    pub const fn with_times(mut self, times: VfsNodeTimes) -> Self {
+        self.times = times;
+        self
+    }
+
     /// Returns the size of the node.
     pub const fn size(&self) -> u64 {
         self.size
@@ -232,6 +272,32 @@ impl VfsNodeAttr {
     pub const fn is_dir(&self) -> bool {
         self.ty.is_dir()
     }
+
+    /// Whether the node is a symbolic link.
+    pub const fn is_symlink(&self) -> bool {
+        self.ty.is_symlink()
+    }
+
+    /// Returns the timestamps of the node.
+    pub const fn times(&self) -> VfsNodeTimes {
+        self.times
+    }
+
+    /// Returns the time of last access, since the UNIX epoch.
+    pub const fn atime(&self) -> Duration {
+        self.times.atime
+    }
+
+    /// Returns the time of last modification, since the UNIX epoch.
+    pub const fn mtime(&self) -> Duration {
+        self.times.mtime
+    }
+
+    /// Returns the time of last status change (or creation), since the UNIX
+    /// epoch.
+    pub const fn ctime(&self) -> Duration {
+        self.times.ctime
+    }
 }
 
 impl VfsDirEntry {
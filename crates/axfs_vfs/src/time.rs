@@ -0,0 +1,83 @@
+use core::sync::atomic::{AtomicPtr, Ordering};
+use core::time::Duration;
+
+static TIME_SOURCE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Sets the function used by filesystems to get the current wall time (since
+/// the UNIX epoch), to timestamp the nodes.
+pub fn set_time_source(f: fn() -> Duration) {
+    TIME_SOURCE.store(f as *mut (), Ordering::Release);
+}
+
+/// Returns the current wall time since the UNIX epoch.
+///
+/// Returns zero (the UNIX epoch) if no time source is set by
+/// [`set_time_source`].
+pub fn current_time() -> Duration {
+    let ptr = TIME_SOURCE.load(Ordering::Acquire);
+    if ptr.is_null() {
+        Duration::ZERO
+    } else {
+        // SAFETY: only function pointers of this type are stored.
+        let f: fn() -> Duration = unsafe { core::mem::transmute(ptr) };
+        f()
+    }
+}
+
+/// Node (file/directory) timestamps, all in durations since the UNIX epoch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VfsNodeTimes {
+    /// Time of last access.
+    pub atime: Duration,
+    /// Time of last modification.
+    pub mtime: Duration,
+    /// Time of last status change, or the creation time for filesystems that
+    /// have no status change time (e.g., FAT).
+    pub ctime: Duration,
+}
+
+impl VfsNodeTimes {
+    /// All timestamps are set to the UNIX epoch.
+    pub const ZERO: Self = Self::new(Duration::ZERO, Duration::ZERO, Duration::ZERO);
+
+    /// Creates a new `VfsNodeTimes` with the given access, modification and
+    /// status change time.
+    pub const fn new(atime: Duration, mtime: Duration, ctime: Duration) -> Self {
+        Self {
+            atime,
+            mtime,
+            ctime,
+        }
+    }
+
+    /// Creates a new `VfsNodeTimes` with all timestamps set to the
+    /// [current time](current_time).
+    pub fn now() -> Self {
+        let now = current_time();
+        Self::new(now, now, now)
+    }
+
+    /// Updates the access time to the current time.
+    pub fn touch_accessed(&mut self) {
+        self.atime = current_time();
+    }
+
+    /// Updates the modification and status change time to the current time.
+    pub fn touch_modified(&mut self) {
+        let now = current_time();
+        self.mtime = now;
+        self.ctime = now;
+    }
+
+    /// Sets the access and modification time, leaving those given `None`
+    /// unchanged. The status change time is updated to the current time.
+    pub fn set(&mut self, atime: Option<Duration>, mtime: Option<Duration>) {
+        if let Some(atime) = atime {
+            self.atime = atime;
+        }
+        if let Some(mtime) = mtime {
+            self.mtime = mtime;
+        }
+        self.ctime = current_time();
+    }
+}
@@ -0,0 +1,209 @@
+//! Driver for the CMOS real-time clock (MC146818) on x86 PCs.
+//!
+//! See <https://wiki.osdev.org/CMOS> for more information.
+
+use core::time::Duration;
+
+use spinlock::SpinNoIrq;
+use x86_64::instructions::port::Port;
+
+use crate::{civil_from_days, days_from_civil, SECS_PER_DAY};
+use crate::{BaseDriverOps, DevError, DevResult, DeviceType, RtcDriverOps};
+
+const CMOS_ADDR_PORT: u16 = 0x70;
+const CMOS_DATA_PORT: u16 = 0x71;
+
+const CMOS_SECOND: u8 = 0x00;
+const CMOS_MINUTE: u8 = 0x02;
+const CMOS_HOUR: u8 = 0x04;
+const CMOS_DAY: u8 = 0x07;
+const CMOS_MONTH: u8 = 0x08;
+const CMOS_YEAR: u8 = 0x09;
+const CMOS_CENTURY: u8 = 0x32;
+const CMOS_STATUS_A: u8 = 0x0A;
+const CMOS_STATUS_B: u8 = 0x0B;
+
+/// Status register A: an update of the time registers is in progress.
+const STATUS_A_UPDATE_IN_PROGRESS: u8 = 1 << 7;
+/// Status register B: inhibit updates while setting the time.
+const STATUS_B_SET: u8 = 1 << 7;
+/// Status register B: 24-hour mode.
+const STATUS_B_24_HOUR: u8 = 1 << 1;
+/// Status register B: binary mode (otherwise BCD).
+const STATUS_B_BINARY: u8 = 1 << 2;
+
+/// Serializes accesses to the index/data port pair, which is shared by all
+/// [`CmosRtc`] instances.
+static CMOS_LOCK: SpinNoIrq<()> = SpinNoIrq::new(());
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct RtcTime {
+    second: u8,
+    minute: u8,
+    hour: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+    century: u8,
+}
+
+/// The CMOS real-time clock.
+///
+/// The time is kept in UTC, with the precision of seconds. Register accesses
+/// are serialized by a global lock with IRQs disabled, as selecting a register
+/// and accessing it are two separate port I/O operations.
+#[derive(Default)]
+pub struct CmosRtc;
+
+impl CmosRtc {
+    /// Constructs a new CMOS RTC instance.
+    pub const fn new() -> Self {
+        Self
+    }
+
+    fn read_reg(&self, reg: u8) -> u8 {
+        unsafe {
+            // keep NMI enabled (bit 7 cleared)
+            Port::<u8>::new(CMOS_ADDR_PORT).write(reg & 0x7f);
+            Port::<u8>::new(CMOS_DATA_PORT).read()
+        }
+    }
+
+    fn write_reg(&self, reg: u8, value: u8) {
+        unsafe {
+            Port::<u8>::new(CMOS_ADDR_PORT).write(reg & 0x7f);
+            Port::<u8>::new(CMOS_DATA_PORT).write(value);
+        }
+    }
+
+    fn read_raw(&self) -> RtcTime {
+        while self.read_reg(CMOS_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0 {
+            core::hint::spin_loop();
+        }
+        RtcTime {
+            second: self.read_reg(CMOS_SECOND),
+            minute: self.read_reg(CMOS_MINUTE),
+            hour: self.read_reg(CMOS_HOUR),
+            day: self.read_reg(CMOS_DAY),
+            month: self.read_reg(CMOS_MONTH),
+            year: self.read_reg(CMOS_YEAR),
+            century: self.read_reg(CMOS_CENTURY),
+        }
+    }
+}
+
+impl BaseDriverOps for CmosRtc {
+    fn device_name(&self) -> &str {
+        "cmos-rtc"
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Rtc
+    }
+}
+
+impl RtcDriverOps for CmosRtc {
+    fn get_time(&self) -> DevResult<Duration> {
+        let _guard = CMOS_LOCK.lock();
+        // read until two consecutive reads agree, to avoid getting inconsistent
+        // values during an update
+        let mut t = self.read_raw();
+        loop {
+            let next = self.read_raw();
+            if next == t {
+                break;
+            }
+            t = next;
+        }
+
+        let status_b = self.read_reg(CMOS_STATUS_B);
+        let pm = t.hour & 0x80 != 0;
+        t.hour &= 0x7f;
+        if status_b & STATUS_B_BINARY == 0 {
+            t.second = bcd_to_binary(t.second);
+            t.minute = bcd_to_binary(t.minute);
+            t.hour = bcd_to_binary(t.hour);
+            t.day = bcd_to_binary(t.day);
+            t.month = bcd_to_binary(t.month);
+            t.year = bcd_to_binary(t.year);
+            t.century = bcd_to_binary(t.century);
+        }
+        if status_b & STATUS_B_24_HOUR == 0 {
+            // 12 AM is 0 o'clock, and 12 PM is 12 o'clock
+            t.hour = (t.hour % 12) + if pm { 12 } else { 0 };
+        }
+        if !(1..=12).contains(&t.month) || !(1..=31).contains(&t.day) {
+            return Err(DevError::BadState);
+        }
+
+        let century = if t.century != 0 { t.century as u64 } else { 20 };
+        let year = century * 100 + t.year as u64;
+        let days = days_from_civil(year, t.month as u64, t.day as u64).ok_or(DevError::BadState)?;
+        let secs =
+            days * SECS_PER_DAY + t.hour as u64 * 3600 + t.minute as u64 * 60 + t.second as u64;
+        Ok(Duration::from_secs(secs))
+    }
+
+    fn set_time(&mut self, time: Duration) -> DevResult {
+        let secs = time.as_secs();
+        let (year, month, day) = civil_from_days(secs / SECS_PER_DAY);
+        if year >= 10000 {
+            return Err(DevError::InvalidParam);
+        }
+        let secs_of_day = secs % SECS_PER_DAY;
+        let mut t = RtcTime {
+            second: (secs_of_day % 60) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            hour: (secs_of_day / 3600) as u8,
+            day: day as u8,
+            month: month as u8,
+            year: (year % 100) as u8,
+            century: (year / 100) as u8,
+        };
+
+        let _guard = CMOS_LOCK.lock();
+        let status_b = self.read_reg(CMOS_STATUS_B);
+        if status_b & STATUS_B_24_HOUR == 0 {
+            let pm = t.hour >= 12;
+            t.hour %= 12;
+            if t.hour == 0 {
+                t.hour = 12;
+            }
+            if status_b & STATUS_B_BINARY == 0 {
+                t.hour = binary_to_bcd(t.hour);
+            }
+            if pm {
+                t.hour |= 0x80;
+            }
+        } else if status_b & STATUS_B_BINARY == 0 {
+            t.hour = binary_to_bcd(t.hour);
+        }
+        if status_b & STATUS_B_BINARY == 0 {
+            t.second = binary_to_bcd(t.second);
+            t.minute = binary_to_bcd(t.minute);
+            t.day = binary_to_bcd(t.day);
+            t.month = binary_to_bcd(t.month);
+            t.year = binary_to_bcd(t.year);
+            t.century = binary_to_bcd(t.century);
+        }
+
+        self.write_reg(CMOS_STATUS_B, status_b | STATUS_B_SET);
+        self.write_reg(CMOS_SECOND, t.second);
+        self.write_reg(CMOS_MINUTE, t.minute);
+        self.write_reg(CMOS_HOUR, t.hour);
+        self.write_reg(CMOS_DAY, t.day);
+        self.write_reg(CMOS_MONTH, t.month);
+        self.write_reg(CMOS_YEAR, t.year);
+        self.write_reg(CMOS_CENTURY, t.century);
+        self.write_reg(CMOS_STATUS_B, status_b & !STATUS_B_SET);
+        Ok(())
+    }
+}
+
+const fn bcd_to_binary(v: u8) -> u8 {
+    (v & 0x0f) + (v >> 4) * 10
+}
+
+const fn binary_to_bcd(v: u8) -> u8 {
+    ((v / 10) << 4) | (v % 10)
+}
@@ -0,0 +1,88 @@
+//! Driver for the Goldfish real-time clock, as emulated by QEMU on the RISC-V
+//! `virt` machine.
+//!
+//! The documentation: <https://android.googlesource.com/platform/external/qemu/+/master/docs/GOLDFISH-VIRTUAL-HARDWARE.TXT>
+
+use core::ptr::NonNull;
+use core::time::Duration;
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_structs,
+    registers::{ReadWrite, WriteOnly},
+};
+
+use crate::{BaseDriverOps, DevError, DevResult, DeviceType, RtcDriverOps};
+
+register_structs! {
+    /// Goldfish RTC registers.
+    GoldfishRtcRegs {
+        /// Low 32 bits of the time in nanoseconds.
+        (0x00 => time_low: ReadWrite<u32>),
+        /// High 32 bits of the time in nanoseconds.
+        (0x04 => time_high: ReadWrite<u32>),
+        /// Low 32 bits of the alarm time.
+        (0x08 => alarm_low: ReadWrite<u32>),
+        /// High 32 bits of the alarm time.
+        (0x0c => alarm_high: ReadWrite<u32>),
+        /// Interrupt enable.
+        (0x10 => irq_enabled: ReadWrite<u32>),
+        /// Clear the alarm.
+        (0x14 => clear_alarm: WriteOnly<u32>),
+        /// Alarm status.
+        (0x18 => alarm_status: ReadWrite<u32>),
+        /// Clear the interrupt.
+        (0x1c => clear_interrupt: WriteOnly<u32>),
+        (0x20 => @END),
+    }
+}
+
+/// The Goldfish real-time clock.
+///
+/// It keeps a 64-bit time in nanoseconds since the UNIX epoch.
+pub struct GoldfishRtc {
+    base: NonNull<GoldfishRtcRegs>,
+}
+
+unsafe impl Send for GoldfishRtc {}
+unsafe impl Sync for GoldfishRtc {}
+
+impl GoldfishRtc {
+    /// Constructs a new Goldfish RTC instance from the base address.
+    pub const fn new(base: *mut u8) -> Self {
+        Self {
+            base: NonNull::new(base).unwrap().cast(),
+        }
+    }
+
+    const fn regs(&self) -> &GoldfishRtcRegs {
+        unsafe { self.base.as_ref() }
+    }
+}
+
+impl BaseDriverOps for GoldfishRtc {
+    fn device_name(&self) -> &str {
+        "goldfish-rtc"
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Rtc
+    }
+}
+
+impl RtcDriverOps for GoldfishRtc {
+    fn get_time(&self) -> DevResult<Duration> {
+        // reading the low 32 bits latches the high 32 bits
+        let low = self.regs().time_low.get() as u64;
+        let high = self.regs().time_high.get() as u64;
+        Ok(Duration::from_nanos((high << 32) | low))
+    }
+
+    fn set_time(&mut self, time: Duration) -> DevResult {
+        let nanos = u64::try_from(time.as_nanos()).map_err(|_| DevError::InvalidParam)?;
+        // writing the low 32 bits commits the time with the high 32 bits
+        self.regs().time_high.set((nanos >> 32) as u32);
+        self.regs().time_low.set(nanos as u32);
+        Ok(())
+    }
+}
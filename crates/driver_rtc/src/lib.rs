@@ -0,0 +1,71 @@
+//! Common traits and types for real-time clock (RTC) device drivers.
+//!
+//! Time is represented as the [`Duration`] since the UNIX epoch
+//! (1970-01-01 00:00:00 UTC).
+
+#![no_std]
+#![feature(doc_auto_cfg)]
+#![feature(const_ptr_as_ref)]
+#![feature(const_option)]
+#![feature(const_nonnull_new)]
+
+#[cfg(all(feature = "cmos", target_arch = "x86_64"))]
+pub mod cmos;
+#[cfg(feature = "goldfish")]
+pub mod goldfish;
+#[cfg(feature = "pl031")]
+pub mod pl031;
+
+#[cfg(test)]
+mod tests;
+
+#[doc(no_inline)]
+pub use core::time::Duration;
+#[doc(no_inline)]
+pub use driver_common::{BaseDriverOps, DevError, DevResult, DeviceType};
+
+/// Operations that require a real-time clock driver to implement.
+pub trait RtcDriverOps: BaseDriverOps {
+    /// Returns the current time since the UNIX epoch.
+    fn get_time(&self) -> DevResult<Duration>;
+
+    /// Sets the current time since the UNIX epoch.
+    ///
+    /// The precision of the time kept by the device may be lower than
+    /// nanoseconds (e.g., seconds).
+    fn set_time(&mut self, time: Duration) -> DevResult;
+}
+
+/// Number of seconds in a day.
+pub const SECS_PER_DAY: u64 = 86400;
+
+/// Returns the number of days since 1970-01-01 of the given date, or `None`
+/// if the date is before it.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+pub const fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400; // [0, 399]
+    let mp = (month + 9) % 12; // March is 0
+    let doy = (153 * mp + 2) / 5 + day - 1; // [0, 365]
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy; // [0, 146096]
+    (era * 146097 + doe).checked_sub(719468)
+}
+
+/// Returns the `(year, month, day)` of the given number of days since
+/// 1970-01-01.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+pub const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097; // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let day = doy - (153 * mp + 2) / 5 + 1; // [1, 31]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 }; // [1, 12]
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
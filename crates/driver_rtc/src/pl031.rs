@@ -0,0 +1,91 @@
+//! Driver for the ARM PrimeCell Real Time Clock (PL031).
+//!
+//! The official documentation: <https://developer.arm.com/documentation/ddi0224/latest>
+
+use core::ptr::NonNull;
+use core::time::Duration;
+
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_structs,
+    registers::{ReadOnly, ReadWrite, WriteOnly},
+};
+
+use crate::{BaseDriverOps, DevError, DevResult, DeviceType, RtcDriverOps};
+
+register_structs! {
+    /// PL031 registers.
+    Pl031Regs {
+        /// Data Register.
+        (0x00 => dr: ReadOnly<u32>),
+        /// Match Register.
+        (0x04 => mr: ReadWrite<u32>),
+        /// Load Register.
+        (0x08 => lr: ReadWrite<u32>),
+        /// Control Register.
+        (0x0c => cr: ReadWrite<u32>),
+        /// Interrupt Mask Set or Clear register.
+        (0x10 => imsc: ReadWrite<u32>),
+        /// Raw Interrupt Status.
+        (0x14 => ris: ReadOnly<u32>),
+        /// Masked Interrupt Status.
+        (0x18 => mis: ReadOnly<u32>),
+        /// Interrupt Clear Register.
+        (0x1c => icr: WriteOnly<u32>),
+        (0x20 => @END),
+    }
+}
+
+/// The PL031 real-time clock.
+///
+/// It is a 32-bit counter incremented every second.
+pub struct Pl031Rtc {
+    base: NonNull<Pl031Regs>,
+}
+
+unsafe impl Send for Pl031Rtc {}
+unsafe impl Sync for Pl031Rtc {}
+
+impl Pl031Rtc {
+    /// Constructs a new PL031 RTC instance from the base address.
+    pub const fn new(base: *mut u8) -> Self {
+        Self {
+            base: NonNull::new(base).unwrap().cast(),
+        }
+    }
+
+    const fn regs(&self) -> &Pl031Regs {
+        unsafe { self.base.as_ref() }
+    }
+
+    /// Starts the counter if it is not running, and masks the alarm interrupt.
+    pub fn init(&mut self) {
+        self.regs().imsc.set(0);
+        self.regs().icr.set(1);
+        if self.regs().cr.get() & 1 == 0 {
+            self.regs().cr.set(1);
+        }
+    }
+}
+
+impl BaseDriverOps for Pl031Rtc {
+    fn device_name(&self) -> &str {
+        "pl031-rtc"
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Rtc
+    }
+}
+
+impl RtcDriverOps for Pl031Rtc {
+    fn get_time(&self) -> DevResult<Duration> {
+        Ok(Duration::from_secs(self.regs().dr.get() as u64))
+    }
+
+    fn set_time(&mut self, time: Duration) -> DevResult {
+        let secs = u32::try_from(time.as_secs()).map_err(|_| DevError::InvalidParam)?;
+        self.regs().lr.set(secs);
+        Ok(())
+    }
+}
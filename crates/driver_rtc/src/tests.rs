@@ -0,0 +1,22 @@
+use crate::{civil_from_days, days_from_civil};
+
+#[test]
+fn test_days_from_civil() {
+    assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+    assert_eq!(days_from_civil(1969, 12, 31), None);
+    assert_eq!(days_from_civil(2000, 3, 1), Some(11017));
+    assert_eq!(days_from_civil(2023, 6, 15), Some(19523));
+    assert_eq!(days_from_civil(2100, 12, 31), Some(47846));
+}
+
+#[test]
+fn test_civil_from_days() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    assert_eq!(civil_from_days(19523), (2023, 6, 15));
+    assert_eq!(civil_from_days(47846), (2100, 12, 31));
+    for days in 0..200_000 {
+        let (y, m, d) = civil_from_days(days);
+        assert_eq!(days_from_civil(y, m, d), Some(days));
+    }
+}
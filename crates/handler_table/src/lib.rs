@@ -10,9 +10,11 @@ pub type Handler = fn();
 
 /// A lock-free table of event handlers.
 ///
-/// It internally uses an array of `AtomicUsize` to store the handlers.
+/// It internally uses an array of `AtomicUsize` to store the handlers, and
+/// another one to count the handled events.
 pub struct HandlerTable<const N: usize> {
     handlers: [AtomicUsize; N],
+    counts: [AtomicUsize; N],
 }
 
 impl<const N: usize> HandlerTable<N> {
@@ -22,6 +24,7 @@ impl<const N: usize> HandlerTable<N> {
         const EMPTY: AtomicUsize = AtomicUsize::new(0);
         Self {
             handlers: [EMPTY; N],
+            counts: [EMPTY; N],
         }
     }
 
@@ -41,9 +44,21 @@ impl<const N: usize> HandlerTable<N> {
         if handler != 0 {
             let handler: Handler = unsafe { core::mem::transmute(handler) };
             handler();
+            self.counts[idx].fetch_add(1, Ordering::Relaxed);
             true
         } else {
             false
         }
     }
+
+    /// Whether a handler is registered for the given index.
+    pub fn is_registered(&self, idx: usize) -> bool {
+        self.handlers[idx].load(Ordering::Acquire) != 0
+    }
+
+    /// Returns the number of events with the given index that have been
+    /// handled.
+    pub fn handled_count(&self, idx: usize) -> usize {
+        self.counts[idx].load(Ordering::Relaxed)
+    }
 }
@@ -179,7 +179,9 @@ impl<M: PagingMetaData, PTE: GenericPTE, IF: PagingIf> PageTable64<M, PTE, IF> {
     /// Unmap a contiguous virtual memory region.
     ///
     /// The region must be mapped before using [`PageTable64::map_region`], or
-    /// unexpected behaviors may occur.
+    /// unexpected behaviors may occur. The address and `size` must be aligned
+    /// to 4K. Huge pages that are partially covered by the region are split
+    /// into smaller pages first, so that only the region is unmapped.
     pub fn unmap_region(&mut self, vaddr: VirtAddr, size: usize) -> PagingResult {
         trace!(
             "unmap_region({:#x}) [{:#x}, {:#x})",
@@ -187,20 +189,106 @@ impl<M: PagingMetaData, PTE: GenericPTE, IF: PagingIf> PageTable64<M, PTE, IF> {
             vaddr,
             vaddr + size,
         );
-        let mut vaddr = vaddr;
-        let mut size = size;
-        while size > 0 {
-            let (_, page_size) = self
-                .unmap(vaddr)
-                .inspect_err(|e| error!("failed to unmap page: {:#x?}, {:?}", vaddr, e))?;
-            assert!(vaddr.is_aligned(page_size));
-            assert!(page_size as usize <= size);
-            vaddr += page_size as usize;
-            size -= page_size as usize;
+        self.for_each_page_in(vaddr, size, |entry, _| entry.clear())
+            .inspect_err(|e| error!("failed to unmap region: {:#x?}, {:?}", vaddr, e))
+    }
+
+    /// Changes the mapping flags of a contiguous virtual memory region to
+    /// `flags`.
+    ///
+    /// The region must be mapped, and the address and `size` must be aligned
+    /// to 4K. Like [`unmap_region`](Self::unmap_region), huge pages that are
+    /// partially covered by the region are split first.
+    ///
+    /// The accessed and dirty bits of the pages are kept. The TLB entries of
+    /// the region are not flushed.
+    pub fn protect_region(
+        &mut self,
+        vaddr: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+    ) -> PagingResult {
+        trace!(
+            "protect_region({:#x}) [{:#x}, {:#x}) {:?}",
+            self.root_paddr(),
+            vaddr,
+            vaddr + size,
+            flags,
+        );
+        self.for_each_page_in(vaddr, size, |entry, page_size| {
+            entry.set_flags(flags, page_size.is_huge())
+        })
+        .inspect_err(|e| error!("failed to protect region: {:#x?}, {:?}", vaddr, e))
+    }
+
+    /// Copies the root-level entries covering `[start, start + size)` from
+    /// another page table, so that both tables share the lower-level tables
+    /// (and hence the mappings) in this range.
+    ///
+    /// The shared lower-level tables are still owned by `other`, and will not
+    /// be deallocated when this page table is dropped. It is usually used to
+    /// share the kernel part of the address space with user page tables.
+    pub fn copy_from(&mut self, other: &Self, start: VirtAddr, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let src_table = self.table_of(other.root_paddr());
+        let dst_table = self.table_of_mut(self.root_paddr());
+        let range = Self::root_index(start)..=Self::root_index(start + (size - 1));
+        dst_table[range.clone()].copy_from_slice(&src_table[range]);
+    }
+
+    /// Allocates the next-level tables for all unused root-level entries
+    /// covering `[start, start + size)`.
+    ///
+    /// After that, new mappings in this range never change the root-level
+    /// table, so they are also visible to other page tables that have copied
+    /// these entries by [`copy_from`](Self::copy_from).
+    pub fn prealloc_root_entries(&mut self, start: VirtAddr, size: usize) -> PagingResult {
+        if size == 0 {
+            return Ok(());
+        }
+        let root = self.table_of_mut(self.root_paddr());
+        for entry in &mut root[Self::root_index(start)..=Self::root_index(start + (size - 1))] {
+            if entry.is_unused() {
+                self.next_table_mut_or_create(entry)?;
+            }
         }
         Ok(())
     }
 
+    /// Creates a deep copy of this page table, with all intermediate tables
+    /// duplicated, so that later changes of one table do not affect the other.
+    ///
+    /// Before a leaf entry (i.e., a page or huge page) is copied, `func` is
+    /// called on it, so that it can be modified in both tables. For example,
+    /// clearing the writable flag to share the pages copy-on-write. The TLB
+    /// entries of this table are not flushed.
+    ///
+    /// The lower-level tables shared from other page tables by
+    /// [`copy_from`](Self::copy_from) are not duplicated, but shared with the
+    /// new table too.
+    pub fn clone_with<F>(&mut self, mut func: F) -> PagingResult<Self>
+    where
+        F: FnMut(&mut PTE),
+    {
+        let mut new = Self::try_new()?;
+        let src_root = self.table_of_mut(self.root_paddr());
+        let dst_root = self.table_of_mut(new.root_paddr());
+        for (src, dst) in src_root.iter_mut().zip(dst_root.iter_mut()) {
+            if src.is_unused() {
+                continue;
+            }
+            if src.is_present() && !src.is_huge() && !self.intrm_tables.contains(&src.paddr()) {
+                // not owned by this table
+                *dst = *src;
+            } else {
+                *dst = self.clone_entry(src, 0, &mut new, &mut func)?;
+            }
+        }
+        Ok(new)
+    }
+
     /// Walk the page table recursively.
     ///
     /// When reaching the leaf page table, call `func` on the current page table
@@ -237,6 +325,16 @@ impl<M: PagingMetaData, PTE: GenericPTE, IF: PagingIf> PageTable64<M, PTE, IF> {
         }
     }
 
+    fn root_index(vaddr: VirtAddr) -> usize {
+        if M::LEVELS == 3 {
+            p3_index(vaddr)
+        } else if M::LEVELS == 4 {
+            p4_index(vaddr)
+        } else {
+            unreachable!()
+        }
+    }
+
     fn table_of<'a>(&self, paddr: PhysAddr) -> &'a [PTE] {
         let ptr = IF::phys_to_virt(paddr).as_ptr() as _;
         unsafe { core::slice::from_raw_parts(ptr, ENTRY_COUNT) }
@@ -294,6 +392,82 @@ impl<M: PagingMetaData, PTE: GenericPTE, IF: PagingIf> PageTable64<M, PTE, IF> {
         Ok((p1e, PageSize::Size4K))
     }
 
+    /// Calls `func` on the leaf entries that map `[vaddr, vaddr + size)`, with
+    /// the page size of each entry. Huge pages that are not fully covered are
+    /// split beforehand.
+    fn for_each_page_in<F>(&mut self, vaddr: VirtAddr, size: usize, mut func: F) -> PagingResult
+    where
+        F: FnMut(&mut PTE, PageSize),
+    {
+        if !vaddr.is_aligned(PageSize::Size4K)
+            || !memory_addr::is_aligned(size, PageSize::Size4K.into())
+        {
+            return Err(PagingError::NotAligned);
+        }
+        let mut vaddr = vaddr;
+        let mut size = size;
+        while size > 0 {
+            let (entry, page_size) = self.get_entry_mut(vaddr)?;
+            if entry.is_unused() {
+                return Err(PagingError::NotMapped);
+            }
+            if page_size.is_huge() && (!vaddr.is_aligned(page_size) || size < page_size as usize) {
+                self.split_huge_page(vaddr, page_size)?;
+                continue;
+            }
+            func(entry, page_size);
+            vaddr += page_size as usize;
+            size -= page_size as usize;
+        }
+        Ok(())
+    }
+
+    /// Splits the huge page of size `page_size` that contains `vaddr` into the
+    /// next smaller pages with the same flags, by replacing it with a new
+    /// next-level table.
+    ///
+    /// The table is installed directly, and the caller must flush the pages it
+    /// changes afterwards, which also invalidates the TLB entries of the huge
+    /// page on x86_64 and RISC-V. With break-before-make, the entry is cleared
+    /// and flushed on all CPUs beforehand, so it fails with
+    /// [`PagingError::InUse`] if the huge page maps the code, stack or page
+    /// tables used in between.
+    fn split_huge_page(&mut self, vaddr: VirtAddr, page_size: PageSize) -> PagingResult {
+        let (sub_size, sub_huge) = match page_size {
+            PageSize::Size1G => (PageSize::Size2M, true),
+            PageSize::Size2M => (PageSize::Size4K, false),
+            PageSize::Size4K => unreachable!(),
+        };
+        let table_paddr = Self::alloc_table()?;
+        self.intrm_tables.push(table_paddr);
+        let (entry, _) = self.get_entry_mut(vaddr)?;
+        let paddr = entry.paddr();
+        let flags = entry.flags();
+        for (i, sub_entry) in self.table_of_mut(table_paddr).iter_mut().enumerate() {
+            *sub_entry = GenericPTE::new_page(paddr + i * sub_size as usize, flags, sub_huge);
+            sub_entry.set_accessed(entry.is_accessed());
+            sub_entry.set_dirty(entry.is_dirty());
+        }
+        if M::BREAK_BEFORE_MAKE {
+            let start = vaddr.align_down(page_size);
+            let range = start.as_usize()..start.as_usize() + page_size as usize;
+            let stack = 0u8;
+            let in_use = [
+                Self::split_huge_page as *const () as usize,
+                &stack as *const u8 as usize,
+                entry as *const PTE as usize,
+                IF::phys_to_virt(table_paddr).as_usize(),
+            ];
+            if in_use.iter().any(|addr| range.contains(addr)) {
+                return Err(PagingError::InUse);
+            }
+            entry.clear();
+            IF::flush_tlb(start, page_size.into());
+        }
+        *entry = GenericPTE::new_table(table_paddr);
+        Ok(())
+    }
+
     fn get_entry_mut_or_create(
         &mut self,
         vaddr: VirtAddr,
@@ -324,6 +498,35 @@ impl<M: PagingMetaData, PTE: GenericPTE, IF: PagingIf> PageTable64<M, PTE, IF> {
         Ok(p1e)
     }
 
+    /// Returns a copy of `entry` at `level` for `new`, with the next-level
+    /// tables (if any) recursively copied into newly allocated tables.
+    fn clone_entry<F>(
+        &self,
+        entry: &mut PTE,
+        level: usize,
+        new: &mut Self,
+        func: &mut F,
+    ) -> PagingResult<PTE>
+    where
+        F: FnMut(&mut PTE),
+    {
+        if level == M::LEVELS - 1 || entry.is_huge() || !entry.is_present() {
+            func(entry);
+            return Ok(*entry);
+        }
+        let src_table = self.next_table_mut(entry)?;
+        let table_paddr = Self::alloc_table()?;
+        // owned by `new` from now on, so that it will be freed on failures
+        new.intrm_tables.push(table_paddr);
+        let dst_table = self.table_of_mut(table_paddr);
+        for (src, dst) in src_table.iter_mut().zip(dst_table.iter_mut()) {
+            if !src.is_unused() {
+                *dst = self.clone_entry(src, level + 1, new, func)?;
+            }
+        }
+        Ok(GenericPTE::new_table(table_paddr))
+    }
+
     fn walk_recursive<F>(
         &self,
         table: &[PTE],
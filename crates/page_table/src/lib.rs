@@ -28,6 +28,9 @@ extern crate log;
 mod arch;
 mod bits64;
 
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests;
+
 use memory_addr::{PhysAddr, VirtAddr};
 
 pub use self::arch::*;
@@ -50,6 +53,9 @@ pub enum PagingError {
     /// The page table entry represents a huge page, but the target physical
     /// frame is 4K in size.
     MappedToHugePage,
+    /// The mapping cannot be changed as it is being used by the operation
+    /// itself (e.g., it maps the running code or stack).
+    InUse,
 }
 
 /// The specialized `Result` type for page table operations.
@@ -69,6 +75,10 @@ pub trait PagingMetaData: Sync + Send + Sized {
     /// The maximum physical address.
     const PA_MAX_ADDR: usize = (1 << Self::PA_MAX_BITS) - 1;
 
+    /// Whether a huge page must be unmapped and flushed from the TLB before it
+    /// is replaced by a next-level table (break-before-make), as on AArch64.
+    const BREAK_BEFORE_MAKE: bool = false;
+
     /// Whether a given physical address is valid.
     #[inline]
     fn paddr_is_valid(paddr: usize) -> bool {
@@ -95,6 +105,13 @@ pub trait PagingIf: Sized {
     ///
     /// Used to access the physical memory directly in page table implementation.
     fn phys_to_virt(paddr: PhysAddr) -> VirtAddr;
+    /// Flushes the TLB entries that map `[vaddr, vaddr + size)` on all CPUs,
+    /// in any page table that may contain the mapping.
+    ///
+    /// Used when splitting huge pages with break-before-make (see
+    /// [`PagingMetaData::BREAK_BEFORE_MAKE`]), other changes to the page table
+    /// are not flushed.
+    fn flush_tlb(vaddr: VirtAddr, size: usize);
 }
 
 /// The page sizes supported by the hardware page table.
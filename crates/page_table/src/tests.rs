@@ -0,0 +1,297 @@
+extern crate std;
+
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::cell::RefCell;
+use std::vec::Vec;
+
+use memory_addr::{PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use page_table_entry::x86_64::X64PTE;
+
+use crate::x86_64::X64PageTable;
+use crate::{
+    GenericPTE, MappingFlags, PageSize, PageTable64, PagingError, PagingIf, PagingMetaData,
+};
+
+const SIZE_2M: usize = PageSize::Size2M as usize;
+const SIZE_1G: usize = PageSize::Size1G as usize;
+
+std::thread_local! {
+    /// The ranges flushed by [`HeapPagingIf::flush_tlb`] in this test.
+    static FLUSHED: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Page tables are allocated from the heap, and "physical" addresses are
+/// identical to virtual ones.
+struct HeapPagingIf;
+
+impl PagingIf for HeapPagingIf {
+    fn alloc_frame() -> Option<PhysAddr> {
+        let layout = Layout::from_size_align(PAGE_SIZE_4K, PAGE_SIZE_4K).unwrap();
+        let ptr = unsafe { alloc_zeroed(layout) };
+        (!ptr.is_null()).then(|| PhysAddr::from(ptr as usize))
+    }
+
+    fn dealloc_frame(paddr: PhysAddr) {
+        let layout = Layout::from_size_align(PAGE_SIZE_4K, PAGE_SIZE_4K).unwrap();
+        unsafe { dealloc(paddr.as_usize() as *mut u8, layout) }
+    }
+
+    fn phys_to_virt(paddr: PhysAddr) -> VirtAddr {
+        VirtAddr::from(paddr.as_usize())
+    }
+
+    fn flush_tlb(vaddr: VirtAddr, size: usize) {
+        FLUSHED.with(|f| f.borrow_mut().push((vaddr.as_usize(), size)));
+    }
+}
+
+type PageTable = X64PageTable<HeapPagingIf>;
+
+/// x86_64 page tables that split huge pages with break-before-make, as on
+/// AArch64.
+struct BbmMetaData;
+
+impl const PagingMetaData for BbmMetaData {
+    const LEVELS: usize = 4;
+    const PA_MAX_BITS: usize = 52;
+    const VA_MAX_BITS: usize = 48;
+    const BREAK_BEFORE_MAKE: bool = true;
+}
+
+type BbmPageTable = PageTable64<BbmMetaData, X64PTE, HeapPagingIf>;
+
+const RW: MappingFlags = MappingFlags::READ.union(MappingFlags::WRITE);
+
+fn query_size(pt: &PageTable, vaddr: usize) -> Option<PageSize> {
+    pt.query(vaddr.into()).ok().map(|(_, _, size)| size)
+}
+
+#[test]
+fn test_map_region_huge() {
+    let mut pt = PageTable::try_new().unwrap();
+    let vaddr = 0x4000_0000 - PAGE_SIZE_4K;
+    let paddr = 0x8000_0000 - PAGE_SIZE_4K;
+    let size = PAGE_SIZE_4K + SIZE_1G + SIZE_2M;
+    pt.map_region(vaddr.into(), paddr.into(), size, RW, true)
+        .unwrap();
+
+    assert_eq!(query_size(&pt, vaddr), Some(PageSize::Size4K));
+    assert_eq!(query_size(&pt, 0x4000_0000), Some(PageSize::Size1G));
+    assert_eq!(query_size(&pt, 0x8000_0000), Some(PageSize::Size2M));
+    let (pa, flags, _) = pt.query((0x4000_0000 + 0x1234).into()).unwrap();
+    assert_eq!(pa, PhysAddr::from(0x8000_1234));
+    assert_eq!(flags, RW);
+
+    pt.unmap_region(vaddr.into(), size).unwrap();
+    assert_eq!(query_size(&pt, vaddr), None);
+    assert_eq!(query_size(&pt, 0x4000_0000), None);
+    assert_eq!(query_size(&pt, 0x8000_0000), None);
+
+    // huge pages are not used if not allowed
+    pt.map_region(0x4000_0000.into(), 0.into(), SIZE_2M, RW, false)
+        .unwrap();
+    assert_eq!(query_size(&pt, 0x4000_0000), Some(PageSize::Size4K));
+}
+
+#[test]
+fn test_unmap_region_split() {
+    let mut pt = PageTable::try_new().unwrap();
+    pt.map_region(0x4000_0000.into(), 0.into(), SIZE_1G, RW, true)
+        .unwrap();
+
+    // unmap one 4K page in the middle of the 1G page
+    let hole = 0x4000_0000 + SIZE_2M + PAGE_SIZE_4K;
+    pt.unmap_region(hole.into(), PAGE_SIZE_4K).unwrap();
+    // the huge pages are replaced in place, the caller flushes the hole
+    assert!(FLUSHED.take().is_empty());
+    assert_eq!(query_size(&pt, hole), None);
+    assert_eq!(query_size(&pt, hole - PAGE_SIZE_4K), Some(PageSize::Size4K));
+    assert_eq!(query_size(&pt, hole + PAGE_SIZE_4K), Some(PageSize::Size4K));
+    assert_eq!(query_size(&pt, 0x4000_0000), Some(PageSize::Size2M));
+    assert_eq!(
+        query_size(&pt, 0x4000_0000 + 2 * SIZE_2M),
+        Some(PageSize::Size2M)
+    );
+    let (pa, flags, _) = pt.query((hole + PAGE_SIZE_4K).into()).unwrap();
+    assert_eq!(pa, PhysAddr::from(SIZE_2M + 2 * PAGE_SIZE_4K));
+    assert_eq!(flags, RW);
+
+    // the hole cannot be unmapped again
+    assert!(matches!(
+        pt.unmap_region(hole.into(), PAGE_SIZE_4K),
+        Err(PagingError::NotMapped)
+    ));
+    assert!(matches!(
+        pt.unmap_region((hole + 1).into(), PAGE_SIZE_4K),
+        Err(PagingError::NotAligned)
+    ));
+
+    // unmap the rest
+    pt.unmap_region(0x4000_0000.into(), hole - 0x4000_0000)
+        .unwrap();
+    pt.unmap_region(
+        (hole + PAGE_SIZE_4K).into(),
+        0x8000_0000 - hole - PAGE_SIZE_4K,
+    )
+    .unwrap();
+    assert_eq!(query_size(&pt, 0x4000_0000), None);
+    assert_eq!(query_size(&pt, 0x8000_0000 - PAGE_SIZE_4K), None);
+}
+
+#[test]
+fn test_split_huge_page_in_use() {
+    // identity-map the 1G page that contains the current stack
+    let stack = 0u8;
+    let page = &stack as *const u8 as usize & !(PAGE_SIZE_4K - 1);
+    let base = page & !(SIZE_1G - 1);
+
+    // the huge page is replaced by the table without being unmapped
+    let mut pt = PageTable::try_new().unwrap();
+    pt.map_region(base.into(), base.into(), SIZE_1G, RW, true)
+        .unwrap();
+    pt.protect_region(page.into(), PAGE_SIZE_4K, MappingFlags::READ)
+        .unwrap();
+    assert!(FLUSHED.take().is_empty());
+    let (pa, flags, size) = pt.query(page.into()).unwrap();
+    assert_eq!(pa, PhysAddr::from(page));
+    assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size4K));
+
+    // with break-before-make, it cannot be split as it maps the stack
+    let mut pt = BbmPageTable::try_new().unwrap();
+    pt.map_region(base.into(), base.into(), SIZE_1G, RW, true)
+        .unwrap();
+    assert!(matches!(
+        pt.protect_region(page.into(), PAGE_SIZE_4K, MappingFlags::READ),
+        Err(PagingError::InUse)
+    ));
+    assert!(FLUSHED.take().is_empty());
+    let (_, flags, size) = pt.query(page.into()).unwrap();
+    assert_eq!((flags, size), (RW, PageSize::Size1G));
+
+    // other huge pages are unmapped and flushed before they are split
+    pt.map_region(0x4000_0000.into(), 0.into(), SIZE_1G, RW, true)
+        .unwrap();
+    pt.protect_region(0x4000_0000.into(), PAGE_SIZE_4K, MappingFlags::READ)
+        .unwrap();
+    assert_eq!(
+        FLUSHED.take(),
+        [(0x4000_0000, SIZE_1G), (0x4000_0000, SIZE_2M)]
+    );
+    let (_, flags, size) = pt.query(0x4000_0000.into()).unwrap();
+    assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size4K));
+    let (_, flags, size) = pt.query((0x4000_0000 + SIZE_2M).into()).unwrap();
+    assert_eq!((flags, size), (RW, PageSize::Size2M));
+}
+
+#[test]
+fn test_protect_region() {
+    let mut pt = PageTable::try_new().unwrap();
+    pt.map_region(0x20_0000.into(), 0x20_0000.into(), 2 * SIZE_2M, RW, true)
+        .unwrap();
+
+    // the whole first 2M page, and the first 4K of the second one
+    pt.protect_region(0x20_0000.into(), SIZE_2M + PAGE_SIZE_4K, MappingFlags::READ)
+        .unwrap();
+    let (_, flags, size) = pt.query(0x20_0000.into()).unwrap();
+    assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size2M));
+    let (pa, flags, size) = pt.query(0x40_0000.into()).unwrap();
+    assert_eq!(pa, PhysAddr::from(0x40_0000));
+    assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size4K));
+    let (pa, flags, size) = pt.query((0x40_0000 + PAGE_SIZE_4K).into()).unwrap();
+    assert_eq!(pa, PhysAddr::from(0x40_0000 + PAGE_SIZE_4K));
+    assert_eq!((flags, size), (RW, PageSize::Size4K));
+
+    assert!(matches!(
+        pt.protect_region(0x60_0000.into(), PAGE_SIZE_4K, RW),
+        Err(PagingError::NotMapped)
+    ));
+}
+
+#[test]
+fn test_clone_with() {
+    let mut pt = PageTable::try_new().unwrap();
+    pt.map_region(0x20_0000.into(), 0x20_0000.into(), SIZE_2M, RW, true)
+        .unwrap();
+    pt.map_region(0x40_0000.into(), 0x1000.into(), PAGE_SIZE_4K, RW, true)
+        .unwrap();
+
+    // share the pages copy-on-write
+    let mut count = 0;
+    let mut new_pt = pt
+        .clone_with(|pte| {
+            pte.set_flags(pte.flags() - MappingFlags::WRITE, pte.is_huge());
+            count += 1;
+        })
+        .unwrap();
+    assert_eq!(count, 2);
+    for pt in [&pt, &new_pt] {
+        let (pa, flags, size) = pt.query(0x20_1000.into()).unwrap();
+        assert_eq!(pa, PhysAddr::from(0x20_1000));
+        assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size2M));
+        let (pa, flags, size) = pt.query(0x40_0000.into()).unwrap();
+        assert_eq!(pa, PhysAddr::from(0x1000));
+        assert_eq!((flags, size), (MappingFlags::READ, PageSize::Size4K));
+    }
+
+    // the tables are not shared
+    new_pt
+        .protect_region(0x40_0000.into(), PAGE_SIZE_4K, RW)
+        .unwrap();
+    pt.unmap_region(0x20_0000.into(), SIZE_2M).unwrap();
+    assert_eq!(pt.query(0x40_0000.into()).unwrap().1, MappingFlags::READ);
+    assert_eq!(new_pt.query(0x40_0000.into()).unwrap().1, RW);
+    assert_eq!(query_size(&new_pt, 0x20_0000), Some(PageSize::Size2M));
+    drop(pt);
+    assert_eq!(query_size(&new_pt, 0x20_0000), Some(PageSize::Size2M));
+}
+
+#[test]
+fn test_clone_with_shared() {
+    let base = 0xffff_8000_0000_0000usize;
+    let mut kernel_pt = PageTable::try_new().unwrap();
+    kernel_pt
+        .map_region(base.into(), 0.into(), PAGE_SIZE_4K, RW, false)
+        .unwrap();
+    let mut pt = PageTable::try_new().unwrap();
+    pt.copy_from(&kernel_pt, base.into(), 1 << 47);
+
+    let mut new_pt = pt
+        .clone_with(|_| panic!("shared entries are not copied"))
+        .unwrap();
+    kernel_pt
+        .map_region(
+            (base + PAGE_SIZE_4K).into(),
+            0.into(),
+            PAGE_SIZE_4K,
+            RW,
+            false,
+        )
+        .unwrap();
+    assert!(new_pt.query((base + PAGE_SIZE_4K).into()).is_ok());
+    new_pt
+        .map_region(0x1000.into(), 0.into(), PAGE_SIZE_4K, RW, false)
+        .unwrap();
+    assert!(pt.query(0x1000.into()).is_err());
+}
+
+#[test]
+fn test_accessed_dirty() {
+    use page_table_entry::x86_64::X64PTE;
+
+    let mut pte = X64PTE::new_page(0x1000.into(), RW, false);
+    assert!(!pte.is_accessed() && !pte.is_dirty());
+    pte.set_accessed(true);
+    pte.set_dirty(true);
+    assert!(pte.is_accessed() && pte.is_dirty());
+
+    // the flags can be changed without losing the accessed and dirty bits
+    pte.set_flags(MappingFlags::READ, false);
+    assert!(pte.is_accessed() && pte.is_dirty());
+    assert_eq!(pte.flags(), MappingFlags::READ);
+    assert_eq!(pte.paddr(), PhysAddr::from(0x1000));
+
+    pte.set_dirty(false);
+    assert!(pte.is_accessed() && !pte.is_dirty());
+    pte.set_accessed(false);
+    assert!(!pte.is_accessed());
+}
@@ -39,6 +39,9 @@ bitflags::bitflags! {
         const PXN =         1 <<  53;
         /// The Execute-never or Unprivileged execute-never field.
         const UXN =         1 <<  54;
+        /// Software dirty bit (bits 55..59 are reserved for software use),
+        /// which is never set by the hardware.
+        const SW_DIRTY =    1 <<  55;
 
         // Next-level attributes in stage 1 VMSAv8-64 Table descriptors:
 
@@ -123,7 +126,8 @@ impl From<MappingFlags> for DescriptorAttr {
             attr |= Self::AP_RO;
         }
         if flags.contains(MappingFlags::USER) {
-            attr |= Self::AP_EL0 | Self::PXN;
+            // user mappings are tagged with the ASID of the address space
+            attr |= Self::AP_EL0 | Self::PXN | Self::NG;
             if !flags.contains(MappingFlags::EXECUTE) {
                 attr |= Self::UXN;
             }
@@ -153,6 +157,14 @@ impl A64PTE {
     pub const fn empty() -> Self {
         Self(0)
     }
+
+    fn set_bits(&mut self, bits: DescriptorAttr, value: bool) {
+        if value {
+            self.0 |= bits.bits();
+        } else {
+            self.0 &= !bits.bits();
+        }
+    }
 }
 
 impl GenericPTE for A64PTE {
@@ -185,6 +197,33 @@ impl GenericPTE for A64PTE {
     fn clear(&mut self) {
         self.0 = 0
     }
+
+    fn set_flags(&mut self, flags: MappingFlags, is_huge: bool) {
+        let mut attr = DescriptorAttr::from(flags);
+        if !is_huge {
+            attr |= DescriptorAttr::NON_BLOCK;
+        }
+        let old = DescriptorAttr::from_bits_truncate(self.0)
+            & (DescriptorAttr::AF | DescriptorAttr::SW_DIRTY);
+        self.0 = (attr | old).bits() | (self.0 & Self::PHYS_ADDR_MASK as u64);
+    }
+    // The hardware management of the access flag and the dirty state
+    // (FEAT_HAFDBS) is not enabled, and access flag faults are not handled.
+    // So the following accessors only read and write the bits in software:
+    // `AF` is set for all new entries, and `SW_DIRTY` is only set by
+    // `set_dirty`.
+    fn is_accessed(&self) -> bool {
+        DescriptorAttr::from_bits_truncate(self.0).contains(DescriptorAttr::AF)
+    }
+    fn is_dirty(&self) -> bool {
+        DescriptorAttr::from_bits_truncate(self.0).contains(DescriptorAttr::SW_DIRTY)
+    }
+    fn set_accessed(&mut self, accessed: bool) {
+        self.set_bits(DescriptorAttr::AF, accessed)
+    }
+    fn set_dirty(&mut self, dirty: bool) {
+        self.set_bits(DescriptorAttr::SW_DIRTY, dirty)
+    }
 }
 
 impl fmt::Debug for A64PTE {
@@ -78,6 +78,14 @@ pub struct Rv64PTE(u64);
 
 impl Rv64PTE {
     const PHYS_ADDR_MASK: u64 = (1 << 54) - (1 << 10); // bits 10..54
+
+    fn set_bits(&mut self, bits: PTEFlags, value: bool) {
+        if value {
+            self.0 |= bits.bits() as u64;
+        } else {
+            self.0 &= !(bits.bits() as u64);
+        }
+    }
 }
 
 impl GenericPTE for Rv64PTE {
@@ -107,6 +115,24 @@ impl GenericPTE for Rv64PTE {
     fn clear(&mut self) {
         self.0 = 0
     }
+
+    fn set_flags(&mut self, flags: MappingFlags, _is_huge: bool) {
+        let old = PTEFlags::from_bits_truncate(self.0 as usize) & (PTEFlags::A | PTEFlags::D);
+        let flags = PTEFlags::from(flags) | old;
+        self.0 = (self.0 & Self::PHYS_ADDR_MASK) | flags.bits() as u64;
+    }
+    fn is_accessed(&self) -> bool {
+        PTEFlags::from_bits_truncate(self.0 as usize).contains(PTEFlags::A)
+    }
+    fn is_dirty(&self) -> bool {
+        PTEFlags::from_bits_truncate(self.0 as usize).contains(PTEFlags::D)
+    }
+    fn set_accessed(&mut self, accessed: bool) {
+        self.set_bits(PTEFlags::A, accessed)
+    }
+    fn set_dirty(&mut self, dirty: bool) {
+        self.set_bits(PTEFlags::D, dirty)
+    }
 }
 
 impl fmt::Debug for Rv64PTE {
@@ -43,6 +43,9 @@ impl From<MappingFlags> for PTF {
         }
         if f.contains(MappingFlags::USER) {
             ret |= Self::USER_ACCESSIBLE;
+        } else {
+            // kernel mappings are shared by all address spaces
+            ret |= Self::GLOBAL;
         }
         if f.contains(MappingFlags::DEVICE) {
             ret |= Self::NO_CACHE | Self::WRITE_THROUGH;
@@ -58,6 +61,14 @@ pub struct X64PTE(u64);
 
 impl X64PTE {
     const PHYS_ADDR_MASK: u64 = 0x000f_ffff_ffff_f000; // bits 12..52
+
+    fn set_bits(&mut self, bits: PTF, value: bool) {
+        if value {
+            self.0 |= bits.bits();
+        } else {
+            self.0 &= !bits.bits();
+        }
+    }
 }
 
 impl GenericPTE for X64PTE {
@@ -90,6 +101,27 @@ impl GenericPTE for X64PTE {
     fn clear(&mut self) {
         self.0 = 0
     }
+
+    fn set_flags(&mut self, flags: MappingFlags, is_huge: bool) {
+        let mut flags = PTF::from(flags);
+        if is_huge {
+            flags |= PTF::HUGE_PAGE;
+        }
+        let old = PTF::from_bits_truncate(self.0) & (PTF::ACCESSED | PTF::DIRTY);
+        self.0 = (self.0 & Self::PHYS_ADDR_MASK) | (flags | old).bits();
+    }
+    fn is_accessed(&self) -> bool {
+        PTF::from_bits_truncate(self.0).contains(PTF::ACCESSED)
+    }
+    fn is_dirty(&self) -> bool {
+        PTF::from_bits_truncate(self.0).contains(PTF::DIRTY)
+    }
+    fn set_accessed(&mut self, accessed: bool) {
+        self.set_bits(PTF::ACCESSED, accessed)
+    }
+    fn set_dirty(&mut self, dirty: bool) {
+        self.set_bits(PTF::DIRTY, dirty)
+    }
 }
 
 impl fmt::Debug for X64PTE {
@@ -24,7 +24,7 @@ pub use self::arch::*;
 bitflags::bitflags! {
     /// Generic page table entry flags that indicate the corresponding mapped
     /// memory region permissions and attributes.
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct MappingFlags: usize {
         /// The memory is readable.
         const READ          = 1 << 0;
@@ -61,4 +61,26 @@ pub trait GenericPTE: Debug + Clone + Copy + Sync + Send + Sized {
     fn is_huge(&self) -> bool;
     /// Set this entry to zero.
     fn clear(&mut self);
+
+    /// Set the flags of this entry to `flags`, keeping the physical address
+    /// and the accessed and dirty bits unchanged.
+    fn set_flags(&mut self, flags: MappingFlags, is_huge: bool);
+    /// Returns whether the page has been accessed (read, written, or fetched)
+    /// since the accessed bit was last cleared.
+    ///
+    /// Not supported on AArch64, where the bit is not updated by the hardware
+    /// and is always set for new entries. It only reflects the value set by
+    /// [`set_accessed`](Self::set_accessed) there.
+    fn is_accessed(&self) -> bool;
+    /// Returns whether the page has been written since the dirty bit was last
+    /// cleared.
+    ///
+    /// Not supported on AArch64, where the dirty bit is a software bit that
+    /// is never set by the hardware. It only reflects the value set by
+    /// [`set_dirty`](Self::set_dirty) there.
+    fn is_dirty(&self) -> bool;
+    /// Sets or clears the accessed bit of this entry.
+    fn set_accessed(&mut self, accessed: bool);
+    /// Sets or clears the dirty bit of this entry.
+    fn set_dirty(&mut self, dirty: bool);
 }
@@ -15,6 +15,19 @@ use core::sync::atomic::{AtomicBool, Ordering};
 
 use kernel_guard::BaseGuard;
 
+/// Low-level interfaces that must be implemented by the crate user, if the
+/// feature `smp` is enabled.
+#[cfg(feature = "smp")]
+#[crate_interface::def_interface]
+pub trait SpinLockIf {
+    /// Called repeatedly while waiting for a lock held by another CPU.
+    ///
+    /// The lock holder may be waiting for the current CPU to handle its IPI
+    /// (e.g., a TLB shootdown), which cannot be received if local IRQs are
+    /// disabled, so such requests should be handled here in that case.
+    fn relax();
+}
+
 /// A [spin lock](https://en.m.wikipedia.org/wiki/Spinlock) providing mutually
 /// exclusive access to data.
 ///
@@ -87,6 +100,7 @@ impl<G: BaseGuard, T: ?Sized> BaseSpinLock<G, T> {
             {
                 // Wait until the lock looks unlocked before retrying
                 while self.is_locked() {
+                    crate_interface::call_interface!(SpinLockIf::relax);
                     core::hint::spin_loop();
                 }
             }
@@ -6,7 +6,9 @@
 //! - `smp`: Use in the **multi-core** environment. For **single-core**
 //!   environment (without this feature), the lock state is unnecessary and
 //!   optimized out. CPU can always get the lock if we follow the proper guard
-//!   in use. By default, this feature is disabled.
+//!   in use. If this feature is enabled, you need to implement the
+//!   [`SpinLockIf`] trait in other crates. By default, this feature is
+//!   disabled.
 
 #![cfg_attr(not(test), no_std)]
 
@@ -16,6 +18,9 @@ use kernel_guard::{NoOp, NoPreempt, NoPreemptIrqSave};
 
 pub use self::base::{BaseSpinLock, BaseSpinLockGuard};
 
+#[cfg(feature = "smp")]
+pub use self::base::SpinLockIf;
+
 /// A spin lock that disables kernel preemption while trying to lock, and
 /// re-enables it after unlocking.
 ///
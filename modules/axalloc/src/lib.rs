@@ -4,6 +4,12 @@
 //! [`core::alloc::GlobalAlloc`]. A static global variable of type
 //! [`GlobalAllocator`] is defined with the `#[global_allocator]` attribute, to
 //! be registered as the standard library’s default allocator.
+//!
+//! # Cargo Features
+//!
+//! - `page-alloc-buddy`: Use [`BuddyPageAllocator`] as the page allocator,
+//!   which supports large and multiple memory regions. Otherwise,
+//!   [`BitmapPageAllocator`] is used, which supports at most 4 GB memory.
 
 #![no_std]
 
@@ -13,8 +19,7 @@ extern crate alloc;
 
 mod page;
 
-use allocator::{AllocResult, BaseAllocator, ByteAllocator, PageAllocator};
-use allocator::{BitmapPageAllocator, SlabByteAllocator};
+use allocator::{AllocResult, BaseAllocator, ByteAllocator, PageAllocator, SlabByteAllocator};
 use core::alloc::{GlobalAlloc, Layout};
 use spinlock::SpinNoIrq;
 
@@ -23,6 +28,17 @@ const MIN_HEAP_SIZE: usize = 0x8000; // 32 K
 
 pub use page::GlobalPage;
 
+#[cfg(feature = "page-alloc-buddy")]
+pub use allocator::BuddyStats;
+
+#[cfg(not(feature = "page-alloc-buddy"))]
+use allocator::BitmapPageAllocator as PageAllocatorImpl;
+#[cfg(feature = "page-alloc-buddy")]
+use allocator::BuddyPageAllocator as PageAllocatorImpl;
+
+#[cfg(doc)]
+use allocator::{BitmapPageAllocator, BuddyPageAllocator};
+
 /// The global allocator used by ArceOS.
 ///
 /// It combines a [`ByteAllocator`] and a [`PageAllocator`] into a simple
@@ -31,10 +47,11 @@ pub use page::GlobalPage;
 /// the byte allocator.
 ///
 /// Currently, [`SlabByteAllocator`] is used as the byte allocator, while
-/// [`BitmapPageAllocator`] is used as the page allocator.
+/// [`BitmapPageAllocator`] (or [`BuddyPageAllocator`] if the
+/// `page-alloc-buddy` feature is enabled) is used as the page allocator.
 pub struct GlobalAllocator {
     balloc: SpinNoIrq<SlabByteAllocator>,
-    palloc: SpinNoIrq<BitmapPageAllocator<PAGE_SIZE>>,
+    palloc: SpinNoIrq<PageAllocatorImpl<PAGE_SIZE>>,
 }
 
 impl GlobalAllocator {
@@ -42,7 +59,7 @@ impl GlobalAllocator {
     pub const fn new() -> Self {
         Self {
             balloc: SpinNoIrq::new(SlabByteAllocator::new()),
-            palloc: SpinNoIrq::new(BitmapPageAllocator::new()),
+            palloc: SpinNoIrq::new(PageAllocatorImpl::new()),
         }
     }
 
@@ -63,9 +80,14 @@ impl GlobalAllocator {
 
     /// Add the given region to the allocator.
     ///
-    /// It will add the whole region to the byte allocator.
+    /// It will add the whole region to the page allocator if the
+    /// `page-alloc-buddy` feature is enabled, otherwise to the byte allocator.
     pub fn add_memory(&self, start_vaddr: usize, size: usize) -> AllocResult {
-        self.balloc.lock().add_memory(start_vaddr, size)
+        if cfg!(feature = "page-alloc-buddy") {
+            self.palloc.lock().add_memory(start_vaddr, size)
+        } else {
+            self.balloc.lock().add_memory(start_vaddr, size)
+        }
     }
 
     /// Allocate arbitrary number of bytes. Returns the left bound of the
@@ -148,6 +170,12 @@ impl GlobalAllocator {
     pub fn available_pages(&self) -> usize {
         self.palloc.lock().available_pages()
     }
+
+    /// Returns the fragmentation statistics of the page allocator.
+    #[cfg(feature = "page-alloc-buddy")]
+    pub fn page_stats(&self) -> BuddyStats {
+        self.palloc.lock().stats()
+    }
 }
 
 unsafe impl GlobalAlloc for GlobalAllocator {
@@ -163,8 +163,9 @@ unsafe impl VirtIoHal for VirtIoHalImpl {
 
     #[inline]
     unsafe fn share(buffer: NonNull<[u8]>, _direction: BufferDirection) -> PhysAddr {
+        // the buffer may be out of the linear mapping, e.g., on the task stack
         let vaddr = buffer.as_ptr() as *mut u8 as usize;
-        virt_to_phys(vaddr.into()).into()
+        axmm::kernel_virt_to_phys(vaddr.into()).into()
     }
 
     #[inline]
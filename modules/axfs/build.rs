@@ -0,0 +1,15 @@
+use std::{env, fs, path::Path};
+
+fn main() {
+    // embed the initramfs archive specified by `AX_INITRAMFS`, or an empty one
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("initramfs.img");
+    let archive = env::var("AX_INITRAMFS").unwrap_or_default();
+    if env::var("CARGO_FEATURE_INITRAMFS").is_ok() && !archive.is_empty() {
+        fs::copy(&archive, &out_path)
+            .unwrap_or_else(|e| panic!("failed to read initramfs {:?}: {}", archive, e));
+        println!("cargo:rerun-if-changed={}", archive);
+    } else {
+        fs::write(&out_path, b"").unwrap();
+    }
+    println!("cargo:rerun-if-env-changed=AX_INITRAMFS");
+}
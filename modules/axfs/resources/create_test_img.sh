@@ -26,5 +26,87 @@ create_test_img() {
 	sudo umount mnt
 }
 
+# ext2/ext4 images are populated from a directory, no need to mount them
+create_ext_test_img() {
+	local name=$1
+	local blkcount=$2
+	local mkfs=$3
+	local root=$(mktemp -d)
+	for i in $(seq 1 1000); do
+	  echo "Rust is cool!" >>"$root/long.txt"
+	done
+	echo "Rust is cool!" >>"$root/short.txt"
+	mkdir -p "$root/very/long/path"
+	echo "Rust is cool!" >>"$root/very/long/path/test.txt"
+	mkdir -p "$root/very-long-dir-name"
+	echo "Rust is cool!" >>"$root/very-long-dir-name/very-long-file-name.txt"
+	echo "Rust is cool!" >>"$root/readonly.txt"
+	chmod 444 "$root/readonly.txt"
+	ln -s "very/long/path/test.txt" "$root/fast-link"
+	ln -s "very-long-dir-name/../very-long-dir-name/very-long-file-name.txt" "$root/slow-link"
+
+	rm -f "$name"
+	$mkfs -b 1024 -d "$root" -L "Test!" -U 12345678-1234-1234-1234-123456789abc "$name" $blkcount
+	rm -rf "$root"
+}
+
+# a GPT disk with an ext2 root partition, and the FAT16 image as a data
+# partition listed in /etc/fstab of the root
+create_part_test_img() {
+	local name=$1
+	local root=$(mktemp -d)
+	mkdir -p "$root/etc"
+	cat >"$root/etc/fstab" <<EOF
+# <source>        <mount point>  <type>  <options>
+PARTLABEL=rootfs  /              ext2    defaults
+LABEL=Test!       /data          auto    defaults
+UUID=1234-5678    /data2         vfat    defaults
+PARTLABEL=backup  /backup        auto    nofail
+EOF
+	echo "Rust is cool!" >>"$root/short.txt"
+
+	local part=$(mktemp)
+	rm -f "$part" "$name"
+	mkfs.ext2 -b 1024 -d "$root" -L rootfs -U 9b7f5c1e-3a4d-4c2b-8e6f-0a1b2c3d4e5f "$part" 1024
+	dd if=/dev/zero of="$name" bs=512 count=9216
+	sfdisk "$name" <<EOF
+label: gpt
+label-id: c0ffee00-0000-4000-8000-0000000000ff
+start=2048, size=2048, type=0fc63daf-8483-4772-8e79-3d69d8477de4, uuid=c0ffee00-0000-4000-8000-000000000001, name=rootfs
+start=4096, size=5000, type=ebd0a0a2-b9e5-4433-87c0-68b6b72699c7, uuid=c0ffee00-0000-4000-8000-000000000002, name=data
+EOF
+	dd if="$part" of="$name" bs=512 seek=2048 conv=notrunc
+	dd if="$CUR_DIR/fat16.img" of="$name" bs=512 seek=4096 conv=notrunc
+	rm -rf "$root" "$part"
+}
+
+# a cpio (newc) archive for the initramfs, with a hard link, a symbolic link
+# and fixed modification times
+create_initramfs_test_img() {
+	local name=$1
+	local root=$(mktemp -d)
+	for i in $(seq 1 1000); do
+	  echo "Rust is cool!" >>"$root/long.txt"
+	done
+	echo "Rust is cool!" >>"$root/short.txt"
+	mkdir -p "$root/very/long/path"
+	echo "Rust is cool!" >>"$root/very/long/path/test.txt"
+	mkdir -p "$root/very-long-dir-name"
+	echo "Rust is cool!" >>"$root/very-long-dir-name/very-long-file-name.txt"
+	mkdir -p "$root/etc"
+	echo "arceos" >>"$root/etc/hostname"
+	ln "$root/etc/hostname" "$root/etc/hostname.bak"
+	ln -s "../very/long/path/test.txt" "$root/etc/fast-link"
+	touch -d @1234567890 "$root/etc/hostname" "$root/etc"
+
+	rm -f "$name"
+	bsdtar -cf "$name" --format newc -C "$root" .
+	rm -rf "$root"
+}
+
 create_test_img "$CUR_DIR/fat16.img" 2500 16
 create_test_img "$CUR_DIR/fat32.img" 34000 32
+create_ext_test_img "$CUR_DIR/ext2.img" 2048 mkfs.ext2
+create_ext_test_img "$CUR_DIR/ext4.img" 4096 mkfs.ext4
+create_part_test_img "$CUR_DIR/gpt.img"
+create_initramfs_test_img "$CUR_DIR/initramfs.cpio"
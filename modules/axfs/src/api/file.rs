@@ -1,5 +1,5 @@
 use axio::{prelude::*, Result, SeekFrom};
-use core::fmt;
+use core::{fmt, ops::Range, time::Duration};
 
 use crate::fops;
 
@@ -10,13 +10,16 @@ pub type FileType = fops::FileType;
 /// Representation of the various permissions on a file.
 pub type Permissions = fops::FilePerm;
 
+/// The type of an advisory file lock.
+pub type LockType = fops::LockType;
+
 /// An object providing access to an open file on the filesystem.
 pub struct File {
     inner: fops::File,
 }
 
 /// Metadata information about a file.
-pub struct Metadata(fops::FileAttr);
+pub struct Metadata(pub(super) fops::FileAttr);
 
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Clone, Debug)]
@@ -64,6 +67,14 @@ impl OpenOptions {
         self
     }
 
+    /// Sets the option to fail with
+    /// [`FilesystemLoop`](axio::Error::FilesystemLoop) if the last component
+    /// of the path is a symbolic link.
+    pub fn nofollow(&mut self, nofollow: bool) -> &mut Self {
+        self.0.nofollow(nofollow);
+        self
+    }
+
     /// Opens a file at `path` with the options specified by `self`.
     pub fn open(&self, path: &str) -> Result<File> {
         fops::File::open(path, &self.0).map(|inner| File { inner })
@@ -90,6 +101,14 @@ impl Metadata {
         self.0.is_file()
     }
 
+    /// Returns `true` if this metadata is for a symbolic link. It is only
+    /// possible for the metadata returned by [`symlink_metadata`].
+    ///
+    /// [`symlink_metadata`]: super::symlink_metadata
+    pub const fn is_symlink(&self) -> bool {
+        self.0.is_symlink()
+    }
+
     /// Returns the size of the file, in bytes, this metadata is for.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> u64 {
@@ -101,6 +120,27 @@ impl Metadata {
         self.0.perm()
     }
 
+    /// Returns the last modification time, as the duration since the UNIX
+    /// epoch.
+    pub const fn modified(&self) -> Duration {
+        self.0.mtime()
+    }
+
+    /// Returns the last access time, as the duration since the UNIX epoch.
+    ///
+    /// Some filesystems only record the date of the last access (e.g., FAT).
+    pub const fn accessed(&self) -> Duration {
+        self.0.atime()
+    }
+
+    /// Returns the creation time, as the duration since the UNIX epoch.
+    ///
+    /// It is the time of the last status change on filesystems that do not
+    /// record the creation time (e.g., ramfs).
+    pub const fn created(&self) -> Duration {
+        self.0.ctime()
+    }
+
     /// Returns the inner raw metadata [`fops::FileAttr`].
     pub const fn raw_metadata(&self) -> &fops::FileAttr {
         &self.0
@@ -113,6 +153,7 @@ impl fmt::Debug for Metadata {
             .field("file_type", &self.0.file_type())
             .field("is_dir", &self.0.is_dir())
             .field("is_file", &self.0.is_file())
+            .field("modified", &self.0.mtime())
             .finish_non_exhaustive()
     }
 }
@@ -156,6 +197,68 @@ impl File {
     pub fn metadata(&self) -> Result<Metadata> {
         self.inner.get_attr().map(Metadata)
     }
+
+    /// Changes the access and modification time of the underlying file,
+    /// leaving those given `None` unchanged.
+    ///
+    /// Times are given as the duration since the UNIX epoch.
+    pub fn set_times(&self, accessed: Option<Duration>, modified: Option<Duration>) -> Result<()> {
+        self.inner.set_times(accessed, modified)
+    }
+
+    /// Changes the modification time of the underlying file.
+    pub fn set_modified(&self, time: Duration) -> Result<()> {
+        self.set_times(None, Some(time))
+    }
+
+    /// Acquires an exclusive advisory lock on the whole file, blocking until
+    /// it can be acquired.
+    ///
+    /// The lock is held by this file object, and released on [`File::unlock`]
+    /// or when the file is dropped.
+    pub fn lock(&self) -> Result<()> {
+        self.inner.flock(Some(LockType::Exclusive), true)
+    }
+
+    /// Acquires a shared advisory lock on the whole file, blocking until it
+    /// can be acquired.
+    pub fn lock_shared(&self) -> Result<()> {
+        self.inner.flock(Some(LockType::Shared), true)
+    }
+
+    /// Tries to acquire an exclusive advisory lock on the whole file. Returns
+    /// [`WouldBlock`](axio::Error::WouldBlock) if it is held by others.
+    pub fn try_lock(&self) -> Result<()> {
+        self.inner.flock(Some(LockType::Exclusive), false)
+    }
+
+    /// Tries to acquire a shared advisory lock on the whole file. Returns
+    /// [`WouldBlock`](axio::Error::WouldBlock) if it is held by others.
+    pub fn try_lock_shared(&self) -> Result<()> {
+        self.inner.flock(Some(LockType::Shared), false)
+    }
+
+    /// Releases the advisory lock on the whole file.
+    pub fn unlock(&self) -> Result<()> {
+        self.inner.flock(None, false)
+    }
+
+    /// Acquires an advisory record lock of `ty` on the bytes in `range`, or
+    /// releases the locks in `range` if `ty` is `None`. See
+    /// [`fops::File::lock_range`] for details.
+    pub fn lock_range(&self, ty: Option<LockType>, range: Range<u64>, wait: bool) -> Result<()> {
+        self.inner.lock_range(ty, range, wait)
+    }
+
+    /// Returns a record lock held by others that prevents acquiring the lock
+    /// of `ty` on `range`, or `None` if it can be acquired.
+    pub fn test_lock_range(
+        &self,
+        ty: LockType,
+        range: Range<u64>,
+    ) -> Result<Option<(LockType, Range<u64>)>> {
+        self.inner.test_lock_range(ty, range)
+    }
 }
 
 impl Read for File {
@@ -4,10 +4,15 @@ mod dir;
 mod file;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
-pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use self::file::{File, FileType, LockType, Metadata, OpenOptions, Permissions};
+pub use crate::dev::CacheStats;
+pub use crate::partition::PartitionInfo;
+pub use crate::root::MountInfo;
 
-use alloc::{string::String, vec::Vec};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use axfs_vfs::VfsOps;
 use axio::{self as io, prelude::*};
+use core::time::Duration;
 
 /// Returns an iterator over the entries within a directory.
 pub fn read_dir(path: &str) -> io::Result<ReadDir> {
@@ -15,9 +20,9 @@ pub fn read_dir(path: &str) -> io::Result<ReadDir> {
 }
 
 /// Returns the canonical, absolute form of a path with all intermediate
-/// components normalized.
+/// components normalized and symbolic links resolved.
 pub fn canonicalize(path: &str) -> io::Result<String> {
-    crate::root::absolute_path(path)
+    crate::root::resolve_absolute_path(path, true)
 }
 
 /// Returns the current working directory as a [`String`].
@@ -59,6 +64,25 @@ pub fn metadata(path: &str) -> io::Result<Metadata> {
     File::open(path)?.metadata()
 }
 
+/// Query the metadata about a file without following symbolic links.
+pub fn symlink_metadata(path: &str) -> io::Result<Metadata> {
+    Ok(Metadata(
+        crate::root::lookup_nofollow(None, path)?.get_attr()?,
+    ))
+}
+
+/// Reads a symbolic link, returning the path that the link points to.
+pub fn read_link(path: &str) -> io::Result<String> {
+    crate::root::read_link(None, path)
+}
+
+/// Creates a new symbolic link at `link` pointing to the `original` path.
+///
+/// The `original` path is not required to exist.
+pub fn symlink(original: &str, link: &str) -> io::Result<()> {
+    crate::root::create_symlink(None, original, link)
+}
+
 /// Creates a new, empty directory at the provided path.
 pub fn create_dir(path: &str) -> io::Result<()> {
     DirBuilder::new().create(path)
@@ -79,3 +103,124 @@ pub fn remove_dir(path: &str) -> io::Result<()> {
 pub fn remove_file(path: &str) -> io::Result<()> {
     crate::root::remove_file(None, path)
 }
+
+/// Rename a file or directory to a new name, replacing the original file if
+/// `to` already exists.
+///
+/// Returns [`CrossesDevices`](io::Error::CrossesDevices) if `from` and `to`
+/// are on different filesystems.
+pub fn rename(from: &str, to: &str) -> io::Result<()> {
+    crate::root::rename(from, to)
+}
+
+/// Creates a new hard link on the filesystem.
+///
+/// The `link` path will be a link pointing to the `original` path. Note that
+/// not all filesystems support hard links (e.g., FAT).
+pub fn hard_link(original: &str, link: &str) -> io::Result<()> {
+    crate::root::link(original, link)
+}
+
+/// Mounts the filesystem `fs` on the directory `path`, which is created if it
+/// does not exist. It can be in another mounted filesystem.
+///
+/// `source` (e.g., the device) and `fs_type` are only for display in
+/// [`mounts`].
+pub fn mount(source: &str, path: &str, fs_type: &str, fs: Arc<dyn VfsOps>) -> io::Result<()> {
+    crate::root::mount(source, path, fs_type, fs)
+}
+
+/// Mounts the filesystem on a partition at the directory `path`, which is
+/// created if it does not exist.
+///
+/// The partition is specified by `source`, in one of the forms:
+///
+/// - `LABEL=<label>` or `UUID=<uuid>`: the filesystem label or UUID (the
+///   volume ID in the form `XXXX-XXXX` for FAT).
+/// - `PARTLABEL=<label>` or `PARTUUID=<uuid>`: the GPT partition name or
+///   unique GUID. For MBR, `PARTUUID` is the disk ID followed by the partition
+///   number (e.g., `12345678-01`).
+/// - The device name, e.g., `vda2` or `/dev/vda2`.
+///
+/// `fs_type` is the filesystem type (e.g., `fatfs` or `ext4fs`), or `auto`
+/// to detect it.
+pub fn mount_device(source: &str, path: &str, fs_type: &str) -> io::Result<()> {
+    crate::fstab::mount_device(source, path, fs_type)
+}
+
+/// Returns all partitions of all block devices.
+pub fn partitions() -> Vec<PartitionInfo> {
+    crate::partition::partitions()
+        .into_iter()
+        .map(|part| part.info)
+        .collect()
+}
+
+/// Unmounts the filesystem mounted on the directory `path`.
+///
+/// Returns [`ResourceBusy`](io::Error::ResourceBusy) if other filesystems are
+/// mounted in it, any file or directory in it is open, or the current
+/// directory is in it. The block caches are synchronized after unmounting.
+pub fn umount(path: &str) -> io::Result<()> {
+    crate::root::umount(path)
+}
+
+/// Returns all mounted filesystems, starting with the root filesystem.
+///
+/// Each [`MountInfo`] is displayed as a line of `/proc/mounts`.
+pub fn mounts() -> Vec<MountInfo> {
+    crate::root::mounts()
+}
+
+/// Adds a device node named `name` to `/dev` (e.g., `fb0` for `/dev/fb0`),
+/// replacing the existing one.
+#[cfg(feature = "devfs")]
+pub fn add_device(name: &'static str, node: axfs_vfs::VfsNodeRef) {
+    crate::root::add_device(name, node)
+}
+
+/// Adds a read-only file at `path` relative to `/proc`, whose contents are
+/// generated by `generate` when it is read (e.g., `net/tcp` for
+/// `/proc/net/tcp`). Missing parent directories are created.
+#[cfg(feature = "procfs")]
+pub fn add_proc_file<F>(path: &'static str, generate: F)
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    crate::root::add_proc_file(path, generate)
+}
+
+/// Changes the access and modification time of a file or directory, leaving
+/// those given `None` unchanged.
+///
+/// Times are given as the duration since the UNIX epoch.
+pub fn set_times(
+    path: &str,
+    accessed: Option<Duration>,
+    modified: Option<Duration>,
+) -> io::Result<()> {
+    crate::root::set_times(None, path, accessed, modified, true)
+}
+
+/// Like [`set_times`], but changes the times of the symbolic link itself
+/// instead of its target if `path` is a symbolic link.
+pub fn set_symlink_times(
+    path: &str,
+    accessed: Option<Duration>,
+    modified: Option<Duration>,
+) -> io::Result<()> {
+    crate::root::set_times(None, path, accessed, modified, false)
+}
+
+/// Writes all modified blocks in the block caches back to the devices.
+pub fn sync() -> io::Result<()> {
+    crate::dev::sync_all().map_err(|e| {
+        warn!("failed to sync block caches: {:?}", e);
+        io::Error::Io
+    })
+}
+
+/// Returns the hit/miss statistics of the block caches.
+pub fn cache_stats() -> CacheStats {
+    crate::dev::cache_stats()
+}
@@ -1,28 +1,238 @@
+//! Block devices with an LRU write-back cache.
+//!
+//! Filesystems access block devices through [`Disk`], a byte-addressed cursor
+//! over a shared [`BlockCache`]. Writes only modify the cached blocks and mark
+//! them dirty; they reach the device when evicted, or when the cache is
+//! synchronized by [`sync_all`] (or [`Disk::sync`]).
+
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec};
 use axdriver::prelude::*;
+use axsync::Mutex;
+
+#[cfg(test)]
+mod tests;
 
 const BLOCK_SIZE: usize = 512;
 
-/// A disk device with a cursor.
+/// The number of blocks held by each cache (256 KiB).
+const CACHE_CAPACITY: usize = 512;
+
+/// All block caches, for [`sync_all`].
+static BLOCK_CACHES: Mutex<Vec<Arc<BlockCache>>> = Mutex::new(Vec::new());
+
+/// Statistics of a block cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of block accesses served from the cache.
+    pub hits: u64,
+    /// The number of block accesses that loaded the block into the cache.
+    pub misses: u64,
+    /// The number of dirty blocks written back to the device.
+    pub writebacks: u64,
+    /// The number of dirty blocks currently in the cache.
+    pub dirty: usize,
+}
+
+impl core::ops::AddAssign for CacheStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.hits += rhs.hits;
+        self.misses += rhs.misses;
+        self.writebacks += rhs.writebacks;
+        self.dirty += rhs.dirty;
+    }
+}
+
+struct CachedBlock {
+    block_id: u64,
+    data: Box<[u8; BLOCK_SIZE]>,
+    dirty: bool,
+    last_used: u64,
+}
+
+struct CacheInner {
+    dev: AxBlockDevice,
+    blocks: Vec<CachedBlock>,
+    /// Maps block IDs to indices in `blocks`.
+    index: BTreeMap<u64, usize>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+/// An LRU write-back cache of a block device.
+pub struct BlockCache {
+    num_blocks: u64,
+    inner: Mutex<CacheInner>,
+}
+
+impl CacheInner {
+    /// Returns the index of the cached block `block_id`, loading it (or
+    /// evicting another block) if it's not cached.
+    ///
+    /// If `fill` is false, the block will be overwritten entirely, so it is not
+    /// read from the device on a miss.
+    fn get(&mut self, block_id: u64, fill: bool) -> DevResult<usize> {
+        if block_id >= self.dev.num_blocks() {
+            return Err(DevError::Io);
+        }
+        self.clock += 1;
+        if let Some(&idx) = self.index.get(&block_id) {
+            self.stats.hits += 1;
+            self.blocks[idx].last_used = self.clock;
+            return Ok(idx);
+        }
+
+        let mut data = Box::new([0; BLOCK_SIZE]);
+        if fill {
+            self.dev.read_block(block_id, data.as_mut_slice())?;
+        }
+        self.stats.misses += 1;
+        let block = CachedBlock {
+            block_id,
+            data,
+            dirty: false,
+            last_used: self.clock,
+        };
+
+        let idx = if self.blocks.len() < CACHE_CAPACITY {
+            self.blocks.push(block);
+            self.blocks.len() - 1
+        } else {
+            // linear scan is fine for a cache of this size, compared to the
+            // device access of a miss
+            let (idx, victim) = self
+                .blocks
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, b)| b.last_used)
+                .unwrap();
+            if victim.dirty {
+                self.dev
+                    .write_block(victim.block_id, victim.data.as_slice())?;
+                self.stats.writebacks += 1;
+                self.stats.dirty -= 1;
+            }
+            self.index.remove(&victim.block_id);
+            self.blocks[idx] = block;
+            idx
+        };
+        self.index.insert(block_id, idx);
+        Ok(idx)
+    }
+
+    fn sync(&mut self) -> DevResult {
+        if self.stats.dirty > 0 {
+            // write back in the order of block IDs, the cache index is sorted
+            for &idx in self.index.values() {
+                let block = &mut self.blocks[idx];
+                if block.dirty {
+                    self.dev
+                        .write_block(block.block_id, block.data.as_slice())?;
+                    block.dirty = false;
+                    self.stats.writebacks += 1;
+                    self.stats.dirty -= 1;
+                }
+            }
+        }
+        self.dev.flush()
+    }
+}
+
+impl BlockCache {
+    /// Creates a new cache of the block device `dev`, and registers it to be
+    /// synchronized by [`sync_all`].
+    pub fn new(dev: AxBlockDevice) -> Arc<Self> {
+        assert_eq!(BLOCK_SIZE, dev.block_size());
+        let cache = Arc::new(Self {
+            num_blocks: dev.num_blocks(),
+            inner: Mutex::new(CacheInner {
+                dev,
+                blocks: Vec::new(),
+                index: BTreeMap::new(),
+                clock: 0,
+                stats: CacheStats::default(),
+            }),
+        });
+        BLOCK_CACHES.lock().push(cache.clone());
+        cache
+    }
+
+    /// Returns the number of blocks of the device.
+    pub fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+
+    /// Reads `buf.len()` bytes at `offset` within the block `block_id`.
+    pub fn read(&self, block_id: u64, offset: usize, buf: &mut [u8]) -> DevResult {
+        let mut inner = self.inner.lock();
+        let idx = inner.get(block_id, true)?;
+        buf.copy_from_slice(&inner.blocks[idx].data[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    /// Writes `buf` at `offset` within the block `block_id`. The block is only
+    /// written to the device when evicted or synchronized.
+    pub fn write(&self, block_id: u64, offset: usize, buf: &[u8]) -> DevResult {
+        let mut inner = self.inner.lock();
+        let idx = inner.get(block_id, buf.len() < BLOCK_SIZE)?;
+        let block = &mut inner.blocks[idx];
+        block.data[offset..offset + buf.len()].copy_from_slice(buf);
+        if !block.dirty {
+            block.dirty = true;
+            inner.stats.dirty += 1;
+        }
+        Ok(())
+    }
+
+    /// Writes all dirty blocks back to the device, then flushes the device.
+    pub fn sync(&self) -> DevResult {
+        self.inner.lock().sync()
+    }
+
+    /// Returns the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().stats
+    }
+}
+
+/// A disk device (or a partition of it) with a cursor.
 pub struct Disk {
     block_id: u64,
     offset: usize,
-    dev: AxBlockDevice,
+    cache: Arc<BlockCache>,
+    /// The first block of the partition on the device.
+    start: u64,
+    num_blocks: u64,
 }
 
 impl Disk {
-    /// Create a new disk.
+    /// Create a new disk, with a new cache of the block device.
     pub fn new(dev: AxBlockDevice) -> Self {
-        assert_eq!(BLOCK_SIZE, dev.block_size());
+        Self::from_cache(BlockCache::new(dev))
+    }
+
+    /// Create a new disk accessing the whole device through a shared block
+    /// cache.
+    pub fn from_cache(cache: Arc<BlockCache>) -> Self {
+        let num_blocks = cache.num_blocks();
+        Self::new_partition(cache, 0, num_blocks)
+    }
+
+    /// Create a new disk accessing `num_blocks` blocks from the block `start`
+    /// of the device, through a shared block cache.
+    pub fn new_partition(cache: Arc<BlockCache>, start: u64, num_blocks: u64) -> Self {
+        assert!(start + num_blocks <= cache.num_blocks());
         Self {
             block_id: 0,
             offset: 0,
-            dev,
+            cache,
+            start,
+            num_blocks,
         }
     }
 
     /// Get the size of the disk.
     pub fn size(&self) -> u64 {
-        self.dev.num_blocks() * BLOCK_SIZE as u64
+        self.num_blocks * BLOCK_SIZE as u64
     }
 
     /// Get the position of the cursor.
@@ -38,55 +248,71 @@ impl Disk {
 
     /// Read within one block, returns the number of bytes read.
     pub fn read_one(&mut self, buf: &mut [u8]) -> DevResult<usize> {
-        let read_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            self.dev
-                .read_block(self.block_id, &mut buf[0..BLOCK_SIZE])?;
-            self.block_id += 1;
-            BLOCK_SIZE
-        } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            buf[..count].copy_from_slice(&data[start..start + count]);
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
-        Ok(read_size)
+        let count = buf.len().min(BLOCK_SIZE - self.offset);
+        self.cache
+            .read(self.device_block()?, self.offset, &mut buf[..count])?;
+        self.advance(count);
+        Ok(count)
     }
 
     /// Write within one block, returns the number of bytes written.
     pub fn write_one(&mut self, buf: &[u8]) -> DevResult<usize> {
-        let write_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            self.dev.write_block(self.block_id, &buf[0..BLOCK_SIZE])?;
-            self.block_id += 1;
-            BLOCK_SIZE
+        let count = buf.len().min(BLOCK_SIZE - self.offset);
+        self.cache
+            .write(self.device_block()?, self.offset, &buf[..count])?;
+        self.advance(count);
+        Ok(count)
+    }
+
+    /// Writes all dirty blocks of the disk back to the device.
+    pub fn sync(&self) -> DevResult {
+        self.cache.sync()
+    }
+
+    /// Returns the block ID on the device of the current block.
+    fn device_block(&self) -> DevResult<u64> {
+        if self.block_id < self.num_blocks {
+            Ok(self.start + self.block_id)
         } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            data[start..start + count].copy_from_slice(&buf[..count]);
-            self.dev.write_block(self.block_id, &data)?;
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
-        Ok(write_size)
+            Err(DevError::Io)
+        }
+    }
+
+    fn advance(&mut self, count: usize) {
+        self.offset += count;
+        if self.offset >= BLOCK_SIZE {
+            self.block_id += 1;
+            self.offset -= BLOCK_SIZE;
+        }
     }
 }
+
+/// Writes all dirty blocks of all block caches back to their devices.
+pub(crate) fn sync_all() -> DevResult {
+    let caches = BLOCK_CACHES.lock().clone();
+    for cache in caches {
+        cache.sync()?;
+    }
+    Ok(())
+}
+
+/// Returns the sum of the statistics of all block caches.
+pub(crate) fn cache_stats() -> CacheStats {
+    let mut stats = CacheStats::default();
+    for cache in BLOCK_CACHES.lock().iter() {
+        stats += cache.stats();
+    }
+    stats
+}
+
+/// Spawns a task that writes dirty blocks back periodically.
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub(crate) fn spawn_flush_task() {
+    const FLUSH_INTERVAL: core::time::Duration = core::time::Duration::from_secs(5);
+    axtask::spawn(|| loop {
+        axtask::sleep(FLUSH_INTERVAL);
+        if let Err(e) = sync_all() {
+            warn!("failed to flush block caches: {:?}", e);
+        }
+    });
+}
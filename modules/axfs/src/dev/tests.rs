@@ -0,0 +1,100 @@
+use alloc::sync::Arc;
+use axdriver::prelude::*;
+use driver_block::ramdisk::RamDisk;
+
+use super::{BlockCache, CacheStats, BLOCK_SIZE, CACHE_CAPACITY};
+
+fn new_cache(num_blocks: usize) -> Arc<BlockCache> {
+    BlockCache::new(RamDisk::new(num_blocks * BLOCK_SIZE))
+}
+
+/// Reads the block `block_id` from the device, bypassing the cache.
+fn device_block(cache: &BlockCache, block_id: u64) -> [u8; BLOCK_SIZE] {
+    let mut buf = [0; BLOCK_SIZE];
+    let mut inner = cache.inner.lock();
+    inner.dev.read_block(block_id, &mut buf).unwrap();
+    buf
+}
+
+fn is_cached(cache: &BlockCache, block_id: u64) -> bool {
+    cache.inner.lock().index.contains_key(&block_id)
+}
+
+#[test]
+fn test_hit_miss() {
+    let cache = new_cache(16);
+    let mut buf = [0; 4];
+    cache.read(0, 0, &mut buf).unwrap(); // miss
+    cache.read(0, 4, &mut buf).unwrap(); // hit
+    cache.write(1, 0, &[1; 4]).unwrap(); // miss
+    cache.write(1, 4, &[1; 4]).unwrap(); // hit
+    cache.write(2, 0, &[2; BLOCK_SIZE]).unwrap(); // miss, not read from the device
+    assert!(cache.read(16, 0, &mut buf).is_err());
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 2,
+            misses: 3,
+            writebacks: 0,
+            dirty: 2,
+        }
+    );
+
+    assert_eq!(device_block(&cache, 1)[..8], [0; 8]);
+    cache.sync().unwrap();
+    assert_eq!(device_block(&cache, 1)[..8], [1; 8]);
+    assert_eq!(device_block(&cache, 2), [2; BLOCK_SIZE]);
+    let stats = cache.stats();
+    assert_eq!((stats.writebacks, stats.dirty), (2, 0));
+
+    // clean blocks are not written again
+    cache.sync().unwrap();
+    assert_eq!(cache.stats().writebacks, 2);
+}
+
+#[test]
+fn test_lru_eviction() {
+    let n = CACHE_CAPACITY as u64;
+    let cache = new_cache(CACHE_CAPACITY + 2);
+    for block_id in 0..n {
+        cache.read(block_id, 0, &mut [0]).unwrap();
+    }
+    // block 0 becomes the most recently used, block 1 the least
+    cache.read(0, 0, &mut [0]).unwrap();
+
+    cache.read(n, 0, &mut [0]).unwrap();
+    assert!(is_cached(&cache, 0));
+    assert!(!is_cached(&cache, 1));
+    assert!(is_cached(&cache, 2));
+
+    cache.read(n + 1, 0, &mut [0]).unwrap();
+    assert!(!is_cached(&cache, 2));
+    assert!(is_cached(&cache, n));
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (1, n + 2));
+    // clean blocks are dropped without writing back
+    assert_eq!(stats.writebacks, 0);
+}
+
+#[test]
+fn test_dirty_eviction() {
+    let n = CACHE_CAPACITY as u64;
+    let cache = new_cache(CACHE_CAPACITY + 1);
+    cache.write(0, 0, &[0xaa; 8]).unwrap();
+    for block_id in 1..n {
+        cache.read(block_id, 0, &mut [0]).unwrap();
+    }
+    assert_eq!(device_block(&cache, 0)[..8], [0; 8]);
+
+    // evicts block 0, which is written back
+    cache.read(n, 0, &mut [0]).unwrap();
+    assert!(!is_cached(&cache, 0));
+    assert_eq!(device_block(&cache, 0)[..8], [0xaa; 8]);
+    let stats = cache.stats();
+    assert_eq!((stats.writebacks, stats.dirty), (1, 0));
+
+    // and loaded again from the device
+    let mut buf = [0; 8];
+    cache.read(0, 0, &mut buf).unwrap();
+    assert_eq!(buf, [0xaa; 8]);
+}
@@ -0,0 +1,131 @@
+//! Device nodes of the console and block devices in `/dev`.
+
+use axfs_vfs::VfsResult;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeTimes, VfsNodeType};
+use axsync::Mutex;
+
+use crate::dev::Disk;
+
+/// The console device behaves like `/dev/console`.
+///
+/// Reads block until at least one byte is available, and writes are printed
+/// to the console directly.
+pub(crate) struct ConsoleDev;
+
+impl ConsoleDev {
+    fn getchar() -> Option<u8> {
+        axhal::console::getchar().map(|c| if c == b'\r' { b'\n' } else { c })
+    }
+}
+
+impl VfsNodeOps for ConsoleDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(
+            VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0)
+                .with_times(VfsNodeTimes::now()),
+        )
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut read_len = 0;
+        loop {
+            while read_len < buf.len() {
+                match Self::getchar() {
+                    Some(c) => {
+                        buf[read_len] = c;
+                        read_len += 1;
+                    }
+                    None => break,
+                }
+            }
+            if read_len > 0 {
+                return Ok(read_len);
+            }
+            #[cfg(feature = "multitask")]
+            axtask::yield_now();
+            #[cfg(not(feature = "multitask"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// A block device node (e.g., `/dev/vda` or `/dev/vda1`), accessing a disk or
+/// partition through the block cache shared with filesystems on it.
+pub(crate) struct BlockDev {
+    disk: Mutex<Disk>,
+    size: u64,
+}
+
+impl BlockDev {
+    pub fn new(disk: Disk) -> Self {
+        Self {
+            size: disk.size(),
+            disk: Mutex::new(disk),
+        }
+    }
+}
+
+impl VfsNodeOps for BlockDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            self.size,
+            0,
+        )
+        .with_times(VfsNodeTimes::now()))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = buf.len().min(self.size.saturating_sub(offset) as usize);
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut read_len = 0;
+        while read_len < len {
+            read_len += disk
+                .read_one(&mut buf[read_len..len])
+                .map_err(|_| VfsError::Io)?;
+        }
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let len = buf.len().min(self.size.saturating_sub(offset) as usize);
+        if len == 0 && !buf.is_empty() {
+            return Err(VfsError::StorageFull);
+        }
+        let mut disk = self.disk.lock();
+        disk.set_position(offset);
+        let mut write_len = 0;
+        while write_len < len {
+            write_len += disk
+                .write_one(&buf[write_len..len])
+                .map_err(|_| VfsError::Io)?;
+        }
+        Ok(len)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.disk.lock().sync().map_err(|_| VfsError::Io)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -5,9 +5,16 @@ use axfs_vfs::{VfsError, VfsNodeRef};
 use axio::SeekFrom;
 use capability::{Cap, WithCap};
 use core::fmt;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+pub use crate::lock::LockType;
+use crate::lock::NodeKey;
+use crate::root::MountRef;
 
 #[cfg(feature = "myfs")]
-pub use crate::dev::Disk;
+pub use crate::dev::{BlockCache, Disk};
 #[cfg(feature = "myfs")]
 pub use crate::fs::myfs::MyFileSystemIf;
 
@@ -25,6 +32,11 @@ pub struct File {
     node: WithCap<VfsNodeRef>,
     is_append: bool,
     offset: u64,
+    /// The unique ID of the opened file, as the owner of file locks.
+    id: u64,
+    /// Keeps the filesystem of the file mounted, and identifies it for file
+    /// locks.
+    mount_ref: MountRef,
 }
 
 /// An opened directory object, with open permissions and a cursor for
@@ -32,6 +44,7 @@ pub struct File {
 pub struct Directory {
     node: WithCap<VfsNodeRef>,
     entry_idx: usize,
+    mount_ref: MountRef,
 }
 
 /// Options and flags which can be used to configure how a file is opened.
@@ -45,6 +58,7 @@ pub struct OpenOptions {
     create: bool,
     create_new: bool,
     // system-specific
+    nofollow: bool,
     _custom_flags: i32,
     _mode: u32,
 }
@@ -61,6 +75,7 @@ impl OpenOptions {
             create: false,
             create_new: false,
             // system-specific
+            nofollow: false,
             _custom_flags: 0,
             _mode: 0o666,
         }
@@ -89,6 +104,11 @@ impl OpenOptions {
     pub fn create_new(&mut self, create_new: bool) {
         self.create_new = create_new;
     }
+    /// Sets the option to fail if the last component of the path is a
+    /// symbolic link (`O_NOFOLLOW`).
+    pub fn nofollow(&mut self, nofollow: bool) {
+        self.nofollow = nofollow;
+    }
 
     const fn is_valid(&self) -> bool {
         if !self.read && !self.write && !self.append {
@@ -112,13 +132,26 @@ impl OpenOptions {
 }
 
 impl File {
-    fn _open_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_at(
+        dir: Option<&VfsNodeRef>,
+        dir_ref: Option<&MountRef>,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         debug!("open file: {} {:?}", path, opts);
         if !opts.is_valid() {
             return ax_err!(InvalidInput);
         }
+        // taken before the lookup, so that the filesystem cannot be unmounted
+        // once the file is found
+        let mount_ref = crate::root::mount_ref(dir_ref, path)?;
 
-        let node_option = crate::root::lookup(dir, path);
+        // `create_new` fails even if the path is a dangling symbolic link
+        let node_option = if opts.nofollow || opts.create_new {
+            crate::root::lookup_nofollow(dir, path)
+        } else {
+            crate::root::lookup(dir, path)
+        };
         let node = if opts.create || opts.create_new {
             match node_option {
                 Ok(node) => {
@@ -138,6 +171,9 @@ impl File {
         };
 
         let attr = node.get_attr()?;
+        if attr.is_symlink() {
+            return ax_err!(FilesystemLoop); // only with `nofollow`
+        }
         if attr.is_dir()
             && (opts.create || opts.create_new || opts.write || opts.append || opts.truncate)
         {
@@ -152,17 +188,20 @@ impl File {
         if opts.truncate {
             node.truncate(0)?;
         }
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
         Ok(Self {
             node: WithCap::new(node, access_cap),
             is_append: opts.append,
             offset: 0,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            mount_ref,
         })
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
     /// [`File`] object.
     pub fn open(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_at(None, path, opts)
+        Self::_open_at(None, None, path, opts)
     }
 
     /// Truncates the file to the specified size.
@@ -221,10 +260,68 @@ impl File {
     pub fn get_attr(&self) -> AxResult<FileAttr> {
         self.node.access(Cap::empty())?.get_attr()
     }
+
+    /// Sets the access and modification time of the file, leaving those given
+    /// `None` unchanged.
+    pub fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> AxResult {
+        self.node.access(Cap::empty())?.set_times(atime, mtime)
+    }
+
+    /// Acquires a whole-file advisory lock of `ty`, or releases the lock if
+    /// `ty` is `None`, like `flock(2)`.
+    ///
+    /// If the lock is held by other opened files in a conflicting type, it
+    /// waits for the release if `wait` is true, otherwise returns
+    /// [`WouldBlock`](axerrno::AxError::WouldBlock).
+    pub fn flock(&self, ty: Option<LockType>, wait: bool) -> AxResult {
+        let node = self.node.access(Cap::empty())?;
+        crate::lock::flock(self.lock_key(node), self.id, ty, wait)
+    }
+
+    /// Acquires an advisory record lock of `ty` on the bytes in `range`, or
+    /// releases the locks in `range` if `ty` is `None`, like `fcntl(2)` with
+    /// `F_SETLK` (or `F_SETLKW` if `wait` is true).
+    ///
+    /// Locks of this file in the range are replaced, and a read lock requires
+    /// the read access, a write lock requires the write access.
+    pub fn lock_range(&self, ty: Option<LockType>, range: Range<u64>, wait: bool) -> AxResult {
+        let cap = match ty {
+            Some(LockType::Shared) => Cap::READ,
+            Some(LockType::Exclusive) => Cap::WRITE,
+            None => Cap::empty(),
+        };
+        let node = self.node.access(cap)?;
+        crate::lock::lock_range(self.lock_key(node), self.id, ty, range, wait)
+    }
+
+    /// Returns a record lock held by other opened files that prevents
+    /// acquiring the lock of `ty` on `range`, like `fcntl(2)` with `F_GETLK`.
+    pub fn test_lock_range(
+        &self,
+        ty: LockType,
+        range: Range<u64>,
+    ) -> AxResult<Option<(LockType, Range<u64>)>> {
+        let node = self.node.access(Cap::empty())?;
+        Ok(crate::lock::test_range(
+            self.lock_key(node),
+            self.id,
+            ty,
+            range,
+        ))
+    }
+
+    fn lock_key(&self, node: &VfsNodeRef) -> NodeKey {
+        NodeKey::new(self.mount_ref.id(), node)
+    }
 }
 
 impl Directory {
-    fn _open_dir_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_dir_at(
+        dir: Option<&VfsNodeRef>,
+        dir_ref: Option<&MountRef>,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         debug!("open dir: {}", path);
         if !opts.read {
             return ax_err!(InvalidInput);
@@ -233,6 +330,7 @@ impl Directory {
             return ax_err!(InvalidInput);
         }
 
+        let mount_ref = crate::root::mount_ref(dir_ref, path)?;
         let node = crate::root::lookup(dir, path)?;
         let attr = node.get_attr()?;
         if !attr.is_dir() {
@@ -247,6 +345,7 @@ impl Directory {
         Ok(Self {
             node: WithCap::new(node, access_cap),
             entry_idx: 0,
+            mount_ref,
         })
     }
 
@@ -261,19 +360,19 @@ impl Directory {
     /// Opens a directory at the path relative to the current directory.
     /// Returns a [`Directory`] object.
     pub fn open_dir(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(None, path, opts)
+        Self::_open_dir_at(None, None, path, opts)
     }
 
     /// Opens a directory at the path relative to this directory. Returns a
     /// [`Directory`] object.
     pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(self.access_at(path)?, path, opts)
+        Self::_open_dir_at(self.access_at(path)?, Some(&self.mount_ref), path, opts)
     }
 
     /// Opens a file at the path relative to this directory. Returns a [`File`]
     /// object.
     pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> AxResult<File> {
-        File::_open_at(self.access_at(path)?, path, opts)
+        File::_open_at(self.access_at(path)?, Some(&self.mount_ref), path, opts)
     }
 
     /// Creates an empty file at the path relative to this directory.
@@ -313,7 +412,9 @@ impl Directory {
 
 impl Drop for File {
     fn drop(&mut self) {
-        unsafe { self.node.access_unchecked().release().ok() };
+        let node = unsafe { self.node.access_unchecked() };
+        crate::lock::release_all(self.lock_key(node), self.id);
+        node.release().ok();
     }
 }
 
@@ -344,6 +445,7 @@ impl fmt::Debug for OpenOptions {
         fmt_opt!(truncate, "TRUNC");
         fmt_opt!(create, "CREATE");
         fmt_opt!(create_new, "CREATE_NEW");
+        fmt_opt!(nofollow, "NOFOLLOW");
         Ok(())
     }
 }
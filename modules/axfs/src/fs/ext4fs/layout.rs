@@ -0,0 +1,626 @@
+//! On-disk structures of the ext2/ext4 filesystem.
+//!
+//! All structures are kept as raw little-endian bytes, so that fields not
+//! known by us are preserved when written back.
+
+use alloc::vec::Vec;
+
+use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+
+/// Byte offset of the superblock from the beginning of the device.
+pub const SUPERBLOCK_OFFSET: u64 = 1024;
+/// Size of the superblock in bytes.
+pub const SUPERBLOCK_SIZE: usize = 1024;
+/// The magic number of ext2/3/4 in the superblock.
+pub const EXT2_MAGIC: u16 = 0xEF53;
+/// Inode number of the root directory.
+pub const ROOT_INO: u32 = 2;
+
+/// Number of block pointers in an inode.
+pub const N_BLOCKS: usize = 15;
+/// Number of direct block pointers in an inode.
+pub const N_DIRECT: usize = 12;
+/// Size of the block pointer array in an inode, also the maximum length of
+/// the target of a fast symbolic link.
+pub const I_BLOCK_SIZE: usize = N_BLOCKS * 4;
+/// Size of the part of the inode we know, the rest of a large inode is kept
+/// untouched.
+pub const INODE_BASE_SIZE: usize = 128;
+
+/// Minimal size of a directory entry (with an empty name).
+pub const DIRENT_HEADER_SIZE: usize = 8;
+/// Maximum length of a file name.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Inode flag: the directory has hashed indexes.
+pub const INODE_INDEX_FL: u32 = 0x1000;
+/// Inode flag: `i_blocks` is in units of filesystem blocks.
+pub const INODE_HUGE_FILE_FL: u32 = 0x4_0000;
+/// Inode flag: the inode uses extents instead of block pointers.
+pub const INODE_EXTENTS_FL: u32 = 0x8_0000;
+/// Inode flag: the inode stores its data in the inode itself.
+pub const INODE_INLINE_DATA_FL: u32 = 0x1000_0000;
+
+/// Magic number of the extent tree node header.
+pub const EXTENT_MAGIC: u16 = 0xF30A;
+/// Size of an extent tree node header, index or leaf entry.
+pub const EXTENT_ENTRY_SIZE: usize = 12;
+/// Extents longer than this are uninitialized (allocated but read as zeros).
+pub const EXTENT_INIT_MAX_LEN: u16 = 32768;
+
+pub const S_IFMT: u16 = 0o170000;
+pub const S_IFLNK: u16 = 0o120000;
+pub const S_IFREG: u16 = 0o100000;
+pub const S_IFDIR: u16 = 0o040000;
+
+/// Feature flags in the superblock.
+pub mod feature {
+    pub const COMPAT_DIR_PREALLOC: u32 = 0x1;
+    pub const COMPAT_IMAGIC_INODES: u32 = 0x2;
+    pub const COMPAT_EXT_ATTR: u32 = 0x8;
+    pub const COMPAT_RESIZE_INODE: u32 = 0x10;
+    pub const COMPAT_DIR_INDEX: u32 = 0x20;
+
+    pub const INCOMPAT_FILETYPE: u32 = 0x2;
+    pub const INCOMPAT_EXTENTS: u32 = 0x40;
+    pub const INCOMPAT_64BIT: u32 = 0x80;
+    pub const INCOMPAT_FLEX_BG: u32 = 0x200;
+    pub const INCOMPAT_CSUM_SEED: u32 = 0x2000;
+    pub const INCOMPAT_LARGEDIR: u32 = 0x4000;
+
+    pub const RO_COMPAT_SPARSE_SUPER: u32 = 0x1;
+    pub const RO_COMPAT_LARGE_FILE: u32 = 0x2;
+
+    /// Compatible features we can maintain when writing, the others (e.g. the
+    /// journal) would be left stale by our writes.
+    pub const COMPAT_WRITE: u32 = COMPAT_DIR_PREALLOC
+        | COMPAT_IMAGIC_INODES
+        | COMPAT_EXT_ATTR
+        | COMPAT_RESIZE_INODE
+        | COMPAT_DIR_INDEX;
+    /// Incompatible features we can read, the filesystem cannot be mounted
+    /// with the others.
+    pub const INCOMPAT_READ: u32 = INCOMPAT_FILETYPE
+        | INCOMPAT_EXTENTS
+        | INCOMPAT_64BIT
+        | INCOMPAT_FLEX_BG
+        | INCOMPAT_CSUM_SEED
+        | INCOMPAT_LARGEDIR;
+    /// Incompatible features we can write.
+    pub const INCOMPAT_WRITE: u32 = INCOMPAT_FILETYPE;
+    /// Read-only compatible features we can write.
+    pub const RO_COMPAT_WRITE: u32 = RO_COMPAT_SPARSE_SUPER | RO_COMPAT_LARGE_FILE;
+}
+
+pub fn get_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+pub fn get_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(buf[off..off + 4].try_into().unwrap())
+}
+
+pub fn set_u16(buf: &mut [u8], off: usize, val: u16) {
+    buf[off..off + 2].copy_from_slice(&val.to_le_bytes());
+}
+
+pub fn set_u32(buf: &mut [u8], off: usize, val: u32) {
+    buf[off..off + 4].copy_from_slice(&val.to_le_bytes());
+}
+
+/// The superblock.
+pub struct Superblock {
+    raw: [u8; SUPERBLOCK_SIZE],
+}
+
+impl Superblock {
+    pub fn from_bytes(raw: [u8; SUPERBLOCK_SIZE]) -> VfsResult<Self> {
+        let sb = Self { raw };
+        if sb.magic() != EXT2_MAGIC {
+            warn!("ext4fs: bad magic number {:#x}", sb.magic());
+            return Err(VfsError::InvalidData);
+        }
+        if sb.log_block_size() > 6 || sb.blocks_per_group() == 0 || sb.inodes_per_group() == 0 {
+            return Err(VfsError::InvalidData);
+        }
+        Ok(sb)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    pub fn inodes_count(&self) -> u32 {
+        get_u32(&self.raw, 0)
+    }
+
+    pub fn blocks_count(&self) -> u64 {
+        let hi = if self.has_incompat(feature::INCOMPAT_64BIT) {
+            get_u32(&self.raw, 0x150) as u64
+        } else {
+            0
+        };
+        get_u32(&self.raw, 4) as u64 | hi << 32
+    }
+
+    pub fn free_blocks_count(&self) -> u32 {
+        get_u32(&self.raw, 12)
+    }
+
+    pub fn set_free_blocks_count(&mut self, count: u32) {
+        set_u32(&mut self.raw, 12, count)
+    }
+
+    pub fn free_inodes_count(&self) -> u32 {
+        get_u32(&self.raw, 16)
+    }
+
+    pub fn set_free_inodes_count(&mut self, count: u32) {
+        set_u32(&mut self.raw, 16, count)
+    }
+
+    pub fn first_data_block(&self) -> u32 {
+        get_u32(&self.raw, 20)
+    }
+
+    fn log_block_size(&self) -> u32 {
+        get_u32(&self.raw, 24)
+    }
+
+    pub fn block_size(&self) -> usize {
+        1024 << self.log_block_size()
+    }
+
+    pub fn blocks_per_group(&self) -> u32 {
+        get_u32(&self.raw, 32)
+    }
+
+    pub fn inodes_per_group(&self) -> u32 {
+        get_u32(&self.raw, 40)
+    }
+
+    pub fn set_mount_time(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 44, secs)
+    }
+
+    pub fn set_write_time(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 48, secs)
+    }
+
+    pub fn inc_mount_count(&mut self) {
+        let count = get_u16(&self.raw, 52);
+        set_u16(&mut self.raw, 52, count.wrapping_add(1))
+    }
+
+    pub fn magic(&self) -> u16 {
+        get_u16(&self.raw, 56)
+    }
+
+    fn rev_level(&self) -> u32 {
+        get_u32(&self.raw, 76)
+    }
+
+    /// The first non-reserved inode.
+    pub fn first_ino(&self) -> u32 {
+        if self.rev_level() == 0 {
+            11
+        } else {
+            get_u32(&self.raw, 84)
+        }
+    }
+
+    pub fn inode_size(&self) -> usize {
+        if self.rev_level() == 0 {
+            INODE_BASE_SIZE
+        } else {
+            get_u16(&self.raw, 88) as usize
+        }
+    }
+
+    pub fn feature_compat(&self) -> u32 {
+        if self.rev_level() == 0 {
+            0
+        } else {
+            get_u32(&self.raw, 92)
+        }
+    }
+
+    pub fn feature_incompat(&self) -> u32 {
+        if self.rev_level() == 0 {
+            0
+        } else {
+            get_u32(&self.raw, 96)
+        }
+    }
+
+    pub fn feature_ro_compat(&self) -> u32 {
+        if self.rev_level() == 0 {
+            0
+        } else {
+            get_u32(&self.raw, 100)
+        }
+    }
+
+    pub fn has_incompat(&self, feature: u32) -> bool {
+        self.feature_incompat() & feature != 0
+    }
+
+    pub fn has_ro_compat(&self, feature: u32) -> bool {
+        self.feature_ro_compat() & feature != 0
+    }
+
+    /// Sets a read-only compatible feature, only for revision 1 and later.
+    pub fn set_ro_compat(&mut self, feature: u32) {
+        let features = self.feature_ro_compat() | feature;
+        set_u32(&mut self.raw, 100, features)
+    }
+
+    /// Size of a group descriptor in bytes.
+    pub fn desc_size(&self) -> usize {
+        if self.has_incompat(feature::INCOMPAT_64BIT) {
+            (get_u16(&self.raw, 0xFE) as usize).max(32)
+        } else {
+            32
+        }
+    }
+
+    pub fn group_count(&self) -> u32 {
+        let data_blocks = self.blocks_count() - self.first_data_block() as u64;
+        data_blocks.div_ceil(self.blocks_per_group() as u64) as u32
+    }
+}
+
+/// The table of block group descriptors.
+pub struct GroupDescTable {
+    raw: Vec<u8>,
+    desc_size: usize,
+    is_64bit: bool,
+}
+
+impl GroupDescTable {
+    pub fn new(raw: Vec<u8>, sb: &Superblock) -> Self {
+        Self {
+            raw,
+            desc_size: sb.desc_size(),
+            is_64bit: sb.has_incompat(feature::INCOMPAT_64BIT),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    fn get_lo_hi(&self, group: u32, lo: usize, hi: usize) -> u64 {
+        let off = group as usize * self.desc_size;
+        let hi = if self.is_64bit {
+            get_u32(&self.raw, off + hi) as u64
+        } else {
+            0
+        };
+        get_u32(&self.raw, off + lo) as u64 | hi << 32
+    }
+
+    fn get_u16(&self, group: u32, field: usize) -> u16 {
+        get_u16(&self.raw, group as usize * self.desc_size + field)
+    }
+
+    fn set_u16(&mut self, group: u32, field: usize, val: u16) {
+        set_u16(&mut self.raw, group as usize * self.desc_size + field, val)
+    }
+
+    pub fn block_bitmap(&self, group: u32) -> u64 {
+        self.get_lo_hi(group, 0, 0x20)
+    }
+
+    pub fn inode_bitmap(&self, group: u32) -> u64 {
+        self.get_lo_hi(group, 4, 0x24)
+    }
+
+    pub fn inode_table(&self, group: u32) -> u64 {
+        self.get_lo_hi(group, 8, 0x28)
+    }
+
+    pub fn free_blocks_count(&self, group: u32) -> u16 {
+        self.get_u16(group, 12)
+    }
+
+    pub fn set_free_blocks_count(&mut self, group: u32, count: u16) {
+        self.set_u16(group, 12, count)
+    }
+
+    pub fn free_inodes_count(&self, group: u32) -> u16 {
+        self.get_u16(group, 14)
+    }
+
+    pub fn set_free_inodes_count(&mut self, group: u32, count: u16) {
+        self.set_u16(group, 14, count)
+    }
+
+    pub fn used_dirs_count(&self, group: u32) -> u16 {
+        self.get_u16(group, 16)
+    }
+
+    pub fn set_used_dirs_count(&mut self, group: u32, count: u16) {
+        self.set_u16(group, 16, count)
+    }
+}
+
+/// The first 128 bytes of an inode, which are the same for all revisions.
+#[derive(Clone)]
+pub struct Inode {
+    raw: [u8; INODE_BASE_SIZE],
+}
+
+impl Inode {
+    /// Creates an empty inode of the given type and permission bits.
+    pub fn new(mode: u16) -> Self {
+        let mut inode = Self {
+            raw: [0; INODE_BASE_SIZE],
+        };
+        inode.set_mode(mode);
+        inode
+    }
+
+    pub fn from_bytes(raw: [u8; INODE_BASE_SIZE]) -> Self {
+        Self { raw }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    pub fn mode(&self) -> u16 {
+        get_u16(&self.raw, 0)
+    }
+
+    pub fn set_mode(&mut self, mode: u16) {
+        set_u16(&mut self.raw, 0, mode)
+    }
+
+    /// The node type from the format bits of the mode, which have the same
+    /// values as [`VfsNodeType`].
+    pub fn node_type(&self) -> VfsResult<VfsNodeType> {
+        Ok(match (self.mode() & S_IFMT) >> 12 {
+            0o1 => VfsNodeType::Fifo,
+            0o2 => VfsNodeType::CharDevice,
+            0o4 => VfsNodeType::Dir,
+            0o6 => VfsNodeType::BlockDevice,
+            0o10 => VfsNodeType::File,
+            0o12 => VfsNodeType::SymLink,
+            0o14 => VfsNodeType::Socket,
+            _ => return Err(VfsError::InvalidData),
+        })
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode() & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode() & S_IFMT == S_IFLNK
+    }
+
+    pub fn size(&self) -> u64 {
+        // `i_size_high` is `i_dir_acl` for directories in ext2
+        let hi = if self.is_dir() {
+            0
+        } else {
+            get_u32(&self.raw, 108) as u64
+        };
+        get_u32(&self.raw, 4) as u64 | hi << 32
+    }
+
+    pub fn set_size(&mut self, size: u64) {
+        set_u32(&mut self.raw, 4, size as u32);
+        if !self.is_dir() {
+            set_u32(&mut self.raw, 108, (size >> 32) as u32);
+        }
+    }
+
+    pub fn atime(&self) -> u32 {
+        get_u32(&self.raw, 8)
+    }
+
+    pub fn set_atime(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 8, secs)
+    }
+
+    pub fn ctime(&self) -> u32 {
+        get_u32(&self.raw, 12)
+    }
+
+    pub fn set_ctime(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 12, secs)
+    }
+
+    pub fn mtime(&self) -> u32 {
+        get_u32(&self.raw, 16)
+    }
+
+    pub fn set_mtime(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 16, secs)
+    }
+
+    pub fn set_dtime(&mut self, secs: u32) {
+        set_u32(&mut self.raw, 20, secs)
+    }
+
+    pub fn links_count(&self) -> u16 {
+        get_u16(&self.raw, 26)
+    }
+
+    pub fn set_links_count(&mut self, count: u16) {
+        set_u16(&mut self.raw, 26, count)
+    }
+
+    /// Number of 512-byte sectors (or filesystem blocks with the
+    /// [`INODE_HUGE_FILE_FL`] flag) used by the inode.
+    pub fn blocks(&self) -> u64 {
+        get_u32(&self.raw, 28) as u64 | (get_u16(&self.raw, 116) as u64) << 32
+    }
+
+    pub fn set_blocks(&mut self, blocks: u64) {
+        set_u32(&mut self.raw, 28, blocks as u32);
+        set_u16(&mut self.raw, 116, (blocks >> 32) as u16);
+    }
+
+    pub fn flags(&self) -> u32 {
+        get_u32(&self.raw, 32)
+    }
+
+    pub fn set_flags(&mut self, flags: u32) {
+        set_u32(&mut self.raw, 32, flags)
+    }
+
+    pub fn block(&self, idx: usize) -> u32 {
+        get_u32(&self.raw, 40 + idx * 4)
+    }
+
+    pub fn set_block(&mut self, idx: usize, blk: u32) {
+        set_u32(&mut self.raw, 40 + idx * 4, blk)
+    }
+
+    /// The raw block pointer array, which holds the extent tree root or the
+    /// target of a fast symbolic link.
+    pub fn block_bytes(&self) -> &[u8] {
+        &self.raw[40..40 + I_BLOCK_SIZE]
+    }
+
+    pub fn block_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.raw[40..40 + I_BLOCK_SIZE]
+    }
+
+    /// The block of extended attributes.
+    pub fn file_acl(&self) -> u32 {
+        get_u32(&self.raw, 104)
+    }
+}
+
+/// An entry in a linear directory block.
+pub struct DirEntry<'a> {
+    pub ino: u32,
+    pub rec_len: usize,
+    pub file_type: u8,
+    pub name: &'a [u8],
+}
+
+impl<'a> DirEntry<'a> {
+    /// Parses the entry at `off` of the directory block.
+    pub fn parse(block: &'a [u8], off: usize) -> VfsResult<Self> {
+        if off + DIRENT_HEADER_SIZE > block.len() {
+            return Err(VfsError::InvalidData);
+        }
+        let rec_len = get_u16(block, off + 4) as usize;
+        let name_len = block[off + 6] as usize;
+        if rec_len < DIRENT_HEADER_SIZE
+            || !rec_len.is_multiple_of(4)
+            || off + rec_len > block.len()
+            || DIRENT_HEADER_SIZE + name_len > rec_len
+        {
+            warn!("ext4fs: corrupted directory entry at offset {}", off);
+            return Err(VfsError::InvalidData);
+        }
+        Ok(Self {
+            ino: get_u32(block, off),
+            rec_len,
+            file_type: block[off + 7],
+            name: &block[off + 8..off + 8 + name_len],
+        })
+    }
+
+    /// Space actually needed by this entry, zero if it's unused.
+    pub fn used_len(&self) -> usize {
+        if self.ino == 0 {
+            0
+        } else {
+            dirent_len(self.name.len())
+        }
+    }
+
+    /// Writes an entry at `off` of the directory block.
+    pub fn write(block: &mut [u8], off: usize, ino: u32, rec_len: usize, ty: u8, name: &[u8]) {
+        set_u32(block, off, ino);
+        set_u16(block, off + 4, rec_len as u16);
+        block[off + 6] = name.len() as u8;
+        block[off + 7] = ty;
+        block[off + 8..off + 8 + name.len()].copy_from_slice(name);
+    }
+
+    /// Changes the inode number of the entry at `off`.
+    pub fn set_ino(block: &mut [u8], off: usize, ino: u32, ty: u8) {
+        set_u32(block, off, ino);
+        block[off + 7] = ty;
+    }
+
+    /// Changes the record length of the entry at `off`.
+    pub fn set_rec_len(block: &mut [u8], off: usize, rec_len: usize) {
+        set_u16(block, off + 4, rec_len as u16);
+    }
+}
+
+/// Length of a directory entry with a name of `name_len` bytes, aligned to 4.
+pub const fn dirent_len(name_len: usize) -> usize {
+    (DIRENT_HEADER_SIZE + name_len + 3) & !3
+}
+
+/// The file type stored in directory entries.
+pub fn dirent_file_type(ty: VfsNodeType) -> u8 {
+    match ty {
+        VfsNodeType::File => 1,
+        VfsNodeType::Dir => 2,
+        VfsNodeType::CharDevice => 3,
+        VfsNodeType::BlockDevice => 4,
+        VfsNodeType::Fifo => 5,
+        VfsNodeType::Socket => 6,
+        VfsNodeType::SymLink => 7,
+    }
+}
+
+/// Header of an extent tree node.
+pub struct ExtentHeader {
+    pub entries: u16,
+    pub depth: u16,
+}
+
+impl ExtentHeader {
+    pub fn parse(node: &[u8]) -> VfsResult<Self> {
+        if get_u16(node, 0) != EXTENT_MAGIC {
+            warn!("ext4fs: bad extent header");
+            return Err(VfsError::InvalidData);
+        }
+        let entries = get_u16(node, 2);
+        if EXTENT_ENTRY_SIZE * (entries as usize + 1) > node.len() {
+            return Err(VfsError::InvalidData);
+        }
+        Ok(Self {
+            entries,
+            depth: get_u16(node, 6),
+        })
+    }
+}
+
+/// Returns the first logical block and the child node block of the `idx`-th
+/// index entry in an extent tree node.
+pub fn extent_index(node: &[u8], idx: usize) -> (u32, u64) {
+    let off = EXTENT_ENTRY_SIZE * (idx + 1);
+    let leaf = get_u32(node, off + 4) as u64 | (get_u16(node, off + 8) as u64) << 32;
+    (get_u32(node, off), leaf)
+}
+
+/// Returns the first logical block, the length, the first physical block and
+/// whether it's initialized of the `idx`-th extent in a leaf node.
+pub fn extent_leaf(node: &[u8], idx: usize) -> (u32, u32, u64, bool) {
+    let off = EXTENT_ENTRY_SIZE * (idx + 1);
+    let len = get_u16(node, off + 4);
+    let start = get_u32(node, off + 8) as u64 | (get_u16(node, off + 6) as u64) << 32;
+    if len > EXTENT_INIT_MAX_LEN {
+        (
+            get_u32(node, off),
+            (len - EXTENT_INIT_MAX_LEN) as u32,
+            start,
+            false,
+        )
+    } else {
+        (get_u32(node, off), len as u32, start, true)
+    }
+}
@@ -0,0 +1,215 @@
+//! The ext2 filesystem, with read-only support for ext4 (e.g. files using
+//! extents).
+//!
+//! Filesystems with features we cannot maintain when writing (such as extents,
+//! journals or metadata checksums) are mounted read-only, and all modifying
+//! operations on them fail with [`VfsError::ReadOnlyFilesystem`].
+
+mod layout;
+mod volume;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsNodeTimes, VfsResult};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
+use axsync::Mutex;
+
+use self::layout::{Inode, ROOT_INO};
+use self::volume::Volume;
+use crate::dev::Disk;
+
+pub struct Ext4FileSystem {
+    volume: Arc<Mutex<Volume>>,
+}
+
+/// A file, directory or symbolic link, identified by its inode number.
+pub struct Ext4Node {
+    volume: Arc<Mutex<Volume>>,
+    ino: u32,
+}
+
+impl Ext4FileSystem {
+    /// Opens the ext2/ext4 volume on `disk`.
+    pub fn new(disk: Disk) -> VfsResult<Self> {
+        Ok(Self {
+            volume: Arc::new(Mutex::new(Volume::open(disk)?)),
+        })
+    }
+}
+
+impl VfsOps for Ext4FileSystem {
+    fn root_dir(&self) -> VfsNodeRef {
+        Ext4Node::new(self.volume.clone(), ROOT_INO)
+    }
+}
+
+impl Ext4Node {
+    fn new(volume: Arc<Mutex<Volume>>, ino: u32) -> Arc<Self> {
+        Arc::new(Self { volume, ino })
+    }
+
+    /// Walks `path` from this node, returns the inode number found.
+    fn walk(&self, vol: &mut Volume, path: &str) -> VfsResult<u32> {
+        let mut ino = self.ino;
+        for name in path.split('/') {
+            match name {
+                "" | "." => {
+                    // the current node must be a directory
+                    if !vol.read_inode(ino)?.is_dir() {
+                        return Err(VfsError::NotADirectory);
+                    }
+                }
+                _ => ino = vol.lookup(ino, name)?,
+            }
+        }
+        Ok(ino)
+    }
+
+    /// Walks to the parent directory of `path`, returns its inode number and
+    /// the last component.
+    fn walk_parent<'a>(&self, vol: &mut Volume, path: &'a str) -> VfsResult<(u32, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (dir_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        Ok((self.walk(vol, dir_path)?, name))
+    }
+
+    fn read_inode(&self) -> VfsResult<Inode> {
+        self.volume.lock().read_inode(self.ino)
+    }
+
+    /// Reads the inode and checks it's a regular file.
+    fn check_file(&self, vol: &mut Volume) -> VfsResult<Inode> {
+        let inode = vol.read_inode(self.ino)?;
+        match inode.node_type()? {
+            VfsNodeType::File => Ok(inode),
+            VfsNodeType::Dir => Err(VfsError::IsADirectory),
+            _ => Err(VfsError::InvalidInput),
+        }
+    }
+}
+
+impl VfsNodeOps for Ext4Node {
+    fn ino(&self) -> Option<u64> {
+        Some(self.ino as u64)
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let inode = self.read_inode()?;
+        let perm = VfsNodePerm::from_bits_truncate(inode.mode());
+        let blocks = if inode.flags() & layout::INODE_HUGE_FILE_FL != 0 {
+            inode.blocks() * (self.volume.lock().block_size() as u64 / 512)
+        } else {
+            inode.blocks()
+        };
+        let times = VfsNodeTimes::new(
+            Duration::from_secs(inode.atime() as u64),
+            Duration::from_secs(inode.mtime() as u64),
+            Duration::from_secs(inode.ctime() as u64),
+        );
+        Ok(VfsNodeAttr::new(perm, inode.node_type()?, inode.size(), blocks).with_times(times))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        self.volume.lock().set_times(self.ino, atime, mtime)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut vol = self.volume.lock();
+        let inode = self.check_file(&mut vol)?;
+        vol.read_file(&inode, offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut vol = self.volume.lock();
+        self.check_file(&mut vol)?;
+        vol.write_file(self.ino, offset, buf)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.volume.lock().sync()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        let mut vol = self.volume.lock();
+        self.check_file(&mut vol)?;
+        vol.truncate_file(self.ino, size)
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut vol = self.volume.lock();
+        let inode = vol.read_inode(self.ino)?;
+        vol.read_link(&inode, buf)
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        if self.ino == ROOT_INO {
+            return None;
+        }
+        let ino = self.volume.lock().lookup(self.ino, "..").ok()?;
+        Some(Self::new(self.volume.clone(), ino))
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        debug!("lookup at ext4fs: {}", path);
+        let ino = self.walk(&mut self.volume.lock(), path)?;
+        Ok(Self::new(self.volume.clone(), ino))
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        debug!("create {:?} at ext4fs: {}", ty, path);
+        let mut vol = self.volume.lock();
+        let (dir, name) = self.walk_parent(&mut vol, path)?;
+        vol.create(dir, name, ty)
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        debug!("remove at ext4fs: {}", path);
+        let mut vol = self.volume.lock();
+        let (dir, name) = self.walk_parent(&mut vol, path)?;
+        vol.unlink(dir, name)
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        debug!("rename at ext4fs: {} -> {}", src_path, dst_path);
+        let mut vol = self.volume.lock();
+        let (src_dir, src_name) = self.walk_parent(&mut vol, src_path)?;
+        let (dst_dir, dst_name) = self.walk_parent(&mut vol, dst_path)?;
+        vol.rename(src_dir, src_name, dst_dir, dst_name)
+    }
+
+    fn link(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        debug!("link at ext4fs: {} -> {}", dst_path, src_path);
+        let mut vol = self.volume.lock();
+        let (src_dir, src_name) = self.walk_parent(&mut vol, src_path)?;
+        let (dst_dir, dst_name) = self.walk_parent(&mut vol, dst_path)?;
+        vol.link(src_dir, src_name, dst_dir, dst_name)
+    }
+
+    fn symlink(&self, target: &str, path: &str) -> VfsResult {
+        debug!("symlink at ext4fs: {} -> {}", path, target);
+        if target.is_empty() {
+            return Err(VfsError::NotFound);
+        }
+        let mut vol = self.volume.lock();
+        let (dir, name) = self.walk_parent(&mut vol, path)?;
+        vol.symlink(dir, name, target)
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let entries = self.volume.lock().read_dir(self.ino)?;
+        let mut count = 0;
+        for ((name, ty), ent) in entries.iter().skip(start_idx).zip(dirents.iter_mut()) {
+            *ent = VfsDirEntry::new(name, *ty);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
@@ -0,0 +1,1047 @@
+//! An opened ext2/ext4 volume: block I/O, allocation, file data and
+//! directory entries.
+//!
+//! All metadata (superblock, group descriptors, bitmaps and inodes) is written
+//! through to the disk at the end of each operation.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::time::Duration;
+
+use axfs_vfs::{VfsError, VfsNodePerm, VfsNodeType, VfsResult};
+
+use super::layout::*;
+use crate::dev::Disk;
+
+/// Maximum depth of an extent tree, to detect loops in corrupted trees.
+const MAX_EXTENT_DEPTH: usize = 5;
+
+/// Returns the current time in seconds, as stored in inodes.
+fn now_secs() -> u32 {
+    axfs_vfs::current_time().as_secs() as u32
+}
+
+pub struct Volume {
+    disk: Disk,
+    sb: Superblock,
+    gdt: GroupDescTable,
+    block_size: usize,
+    group_count: u32,
+    writable: bool,
+    sb_dirty: bool,
+    gdt_dirty: bool,
+}
+
+impl Volume {
+    /// Opens the volume on the disk, the volume is read-only if it has
+    /// features we cannot maintain (e.g. extents).
+    pub fn open(mut disk: Disk) -> VfsResult<Self> {
+        let mut raw = [0; SUPERBLOCK_SIZE];
+        read_exact(&mut disk, SUPERBLOCK_OFFSET, &mut raw)?;
+        let mut sb = Superblock::from_bytes(raw)?;
+
+        let unsupported = sb.feature_incompat() & !feature::INCOMPAT_READ;
+        if unsupported != 0 {
+            warn!(
+                "ext4fs: unsupported incompatible features {:#x}",
+                unsupported
+            );
+            return Err(VfsError::Unsupported);
+        }
+        let writable = sb.feature_compat() & !feature::COMPAT_WRITE == 0
+            && sb.feature_incompat() & !feature::INCOMPAT_WRITE == 0
+            && sb.feature_ro_compat() & !feature::RO_COMPAT_WRITE == 0;
+        if !writable {
+            info!("ext4fs: features not supported for writing, mounted read-only");
+        }
+
+        let block_size = sb.block_size();
+        let group_count = sb.group_count();
+        let mut raw = vec![0; group_count as usize * sb.desc_size()];
+        let gdt_block = sb.first_data_block() as u64 + 1;
+        read_exact(&mut disk, gdt_block * block_size as u64, &mut raw)?;
+        let gdt = GroupDescTable::new(raw, &sb);
+
+        if writable {
+            sb.inc_mount_count();
+            sb.set_mount_time(now_secs());
+        }
+        let mut vol = Self {
+            disk,
+            sb,
+            gdt,
+            block_size,
+            group_count,
+            writable,
+            sb_dirty: writable,
+            gdt_dirty: false,
+        };
+        vol.flush_meta()?;
+        Ok(vol)
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn check_writable(&self) -> VfsResult {
+        if self.writable {
+            Ok(())
+        } else {
+            Err(VfsError::ReadOnlyFilesystem)
+        }
+    }
+
+    /// Writes the superblock and group descriptors back if they are modified.
+    fn flush_meta(&mut self) -> VfsResult {
+        if self.sb_dirty {
+            self.sb.set_write_time(now_secs());
+            write_all(&mut self.disk, SUPERBLOCK_OFFSET, self.sb.as_bytes())?;
+            self.sb_dirty = false;
+        }
+        if self.gdt_dirty {
+            let pos = (self.sb.first_data_block() as u64 + 1) * self.block_size as u64;
+            write_all(&mut self.disk, pos, self.gdt.as_bytes())?;
+            self.gdt_dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Writes all modified blocks of the volume back to the device.
+    pub fn sync(&mut self) -> VfsResult {
+        self.flush_meta()?;
+        self.disk.sync().map_err(|_| VfsError::Io)
+    }
+
+    fn read_block(&mut self, blk: u64) -> VfsResult<Vec<u8>> {
+        let mut buf = vec![0; self.block_size];
+        read_exact(&mut self.disk, blk * self.block_size as u64, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn write_block(&mut self, blk: u64, buf: &[u8]) -> VfsResult {
+        write_all(&mut self.disk, blk * self.block_size as u64, buf)
+    }
+
+    /// Number of 512-byte sectors in a block, the unit of `i_blocks`.
+    fn sectors_per_block(&self) -> u64 {
+        self.block_size as u64 / 512
+    }
+
+    // block and inode allocation:
+
+    fn inode_group(&self, ino: u32) -> u32 {
+        (ino - 1) / self.sb.inodes_per_group()
+    }
+
+    /// Allocates a zeroed block, preferably in the block group `goal`.
+    fn alloc_block(&mut self, goal: u32) -> VfsResult<u64> {
+        let first = self.sb.first_data_block() as u64;
+        let per_group = self.sb.blocks_per_group() as u64;
+        for i in 0..self.group_count {
+            let group = (goal + i) % self.group_count;
+            if self.gdt.free_blocks_count(group) == 0 {
+                continue;
+            }
+            let group_start = first + group as u64 * per_group;
+            let nbits = per_group.min(self.sb.blocks_count() - group_start) as usize;
+            let bitmap_blk = self.gdt.block_bitmap(group);
+            let mut bitmap = self.read_block(bitmap_blk)?;
+            if let Some(bit) = find_zero_bit(&bitmap, 0, nbits) {
+                bitmap[bit / 8] |= 1 << (bit % 8);
+                self.write_block(bitmap_blk, &bitmap)?;
+                let free = self.gdt.free_blocks_count(group);
+                self.gdt.set_free_blocks_count(group, free - 1);
+                let free = self.sb.free_blocks_count();
+                self.sb.set_free_blocks_count(free.saturating_sub(1));
+                self.sb_dirty = true;
+                self.gdt_dirty = true;
+
+                let blk = group_start + bit as u64;
+                self.write_block(blk, &vec![0; self.block_size])?;
+                return Ok(blk);
+            }
+        }
+        Err(VfsError::StorageFull)
+    }
+
+    fn free_block(&mut self, blk: u64) -> VfsResult {
+        let first = self.sb.first_data_block() as u64;
+        let per_group = self.sb.blocks_per_group() as u64;
+        if blk < first || blk >= self.sb.blocks_count() {
+            warn!("ext4fs: freeing block {} out of range", blk);
+            return Err(VfsError::InvalidData);
+        }
+        let group = ((blk - first) / per_group) as u32;
+        let bit = ((blk - first) % per_group) as usize;
+        let bitmap_blk = self.gdt.block_bitmap(group);
+        let mut bitmap = self.read_block(bitmap_blk)?;
+        if bitmap[bit / 8] & (1 << (bit % 8)) == 0 {
+            warn!("ext4fs: freeing free block {}", blk);
+            return Err(VfsError::InvalidData);
+        }
+        bitmap[bit / 8] &= !(1 << (bit % 8));
+        self.write_block(bitmap_blk, &bitmap)?;
+        let free = self.gdt.free_blocks_count(group);
+        self.gdt.set_free_blocks_count(group, free + 1);
+        let free = self.sb.free_blocks_count();
+        self.sb.set_free_blocks_count(free + 1);
+        self.sb_dirty = true;
+        self.gdt_dirty = true;
+        Ok(())
+    }
+
+    /// Allocates an inode, preferably in the block group `goal`, and zeroes it
+    /// on the disk.
+    fn alloc_inode(&mut self, goal: u32, is_dir: bool) -> VfsResult<u32> {
+        let per_group = self.sb.inodes_per_group();
+        for i in 0..self.group_count {
+            let group = (goal + i) % self.group_count;
+            if self.gdt.free_inodes_count(group) == 0 {
+                continue;
+            }
+            // skip the reserved inodes
+            let start = if group == 0 {
+                self.sb.first_ino() as usize - 1
+            } else {
+                0
+            };
+            let bitmap_blk = self.gdt.inode_bitmap(group);
+            let mut bitmap = self.read_block(bitmap_blk)?;
+            if let Some(bit) = find_zero_bit(&bitmap, start, per_group as usize) {
+                bitmap[bit / 8] |= 1 << (bit % 8);
+                self.write_block(bitmap_blk, &bitmap)?;
+                let free = self.gdt.free_inodes_count(group);
+                self.gdt.set_free_inodes_count(group, free - 1);
+                if is_dir {
+                    let dirs = self.gdt.used_dirs_count(group);
+                    self.gdt.set_used_dirs_count(group, dirs + 1);
+                }
+                let free = self.sb.free_inodes_count();
+                self.sb.set_free_inodes_count(free.saturating_sub(1));
+                self.sb_dirty = true;
+                self.gdt_dirty = true;
+
+                let ino = group * per_group + bit as u32 + 1;
+                let pos = self.inode_pos(ino)?;
+                write_all(&mut self.disk, pos, &vec![0; self.sb.inode_size()])?;
+                return Ok(ino);
+            }
+        }
+        Err(VfsError::StorageFull)
+    }
+
+    fn free_inode(&mut self, ino: u32, is_dir: bool) -> VfsResult {
+        let group = self.inode_group(ino);
+        let bit = ((ino - 1) % self.sb.inodes_per_group()) as usize;
+        let bitmap_blk = self.gdt.inode_bitmap(group);
+        let mut bitmap = self.read_block(bitmap_blk)?;
+        bitmap[bit / 8] &= !(1 << (bit % 8));
+        self.write_block(bitmap_blk, &bitmap)?;
+        let free = self.gdt.free_inodes_count(group);
+        self.gdt.set_free_inodes_count(group, free + 1);
+        if is_dir {
+            let dirs = self.gdt.used_dirs_count(group);
+            self.gdt.set_used_dirs_count(group, dirs.saturating_sub(1));
+        }
+        let free = self.sb.free_inodes_count();
+        self.sb.set_free_inodes_count(free + 1);
+        self.sb_dirty = true;
+        self.gdt_dirty = true;
+        Ok(())
+    }
+
+    // inodes:
+
+    fn inode_pos(&self, ino: u32) -> VfsResult<u64> {
+        if ino == 0 || ino > self.sb.inodes_count() {
+            warn!("ext4fs: invalid inode number {}", ino);
+            return Err(VfsError::InvalidData);
+        }
+        let per_group = self.sb.inodes_per_group();
+        let table = self.gdt.inode_table(self.inode_group(ino));
+        let idx = ((ino - 1) % per_group) as u64;
+        Ok(table * self.block_size as u64 + idx * self.sb.inode_size() as u64)
+    }
+
+    pub fn read_inode(&mut self, ino: u32) -> VfsResult<Inode> {
+        let pos = self.inode_pos(ino)?;
+        let mut raw = [0; INODE_BASE_SIZE];
+        read_exact(&mut self.disk, pos, &mut raw)?;
+        Ok(Inode::from_bytes(raw))
+    }
+
+    fn write_inode(&mut self, ino: u32, inode: &Inode) -> VfsResult {
+        let pos = self.inode_pos(ino)?;
+        write_all(&mut self.disk, pos, inode.as_bytes())
+    }
+
+    /// Creates a new inode with the given mode near its parent directory.
+    fn new_inode(&mut self, parent: u32, mode: u16) -> VfsResult<(u32, Inode)> {
+        let is_dir = mode & S_IFMT == S_IFDIR;
+        let ino = self.alloc_inode(self.inode_group(parent), is_dir)?;
+        let mut inode = Inode::new(mode);
+        let now = now_secs();
+        inode.set_atime(now);
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        inode.set_links_count(1);
+        Ok((ino, inode))
+    }
+
+    /// Frees the blocks and the inode whose last link is removed.
+    fn release_inode(&mut self, ino: u32, mut inode: Inode) -> VfsResult {
+        if inode.file_acl() != 0 {
+            warn!("ext4fs: extended attributes of inode {} are leaked", ino);
+        }
+        if !self.is_fast_symlink(&inode) {
+            self.free_blocks_from(&mut inode, 0)?;
+        }
+        inode.set_links_count(0);
+        inode.set_size(0);
+        // the deletion time must not be zero (in use) or look like an inode
+        // number (a link in the orphan list), even without a real-time clock
+        inode.set_dtime(now_secs().max(self.sb.inodes_count() + 1));
+        self.write_inode(ino, &inode)?;
+        self.free_inode(ino, inode.is_dir())
+    }
+
+    /// Adds `delta` to the link count of the inode, and releases it if the
+    /// count drops to zero.
+    fn adjust_links(&mut self, ino: u32, delta: i32) -> VfsResult {
+        let mut inode = self.read_inode(ino)?;
+        let links = (inode.links_count() as i32 + delta).max(0);
+        inode.set_links_count(links as u16);
+        inode.set_ctime(now_secs());
+        if links == 0 {
+            self.release_inode(ino, inode)
+        } else {
+            self.write_inode(ino, &inode)
+        }
+    }
+
+    // block mapping:
+
+    /// Returns the slot in the block pointer array of the inode, and the
+    /// indexes in each level of indirect blocks of the `lblk`-th block.
+    fn block_path(&self, lblk: u64) -> VfsResult<(usize, Vec<usize>)> {
+        if lblk < N_DIRECT as u64 {
+            return Ok((lblk as usize, Vec::new()));
+        }
+        let per_block = (self.block_size / 4) as u64;
+        let mut rel = lblk - N_DIRECT as u64;
+        let mut span = per_block;
+        for depth in 1..=3 {
+            if rel < span {
+                let mut path = vec![0; depth];
+                for idx in path.iter_mut().rev() {
+                    *idx = (rel % per_block) as usize;
+                    rel /= per_block;
+                }
+                return Ok((N_DIRECT + depth - 1, path));
+            }
+            rel -= span;
+            span *= per_block;
+        }
+        Err(VfsError::InvalidInput) // file too large
+    }
+
+    /// Returns the physical block of the `lblk`-th block of the inode, or
+    /// `None` if it's a hole.
+    fn map_block(&mut self, inode: &Inode, lblk: u64) -> VfsResult<Option<u64>> {
+        if inode.flags() & INODE_EXTENTS_FL != 0 {
+            return self.map_extent(inode, lblk);
+        }
+        let (slot, path) = match self.block_path(lblk) {
+            Ok(path) => path,
+            Err(_) => return Ok(None),
+        };
+        let mut blk = inode.block(slot) as u64;
+        for idx in path {
+            if blk == 0 {
+                break;
+            }
+            blk = get_u32(&self.read_block(blk)?, idx * 4) as u64;
+        }
+        Ok(if blk == 0 { None } else { Some(blk) })
+    }
+
+    /// Like [`Self::map_block`], but allocates the missing data and indirect
+    /// blocks.
+    fn map_block_alloc(&mut self, ino: u32, inode: &mut Inode, lblk: u64) -> VfsResult<u64> {
+        if inode.flags() & INODE_EXTENTS_FL != 0 {
+            return Err(VfsError::ReadOnlyFilesystem);
+        }
+        let (slot, path) = self.block_path(lblk)?;
+        let group = self.inode_group(ino);
+        let mut blk = inode.block(slot) as u64;
+        if blk == 0 {
+            blk = self.alloc_block(group)?;
+            inode.set_block(slot, blk as u32);
+            inode.set_blocks(inode.blocks() + self.sectors_per_block());
+        }
+        for idx in path {
+            let mut buf = self.read_block(blk)?;
+            let mut next = get_u32(&buf, idx * 4) as u64;
+            if next == 0 {
+                next = self.alloc_block(group)?;
+                set_u32(&mut buf, idx * 4, next as u32);
+                self.write_block(blk, &buf)?;
+                inode.set_blocks(inode.blocks() + self.sectors_per_block());
+            }
+            blk = next;
+        }
+        Ok(blk)
+    }
+
+    /// Looks up the `lblk`-th block in the extent tree of the inode.
+    fn map_extent(&mut self, inode: &Inode, lblk: u64) -> VfsResult<Option<u64>> {
+        let lblk = match u32::try_from(lblk) {
+            Ok(lblk) => lblk,
+            Err(_) => return Ok(None),
+        };
+        let mut node = inode.block_bytes().to_vec();
+        for _ in 0..MAX_EXTENT_DEPTH {
+            let header = ExtentHeader::parse(&node)?;
+            if header.depth == 0 {
+                for i in 0..header.entries as usize {
+                    let (first, len, start, init) = extent_leaf(&node, i);
+                    if lblk >= first && lblk - first < len {
+                        // uninitialized extents are read as zeros
+                        return Ok(init.then_some(start + (lblk - first) as u64));
+                    }
+                }
+                return Ok(None);
+            }
+            let mut child = None;
+            for i in 0..header.entries as usize {
+                let (first, leaf) = extent_index(&node, i);
+                if first > lblk {
+                    break;
+                }
+                child = Some(leaf);
+            }
+            match child {
+                Some(blk) => node = self.read_block(blk)?,
+                None => return Ok(None),
+            }
+        }
+        warn!("ext4fs: extent tree too deep");
+        Err(VfsError::InvalidData)
+    }
+
+    /// Frees all blocks of the inode from the `keep`-th block.
+    fn free_blocks_from(&mut self, inode: &mut Inode, keep: u64) -> VfsResult {
+        if inode.flags() & INODE_EXTENTS_FL != 0 {
+            return Err(VfsError::ReadOnlyFilesystem);
+        }
+        for slot in keep.min(N_DIRECT as u64) as usize..N_DIRECT {
+            let blk = inode.block(slot);
+            if blk != 0 {
+                self.free_block(blk as u64)?;
+                inode.set_block(slot, 0);
+                inode.set_blocks(inode.blocks().saturating_sub(self.sectors_per_block()));
+            }
+        }
+
+        let per_block = (self.block_size / 4) as u64;
+        let mut base = N_DIRECT as u64;
+        let mut span = per_block;
+        for level in 1..=3 {
+            let slot = N_DIRECT + level - 1;
+            let blk = inode.block(slot) as u64;
+            if blk != 0 && base + span > keep && self.free_tree(inode, blk, level, base, keep)? {
+                self.free_block(blk)?;
+                inode.set_block(slot, 0);
+                inode.set_blocks(inode.blocks().saturating_sub(self.sectors_per_block()));
+            }
+            base += span;
+            span *= per_block;
+        }
+        Ok(())
+    }
+
+    /// Frees the blocks from the `keep`-th block in the indirect block `blk`
+    /// of the given level, whose first entry maps the `base`-th block.
+    ///
+    /// Returns `true` if all entries are freed and `blk` can also be freed.
+    fn free_tree(
+        &mut self,
+        inode: &mut Inode,
+        blk: u64,
+        level: usize,
+        base: u64,
+        keep: u64,
+    ) -> VfsResult<bool> {
+        let per_block = self.block_size / 4;
+        let span = (per_block as u64).pow(level as u32 - 1);
+        let mut buf = self.read_block(blk)?;
+        let mut empty = true;
+        let mut modified = false;
+        for i in 0..per_block {
+            let child = get_u32(&buf, i * 4) as u64;
+            if child == 0 {
+                continue;
+            }
+            let child_base = base + i as u64 * span;
+            let freed = if child_base >= keep {
+                level == 1 || self.free_tree(inode, child, level - 1, child_base, keep)?
+            } else if level > 1 && child_base + span > keep {
+                self.free_tree(inode, child, level - 1, child_base, keep)?
+            } else {
+                false
+            };
+            if freed {
+                self.free_block(child)?;
+                set_u32(&mut buf, i * 4, 0);
+                inode.set_blocks(inode.blocks().saturating_sub(self.sectors_per_block()));
+                modified = true;
+            } else {
+                empty = false;
+            }
+        }
+        if !empty && modified {
+            self.write_block(blk, &buf)?;
+        }
+        Ok(empty)
+    }
+
+    // file data:
+
+    pub fn read_file(&mut self, inode: &Inode, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let size = inode.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        let len = (buf.len() as u64).min(size - offset) as usize;
+        let bs = self.block_size as u64;
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done as u64;
+            let off = (pos % bs) as usize;
+            let n = (self.block_size - off).min(len - done);
+            match self.map_block(inode, pos / bs)? {
+                Some(blk) => read_exact(
+                    &mut self.disk,
+                    blk * bs + off as u64,
+                    &mut buf[done..done + n],
+                )?,
+                None => buf[done..done + n].fill(0),
+            }
+            done += n;
+        }
+        Ok(len)
+    }
+
+    pub fn write_file(&mut self, ino: u32, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        let bs = self.block_size as u64;
+        let mut done = 0;
+        let mut res = Ok(());
+        while done < buf.len() {
+            let pos = offset + done as u64;
+            let off = (pos % bs) as usize;
+            let n = (self.block_size - off).min(buf.len() - done);
+            res = self
+                .map_block_alloc(ino, &mut inode, pos / bs)
+                .and_then(|blk| {
+                    write_all(&mut self.disk, blk * bs + off as u64, &buf[done..done + n])
+                });
+            if res.is_err() {
+                break;
+            }
+            done += n;
+        }
+
+        // record the blocks allocated even if failed
+        let end = offset + done as u64;
+        if end > inode.size() {
+            inode.set_size(end);
+            if end > i32::MAX as u64 && !self.sb.has_ro_compat(feature::RO_COMPAT_LARGE_FILE) {
+                self.sb.set_ro_compat(feature::RO_COMPAT_LARGE_FILE);
+                self.sb_dirty = true;
+            }
+        }
+        if done > 0 {
+            let now = now_secs();
+            inode.set_mtime(now);
+            inode.set_ctime(now);
+        }
+        self.write_inode(ino, &inode)?;
+        self.flush_meta()?;
+        match res {
+            Err(e) if done == 0 => Err(e),
+            _ => Ok(done),
+        }
+    }
+
+    pub fn truncate_file(&mut self, ino: u32, size: u64) -> VfsResult {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        if size < inode.size() {
+            let bs = self.block_size as u64;
+            self.free_blocks_from(&mut inode, size.div_ceil(bs))?;
+            // zero the rest of the last block, which is read if extended later
+            if !size.is_multiple_of(bs) {
+                if let Some(blk) = self.map_block(&inode, size / bs)? {
+                    let off = (size % bs) as usize;
+                    let zeros = vec![0; self.block_size - off];
+                    write_all(&mut self.disk, blk * bs + off as u64, &zeros)?;
+                }
+            }
+        }
+        inode.set_size(size);
+        let now = now_secs();
+        inode.set_mtime(now);
+        inode.set_ctime(now);
+        self.write_inode(ino, &inode)?;
+        self.flush_meta()
+    }
+
+    pub fn set_times(
+        &mut self,
+        ino: u32,
+        atime: Option<Duration>,
+        mtime: Option<Duration>,
+    ) -> VfsResult {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        if let Some(atime) = atime {
+            inode.set_atime(atime.as_secs() as u32);
+        }
+        if let Some(mtime) = mtime {
+            inode.set_mtime(mtime.as_secs() as u32);
+        }
+        inode.set_ctime(now_secs());
+        self.write_inode(ino, &inode)
+    }
+
+    // symbolic links:
+
+    /// Whether the target of the symbolic link is stored in the inode.
+    fn is_fast_symlink(&self, inode: &Inode) -> bool {
+        let acl_blocks = if inode.file_acl() != 0 {
+            self.sectors_per_block()
+        } else {
+            0
+        };
+        inode.is_symlink()
+            && inode.flags() & (INODE_EXTENTS_FL | INODE_INLINE_DATA_FL) == 0
+            && inode.blocks() == acl_blocks
+    }
+
+    pub fn read_link(&mut self, inode: &Inode, buf: &mut [u8]) -> VfsResult<usize> {
+        if !inode.is_symlink() {
+            return Err(VfsError::InvalidInput);
+        }
+        if self.is_fast_symlink(inode) {
+            let len = (inode.size() as usize).min(buf.len()).min(I_BLOCK_SIZE);
+            buf[..len].copy_from_slice(&inode.block_bytes()[..len]);
+            Ok(len)
+        } else {
+            self.read_file(inode, 0, buf)
+        }
+    }
+
+    // directories:
+
+    /// Finds the first directory block on which `f` returns `Some`, returns
+    /// the block number, its contents and the result of `f`.
+    fn find_in_dir<T>(
+        &mut self,
+        dir: &Inode,
+        mut f: impl FnMut(&[u8]) -> VfsResult<Option<T>>,
+    ) -> VfsResult<Option<(u64, Vec<u8>, T)>> {
+        let nblocks = dir.size().div_ceil(self.block_size as u64);
+        for lblk in 0..nblocks {
+            if let Some(blk) = self.map_block(dir, lblk)? {
+                let buf = self.read_block(blk)?;
+                if let Some(res) = f(&buf)? {
+                    return Ok(Some((blk, buf, res)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the entry with the given name in the directory block, returns
+    /// the offsets of its previous entry and itself.
+    fn find_entry_in_block(block: &[u8], name: &[u8]) -> VfsResult<Option<(Option<usize>, usize)>> {
+        let mut prev = None;
+        let mut off = 0;
+        while off < block.len() {
+            let entry = DirEntry::parse(block, off)?;
+            if entry.ino != 0 && entry.name == name {
+                return Ok(Some((prev, off)));
+            }
+            prev = Some(off);
+            off += entry.rec_len;
+        }
+        Ok(None)
+    }
+
+    fn read_dir_inode(&mut self, ino: u32) -> VfsResult<Inode> {
+        let inode = self.read_inode(ino)?;
+        if inode.is_dir() {
+            Ok(inode)
+        } else {
+            Err(VfsError::NotADirectory)
+        }
+    }
+
+    /// Looks up the inode number of `name` in the directory.
+    pub fn lookup(&mut self, dir_ino: u32, name: &str) -> VfsResult<u32> {
+        let dir = self.read_dir_inode(dir_ino)?;
+        let found = self.find_in_dir(&dir, |block| {
+            Self::find_entry_in_block(block, name.as_bytes())
+        })?;
+        match found {
+            Some((_, block, (_, off))) => Ok(DirEntry::parse(&block, off)?.ino),
+            None => Err(VfsError::NotFound),
+        }
+    }
+
+    /// Returns the names and types of all entries in the directory.
+    pub fn read_dir(&mut self, dir_ino: u32) -> VfsResult<Vec<(String, VfsNodeType)>> {
+        let dir = self.read_dir_inode(dir_ino)?;
+        let mut entries = Vec::new();
+        self.find_in_dir(&dir, |block| {
+            let mut off = 0;
+            while off < block.len() {
+                let entry = DirEntry::parse(block, off)?;
+                if entry.ino != 0 {
+                    let name = String::from_utf8_lossy(entry.name).into_owned();
+                    entries.push((name, entry.ino, entry.file_type));
+                }
+                off += entry.rec_len;
+            }
+            Ok(None::<()>)
+        })?;
+
+        let mut res = Vec::with_capacity(entries.len());
+        for (name, ino, file_type) in entries {
+            let ty = match file_type {
+                1 => VfsNodeType::File,
+                2 => VfsNodeType::Dir,
+                3 => VfsNodeType::CharDevice,
+                4 => VfsNodeType::BlockDevice,
+                5 => VfsNodeType::Fifo,
+                6 => VfsNodeType::Socket,
+                7 => VfsNodeType::SymLink,
+                // no file type in the entry, read it from the inode
+                _ => self.read_inode(ino)?.node_type()?,
+            };
+            res.push((name, ty));
+        }
+        Ok(res)
+    }
+
+    fn is_dir_empty(&mut self, dir: &Inode) -> VfsResult<bool> {
+        let found = self.find_in_dir(dir, |block| {
+            let mut off = 0;
+            while off < block.len() {
+                let entry = DirEntry::parse(block, off)?;
+                if entry.ino != 0 && entry.name != b"." && entry.name != b".." {
+                    return Ok(Some(()));
+                }
+                off += entry.rec_len;
+            }
+            Ok(None)
+        })?;
+        Ok(found.is_none())
+    }
+
+    /// Updates the times of the modified directory, and drops its hashed
+    /// indexes which we don't maintain (as Linux's ext2 does).
+    fn touch_dir(&mut self, dir_ino: u32) -> VfsResult {
+        let mut dir = self.read_inode(dir_ino)?;
+        dir.set_flags(dir.flags() & !INODE_INDEX_FL);
+        let now = now_secs();
+        dir.set_mtime(now);
+        dir.set_ctime(now);
+        self.write_inode(dir_ino, &dir)
+    }
+
+    fn add_entry(&mut self, dir_ino: u32, name: &str, ino: u32, ty: VfsNodeType) -> VfsResult {
+        let name = name.as_bytes();
+        let needed = dirent_len(name.len());
+        let file_type = if self.sb.has_incompat(feature::INCOMPAT_FILETYPE) {
+            dirent_file_type(ty)
+        } else {
+            0
+        };
+
+        let mut dir = self.read_dir_inode(dir_ino)?;
+        let found = self.find_in_dir(&dir, |block| {
+            let mut off = 0;
+            while off < block.len() {
+                let entry = DirEntry::parse(block, off)?;
+                if entry.rec_len - entry.used_len() >= needed {
+                    return Ok(Some((off, entry.used_len(), entry.rec_len)));
+                }
+                off += entry.rec_len;
+            }
+            Ok(None)
+        })?;
+        if let Some((blk, mut block, (off, used, rec_len))) = found {
+            // split the free space of an existing entry
+            if used != 0 {
+                DirEntry::set_rec_len(&mut block, off, used);
+            }
+            DirEntry::write(&mut block, off + used, ino, rec_len - used, file_type, name);
+            self.write_block(blk, &block)?;
+        } else {
+            // append a new block to the directory
+            let size = dir.size();
+            let blk = self.map_block_alloc(dir_ino, &mut dir, size / self.block_size as u64)?;
+            let mut block = vec![0; self.block_size];
+            DirEntry::write(&mut block, 0, ino, self.block_size, file_type, name);
+            self.write_block(blk, &block)?;
+            dir.set_size(size + self.block_size as u64);
+            self.write_inode(dir_ino, &dir)?;
+        }
+        self.touch_dir(dir_ino)
+    }
+
+    fn remove_entry(&mut self, dir_ino: u32, name: &str) -> VfsResult {
+        let dir = self.read_dir_inode(dir_ino)?;
+        let found = self.find_in_dir(&dir, |block| {
+            Self::find_entry_in_block(block, name.as_bytes())
+        })?;
+        let (blk, mut block, (prev, off)) = found.ok_or(VfsError::NotFound)?;
+        let rec_len = DirEntry::parse(&block, off)?.rec_len;
+        match prev {
+            // merge into the previous entry
+            Some(prev) => {
+                let prev_len = DirEntry::parse(&block, prev)?.rec_len;
+                DirEntry::set_rec_len(&mut block, prev, prev_len + rec_len);
+            }
+            None => DirEntry::set_ino(&mut block, off, 0, 0),
+        }
+        self.write_block(blk, &block)?;
+        self.touch_dir(dir_ino)
+    }
+
+    /// Points the existing entry `name` in the directory to another inode.
+    fn replace_entry(&mut self, dir_ino: u32, name: &str, ino: u32, ty: VfsNodeType) -> VfsResult {
+        let file_type = if self.sb.has_incompat(feature::INCOMPAT_FILETYPE) {
+            dirent_file_type(ty)
+        } else {
+            0
+        };
+        let dir = self.read_dir_inode(dir_ino)?;
+        let found = self.find_in_dir(&dir, |block| {
+            Self::find_entry_in_block(block, name.as_bytes())
+        })?;
+        let (blk, mut block, (_, off)) = found.ok_or(VfsError::NotFound)?;
+        DirEntry::set_ino(&mut block, off, ino, file_type);
+        self.write_block(blk, &block)?;
+        self.touch_dir(dir_ino)
+    }
+
+    /// Checks the new name doesn't exist in the directory.
+    fn check_new_name(&mut self, dir_ino: u32, name: &str) -> VfsResult {
+        if name.len() > MAX_NAME_LEN {
+            return Err(VfsError::InvalidInput);
+        }
+        match self.lookup(dir_ino, name) {
+            Ok(_) => Err(VfsError::AlreadyExists),
+            Err(VfsError::NotFound) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates a regular file or a directory named `name` in the directory.
+    pub fn create(&mut self, dir_ino: u32, name: &str, ty: VfsNodeType) -> VfsResult {
+        self.check_writable()?;
+        self.check_new_name(dir_ino, name)?;
+        let mode = match ty {
+            VfsNodeType::File => S_IFREG | VfsNodePerm::default_file().bits(),
+            VfsNodeType::Dir => S_IFDIR | VfsNodePerm::default_dir().bits(),
+            _ => return Err(VfsError::Unsupported),
+        };
+        let (ino, mut inode) = self.new_inode(dir_ino, mode)?;
+        if ty == VfsNodeType::Dir {
+            let blk = self.map_block_alloc(ino, &mut inode, 0)?;
+            let file_type = if self.sb.has_incompat(feature::INCOMPAT_FILETYPE) {
+                dirent_file_type(VfsNodeType::Dir)
+            } else {
+                0
+            };
+            let mut block = vec![0; self.block_size];
+            let dot_len = dirent_len(1);
+            DirEntry::write(&mut block, 0, ino, dot_len, file_type, b".");
+            DirEntry::write(
+                &mut block,
+                dot_len,
+                dir_ino,
+                self.block_size - dot_len,
+                file_type,
+                b"..",
+            );
+            self.write_block(blk, &block)?;
+            inode.set_size(self.block_size as u64);
+            inode.set_links_count(2);
+        }
+        self.write_inode(ino, &inode)?;
+        self.add_entry(dir_ino, name, ino, ty)?;
+        if ty == VfsNodeType::Dir {
+            self.adjust_links(dir_ino, 1)?;
+        }
+        self.flush_meta()
+    }
+
+    /// Creates a symbolic link named `name` in the directory.
+    pub fn symlink(&mut self, dir_ino: u32, name: &str, target: &str) -> VfsResult {
+        self.check_writable()?;
+        self.check_new_name(dir_ino, name)?;
+        if target.len() > self.block_size {
+            return Err(VfsError::InvalidInput);
+        }
+        let mode = S_IFLNK | VfsNodePerm::default_symlink().bits();
+        let (ino, mut inode) = self.new_inode(dir_ino, mode)?;
+        if target.len() < I_BLOCK_SIZE {
+            inode.block_bytes_mut()[..target.len()].copy_from_slice(target.as_bytes());
+            inode.set_size(target.len() as u64);
+            self.write_inode(ino, &inode)?;
+        } else {
+            self.write_inode(ino, &inode)?;
+            self.write_file(ino, 0, target.as_bytes())?;
+        }
+        self.add_entry(dir_ino, name, ino, VfsNodeType::SymLink)?;
+        self.flush_meta()
+    }
+
+    /// Removes the entry `name` from the directory, the directory to remove
+    /// must be empty.
+    pub fn unlink(&mut self, dir_ino: u32, name: &str) -> VfsResult {
+        self.check_writable()?;
+        if name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let ino = self.lookup(dir_ino, name)?;
+        let inode = self.read_inode(ino)?;
+        if inode.is_dir() && !self.is_dir_empty(&inode)? {
+            return Err(VfsError::DirectoryNotEmpty);
+        }
+        self.remove_entry(dir_ino, name)?;
+        if inode.is_dir() {
+            self.adjust_links(dir_ino, -1)?;
+            self.release_inode(ino, inode)?;
+        } else {
+            self.adjust_links(ino, -1)?;
+        }
+        self.flush_meta()
+    }
+
+    /// Creates a hard link `dst_name` in `dst_dir` to `src_name` in `src_dir`.
+    pub fn link(
+        &mut self,
+        src_dir: u32,
+        src_name: &str,
+        dst_dir: u32,
+        dst_name: &str,
+    ) -> VfsResult {
+        self.check_writable()?;
+        let ino = self.lookup(src_dir, src_name)?;
+        let inode = self.read_inode(ino)?;
+        if inode.is_dir() {
+            return Err(VfsError::PermissionDenied); // hard links to directories are not allowed
+        }
+        self.check_new_name(dst_dir, dst_name)?;
+        self.add_entry(dst_dir, dst_name, ino, inode.node_type()?)?;
+        self.adjust_links(ino, 1)?;
+        self.flush_meta()
+    }
+
+    /// Moves `src_name` in `src_dir` to `dst_name` in `dst_dir`, replacing the
+    /// existing destination.
+    pub fn rename(
+        &mut self,
+        src_dir: u32,
+        src_name: &str,
+        dst_dir: u32,
+        dst_name: &str,
+    ) -> VfsResult {
+        self.check_writable()?;
+        if [src_name, dst_name].iter().any(|&n| n == "." || n == "..") {
+            return Err(VfsError::InvalidInput);
+        }
+        let ino = self.lookup(src_dir, src_name)?;
+        let inode = self.read_inode(ino)?;
+        let ty = inode.node_type()?;
+        if src_dir == dst_dir && src_name == dst_name {
+            return Ok(());
+        }
+
+        match self.lookup(dst_dir, dst_name) {
+            Ok(dst_ino) if dst_ino == ino => return Ok(()), // hard links of the same inode
+            Ok(dst_ino) => {
+                let dst = self.read_inode(dst_ino)?;
+                if dst.is_dir() {
+                    if !inode.is_dir() {
+                        return Err(VfsError::IsADirectory);
+                    }
+                    if !self.is_dir_empty(&dst)? {
+                        return Err(VfsError::DirectoryNotEmpty);
+                    }
+                } else if inode.is_dir() {
+                    return Err(VfsError::NotADirectory);
+                }
+                self.replace_entry(dst_dir, dst_name, ino, ty)?;
+                if dst.is_dir() {
+                    self.adjust_links(dst_dir, -1)?;
+                    self.release_inode(dst_ino, dst)?;
+                } else {
+                    self.adjust_links(dst_ino, -1)?;
+                }
+            }
+            Err(VfsError::NotFound) => {
+                self.check_new_name(dst_dir, dst_name)?;
+                self.add_entry(dst_dir, dst_name, ino, ty)?;
+            }
+            Err(e) => return Err(e),
+        }
+        self.remove_entry(src_dir, src_name)?;
+
+        if inode.is_dir() && src_dir != dst_dir {
+            self.replace_entry(ino, "..", dst_dir, VfsNodeType::Dir)?;
+            self.adjust_links(src_dir, -1)?;
+            self.adjust_links(dst_dir, 1)?;
+        }
+        let mut inode = self.read_inode(ino)?;
+        inode.set_ctime(now_secs());
+        self.write_inode(ino, &inode)?;
+        self.flush_meta()
+    }
+}
+
+/// Finds the first zero bit in `bitmap[start..end]`.
+fn find_zero_bit(bitmap: &[u8], start: usize, end: usize) -> Option<usize> {
+    let end = end.min(bitmap.len() * 8);
+    (start..end).find(|&bit| bitmap[bit / 8] & (1 << (bit % 8)) == 0)
+}
+
+fn read_exact(disk: &mut Disk, pos: u64, mut buf: &mut [u8]) -> VfsResult {
+    disk.set_position(pos);
+    while !buf.is_empty() {
+        match disk.read_one(buf) {
+            Ok(0) => return Err(VfsError::UnexpectedEof),
+            Ok(n) => buf = &mut buf[n..],
+            Err(_) => return Err(VfsError::Io),
+        }
+    }
+    Ok(())
+}
+
+fn write_all(disk: &mut Disk, pos: u64, mut buf: &[u8]) -> VfsResult {
+    disk.set_position(pos);
+    while !buf.is_empty() {
+        match disk.write_one(buf) {
+            Ok(0) => return Err(VfsError::WriteZero),
+            Ok(n) => buf = &buf[n..],
+            Err(_) => return Err(VfsError::Io),
+        }
+    }
+    Ok(())
+}
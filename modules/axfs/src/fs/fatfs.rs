@@ -1,22 +1,66 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::{format, vec::Vec};
 use core::cell::UnsafeCell;
+use core::ops::Bound;
+use core::time::Duration;
 
-use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsNodeTimes, VfsResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axsync::Mutex;
-use fatfs::{Dir, File, LossyOemCpConverter, NullTimeProvider, Read, Seek, SeekFrom, Write};
+use fatfs::{Dir, DirEntry, File, LossyOemCpConverter, Read, Seek, SeekFrom, Write};
 
 use crate::dev::Disk;
 
 const BLOCK_SIZE: usize = 512;
 
+/// The first and last years that can be represented in FAT timestamps.
+const FAT_YEAR_RANGE: (u64, u64) = (1980, 2107);
+
+type FatDir = Dir<'static, Disk, WallTimeProvider, LossyOemCpConverter>;
+type FatFile = File<'static, Disk, WallTimeProvider, LossyOemCpConverter>;
+type FatDirEntry = DirEntry<'static, Disk, WallTimeProvider, LossyOemCpConverter>;
+
 pub struct FatFileSystem {
-    inner: fatfs::FileSystem<Disk, NullTimeProvider, LossyOemCpConverter>,
+    inner: fatfs::FileSystem<Disk, WallTimeProvider, LossyOemCpConverter>,
     root_dir: UnsafeCell<Option<VfsNodeRef>>,
+    /// Timestamps of the root directory, which has no directory entry.
+    root_times: VfsNodeTimes,
+    inodes: Mutex<InodeTable>,
+}
+
+pub struct FileWrapper<'a> {
+    file: Mutex<File<'a, Disk, WallTimeProvider, LossyOemCpConverter>>,
+    times: Mutex<VfsNodeTimes>,
+    ino: u64,
+}
+
+pub struct DirWrapper<'a> {
+    fs: &'static FatFileSystem,
+    dir: Dir<'a, Disk, WallTimeProvider, LossyOemCpConverter>,
+    times: VfsNodeTimes,
+    /// The canonical path from the root, see [`InodeTable`].
+    path: String,
+    ino: u64,
 }
 
-pub struct FileWrapper<'a>(Mutex<File<'a, Disk, NullTimeProvider, LossyOemCpConverter>>);
-pub struct DirWrapper<'a>(Dir<'a, Disk, NullTimeProvider, LossyOemCpConverter>);
+/// Inode numbers of the files, as FAT has none.
+///
+/// Files are identified by their canonical paths, i.e., the short names of
+/// the path components joined by `/`, since short names are unique in a
+/// directory. Numbers are kept when files are renamed, so that a file keeps
+/// its number as long as it exists.
+struct InodeTable {
+    inos: BTreeMap<String, u64>,
+    next_ino: u64,
+}
+
+/// A node found by [`DirWrapper::walk`].
+enum FoundNode {
+    Dir(FatDir, VfsNodeTimes),
+    File(FatFile, VfsNodeTimes),
+}
 
 unsafe impl Sync for FatFileSystem {}
 unsafe impl Send for FatFileSystem {}
@@ -26,69 +70,229 @@ unsafe impl<'a> Send for DirWrapper<'a> {}
 unsafe impl<'a> Sync for DirWrapper<'a> {}
 
 impl FatFileSystem {
+    /// Formats `disk` as an empty FAT volume.
     #[cfg(feature = "use-ramdisk")]
-    pub fn new(mut disk: Disk) -> Self {
+    pub fn format(disk: &mut Disk) -> VfsResult {
         let opts = fatfs::FormatVolumeOptions::new();
-        fatfs::format_volume(&mut disk, opts).expect("failed to format volume");
-        let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
-            .expect("failed to initialize FAT filesystem");
-        Self {
+        fatfs::format_volume(disk, opts).map_err(as_vfs_err)
+    }
+
+    /// Opens the FAT volume on `disk`.
+    ///
+    /// The filesystem is never freed, since its nodes borrow it for `'static`.
+    pub fn new(disk: Disk) -> VfsResult<Arc<Self>> {
+        let inner = fatfs::FileSystem::new(
+            disk,
+            fatfs::FsOptions::new().time_provider(WallTimeProvider),
+        )
+        .map_err(as_vfs_err)?;
+        let fs = Arc::new(Self {
             inner,
             root_dir: UnsafeCell::new(None),
-        }
+            // the root directory has no directory entry, use the mount time
+            root_times: VfsNodeTimes::now(),
+            inodes: Mutex::new(InodeTable::new()),
+        });
+        // leak one reference to make it `'static`
+        let static_fs: &'static Self = unsafe { &*Arc::into_raw(fs.clone()) };
+        static_fs.init();
+        Ok(fs)
+    }
+
+    fn init(&'static self) {
+        // must be called before later operations
+        let root_dir = self.new_dir(self.inner.root_dir(), self.root_times, String::new());
+        unsafe { *self.root_dir.get() = Some(root_dir) }
+    }
+
+    fn new_file(&'static self, file: FatFile, times: VfsNodeTimes, path: &str) -> Arc<FileWrapper> {
+        Arc::new(FileWrapper {
+            file: Mutex::new(file),
+            times: Mutex::new(times),
+            ino: self.inodes.lock().get(path),
+        })
     }
 
-    #[cfg(not(feature = "use-ramdisk"))]
-    pub fn new(disk: Disk) -> Self {
-        let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
-            .expect("failed to initialize FAT filesystem");
+    fn new_dir(&'static self, dir: FatDir, times: VfsNodeTimes, path: String) -> Arc<DirWrapper> {
+        Arc::new(DirWrapper {
+            fs: self,
+            dir,
+            times,
+            ino: self.inodes.lock().get(&path),
+            path,
+        })
+    }
+
+    /// Returns the timestamps of `dir`, which is looked up by `..`.
+    fn dir_times(&self, dir: &FatDir) -> VfsNodeTimes {
+        // the `.` entry of a subdirectory is created along with the directory
+        // and has the same timestamps, while the root directory has none
+        dir.iter()
+            .filter_map(Result::ok)
+            .find(|entry| entry.short_file_name() == ".")
+            .map_or(self.root_times, |entry| entry_times(&entry))
+    }
+}
+
+impl InodeTable {
+    const fn new() -> Self {
         Self {
-            inner,
-            root_dir: UnsafeCell::new(None),
+            inos: BTreeMap::new(),
+            next_ino: 1,
         }
     }
 
-    pub fn init(&'static self) {
-        // must be called before later operations
-        unsafe { *self.root_dir.get() = Some(Self::new_dir(self.inner.root_dir())) }
+    /// Returns the inode number of the file at `path`, assigns a new one if
+    /// it has none.
+    fn get(&mut self, path: &str) -> u64 {
+        if let Some(&ino) = self.inos.get(path) {
+            return ino;
+        }
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inos.insert(path.into(), ino);
+        ino
     }
 
-    fn new_file(file: File<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<FileWrapper> {
-        Arc::new(FileWrapper(Mutex::new(file)))
+    /// Removes the files at `path` and in it, returns their paths relative to
+    /// `path` with the inode numbers.
+    fn remove(&mut self, path: &str) -> Vec<(String, u64)> {
+        let prefix = format!("{}/", path);
+        let paths: Vec<String> = self
+            .inos
+            .range::<str, _>((Bound::Included(path), Bound::Unbounded))
+            .map(|(p, _)| p)
+            .take_while(|p| p.starts_with(path))
+            .filter(|p| *p == path || p.starts_with(&prefix))
+            .cloned()
+            .collect();
+        paths
+            .into_iter()
+            .map(|p| {
+                let ino = self.inos.remove(&p).unwrap();
+                (String::from(&p[path.len()..]), ino)
+            })
+            .collect()
     }
 
-    fn new_dir(dir: Dir<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<DirWrapper> {
-        Arc::new(DirWrapper(dir))
+    /// Moves the inode numbers of the files at `src_path` and in it to
+    /// `dst_path`, which replaces the files there.
+    fn rename(&mut self, src_path: &str, dst_path: &str) {
+        let moved = self.remove(src_path);
+        self.remove(dst_path);
+        for (rest, ino) in moved {
+            self.inos.insert(format!("{}{}", dst_path, rest), ino);
+        }
     }
 }
 
 impl VfsNodeOps for FileWrapper<'static> {
     axfs_vfs::impl_vfs_non_dir_default! {}
 
+    fn ino(&self) -> Option<u64> {
+        Some(self.ino)
+    }
+
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        let size = self.0.lock().seek(SeekFrom::End(0)).map_err(as_vfs_err)?;
+        let size = self
+            .file
+            .lock()
+            .seek(SeekFrom::End(0))
+            .map_err(as_vfs_err)?;
         let blocks = (size + BLOCK_SIZE as u64 - 1) / BLOCK_SIZE as u64;
         // FAT fs doesn't support permissions, we just set everything to 755
         let perm = VfsNodePerm::from_bits_truncate(0o755);
-        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, size, blocks))
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, size, blocks).with_times(*self.times.lock()))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        let mut file = self.file.lock();
+        let mut times = self.times.lock();
+        // FAT only records the date of the last access
+        if let Some(atime) = atime {
+            let date = datetime_from_duration(atime).date;
+            file.set_accessed(date);
+            times.atime = duration_from_date(date);
+        }
+        if let Some(mtime) = mtime {
+            let datetime = datetime_from_duration(mtime);
+            file.set_modified(datetime);
+            times.mtime = duration_from_datetime(datetime);
+        }
+        file.flush().map_err(as_vfs_err)
     }
 
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
         file.read(buf).map_err(as_vfs_err)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
-        file.write(buf).map_err(as_vfs_err)
+        let len = file.write(buf).map_err(as_vfs_err)?;
+        // `fatfs` updates the modification time of the entry on write
+        self.times.lock().mtime = now_duration();
+        Ok(len)
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(size)).map_err(as_vfs_err)?; // TODO: more efficient
-        file.truncate().map_err(as_vfs_err)
+        file.truncate().map_err(as_vfs_err)?;
+        self.times.lock().mtime = now_duration();
+        Ok(())
+    }
+}
+
+impl DirWrapper<'static> {
+    /// Walks `path` from this directory, returns the node found and its
+    /// canonical path.
+    ///
+    /// Names are case-insensitive, and can be either long or short names. The
+    /// entries are searched to get the timestamps and short names, as
+    /// `fatfs::File` and `fatfs::Dir` do not keep them.
+    fn walk(&self, path: &str) -> VfsResult<(FoundNode, String)> {
+        let mut dir = self.dir.clone();
+        let mut times = self.times;
+        let mut canonical = self.path.clone();
+        let mut names = path.split('/').filter(|s| !s.is_empty() && *s != ".");
+        let mut next = names.next();
+        while let Some(name) = next {
+            next = names.next();
+            if name == ".." {
+                dir = dir.open_dir("..").or(Err(VfsError::NotFound))?;
+                times = self.fs.dir_times(&dir);
+                canonical.truncate(canonical.rfind('/').unwrap_or(0));
+                continue;
+            }
+            let entry = find_entry(&dir, name)?;
+            if !canonical.is_empty() {
+                canonical.push('/');
+            }
+            canonical.push_str(&entry.short_file_name());
+            if !entry.is_dir() {
+                return match next {
+                    Some(_) => Err(VfsError::NotADirectory),
+                    None => Ok((
+                        FoundNode::File(entry.to_file(), entry_times(&entry)),
+                        canonical,
+                    )),
+                };
+            }
+            dir = entry.to_dir();
+            times = entry_times(&entry);
+        }
+        Ok((FoundNode::Dir(dir, times), canonical))
+    }
+
+    /// Returns a path next to `path` that does not exist yet.
+    fn unused_name(&self, path: &str) -> String {
+        (0..)
+            .map(|i| format!("{}.{}~", path, i))
+            .find(|p| self.dir.open_file(p).is_err() && self.dir.open_dir(p).is_err())
+            .unwrap()
     }
 }
 
@@ -102,32 +306,29 @@ impl VfsNodeOps for DirWrapper<'static> {
             VfsNodeType::Dir,
             BLOCK_SIZE as u64,
             1,
-        ))
+        )
+        .with_times(self.times))
+    }
+
+    fn ino(&self) -> Option<u64> {
+        Some(self.ino)
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
-        self.0
-            .open_dir("..")
-            .map_or(None, |dir| Some(FatFileSystem::new_dir(dir)))
+        let dir = self.dir.open_dir("..").ok()?;
+        let times = self.fs.dir_times(&dir);
+        let path = self.path.rsplit_once('/').map_or("", |(parent, _)| parent);
+        Some(self.fs.new_dir(dir, times, path.into()))
     }
 
     fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
         debug!("lookup at fatfs: {}", path);
-        let path = path.trim_matches('/');
-        if path.is_empty() || path == "." {
+        if path.split('/').all(|s| s.is_empty() || s == ".") {
             return Ok(self.clone());
         }
-        if let Some(rest) = path.strip_prefix("./") {
-            return self.lookup(rest);
-        }
-
-        // TODO: use `fatfs::Dir::find_entry`, but it's not public.
-        if let Ok(file) = self.0.open_file(path) {
-            Ok(FatFileSystem::new_file(file))
-        } else if let Ok(dir) = self.0.open_dir(path) {
-            Ok(FatFileSystem::new_dir(dir))
-        } else {
-            Err(VfsError::NotFound)
+        match self.walk(path)? {
+            (FoundNode::Dir(dir, times), path) => Ok(self.fs.new_dir(dir, times, path)),
+            (FoundNode::File(file, times), path) => Ok(self.fs.new_file(file, times, &path)),
         }
     }
 
@@ -143,11 +344,11 @@ impl VfsNodeOps for DirWrapper<'static> {
 
         match ty {
             VfsNodeType::File => {
-                self.0.create_file(path).map_err(as_vfs_err)?;
+                self.dir.create_file(path).map_err(as_vfs_err)?;
                 Ok(())
             }
             VfsNodeType::Dir => {
-                self.0.create_dir(path).map_err(as_vfs_err)?;
+                self.dir.create_dir(path).map_err(as_vfs_err)?;
                 Ok(())
             }
             _ => Err(VfsError::Unsupported),
@@ -161,11 +362,87 @@ impl VfsNodeOps for DirWrapper<'static> {
         if let Some(rest) = path.strip_prefix("./") {
             return self.remove(rest);
         }
-        self.0.remove(path).map_err(as_vfs_err)
+        let (_, canonical) = self.walk(path)?;
+        self.dir.remove(path).map_err(as_vfs_err)?;
+        self.fs.inodes.lock().remove(&canonical);
+        Ok(())
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        debug!("rename at fatfs: {} -> {}", src_path, dst_path);
+        let src_path = src_path.trim_matches('/');
+        let dst_path = dst_path.trim_matches('/');
+        let src_is_dir = if self.dir.open_file(src_path).is_ok() {
+            false
+        } else if self.dir.open_dir(src_path).is_ok() {
+            true
+        } else {
+            return Err(VfsError::NotFound);
+        };
+
+        // `fatfs` does not replace an existing destination, so move it aside
+        // first, and put it back if the rename fails. Names are
+        // case-insensitive, the destination may be the source itself.
+        let mut backup = None;
+        if !src_path.eq_ignore_ascii_case(dst_path) {
+            let dst_exists = if self.dir.open_file(dst_path).is_ok() {
+                if src_is_dir {
+                    return Err(VfsError::NotADirectory);
+                }
+                true
+            } else if let Ok(dir) = self.dir.open_dir(dst_path) {
+                if !src_is_dir {
+                    return Err(VfsError::IsADirectory);
+                }
+                let is_empty = dir.iter().all(|entry| {
+                    entry.map_or(true, |e| matches!(e.short_file_name().as_str(), "." | ".."))
+                });
+                if !is_empty {
+                    return Err(VfsError::DirectoryNotEmpty);
+                }
+                true
+            } else {
+                false
+            };
+            if dst_exists {
+                let tmp_path = self.unused_name(dst_path);
+                self.dir
+                    .rename(dst_path, &self.dir, &tmp_path)
+                    .map_err(as_vfs_err)?;
+                backup = Some(tmp_path);
+            }
+        } else if src_path == dst_path {
+            return Ok(());
+        }
+
+        let (_, src_canonical) = self.walk(src_path)?;
+        let res = self.dir.rename(src_path, &self.dir, dst_path);
+        if let Some(tmp_path) = backup {
+            if res.is_ok() {
+                if let Err(e) = self.dir.remove(&tmp_path) {
+                    warn!("fatfs: failed to remove {}: {:?}", tmp_path, e);
+                }
+            } else if let Err(e) = self.dir.rename(&tmp_path, &self.dir, dst_path) {
+                warn!(
+                    "fatfs: failed to restore {} from {}: {:?}",
+                    dst_path, tmp_path, e
+                );
+            }
+        }
+        res.map_err(as_vfs_err)?;
+        // the short name may be changed
+        let (_, dst_canonical) = self.walk(dst_path)?;
+        self.fs.inodes.lock().rename(&src_canonical, &dst_canonical);
+        Ok(())
+    }
+
+    fn link(&self, _src_path: &str, _dst_path: &str) -> VfsResult {
+        // FAT does not support hard links
+        Err(VfsError::Unsupported)
     }
 
     fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
-        let mut iter = self.0.iter().skip(start_idx);
+        let mut iter = self.dir.iter().skip(start_idx);
         for (i, out_entry) in dirents.iter_mut().enumerate() {
             let x = iter.next();
             match x {
@@ -231,7 +508,7 @@ impl Write for Disk {
         Ok(write_len)
     }
     fn flush(&mut self) -> Result<(), Self::Error> {
-        Ok(())
+        self.sync().map_err(|_| ())
     }
 }
 
@@ -252,6 +529,102 @@ impl Seek for Disk {
     }
 }
 
+/// A [`fatfs::TimeProvider`] that reads the current time from the wall clock,
+/// see [`axfs_vfs::current_time`].
+///
+/// FAT timestamps are in local time, which is assumed to be UTC. If the wall
+/// clock is not set, the DOS epoch (1980-01-01 00:00:00) is used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallTimeProvider;
+
+impl WallTimeProvider {
+    fn now() -> fatfs::DateTime {
+        datetime_from_duration(axfs_vfs::current_time())
+    }
+}
+
+impl fatfs::TimeProvider for WallTimeProvider {
+    fn get_current_date(&self) -> fatfs::Date {
+        Self::now().date
+    }
+
+    fn get_current_date_time(&self) -> fatfs::DateTime {
+        Self::now()
+    }
+}
+
+/// Converts the time since the UNIX epoch to a FAT timestamp.
+///
+/// FAT timestamps are in local time, which is assumed to be UTC. Times out of
+/// the range of FAT (1980 to 2107) are clamped.
+fn datetime_from_duration(time: Duration) -> fatfs::DateTime {
+    let secs = time.as_secs();
+    let (year, month, day) = driver_rtc::civil_from_days(secs / driver_rtc::SECS_PER_DAY);
+    let (year, month, day, secs_of_day, millis) = if year < FAT_YEAR_RANGE.0 {
+        (FAT_YEAR_RANGE.0, 1, 1, 0, 0)
+    } else if year > FAT_YEAR_RANGE.1 {
+        (FAT_YEAR_RANGE.1, 12, 31, driver_rtc::SECS_PER_DAY - 1, 999)
+    } else {
+        let millis = time.subsec_millis() as u64;
+        (year, month, day, secs % driver_rtc::SECS_PER_DAY, millis)
+    };
+    fatfs::DateTime::new(
+        fatfs::Date::new(year as u16, month as u16, day as u16),
+        fatfs::Time::new(
+            (secs_of_day / 3600) as u16,
+            (secs_of_day / 60 % 60) as u16,
+            (secs_of_day % 60) as u16,
+            millis as u16,
+        ),
+    )
+}
+
+/// Converts a FAT date to the time since the UNIX epoch. Returns zero if the
+/// date is invalid (e.g., not set).
+fn duration_from_date(date: fatfs::Date) -> Duration {
+    if date.month == 0 || date.day == 0 {
+        return Duration::ZERO;
+    }
+    let days = driver_rtc::days_from_civil(date.year as u64, date.month as u64, date.day as u64);
+    Duration::from_secs(days.unwrap_or(0) * driver_rtc::SECS_PER_DAY)
+}
+
+/// Converts a FAT timestamp to the time since the UNIX epoch.
+fn duration_from_datetime(datetime: fatfs::DateTime) -> Duration {
+    let date = duration_from_date(datetime.date);
+    if date.is_zero() {
+        return date;
+    }
+    let time = &datetime.time;
+    let secs = time.hour as u64 * 3600 + time.min as u64 * 60 + time.sec as u64;
+    date + Duration::from_secs(secs) + Duration::from_millis(time.millis as u64)
+}
+
+/// Returns the current time as it will be recorded by [`WallTimeProvider`].
+fn now_duration() -> Duration {
+    duration_from_datetime(WallTimeProvider::now())
+}
+
+/// Finds the entry `name` in `dir`, either by the long or the short name.
+// TODO: use `fatfs::Dir::find_entry`, but it's not public.
+fn find_entry(dir: &FatDir, name: &str) -> VfsResult<FatDirEntry> {
+    dir.iter()
+        .filter_map(Result::ok)
+        .find(|entry| {
+            entry.file_name().eq_ignore_ascii_case(name)
+                || entry.short_file_name().eq_ignore_ascii_case(name)
+        })
+        .ok_or(VfsError::NotFound)
+}
+
+fn entry_times(entry: &DirEntry<'_, Disk, WallTimeProvider, LossyOemCpConverter>) -> VfsNodeTimes {
+    VfsNodeTimes::new(
+        duration_from_date(entry.accessed()),
+        duration_from_datetime(entry.modified()),
+        duration_from_datetime(entry.created()),
+    )
+}
+
 const fn as_vfs_err(err: fatfs::Error<()>) -> VfsError {
     use fatfs::Error::*;
     match err {
@@ -1,13 +1,17 @@
-cfg_if::cfg_if! {
-    if #[cfg(feature = "myfs")] {
-        pub mod myfs;
-    } else if #[cfg(feature = "fatfs")] {
-        pub mod fatfs;
-    }
-}
+#[cfg(feature = "myfs")]
+pub mod myfs;
+
+#[cfg(feature = "ext4fs")]
+pub mod ext4fs;
+
+#[cfg(feature = "fatfs")]
+pub mod fatfs;
 
 #[cfg(feature = "devfs")]
 pub use axfs_devfs as devfs;
 
+#[cfg(feature = "procfs")]
+pub use axfs_procfs as procfs;
+
 #[cfg(feature = "ramfs")]
 pub use axfs_ramfs as ramfs;
@@ -0,0 +1,130 @@
+//! Mounting partitions by a fstab-like list.
+//!
+//! After the root filesystem is mounted, the filesystems listed in
+//! `/etc/fstab` on it are mounted. Each line has the form:
+//!
+//! ```text
+//! <source> <mount point> <type> [<options>]
+//! ```
+//!
+//! - `<source>` specifies the partition, see [`mount_device`].
+//! - `<type>` is `auto` to detect the filesystem type, `fatfs` (or `vfat`,
+//!   `fat`, `msdos`), or `ext4fs` (or `ext2`, `ext3`, `ext4`).
+//! - `<options>` and other fields are ignored.
+//!
+//! Empty lines and lines starting with `#` are ignored, so are entries of the
+//! root (`/`) or with the type `swap` or `none`.
+
+use alloc::{format, sync::Arc};
+use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axfs_vfs::VfsOps;
+
+use crate::dev::Disk;
+use crate::partition::{self, Partition};
+
+/// The path of the list of filesystems to mount on the root filesystem.
+pub(crate) const FSTAB_PATH: &str = "/etc/fstab";
+
+/// The type of the root filesystem.
+#[cfg(not(feature = "myfs"))]
+pub(crate) const ROOT_FS_TYPE: &str = if cfg!(feature = "ext4fs") {
+    "ext4fs"
+} else {
+    "fatfs"
+};
+
+/// Returns the partition for the root filesystem.
+///
+/// It can be specified at build time by the environment variable `AX_ROOT`,
+/// in the same form as the source of fstab entries (e.g., `LABEL=rootfs`).
+/// Otherwise, it's the first partition containing the filesystem of
+/// [`ROOT_FS_TYPE`], or the first partition if none does. Returns `None` if
+/// there are no partitions.
+pub(crate) fn root_partition() -> Option<Partition> {
+    if let Some(source) = option_env!("AX_ROOT") {
+        return Some(
+            partition::find_partition(source)
+                .unwrap_or_else(|| panic!("root partition {:?} not found", source)),
+        );
+    }
+    let parts = partition::partitions();
+    #[cfg(all(not(feature = "myfs"), not(feature = "use-ramdisk")))]
+    if let Some(part) = parts
+        .iter()
+        .find(|part| part.info.fs_type == Some(ROOT_FS_TYPE))
+    {
+        return Some(part.clone());
+    }
+    parts.into_iter().next()
+}
+
+/// Opens the filesystem of `fs_type` on `disk`, returns it with the canonical
+/// filesystem type.
+pub(crate) fn open_fs(disk: Disk, fs_type: &str) -> AxResult<(Arc<dyn VfsOps>, &'static str)> {
+    match fs_type {
+        #[cfg(feature = "fatfs")]
+        "fatfs" | "vfat" | "fat" | "msdos" => {
+            Ok((crate::fs::fatfs::FatFileSystem::new(disk)?, "fatfs"))
+        }
+        #[cfg(feature = "ext4fs")]
+        "ext4fs" | "ext2" | "ext3" | "ext4" => Ok((
+            Arc::new(crate::fs::ext4fs::Ext4FileSystem::new(disk)?),
+            "ext4fs",
+        )),
+        _ => ax_err!(Unsupported, "unsupported filesystem type"),
+    }
+}
+
+/// Mounts the filesystem on the partition specified by `source` at `path`.
+///
+/// `source` is one of `LABEL=<label>`, `UUID=<uuid>`, `PARTLABEL=<label>`,
+/// `PARTUUID=<uuid>`, or the device name (e.g., `vda2` or `/dev/vda2`). If
+/// `fs_type` is `auto`, the filesystem type is detected.
+pub(crate) fn mount_device(source: &str, path: &str, fs_type: &str) -> AxResult {
+    let part = partition::find_partition(source)
+        .ok_or_else(|| ax_err_type!(NotFound, "partition not found"))?;
+    let dev_path = format!("/dev/{}", part.info.name);
+    if crate::root::mounts().iter().any(|m| m.source == dev_path) {
+        return ax_err!(ResourceBusy, "partition already mounted");
+    }
+    let fs_type = match fs_type {
+        "auto" => part
+            .info
+            .fs_type
+            .ok_or_else(|| ax_err_type!(Unsupported, "unknown filesystem type"))?,
+        _ => fs_type,
+    };
+    let (fs, fs_type) = open_fs(part.disk(), fs_type)?;
+    crate::root::mount(&dev_path, path, fs_type, fs)
+}
+
+/// Mounts all filesystems listed in the fstab file at `path`, if it exists.
+pub(crate) fn mount_all(path: &str) {
+    let fstab = match crate::api::read_to_string(path) {
+        Ok(fstab) => fstab,
+        Err(AxError::NotFound) => return,
+        Err(e) => {
+            warn!("failed to read {}: {:?}", path, e);
+            return;
+        }
+    };
+    for line in fstab.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(source), Some(mount_path), Some(fs_type)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            warn!("{}: invalid entry {:?}", path, line);
+            continue;
+        };
+        if mount_path == "/" || fs_type == "swap" || fs_type == "none" {
+            continue;
+        }
+        match mount_device(source, mount_path, fs_type) {
+            Ok(()) => info!("  mounted {} at {} ({})", source, mount_path, fs_type),
+            Err(e) => warn!("failed to mount {} at {}: {:?}", source, mount_path, e),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Initial RAM filesystem unpacked from an archive embedded in the kernel.
+//!
+//! The archive is specified by the environment variable `AX_INITRAMFS` at
+//! build time (set by `make INITRAMFS=<path>`). It can be a cpio archive in the
+//! `newc` format (e.g., created by `cpio -o -H newc`), or a tar archive in the
+//! ustar format, including GNU long names and pax extended headers.
+//!
+//! Regular files, directories, symbolic links and hard links are unpacked,
+//! with their modification times. Other types of entries (e.g., device nodes)
+//! are skipped, and permission bits are ignored.
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+use axerrno::{ax_err, ax_err_type, AxResult};
+use axfs_ramfs::RamFileSystem;
+use axfs_vfs::{VfsNodeRef, VfsNodeType, VfsOps};
+use core::{str, time::Duration};
+
+/// The archive embedded by the build script, empty if not specified.
+static ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/initramfs.img"));
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+enum EntryKind<'a> {
+    File(&'a [u8]),
+    Dir,
+    Symlink(&'a str),
+    /// A hard link to the entry at the path. The data, if not empty, is
+    /// written to the linked file (cpio stores it with the last link).
+    HardLink(String, &'a [u8]),
+}
+
+struct Entry<'a> {
+    path: String,
+    kind: EntryKind<'a>,
+    mtime: u64,
+}
+
+/// Creates a RAM filesystem from the embedded archive, returns `None` if no
+/// archive is embedded.
+pub(crate) fn new_initramfs() -> Option<Arc<RamFileSystem>> {
+    if ARCHIVE.is_empty() {
+        return None;
+    }
+    let fs = RamFileSystem::new();
+    unpack(&fs.root_dir(), ARCHIVE).expect("failed to unpack the initramfs");
+    Some(Arc::new(fs))
+}
+
+/// Unpacks the cpio or tar `archive` to the directory `root`.
+fn unpack(root: &VfsNodeRef, archive: &[u8]) -> AxResult {
+    let mut dir_times = Vec::new();
+    let mut add_entry = |entry: Entry| -> AxResult {
+        // paths in archives are usually relative (e.g., "./etc/fstab")
+        let path = axfs_vfs::path::canonicalize(&alloc::format!("/{}", entry.path));
+        let path = path.trim_matches('/');
+        let mtime = Some(Duration::from_secs(entry.mtime));
+        if path.is_empty() {
+            dir_times.push((String::new(), mtime));
+            return Ok(());
+        }
+        if let Some(pos) = path.rfind('/') {
+            create_dirs(root, &path[..pos])?;
+        }
+        let node = match entry.kind {
+            EntryKind::Dir => {
+                create_dirs(root, path)?;
+                dir_times.push((String::from(path), mtime));
+                return Ok(());
+            }
+            EntryKind::File(data) => {
+                let node = match root.clone().lookup(path) {
+                    Ok(node) => {
+                        node.truncate(0)?; // replaced by a later entry
+                        node
+                    }
+                    Err(_) => {
+                        root.create(path, VfsNodeType::File)?;
+                        root.clone().lookup(path)?
+                    }
+                };
+                node.write_at(0, data)?;
+                node
+            }
+            EntryKind::Symlink(target) => {
+                root.symlink(target, path)?;
+                root.clone().lookup(path)?
+            }
+            EntryKind::HardLink(target, data) => {
+                let target = axfs_vfs::path::canonicalize(&alloc::format!("/{}", target));
+                root.link(target.trim_matches('/'), path)?;
+                let node = root.clone().lookup(path)?;
+                if !data.is_empty() {
+                    node.truncate(0)?;
+                    node.write_at(0, data)?;
+                }
+                node
+            }
+        };
+        node.set_times(None, mtime)
+    };
+
+    if archive.starts_with(b"070701") || archive.starts_with(b"070702") {
+        unpack_cpio(archive, &mut add_entry)?;
+    } else if archive.get(257..262) == Some(b"ustar") {
+        unpack_tar(archive, &mut add_entry)?;
+    } else {
+        return ax_err!(InvalidData, "unknown initramfs format");
+    }
+
+    // set the times of directories at last, as adding entries modifies them
+    for (path, mtime) in dir_times.into_iter().rev() {
+        root.clone().lookup(&path)?.set_times(None, mtime)?;
+    }
+    Ok(())
+}
+
+/// Creates the directory `path` and its missing parents.
+fn create_dirs(root: &VfsNodeRef, path: &str) -> AxResult {
+    for (pos, _) in path.match_indices('/').chain([(path.len(), "")]) {
+        let dir = &path[..pos];
+        match root.clone().lookup(dir) {
+            Ok(node) if node.get_attr()?.is_dir() => {}
+            Ok(_) => return ax_err!(NotADirectory),
+            Err(_) => root.create(dir, VfsNodeType::Dir)?,
+        }
+    }
+    Ok(())
+}
+
+fn utf8(bytes: &[u8]) -> AxResult<&str> {
+    str::from_utf8(bytes).map_err(|_| ax_err_type!(InvalidData, "invalid UTF-8 name"))
+}
+
+/// Returns `len` bytes at `offset` of the archive.
+fn slice(archive: &[u8], offset: usize, len: usize) -> AxResult<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| archive.get(offset..end))
+        .ok_or_else(|| ax_err_type!(InvalidData, "truncated initramfs"))
+}
+
+fn unpack_cpio<'a>(archive: &'a [u8], f: &mut impl FnMut(Entry<'a>) -> AxResult) -> AxResult {
+    const HEADER_SIZE: usize = 110;
+    const S_IFMT: u32 = 0o170000;
+    const S_IFDIR: u32 = 0o040000;
+    const S_IFREG: u32 = 0o100000;
+    const S_IFLNK: u32 = 0o120000;
+
+    // paths of files with multiple links, by (device, inode)
+    let mut links = BTreeMap::new();
+    let mut offset = 0;
+    loop {
+        let header = slice(archive, offset, HEADER_SIZE)?;
+        if !header.starts_with(b"070701") && !header.starts_with(b"070702") {
+            return ax_err!(InvalidData, "invalid cpio header");
+        }
+        // 13 fields of 8 hexadecimal digits after the magic
+        let field = |idx: usize| -> AxResult<u32> {
+            let hex = utf8(&header[6 + idx * 8..14 + idx * 8])?;
+            u32::from_str_radix(hex, 16)
+                .map_err(|_| ax_err_type!(InvalidData, "invalid cpio header"))
+        };
+        let (ino, mode, nlink, mtime) = (field(0)?, field(1)?, field(4)?, field(5)?);
+        let (file_size, dev) = (field(6)? as usize, (field(7)?, field(8)?));
+        let name_size = field(11)? as usize;
+
+        let name = slice(archive, offset + HEADER_SIZE, name_size)?;
+        let name = utf8(name.strip_suffix(b"\0").unwrap_or(name))?;
+        let data_offset = (offset + HEADER_SIZE + name_size).next_multiple_of(4);
+        let data = slice(archive, data_offset, file_size)?;
+        offset = (data_offset + file_size).next_multiple_of(4);
+        if name == "TRAILER!!!" {
+            return Ok(());
+        }
+
+        let kind = match mode & S_IFMT {
+            S_IFREG if nlink > 1 => match links.get(&(dev, ino)) {
+                Some(target) => EntryKind::HardLink(String::clone(target), data),
+                None => {
+                    links.insert((dev, ino), String::from(name));
+                    EntryKind::File(data)
+                }
+            },
+            S_IFREG => EntryKind::File(data),
+            S_IFDIR => EntryKind::Dir,
+            S_IFLNK => EntryKind::Symlink(utf8(data)?),
+            _ => {
+                warn!("initramfs: skip {:?} of mode {:#o}", name, mode);
+                continue;
+            }
+        };
+        f(Entry {
+            path: name.into(),
+            kind,
+            mtime: mtime as u64,
+        })?;
+    }
+}
+
+/// Parses a numeric field of tar headers, in octal or base-256 (GNU).
+fn tar_number(field: &[u8]) -> AxResult<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Ok(field[1..].iter().fold(0, |n, &b| (n << 8) | b as u64));
+    }
+    let s = utf8(field)?.trim_matches(|c| c == '\0' || c == ' ');
+    if s.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(s, 8).map_err(|_| ax_err_type!(InvalidData, "invalid tar header"))
+}
+
+/// Returns the string of a NUL-terminated field of tar headers.
+fn tar_str(field: &[u8]) -> AxResult<&str> {
+    utf8(field.split(|&b| b == 0).next().unwrap())
+}
+
+fn unpack_tar<'a>(archive: &'a [u8], f: &mut impl FnMut(Entry<'a>) -> AxResult) -> AxResult {
+    // overrides of the next entry by GNU long names or pax headers
+    let mut long_name: Option<&str> = None;
+    let mut long_link: Option<&str> = None;
+    let mut offset = 0;
+    while offset + TAR_BLOCK_SIZE <= archive.len() {
+        let header = &archive[offset..offset + TAR_BLOCK_SIZE];
+        if header.iter().all(|&b| b == 0) {
+            break; // end of archive
+        }
+        let checksum = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+            .sum::<u64>();
+        if checksum != tar_number(&header[148..156])? {
+            return ax_err!(InvalidData, "invalid tar checksum");
+        }
+
+        let size = tar_number(&header[124..136])? as usize;
+        let data = slice(archive, offset + TAR_BLOCK_SIZE, size)?;
+        offset += TAR_BLOCK_SIZE + size.next_multiple_of(TAR_BLOCK_SIZE);
+
+        let type_flag = header[156];
+        match type_flag {
+            b'L' => long_name = Some(tar_str(data)?),
+            b'K' => long_link = Some(tar_str(data)?),
+            b'x' => {
+                let (path, link) = parse_pax(data)?;
+                long_name = path.or(long_name);
+                long_link = link.or(long_link);
+            }
+            b'g' => {} // global pax headers are not used
+            _ => {
+                let path = match long_name.take() {
+                    Some(name) => String::from(name),
+                    None => {
+                        let (name, prefix) =
+                            (tar_str(&header[..100])?, tar_str(&header[345..500])?);
+                        if prefix.is_empty() {
+                            String::from(name)
+                        } else {
+                            alloc::format!("{}/{}", prefix, name)
+                        }
+                    }
+                };
+                let link = match long_link.take() {
+                    Some(link) => link,
+                    None => tar_str(&header[157..257])?,
+                };
+                let kind = match type_flag {
+                    b'0' | b'\0' | b'7' if path.ends_with('/') => EntryKind::Dir, // old tar
+                    b'0' | b'\0' | b'7' => EntryKind::File(data),
+                    b'1' => EntryKind::HardLink(String::from(link), &[]),
+                    b'2' => EntryKind::Symlink(link),
+                    b'5' => EntryKind::Dir,
+                    _ => {
+                        warn!("initramfs: skip {:?} of type {:?}", path, type_flag as char);
+                        continue;
+                    }
+                };
+                f(Entry {
+                    path,
+                    kind,
+                    mtime: tar_number(&header[136..148])?,
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses the records of a pax extended header, returns the `path` and
+/// `linkpath` values if present.
+fn parse_pax(data: &[u8]) -> AxResult<(Option<&str>, Option<&str>)> {
+    let (mut path, mut link) = (None, None);
+    let mut rest = data;
+    // each record is "<length> <key>=<value>\n", the length includes itself
+    while !rest.is_empty() && rest[0] != 0 {
+        let invalid = || ax_err_type!(InvalidData, "invalid pax header");
+        let space = rest.iter().position(|&b| b == b' ').ok_or_else(invalid)?;
+        let len: usize = utf8(&rest[..space])?.parse().map_err(|_| invalid())?;
+        if len <= space + 1 || len > rest.len() {
+            return Err(invalid());
+        }
+        let record = utf8(&rest[space + 1..len - 1])?;
+        match record.split_once('=') {
+            Some(("path", value)) => path = Some(value),
+            Some(("linkpath", value)) => link = Some(value),
+            _ => {}
+        }
+        rest = &rest[len..];
+    }
+    Ok((path, link))
+}
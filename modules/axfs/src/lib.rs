@@ -2,14 +2,46 @@
 //!
 //! It provides unified filesystem operations for various filesystems.
 //!
+//! # Partitions
+//!
+//! Block devices are named `vda`, `vdb`, ..., and their MBR or GPT partitions
+//! `vda1`, `vda2`, .... The main filesystem is mounted on `/` from the first
+//! partition containing it, or the partition specified by the environment
+//! variable `AX_ROOT` at build time (e.g., `AX_ROOT=PARTLABEL=rootfs`). Other
+//! partitions can be mounted by listing them in `/etc/fstab` on the main
+//! filesystem, or by [`api::mount_device`].
+//!
+//! # Initramfs
+//!
+//! With the `initramfs` feature, a cpio (`newc`) or tar archive can be embedded
+//! into the kernel image, by specifying its path with the environment variable
+//! `AX_INITRAMFS` at build time (e.g., `make INITRAMFS=initramfs.cpio`). It is
+//! unpacked into a [`axfs_ramfs::RamFileSystem`] at boot, which is mounted on
+//! `/` if there is no block device, or on `/initrd` otherwise.
+//!
 //! # Cargo Features
 //!
-//! - `fatfs`: Use [FAT] as the main filesystem and mount it on `/`. This feature
-//!    is **enabled** by default.
-//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. This feature is
+//! - `fatfs`: Support [FAT] and use it as the main filesystem. This feature is
 //!    **enabled** by default.
+//! - `ext4fs`: Support [ext2] and use it as the main filesystem. Volumes with
+//!    [ext4] features (e.g. extents) are also supported, but mounted
+//!    read-only. This feature is **disabled** by default, but it will override
+//!    the `fatfs` feature as the main filesystem if both are enabled.
+//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. It contains
+//!    `null`, `zero`, `random`, `urandom`, `console`, and the block devices and
+//!    partitions (e.g., `vda`, `vda1`). Other devices can be added by
+//!    [`api::add_device`]. This feature is **enabled** by default.
+//! - `procfs`: Mount [`axfs_procfs::ProcFileSystem`] on `/proc`. It contains
+//!    `/proc/mounts`, and other files can be added by [`api::add_proc_file`].
+//!    This feature is **enabled** by default.
 //! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/tmp`. This feature is
 //!    **enabled** by default.
+//! - `initramfs`: Unpack the archive embedded at build time into a RAM
+//!    filesystem, see [Initramfs](#initramfs). This feature is **disabled** by
+//!    default.
+//! - `multitask` and `irq`: If both are enabled, spawn a task that writes the
+//!    dirty blocks of the block caches back to the devices periodically.
+//!    Otherwise, they are only written back when evicted or on [`api::sync`].
 //! - `myfs`: Allow users to define their custom filesystems to override the
 //!    default. In this case, [`MyFileSystemIf`] is required to be implemented
 //!    to create and initialize other filesystems. This feature is **disabled** by
@@ -17,6 +49,8 @@
 //!    both are enabled.
 //!
 //! [FAT]: https://en.wikipedia.org/wiki/File_Allocation_Table
+//! [ext2]: https://en.wikipedia.org/wiki/Ext2
+//! [ext4]: https://en.wikipedia.org/wiki/Ext4
 //! [`MyFileSystemIf`]: fops::MyFileSystemIf
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
@@ -27,7 +61,14 @@ extern crate log;
 extern crate alloc;
 
 mod dev;
+#[cfg(feature = "devfs")]
+mod devices;
 mod fs;
+mod fstab;
+#[cfg(feature = "initramfs")]
+mod initramfs;
+mod lock;
+mod partition;
 mod root;
 
 pub mod api;
@@ -36,10 +77,28 @@ pub mod fops;
 use axdriver::{prelude::*, AxDeviceContainer};
 
 /// Initializes filesystems by block devices.
+///
+/// The partitions of all block devices are scanned, and the root filesystem
+/// is mounted from one of them. Then the filesystems listed in `/etc/fstab`
+/// on it are mounted, see [`api::mount_device`] for the format of sources.
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
+    axfs_vfs::set_time_source(axhal::time::wall_time);
+
+    let mut dev_idx = 0;
+    while let Some(dev) = blk_devs.take_one() {
+        let name = alloc::format!("vd{}", (b'a' + dev_idx) as char);
+        info!(
+            "  use block device {}: {:?} as {}",
+            dev_idx,
+            dev.device_name(),
+            name
+        );
+        self::partition::add_device(&name, self::dev::BlockCache::new(dev));
+        dev_idx += 1;
+    }
+    self::root::init_rootfs();
 
-    let dev = blk_devs.take_one().expect("No block device found!");
-    info!("  use block device 0: {:?}", dev.device_name());
-    self::root::init_rootfs(self::dev::Disk::new(dev));
+    #[cfg(all(feature = "multitask", feature = "irq"))]
+    self::dev::spawn_flush_task();
 }
@@ -0,0 +1,250 @@
+//! Advisory file locks.
+//!
+//! Two independent kinds of locks are supported, both tracked per node:
+//!
+//! - Whole-file locks, like `flock(2)`.
+//! - Byte-range record locks, like `fcntl(2)` with `F_SETLK`, `F_SETLKW` and
+//!   `F_GETLK`.
+//!
+//! Locks are owned by opened files (i.e., open file descriptions), and are
+//! released when the file is closed. As there is only one process, record
+//! locks behave like Linux open file description locks (`F_OFD_SETLK`), so
+//! that they work between multiple threads.
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use axerrno::{ax_err, AxResult};
+use axfs_vfs::VfsNodeRef;
+use axsync::Mutex;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The type of a file lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockType {
+    /// A shared (read) lock, which can be held by multiple owners.
+    Shared,
+    /// An exclusive (write) lock, which can be held by only one owner.
+    Exclusive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockKind {
+    Whole,
+    Record,
+}
+
+#[derive(Debug, Clone)]
+struct FileLock {
+    kind: LockKind,
+    ty: LockType,
+    owner: u64,
+    range: Range<u64>,
+}
+
+impl FileLock {
+    fn conflicts(&self, kind: LockKind, ty: LockType, owner: u64, range: &Range<u64>) -> bool {
+        self.kind == kind
+            && self.owner != owner
+            && (self.ty == LockType::Exclusive || ty == LockType::Exclusive)
+            && self.range.start < range.end
+            && range.start < self.range.end
+    }
+}
+
+/// Identifies a file by the filesystem it is in and its inode number, as a
+/// file may be looked up as different nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct NodeKey {
+    fs: usize,
+    ino: u64,
+}
+
+impl NodeKey {
+    /// Creates the key of `node` in the filesystem identified by `fs`.
+    ///
+    /// The address of the node is used if the filesystem has no inode
+    /// numbers, see [`ino`](axfs_vfs::VfsNodeOps::ino).
+    pub(crate) fn new(fs: usize, node: &VfsNodeRef) -> Self {
+        let ino = node
+            .ino()
+            .unwrap_or(Arc::as_ptr(node) as *const () as usize as u64);
+        Self { fs, ino }
+    }
+}
+
+/// Locks of all files.
+static LOCKS: Mutex<BTreeMap<NodeKey, Vec<FileLock>>> = Mutex::new(BTreeMap::new());
+
+/// Increased when any lock is released, for waiters to check again.
+static RELEASE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "multitask")]
+static WAIT_QUEUE: axtask::WaitQueue = axtask::WaitQueue::new();
+
+fn notify_released() {
+    RELEASE_COUNT.fetch_add(1, Ordering::Release);
+    #[cfg(feature = "multitask")]
+    WAIT_QUEUE.notify_all(false);
+}
+
+/// Removes the locks of `kind` owned by `owner` within `range`, splitting
+/// the locks partially in the range. Returns whether any lock is removed.
+fn remove_locks(locks: &mut Vec<FileLock>, kind: LockKind, owner: u64, range: &Range<u64>) -> bool {
+    let mut removed = false;
+    let mut remains = Vec::new();
+    locks.retain(|lock| {
+        if lock.kind != kind
+            || lock.owner != owner
+            || lock.range.end <= range.start
+            || range.end <= lock.range.start
+        {
+            return true;
+        }
+        if lock.range.start < range.start {
+            let mut left = lock.clone();
+            left.range.end = range.start;
+            remains.push(left);
+        }
+        if range.end < lock.range.end {
+            let mut right = lock.clone();
+            right.range.start = range.end;
+            remains.push(right);
+        }
+        removed = true;
+        false
+    });
+    locks.extend(remains);
+    removed
+}
+
+fn set_lock(
+    key: NodeKey,
+    kind: LockKind,
+    owner: u64,
+    ty: Option<LockType>,
+    range: Range<u64>,
+    wait: bool,
+) -> AxResult {
+    if range.start >= range.end {
+        return ax_err!(InvalidInput);
+    }
+    loop {
+        let mut all_locks = LOCKS.lock();
+        let Some(ty) = ty else {
+            // unlock
+            if let Some(locks) = all_locks.get_mut(&key) {
+                if remove_locks(locks, kind, owner, &range) {
+                    if locks.is_empty() {
+                        all_locks.remove(&key);
+                    }
+                    notify_released();
+                }
+            }
+            return Ok(());
+        };
+
+        let locks = all_locks.entry(key).or_default();
+        if !locks
+            .iter()
+            .any(|lock| lock.conflicts(kind, ty, owner, &range))
+        {
+            // replace the locks of the owner in the range, and merge the
+            // adjacent ones of the same type
+            let released = remove_locks(locks, kind, owner, &range);
+            let mut range = range;
+            locks.retain(|lock| {
+                let mergeable = lock.kind == kind
+                    && lock.owner == owner
+                    && lock.ty == ty
+                    && (lock.range.end == range.start || range.end == lock.range.start);
+                if mergeable {
+                    range = range.start.min(lock.range.start)..range.end.max(lock.range.end);
+                }
+                !mergeable
+            });
+            locks.push(FileLock {
+                kind,
+                ty,
+                owner,
+                range,
+            });
+            if released {
+                notify_released(); // may be converted from exclusive to shared
+            }
+            return Ok(());
+        }
+        if locks.is_empty() {
+            all_locks.remove(&key);
+        }
+        if !wait {
+            return ax_err!(WouldBlock);
+        }
+
+        let count = RELEASE_COUNT.load(Ordering::Acquire);
+        drop(all_locks);
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "multitask")] {
+                WAIT_QUEUE.wait_until(|| RELEASE_COUNT.load(Ordering::Acquire) != count);
+            } else {
+                // no other tasks can release the lock
+                let _ = count;
+                return ax_err!(WouldBlock);
+            }
+        }
+    }
+}
+
+/// Acquires (or converts) the whole-file lock of the file `key` for `owner`,
+/// or releases it if `ty` is `None`.
+///
+/// If the lock is held by other owners in a conflicting type, it waits for
+/// the release if `wait` is true, otherwise returns [`WouldBlock`].
+///
+/// [`WouldBlock`]: axerrno::AxError::WouldBlock
+pub(crate) fn flock(key: NodeKey, owner: u64, ty: Option<LockType>, wait: bool) -> AxResult {
+    set_lock(key, LockKind::Whole, owner, ty, 0..u64::MAX, wait)
+}
+
+/// Acquires (or converts) the record lock of `range` in the file `key` for
+/// `owner`, or releases it if `ty` is `None`. See [`flock`] for the waiting
+/// behavior.
+pub(crate) fn lock_range(
+    key: NodeKey,
+    owner: u64,
+    ty: Option<LockType>,
+    range: Range<u64>,
+    wait: bool,
+) -> AxResult {
+    set_lock(key, LockKind::Record, owner, ty, range, wait)
+}
+
+/// Returns a record lock of other owners that conflicts with acquiring the
+/// lock of `ty` on `range` in the file `key`, or `None` if it can be acquired.
+pub(crate) fn test_range(
+    key: NodeKey,
+    owner: u64,
+    ty: LockType,
+    range: Range<u64>,
+) -> Option<(LockType, Range<u64>)> {
+    LOCKS.lock().get(&key).and_then(|locks| {
+        locks
+            .iter()
+            .find(|lock| lock.conflicts(LockKind::Record, ty, owner, &range))
+            .map(|lock| (lock.ty, lock.range.clone()))
+    })
+}
+
+/// Releases all locks of the file `key` held by `owner`.
+pub(crate) fn release_all(key: NodeKey, owner: u64) {
+    let mut all_locks = LOCKS.lock();
+    if let Some(locks) = all_locks.get_mut(&key) {
+        let len = locks.len();
+        locks.retain(|lock| lock.owner != owner);
+        if locks.len() != len {
+            notify_released();
+        }
+        if locks.is_empty() {
+            all_locks.remove(&key);
+        }
+    }
+}
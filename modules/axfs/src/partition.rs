@@ -0,0 +1,403 @@
+//! Partition tables (MBR and GPT) of block devices, and the filesystems on the
+//! partitions.
+//!
+//! Block devices are named `vda`, `vdb`, ... in the order they are found, and
+//! their partitions `vda1`, `vda2`, ... in the order of the partition table
+//! (logical partitions of MBR start from 5). A device without a partition
+//! table is treated as one partition named after the device.
+
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+use axsync::Mutex;
+
+use crate::dev::{BlockCache, Disk};
+
+const BLOCK_SIZE: usize = 512;
+
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+const MBR_DISK_ID_OFFSET: usize = 440;
+const MBR_ENTRIES_OFFSET: usize = 446;
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xee;
+const MBR_TYPES_EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+
+const GPT_SIGNATURE: &[u8] = b"EFI PART";
+const GPT_HEADER_LBA: u64 = 1;
+const GPT_NAME_LEN: usize = 36; // in UTF-16 code units
+
+const EXT_SUPERBLOCK_OFFSET: u64 = 1024;
+const EXT_MAGIC: u16 = 0xef53;
+
+/// All partitions of all block devices.
+static PARTITIONS: Mutex<Vec<Partition>> = Mutex::new(Vec::new());
+
+/// Information about a partition, returned by
+/// [`partitions`](crate::api::partitions).
+#[derive(Debug, Clone)]
+pub struct PartitionInfo {
+    /// The device name (e.g., `vda1`).
+    pub name: String,
+    /// The first block (512 bytes) of the partition on the device.
+    pub start: u64,
+    /// The number of blocks (512 bytes) of the partition.
+    pub num_blocks: u64,
+    /// The GPT partition name (`PARTLABEL` in fstab).
+    pub part_label: Option<String>,
+    /// The GPT unique partition GUID, or the MBR disk ID followed by the
+    /// partition number (`PARTUUID` in fstab).
+    pub part_uuid: Option<String>,
+    /// The filesystem type detected on the partition (`fatfs` or `ext4fs`).
+    pub fs_type: Option<&'static str>,
+    /// The filesystem label (`LABEL` in fstab).
+    pub label: Option<String>,
+    /// The filesystem UUID or volume ID (`UUID` in fstab).
+    pub uuid: Option<String>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Partition {
+    pub info: PartitionInfo,
+    cache: Arc<BlockCache>,
+}
+
+impl Partition {
+    fn new(name: String, cache: &Arc<BlockCache>, start: u64, num_blocks: u64) -> Self {
+        let mut part = Self {
+            info: PartitionInfo {
+                name,
+                start,
+                num_blocks,
+                part_label: None,
+                part_uuid: None,
+                fs_type: None,
+                label: None,
+                uuid: None,
+            },
+            cache: cache.clone(),
+        };
+        part.probe_fs();
+        part
+    }
+
+    /// Returns a [`Disk`] accessing the partition.
+    pub fn disk(&self) -> Disk {
+        Disk::new_partition(self.cache.clone(), self.info.start, self.info.num_blocks)
+    }
+
+    /// Returns whether the partition is specified by `source`, which is one of
+    /// `LABEL=<label>`, `UUID=<uuid>`, `PARTLABEL=<label>`, `PARTUUID=<uuid>`,
+    /// or the device name with an optional `/dev/` prefix.
+    pub fn matches(&self, source: &str) -> bool {
+        let info = &self.info;
+        let eq = |field: &Option<String>, value: &str| field.as_deref() == Some(value);
+        let eq_uuid = |field: &Option<String>, value: &str| {
+            field
+                .as_deref()
+                .is_some_and(|uuid| uuid.eq_ignore_ascii_case(value))
+        };
+        if let Some(label) = source.strip_prefix("LABEL=") {
+            eq(&info.label, label)
+        } else if let Some(uuid) = source.strip_prefix("UUID=") {
+            eq_uuid(&info.uuid, uuid)
+        } else if let Some(label) = source.strip_prefix("PARTLABEL=") {
+            eq(&info.part_label, label)
+        } else if let Some(uuid) = source.strip_prefix("PARTUUID=") {
+            eq_uuid(&info.part_uuid, uuid)
+        } else {
+            source.strip_prefix("/dev/").unwrap_or(source) == info.name
+        }
+    }
+
+    fn read(&self, pos: u64, buf: &mut [u8]) -> bool {
+        let mut disk = self.disk();
+        disk.set_position(pos);
+        let mut read_len = 0;
+        while read_len < buf.len() {
+            match disk.read_one(&mut buf[read_len..]) {
+                Ok(n) => read_len += n,
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+
+    /// Detects the filesystem on the partition, and reads its label and UUID.
+    fn probe_fs(&mut self) {
+        let mut sb = [0; 1024];
+        if self.read(EXT_SUPERBLOCK_OFFSET, &mut sb) && get_u16(&sb, 56) == EXT_MAGIC {
+            self.info.fs_type = Some("ext4fs");
+            self.info.uuid = Some(format_guid(&sb[104..120], false));
+            self.info.label = parse_label(&sb[120..136]);
+            return;
+        }
+
+        let mut boot = [0; BLOCK_SIZE];
+        if !self.read(0, &mut boot) || !is_fat_boot_sector(&boot) {
+            return;
+        }
+        // the extended BPB is at 36 for FAT12/16, or at 64 for FAT32
+        let ebpb = if &boot[82..85] == b"FAT" { 64 } else { 36 };
+        let volume_id = get_u32(&boot, ebpb + 3);
+        self.info.fs_type = Some("fatfs");
+        self.info.uuid = Some(format!(
+            "{:04X}-{:04X}",
+            volume_id >> 16,
+            volume_id & 0xffff
+        ));
+        self.info.label =
+            parse_label(&boot[ebpb + 7..ebpb + 18]).filter(|label| label != "NO NAME");
+    }
+}
+
+/// Scans the partition table of the block device `dev_name`, and adds all of
+/// its partitions to the partition list.
+pub(crate) fn add_device(dev_name: &str, cache: Arc<BlockCache>) {
+    let whole = Partition::new(dev_name.into(), &cache, 0, cache.num_blocks());
+    let parts = if whole.info.fs_type.is_some() {
+        vec![] // a filesystem on the whole device, no partition table
+    } else {
+        let mut sector = [0; BLOCK_SIZE];
+        if whole.read(0, &mut sector) {
+            parse_mbr(&whole, &sector)
+        } else {
+            vec![]
+        }
+    };
+
+    let mut partitions = PARTITIONS.lock();
+    if parts.is_empty() {
+        info!(
+            "  {}: no partition table, {:?}",
+            dev_name, whole.info.fs_type
+        );
+        partitions.push(whole);
+    } else {
+        for part in parts {
+            info!(
+                "  {}: [{:#x}, {:#x}) {:?} label={:?} partlabel={:?}",
+                part.info.name,
+                part.info.start,
+                part.info.start + part.info.num_blocks,
+                part.info.fs_type,
+                part.info.label,
+                part.info.part_label,
+            );
+            partitions.push(part);
+        }
+    }
+}
+
+/// Returns all partitions of all block devices.
+pub(crate) fn partitions() -> Vec<Partition> {
+    PARTITIONS.lock().clone()
+}
+
+/// Finds the partition specified by `source`, see [`Partition::matches`].
+pub(crate) fn find_partition(source: &str) -> Option<Partition> {
+    PARTITIONS
+        .lock()
+        .iter()
+        .find(|part| part.matches(source))
+        .cloned()
+}
+
+fn parse_mbr(whole: &Partition, sector: &[u8; BLOCK_SIZE]) -> Vec<Partition> {
+    if sector[510..] != MBR_SIGNATURE {
+        return vec![];
+    }
+    let entries = (0..4).map(|i| MbrEntry::parse(sector, i));
+    if entries.clone().any(|e| e.status & 0x7f != 0) {
+        return vec![]; // not a valid MBR
+    }
+    if entries.clone().any(|e| e.ty == MBR_TYPE_GPT_PROTECTIVE) {
+        return parse_gpt(whole);
+    }
+
+    let dev_name = &whole.info.name;
+    let disk_id = get_u32(sector, MBR_DISK_ID_OFFSET);
+    let mut parts = Vec::new();
+    let mut new_part = |num: usize, start: u64, count: u64| {
+        if count == 0 || start + count > whole.info.num_blocks {
+            warn!("{}{}: partition out of the device, ignored", dev_name, num);
+            return;
+        }
+        let mut part = Partition::new(format!("{}{}", dev_name, num), &whole.cache, start, count);
+        part.info.part_uuid = Some(format!("{:08x}-{:02x}", disk_id, num));
+        parts.push(part);
+    };
+
+    let mut logical_num = 5;
+    for (i, entry) in entries.enumerate() {
+        if entry.ty == 0 {
+            continue;
+        }
+        if !MBR_TYPES_EXTENDED.contains(&entry.ty) {
+            new_part(i + 1, entry.start, entry.count);
+            continue;
+        }
+        // follow the chain of extended boot records, each has a logical
+        // partition relative to itself, and the next record relative to the
+        // extended partition
+        let ext_start = entry.start;
+        let mut ebr_start = ext_start;
+        let mut ebr = [0; BLOCK_SIZE];
+        while logical_num < 64 && whole.read(ebr_start * BLOCK_SIZE as u64, &mut ebr) {
+            if ebr[510..] != MBR_SIGNATURE {
+                break;
+            }
+            let (logical, next) = (MbrEntry::parse(&ebr, 0), MbrEntry::parse(&ebr, 1));
+            if logical.ty != 0 {
+                new_part(logical_num, ebr_start + logical.start, logical.count);
+                logical_num += 1;
+            }
+            if next.ty == 0 || next.start == 0 {
+                break;
+            }
+            ebr_start = ext_start + next.start;
+        }
+    }
+    parts
+}
+
+fn parse_gpt(whole: &Partition) -> Vec<Partition> {
+    let dev_name = &whole.info.name;
+    let mut header = [0; BLOCK_SIZE];
+    if !whole.read(GPT_HEADER_LBA * BLOCK_SIZE as u64, &mut header) || &header[..8] != GPT_SIGNATURE
+    {
+        warn!("{}: invalid GPT header", dev_name);
+        return vec![];
+    }
+    let header_size = get_u32(&header, 12) as usize;
+    if !(92..=BLOCK_SIZE).contains(&header_size) {
+        warn!("{}: invalid GPT header size {}", dev_name, header_size);
+        return vec![];
+    }
+    let mut crc_header = header;
+    crc_header[16..20].fill(0);
+    if crc32(&crc_header[..header_size]) != get_u32(&header, 16) {
+        warn!("{}: GPT header checksum mismatch", dev_name);
+        return vec![];
+    }
+
+    let entries_lba = get_u64(&header, 72);
+    let num_entries = get_u32(&header, 80) as usize;
+    let entry_size = get_u32(&header, 84) as usize;
+    if entry_size < 128 || num_entries > 1024 {
+        warn!("{}: unsupported GPT entries", dev_name);
+        return vec![];
+    }
+    let mut entries = vec![0; num_entries * entry_size];
+    if !whole.read(entries_lba * BLOCK_SIZE as u64, &mut entries)
+        || crc32(&entries) != get_u32(&header, 88)
+    {
+        warn!("{}: GPT entries checksum mismatch", dev_name);
+        return vec![];
+    }
+
+    let mut parts = Vec::new();
+    for (i, entry) in entries.chunks_exact(entry_size).enumerate() {
+        if entry[..16].iter().all(|&b| b == 0) {
+            continue; // unused entry
+        }
+        let (first, last) = (get_u64(entry, 32), get_u64(entry, 40));
+        if first > last || last >= whole.info.num_blocks {
+            warn!(
+                "{}{}: partition out of the device, ignored",
+                dev_name,
+                i + 1
+            );
+            continue;
+        }
+        let name = format!("{}{}", dev_name, i + 1);
+        let mut part = Partition::new(name, &whole.cache, first, last - first + 1);
+        part.info.part_uuid = Some(format_guid(&entry[16..32], true));
+        let name_utf16 = (0..GPT_NAME_LEN)
+            .map(|j| get_u16(entry, 56 + j * 2))
+            .take_while(|&c| c != 0);
+        let part_label: String = char::decode_utf16(name_utf16)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        if !part_label.is_empty() {
+            part.info.part_label = Some(part_label);
+        }
+        parts.push(part);
+    }
+    parts
+}
+
+struct MbrEntry {
+    status: u8,
+    ty: u8,
+    start: u64,
+    count: u64,
+}
+
+impl MbrEntry {
+    fn parse(sector: &[u8], idx: usize) -> Self {
+        let entry = &sector[MBR_ENTRIES_OFFSET + idx * 16..];
+        Self {
+            status: entry[0],
+            ty: entry[4],
+            start: get_u32(entry, 8) as u64,
+            count: get_u32(entry, 12) as u64,
+        }
+    }
+}
+
+fn is_fat_boot_sector(sector: &[u8; BLOCK_SIZE]) -> bool {
+    sector[510..] == MBR_SIGNATURE
+        && get_u16(sector, 11) == BLOCK_SIZE as u16 // bytes per sector
+        && (&sector[54..57] == b"FAT" || &sector[82..85] == b"FAT")
+}
+
+/// Formats a 16-byte GUID. In GPT, the first three fields are little-endian.
+fn format_guid(b: &[u8], mixed_endian: bool) -> String {
+    let mut b: [u8; 16] = b.try_into().unwrap();
+    if mixed_endian {
+        b[0..4].reverse();
+        b[4..6].reverse();
+        b[6..8].reverse();
+    }
+    let hex = |range: core::ops::Range<usize>| {
+        b[range]
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect::<String>()
+    };
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex(0..4),
+        hex(4..6),
+        hex(6..8),
+        hex(8..10),
+        hex(10..16)
+    )
+}
+
+/// Parses a label padded with spaces or NULs.
+fn parse_label(b: &[u8]) -> Option<String> {
+    let label = String::from_utf8_lossy(b);
+    let label = label.trim_end_matches(['\0', ' ']);
+    (!label.is_empty()).then(|| label.into())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+fn get_u16(b: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(b[offset..offset + 2].try_into().unwrap())
+}
+
+fn get_u32(b: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(b[offset..offset + 4].try_into().unwrap())
+}
+
+fn get_u64(b: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(b[offset..offset + 8].try_into().unwrap())
+}
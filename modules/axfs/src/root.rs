@@ -1,33 +1,92 @@
-//! Root directory of the filesystem
+//! Root directory of the filesystem, and the mount table.
 //!
-//! TODO: it doesn't work very well if the mount points have containment relationships.
+//! Filesystems can be mounted on any directory, including directories in other
+//! mounted filesystems (nested mounts). A path is looked up in the filesystem
+//! mounted at its longest matching prefix, compared component by component.
 
-use alloc::{string::String, sync::Arc, vec::Vec};
-use axerrno::{ax_err, AxError, AxResult};
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+use axerrno::{ax_err, ax_err_type, AxError, AxResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
 use axsync::Mutex;
+use core::{fmt, time::Duration};
 use lazy_init::LazyInit;
 
-use crate::{api::FileType, fs};
+use crate::{api::FileType, dev::Disk, fs};
 
 static CURRENT_DIR_PATH: Mutex<String> = Mutex::new(String::new());
-static CURRENT_DIR: LazyInit<Mutex<VfsNodeRef>> = LazyInit::new();
 
 struct MountPoint {
-    path: &'static str,
+    path: String,
     fs: Arc<dyn VfsOps>,
+    source: String,
+    fs_type: String,
+    mount_ref: MountRef,
 }
 
 struct RootDirectory {
     main_fs: Arc<dyn VfsOps>,
-    mounts: Vec<MountPoint>,
+    main_source: String,
+    main_fs_type: &'static str,
+    main_ref: MountRef,
+    mounts: Mutex<Vec<MountPoint>>,
+}
+
+/// A reference to a mounted filesystem, held by opened files and directories
+/// in it, so that the filesystem is not unmounted while they are open.
+#[derive(Clone)]
+pub(crate) struct MountRef(Arc<()>);
+
+impl MountRef {
+    fn new() -> Self {
+        Self(Arc::new(()))
+    }
+
+    /// Returns the unique ID of the filesystem while it is mounted.
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Returns whether any opened file or directory holds the reference.
+    fn is_busy(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+/// Information about a mounted filesystem, returned by
+/// [`mounts`](crate::api::mounts).
+///
+/// It is formatted as a line of `/proc/mounts` when displayed.
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    /// The mounted device or resource (e.g., `/dev/vda`).
+    pub source: String,
+    /// The absolute path of the mount point.
+    pub path: String,
+    /// The filesystem type (e.g., `fatfs`, `ramfs`).
+    pub fs_type: String,
 }
 
 static ROOT_DIR: LazyInit<Arc<RootDirectory>> = LazyInit::new();
 
+#[cfg(feature = "devfs")]
+static DEV_FS: LazyInit<Arc<fs::devfs::DeviceFileSystem>> = LazyInit::new();
+
+#[cfg(feature = "procfs")]
+static PROC_FS: LazyInit<Arc<fs::procfs::ProcFileSystem>> = LazyInit::new();
+
+/// Maximum number of symbolic links that can be followed in one path lookup,
+/// the same as `MAXSYMLINKS` in Linux.
+const MAX_SYMLINK_FOLLOWS: usize = 40;
+
 impl MountPoint {
-    pub fn new(path: &'static str, fs: Arc<dyn VfsOps>) -> Self {
-        Self { path, fs }
+    pub fn new(path: String, fs: Arc<dyn VfsOps>, source: &str, fs_type: &str) -> Self {
+        Self {
+            path,
+            fs,
+            source: source.into(),
+            fs_type: fs_type.into(),
+            mount_ref: MountRef::new(),
+        }
     }
 }
 
@@ -37,37 +96,125 @@ impl Drop for MountPoint {
     }
 }
 
+impl fmt::Display for MountInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} rw 0 0", self.source, self.path, self.fs_type)
+    }
+}
+
+/// If `path` is `mount_path` or under it, returns the rest of `path` relative
+/// to `mount_path`. Both paths are compared component by component.
+fn strip_mount_path<'a>(mount_path: &str, path: &'a str) -> Option<&'a str> {
+    let mut rest = path;
+    for name in mount_path.split('/').filter(|s| !s.is_empty()) {
+        rest = rest.trim_start_matches('/').strip_prefix(name)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None; // e.g., "/tmpfoo" is not under "/tmp"
+        }
+    }
+    Some(rest)
+}
+
 impl RootDirectory {
-    pub const fn new(main_fs: Arc<dyn VfsOps>) -> Self {
+    pub fn new(main_fs: Arc<dyn VfsOps>, main_source: String, main_fs_type: &'static str) -> Self {
         Self {
             main_fs,
-            mounts: Vec::new(),
+            main_source,
+            main_fs_type,
+            main_ref: MountRef::new(),
+            mounts: Mutex::new(Vec::new()),
         }
     }
 
-    pub fn mount(&mut self, path: &'static str, fs: Arc<dyn VfsOps>) -> AxResult {
+    /// Mounts `fs` at the absolute canonical `path`, the mount point
+    /// directory is created if it does not exist.
+    pub fn mount(&self, path: &str, fs: Arc<dyn VfsOps>, source: &str, fs_type: &str) -> AxResult {
         if path == "/" {
             return ax_err!(InvalidInput, "cannot mount root filesystem");
         }
         if !path.starts_with('/') {
             return ax_err!(InvalidInput, "mount path must start with '/'");
         }
-        if self.mounts.iter().any(|mp| mp.path == path) {
+        if self.contains(path) {
             return ax_err!(InvalidInput, "mount point already exists");
         }
-        // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
-        self.mounts.push(MountPoint::new(path, fs));
+        // create the mount point in the parent filesystem if it does not exist
+        let mount_point = self.lookup_mounted_fs(path, |parent_fs, rest_path| {
+            parent_fs.root_dir().create(rest_path, FileType::Dir)?;
+            parent_fs.root_dir().lookup(rest_path)
+        })?;
+        if !mount_point.get_attr()?.is_dir() {
+            return ax_err!(NotADirectory);
+        }
+        // check again with the mount table locked, for concurrent mounts
+        let mut mounts = self.mounts.lock();
+        if mounts.iter().any(|mp| mp.path == path) {
+            return ax_err!(InvalidInput, "mount point already exists");
+        }
+        fs.mount(path, mount_point)?;
+        mounts.push(MountPoint::new(path.into(), fs, source, fs_type));
         Ok(())
     }
 
-    pub fn _umount(&mut self, path: &str) {
-        self.mounts.retain(|mp| mp.path != path);
+    /// Unmounts the filesystem mounted at the absolute canonical `path`.
+    ///
+    /// Returns [`ResourceBusy`](AxError::ResourceBusy) if other filesystems
+    /// are mounted in it, any file or directory in it is open, or the current
+    /// directory is in it.
+    pub fn umount(&self, path: &str) -> AxResult {
+        if path == "/" {
+            return ax_err!(ResourceBusy, "cannot unmount root filesystem");
+        }
+        let mut mounts = self.mounts.lock();
+        let idx = mounts
+            .iter()
+            .position(|mp| mp.path == path)
+            .ok_or_else(|| ax_err_type!(InvalidInput, "not a mount point"))?;
+        if mounts
+            .iter()
+            .any(|mp| mp.path != path && strip_mount_path(path, &mp.path).is_some())
+        {
+            return ax_err!(ResourceBusy, "has nested mount points");
+        }
+        if mounts[idx].mount_ref.is_busy() {
+            return ax_err!(ResourceBusy, "has open files");
+        }
+        if strip_mount_path(path, &CURRENT_DIR_PATH.lock()).is_some() {
+            return ax_err!(ResourceBusy, "the current directory is in use");
+        }
+        mounts.remove(idx);
+        Ok(())
+    }
+
+    /// Returns the [`MountRef`] of the filesystem where the absolute canonical
+    /// `path` is.
+    pub fn mount_ref(&self, path: &str) -> MountRef {
+        let path = path.trim_matches('/');
+        self.mounts
+            .lock()
+            .iter()
+            .filter(|mp| strip_mount_path(&mp.path, path).is_some())
+            .max_by_key(|mp| mp.path.len())
+            .map_or_else(|| self.main_ref.clone(), |mp| mp.mount_ref.clone())
     }
 
     pub fn contains(&self, path: &str) -> bool {
-        self.mounts.iter().any(|mp| mp.path == path)
+        self.mounts.lock().iter().any(|mp| mp.path == path)
+    }
+
+    pub fn mount_infos(&self) -> Vec<MountInfo> {
+        let root = MountInfo {
+            source: self.main_source.clone(),
+            path: "/".into(),
+            fs_type: self.main_fs_type.into(),
+        };
+        let mounts = self.mounts.lock();
+        let infos = mounts.iter().map(|mp| MountInfo {
+            source: mp.source.clone(),
+            path: mp.path.clone(),
+            fs_type: mp.fs_type.clone(),
+        });
+        core::iter::once(root).chain(infos).collect()
     }
 
     fn lookup_mounted_fs<F, T>(&self, path: &str, f: F) -> AxResult<T>
@@ -80,25 +227,47 @@ impl RootDirectory {
             return self.lookup_mounted_fs(rest, f);
         }
 
-        let mut idx = 0;
-        let mut max_len = 0;
-
         // Find the filesystem that has the longest mounted path match
         // TODO: more efficient, e.g. trie
-        for (i, mp) in self.mounts.iter().enumerate() {
-            // skip the first '/'
-            if path.starts_with(&mp.path[1..]) && mp.path.len() - 1 > max_len {
-                max_len = mp.path.len() - 1;
-                idx = i;
+        let mut matched = None;
+        let mut max_len = 0;
+        for mp in self.mounts.lock().iter() {
+            if let Some(rest) = strip_mount_path(&mp.path, path) {
+                if mp.path.len() > max_len {
+                    max_len = mp.path.len();
+                    matched = Some((mp.fs.clone(), rest));
+                }
             }
         }
 
-        if max_len == 0 {
-            f(self.main_fs.clone(), path) // not matched any mount point
-        } else {
-            f(self.mounts[idx].fs.clone(), &path[max_len..]) // matched at `idx`
+        // call `f` after the mount table is unlocked, as it may lookup again
+        match matched {
+            Some((fs, rest)) => f(fs, rest.trim_start_matches('/')),
+            None => f(self.main_fs.clone(), path), // not matched any mount point
         }
     }
+
+    /// Looks up the filesystem where both `src_path` and `dst_path` are, and
+    /// calls `f` with the paths relative to its root.
+    ///
+    /// Returns [`CrossesDevices`](AxError::CrossesDevices) if they are in
+    /// different filesystems.
+    fn lookup_same_fs<F>(&self, src_path: &str, dst_path: &str, f: F) -> AxResult
+    where
+        F: FnOnce(Arc<dyn VfsOps>, &str, &str) -> AxResult,
+    {
+        self.lookup_mounted_fs(src_path, |src_fs, src_rest| {
+            self.lookup_mounted_fs(dst_path, |dst_fs, dst_rest| {
+                if src_rest.is_empty() || dst_rest.is_empty() {
+                    ax_err!(ResourceBusy) // cannot rename or link mount points
+                } else if !Arc::ptr_eq(&src_fs, &dst_fs) {
+                    ax_err!(CrossesDevices)
+                } else {
+                    f(src_fs, src_rest, dst_rest)
+                }
+            })
+        })
+    }
 }
 
 impl VfsNodeOps for RootDirectory {
@@ -108,6 +277,10 @@ impl VfsNodeOps for RootDirectory {
         self.main_fs.root_dir().get_attr()
     }
 
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        self.main_fs.root_dir().set_times(atime, mtime)
+    }
+
     fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
         self.lookup_mounted_fs(path, |fs, rest_path| fs.root_dir().lookup(rest_path))
     }
@@ -131,57 +304,127 @@ impl VfsNodeOps for RootDirectory {
             }
         })
     }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        self.lookup_same_fs(src_path, dst_path, |fs, src, dst| {
+            fs.root_dir().rename(src, dst)
+        })
+    }
+
+    fn link(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        self.lookup_same_fs(src_path, dst_path, |fs, src, dst| {
+            fs.root_dir().link(src, dst)
+        })
+    }
+
+    fn symlink(&self, target: &str, path: &str) -> VfsResult {
+        self.lookup_mounted_fs(path, |fs, rest_path| {
+            if rest_path.is_empty() {
+                ax_err!(AlreadyExists)
+            } else {
+                fs.root_dir().symlink(target, rest_path)
+            }
+        })
+    }
 }
 
-pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
+/// Opens the main filesystem on `disk`, returns it with its type.
+fn open_main_fs(#[allow(unused_mut)] mut disk: Disk) -> (Arc<dyn VfsOps>, &'static str) {
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
-            let main_fs = fs::myfs::new_myfs(disk);
-        } else if #[cfg(feature = "fatfs")] {
-            static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
-            FAT_FS.init_by(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
-            FAT_FS.init();
-            let main_fs = FAT_FS.clone();
+            (fs::myfs::new_myfs(disk), "myfs")
+        } else {
+            #[cfg(all(feature = "use-ramdisk", not(feature = "ext4fs")))]
+            fs::fatfs::FatFileSystem::format(&mut disk).expect("failed to format volume");
+            crate::fstab::open_fs(disk, crate::fstab::ROOT_FS_TYPE)
+                .expect("failed to initialize the root filesystem")
         }
     }
+}
 
-    let mut root_dir = RootDirectory::new(main_fs);
+pub(crate) fn init_rootfs() {
+    #[cfg(feature = "initramfs")]
+    let mut initramfs = crate::initramfs::new_initramfs();
+    let (main_fs, main_source, main_fs_type) = match crate::fstab::root_partition() {
+        Some(root_part) => {
+            let (main_fs, main_fs_type) = open_main_fs(root_part.disk());
+            (
+                main_fs,
+                format!("/dev/{}", root_part.info.name),
+                main_fs_type,
+            )
+        }
+        // boot from the initramfs if there is no disk
+        #[cfg(feature = "initramfs")]
+        None if initramfs.is_some() => {
+            let main_fs: Arc<dyn VfsOps> = initramfs.take().unwrap();
+            (main_fs, String::from("initramfs"), "ramfs")
+        }
+        None => panic!("No block device found!"),
+    };
+    info!("  mount {} on / ({})", main_source, main_fs_type);
+    let root_dir = RootDirectory::new(main_fs, main_source, main_fs_type);
 
     #[cfg(feature = "devfs")]
     {
         let null = fs::devfs::NullDev;
         let zero = fs::devfs::ZeroDev;
         let bar = fs::devfs::ZeroDev;
-        let devfs = fs::devfs::DeviceFileSystem::new();
+        let random = Arc::new(fs::devfs::RandomDev::new(axhal::time::current_time_nanos()));
+        let devfs = Arc::new(fs::devfs::DeviceFileSystem::new());
         let foo_dir = devfs.mkdir("foo");
         devfs.add("null", Arc::new(null));
         devfs.add("zero", Arc::new(zero));
+        devfs.add("random", random.clone());
+        devfs.add("urandom", random);
+        devfs.add("console", Arc::new(crate::devices::ConsoleDev));
         foo_dir.add("bar", Arc::new(bar));
+        for part in crate::partition::partitions() {
+            // device nodes are never removed, so their names can be leaked
+            let name = alloc::boxed::Box::leak(part.info.name.clone().into_boxed_str());
+            devfs.add(name, Arc::new(crate::devices::BlockDev::new(part.disk())));
+        }
 
         root_dir
-            .mount("/dev", Arc::new(devfs))
+            .mount("/dev", devfs.clone(), "devfs", "devfs")
             .expect("failed to mount devfs at /dev");
+        DEV_FS.init_by(devfs);
     }
 
     #[cfg(feature = "ramfs")]
     {
         let ramfs = fs::ramfs::RamFileSystem::new();
         root_dir
-            .mount("/tmp", Arc::new(ramfs))
+            .mount("/tmp", Arc::new(ramfs), "ramfs", "ramfs")
             .expect("failed to mount ramfs at /tmp");
     }
 
+    #[cfg(feature = "procfs")]
+    {
+        let procfs = Arc::new(fs::procfs::ProcFileSystem::new());
+        procfs.add_file("mounts", || {
+            mounts()
+                .iter()
+                .map(|mount| format!("{}\n", mount))
+                .collect()
+        });
+        root_dir
+            .mount("/proc", procfs.clone(), "procfs", "procfs")
+            .expect("failed to mount procfs at /proc");
+        PROC_FS.init_by(procfs);
+    }
+
+    #[cfg(feature = "initramfs")]
+    if let Some(initramfs) = initramfs {
+        root_dir
+            .mount("/initrd", initramfs, "initramfs", "ramfs")
+            .expect("failed to mount initramfs at /initrd");
+    }
+
     ROOT_DIR.init_by(Arc::new(root_dir));
-    CURRENT_DIR.init_by(Mutex::new(ROOT_DIR.clone()));
     *CURRENT_DIR_PATH.lock() = "/".into();
-}
 
-fn parent_node_of(dir: Option<&VfsNodeRef>, path: &str) -> VfsNodeRef {
-    if path.starts_with('/') {
-        ROOT_DIR.clone()
-    } else {
-        dir.cloned().unwrap_or_else(|| CURRENT_DIR.lock().clone())
-    }
+    crate::fstab::mount_all(crate::fstab::FSTAB_PATH);
 }
 
 pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
@@ -193,11 +436,138 @@ pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
     }
 }
 
-pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+/// Reads the target path of the symbolic link `node`.
+fn read_link_node(node: &VfsNodeRef) -> AxResult<String> {
+    let attr = node.get_attr()?;
+    if !attr.is_symlink() {
+        return ax_err!(InvalidInput);
+    }
+    let mut buf = vec![0; attr.size() as usize];
+    let len = node.readlink(&mut buf)?;
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|_| AxError::InvalidData)
+}
+
+/// Resolves the symbolic links in `path`, returns the directory node where
+/// the lookup starts and the resolved path relative to it.
+///
+/// The resolved path contains no symbolic links, except the last component
+/// if `follow` is `false`. The last component is allowed to be absent (e.g.,
+/// to be created). Returns [`FilesystemLoop`](AxError::FilesystemLoop) if
+/// too many symbolic links are encountered.
+fn resolve_path(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    follow: bool,
+) -> AxResult<(VfsNodeRef, String)> {
+    // paths relative to the current directory are looked up from the root, so
+    // that `..` can cross mount points
+    let cwd_path;
+    let (mut base, path) = match dir {
+        Some(dir) if !path.starts_with('/') => (dir.clone(), path),
+        None if !path.starts_with('/') => {
+            cwd_path = CURRENT_DIR_PATH.lock().clone() + path;
+            (ROOT_DIR.clone() as VfsNodeRef, cwd_path.as_str())
+        }
+        _ => (ROOT_DIR.clone() as VfsNodeRef, path),
+    };
+    let mut resolved = String::new();
+    // nodes of the components in `resolved`, so that each step only looks up
+    // one component from its parent
+    let mut nodes: Vec<VfsNodeRef> = Vec::new();
+    // a trailing '/' requires the last component to be a directory
+    let follow = follow || path.ends_with('/');
+    // components to be resolved, in reverse order
+    let mut pending: Vec<String> = path
+        .split('/')
+        .rev()
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    let mut follows = 0;
+
+    while let Some(name) = pending.pop() {
+        match name.as_str() {
+            "." => continue,
+            ".." => {
+                // no symbolic links in `resolved`, so just remove the last component
+                if resolved.is_empty() || resolved.rsplit('/').next() == Some("..") {
+                    if is_root_dir(&base) {
+                        continue;
+                    }
+                } else {
+                    resolved.truncate(resolved.rfind('/').unwrap_or(0));
+                    nodes.pop();
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        let is_last = pending.is_empty();
+        let parent = nodes.last().unwrap_or(&base).clone();
+        let parent_len = resolved.len();
+        push_component(&mut resolved, &name);
+        // mount points are only known by the root directory
+        let node = if is_root_dir(&base) && ROOT_DIR.contains(&format!("/{}", resolved)) {
+            ROOT_DIR.clone().lookup(&resolved)
+        } else {
+            parent.lookup(&name)
+        };
+        let node = match node {
+            Ok(node) => node,
+            Err(AxError::NotFound) if is_last => break,
+            Err(e) => return Err(e),
+        };
+        if node.get_attr()?.is_symlink() && (follow || !is_last) {
+            resolved.truncate(parent_len);
+            follows += 1;
+            if follows > MAX_SYMLINK_FOLLOWS {
+                return ax_err!(FilesystemLoop);
+            }
+            let target = read_link_node(&node)?;
+            if target.starts_with('/') {
+                base = ROOT_DIR.clone();
+                resolved.clear();
+                nodes.clear();
+            }
+            pending.extend(
+                target
+                    .split('/')
+                    .rev()
+                    .filter(|s| !s.is_empty())
+                    .map(String::from),
+            );
+        } else {
+            nodes.push(node);
+        }
+    }
+    Ok((base, resolved))
+}
+
+fn is_root_dir(node: &VfsNodeRef) -> bool {
+    Arc::ptr_eq(node, &(ROOT_DIR.clone() as VfsNodeRef))
+}
+
+fn push_component(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(name);
+}
+
+/// Resolves the symbolic links in `path`, returns the absolute path.
+pub(crate) fn resolve_absolute_path(path: &str, follow: bool) -> AxResult<String> {
+    let (_, path) = resolve_path(None, &absolute_path(path)?, follow)?;
+    Ok(String::from("/") + &path)
+}
+
+fn lookup_inner(dir: Option<&VfsNodeRef>, path: &str, follow: bool) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     }
-    let node = parent_node_of(dir, path).lookup(path)?;
+    let (base, rel_path) = resolve_path(dir, path, follow)?;
+    let node = base.lookup(&rel_path)?;
     if path.ends_with('/') && !node.get_attr()?.is_dir() {
         ax_err!(NotADirectory)
     } else {
@@ -205,34 +575,69 @@ pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRe
     }
 }
 
+/// Looks up the node at `path`, following all symbolic links.
+pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_inner(dir, path, true)
+}
+
+/// Looks up the node at `path`, returns the symbolic link itself instead of
+/// its target if the last component is a symbolic link.
+pub(crate) fn lookup_nofollow(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_inner(dir, path, false)
+}
+
 pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     } else if path.ends_with('/') {
         return ax_err!(NotADirectory);
     }
-    let parent = parent_node_of(dir, path);
-    parent.create(path, VfsNodeType::File)?;
-    parent.lookup(path)
+    let (parent, path) = resolve_path(dir, path, true)?;
+    parent.create(&path, VfsNodeType::File)?;
+    parent.lookup(&path)
 }
 
 pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    match lookup(dir, path) {
+    match lookup_nofollow(dir, path) {
         Ok(_) => ax_err!(AlreadyExists),
-        Err(AxError::NotFound) => parent_node_of(dir, path).create(path, VfsNodeType::Dir),
+        Err(AxError::NotFound) => {
+            let (parent, path) = resolve_path(dir, path, false)?;
+            parent.create(&path, VfsNodeType::Dir)
+        }
         Err(e) => Err(e),
     }
 }
 
+pub(crate) fn create_symlink(dir: Option<&VfsNodeRef>, target: &str, path: &str) -> AxResult {
+    if path.is_empty() || target.is_empty() {
+        return ax_err!(NotFound);
+    } else if path.ends_with('/') {
+        return ax_err!(NotADirectory);
+    }
+    match lookup_nofollow(dir, path) {
+        Ok(_) => ax_err!(AlreadyExists),
+        Err(AxError::NotFound) => {
+            let (parent, path) = resolve_path(dir, path, false)?;
+            parent.symlink(target, &path)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn read_link(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<String> {
+    read_link_node(&lookup_nofollow(dir, path)?)
+}
+
 pub(crate) fn remove_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path)?;
     let attr = node.get_attr()?;
     if attr.is_dir() {
         ax_err!(IsADirectory)
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, path) = resolve_path(dir, path, false)?;
+        parent.remove(&path)
     }
 }
 
@@ -254,15 +659,81 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
         return ax_err!(PermissionDenied);
     }
 
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path.trim_end_matches('/'))?;
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, path) = resolve_path(dir, path.trim_end_matches('/'), false)?;
+        parent.remove(&path)
+    }
+}
+
+pub(crate) fn rename(old: &str, new: &str) -> AxResult {
+    let old = resolve_absolute_path(old, false)?;
+    let new = resolve_absolute_path(new, false)?;
+    if new.starts_with(&old) && new[old.len()..].starts_with('/') {
+        return ax_err!(InvalidInput); // cannot move a directory into itself
     }
+    ROOT_DIR.rename(&old, &new)
+}
+
+pub(crate) fn link(old: &str, new: &str) -> AxResult {
+    let old = resolve_absolute_path(old, false)?;
+    let new = resolve_absolute_path(new, false)?;
+    ROOT_DIR.link(&old, &new)
+}
+
+pub(crate) fn set_times(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    atime: Option<Duration>,
+    mtime: Option<Duration>,
+    follow: bool,
+) -> AxResult {
+    lookup_inner(dir, path, follow)?.set_times(atime, mtime)
+}
+
+pub(crate) fn mount(source: &str, path: &str, fs_type: &str, fs: Arc<dyn VfsOps>) -> AxResult {
+    let path = resolve_absolute_path(path, true)?;
+    ROOT_DIR.mount(&path, fs, source, fs_type)
+}
+
+/// Returns the [`MountRef`] of the filesystem where `path` is, which is
+/// relative to the directory opened with `dir_ref` if given.
+pub(crate) fn mount_ref(dir_ref: Option<&MountRef>, path: &str) -> AxResult<MountRef> {
+    match dir_ref {
+        Some(dir_ref) if !path.starts_with('/') => Ok(dir_ref.clone()),
+        _ => Ok(ROOT_DIR.mount_ref(&resolve_absolute_path(path, true)?)),
+    }
+}
+
+pub(crate) fn umount(path: &str) -> AxResult {
+    ROOT_DIR.umount(&resolve_absolute_path(path, true)?)?;
+    // write back the blocks left dirty by the unmounted filesystem
+    crate::dev::sync_all().map_err(|e| {
+        warn!("failed to sync block caches: {:?}", e);
+        AxError::Io
+    })
+}
+
+pub(crate) fn mounts() -> Vec<MountInfo> {
+    ROOT_DIR.mount_infos()
+}
+
+#[cfg(feature = "devfs")]
+pub(crate) fn add_device(name: &'static str, node: VfsNodeRef) {
+    DEV_FS.add(name, node);
+}
+
+#[cfg(feature = "procfs")]
+pub(crate) fn add_proc_file<F>(path: &'static str, generate: F)
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    PROC_FS.add_file(path, generate);
 }
 
 pub(crate) fn current_dir() -> AxResult<String> {
@@ -275,7 +746,6 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
         abs_path += "/";
     }
     if abs_path == "/" {
-        *CURRENT_DIR.lock() = ROOT_DIR.clone();
         *CURRENT_DIR_PATH.lock() = "/".into();
         return Ok(());
     }
@@ -287,7 +757,6 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     } else if !attr.perm().owner_executable() {
         ax_err!(PermissionDenied)
     } else {
-        *CURRENT_DIR.lock() = node;
         *CURRENT_DIR_PATH.lock() = abs_path;
         Ok(())
     }
@@ -1,5 +1,8 @@
 use axfs::api as fs;
+use axfs_ramfs::RamFileSystem;
 use axio as io;
+use core::time::Duration;
+use std::sync::Arc;
 
 use fs::{File, FileType, OpenOptions};
 use io::{prelude::*, Error, Result};
@@ -216,6 +219,22 @@ fn test_devfs_ramfs() -> Result<()> {
     assert_eq!(md.file_type(), FileType::CharDevice);
     assert!(!md.is_dir());
 
+    // read /dev/urandom and stat other devices
+    File::open("/dev/urandom")?.read_exact(&mut buf)?;
+    assert_ne!(buf, [0; N]);
+    assert_eq!(fs::write("/dev/random", "entropy"), Ok(()));
+    assert_eq!(
+        fs::metadata("/dev/console")?.file_type(),
+        FileType::CharDevice
+    );
+    for mount in fs::mounts() {
+        if let Some(dev) = mount.source.strip_prefix("/dev/") {
+            let md = fs::metadata(&mount.source)?;
+            println!("metadata of block device {:?}: {:?}", dev, md);
+            assert_eq!(md.file_type(), FileType::BlockDevice);
+        }
+    }
+
     // error cases
     assert_err!(fs::metadata("/dev/null/"), NotADirectory);
     assert_err!(fs::create_dir("dev"), AlreadyExists);
@@ -252,6 +271,269 @@ fn test_devfs_ramfs() -> Result<()> {
     Ok(())
 }
 
+fn test_file_times() -> Result<()> {
+    // 2023-11-14 22:13:20 UTC, representable in all filesystems
+    let time = Duration::from_secs(1_700_000_000);
+
+    for fname in ["times.txt", "/tmp/times.txt"] {
+        println!("set times of {:?}:", fname);
+        fs::write(fname, "test")?;
+        let file = File::open(fname)?;
+        file.set_modified(time)?;
+        assert_eq!(file.metadata()?.modified(), time);
+        drop(file);
+
+        // check the timestamps are kept after reopening
+        let md = fs::metadata(fname)?;
+        println!("metadata of {:?}: {:?}", fname, md);
+        assert_eq!(md.modified(), time);
+
+        fs::set_times(fname, Some(time), None)?;
+        let md = fs::metadata(fname)?;
+        assert_eq!(md.accessed().as_secs() / 86400, time.as_secs() / 86400);
+        assert_eq!(md.modified(), time);
+        fs::remove_file(fname)?;
+    }
+
+    assert_err!(fs::set_times("/not/exist/file", None, Some(time)), NotFound);
+
+    println!("test_file_times() OK!");
+    Ok(())
+}
+
+fn test_rename_link() -> Result<()> {
+    println!("rename files and directories:");
+    fs::create_dir("rename_dir")?;
+    fs::write("rename_dir/a.txt", "hello")?;
+    fs::rename("rename_dir/a.txt", "b.txt")?;
+    assert_err!(fs::metadata("rename_dir/a.txt"), NotFound);
+    assert_eq!(fs::read_to_string("b.txt")?, "hello");
+
+    fs::write("c.txt", "world")?;
+    fs::rename("b.txt", "c.txt")?; // replace the existing file
+    assert_eq!(fs::read_to_string("c.txt")?, "hello");
+    fs::rename("c.txt", "rename_dir/c.txt")?;
+    assert_err!(fs::rename("rename_dir", "rename_dir/sub"), InvalidInput);
+    fs::rename("rename_dir", "rename_dir2")?;
+    assert_eq!(fs::read_to_string("rename_dir2/c.txt")?, "hello");
+
+    println!("rename across filesystems:");
+    assert_err!(
+        fs::rename("rename_dir2/c.txt", "/tmp/c.txt"),
+        CrossesDevices
+    );
+    assert_err!(fs::rename("/tmp", "/tmp2"), ResourceBusy);
+    fs::remove_file("rename_dir2/c.txt")?;
+    fs::remove_dir("rename_dir2")?;
+
+    println!("create hard links in ramfs:");
+    fs::write("/tmp/link_src.txt", "linked")?;
+    fs::hard_link("/tmp/link_src.txt", "/tmp/link_dst.txt")?;
+    fs::remove_file("/tmp/link_src.txt")?;
+    assert_eq!(fs::read_to_string("/tmp/link_dst.txt")?, "linked");
+    assert_err!(fs::hard_link("/tmp", "/tmp/dir_link"), ResourceBusy);
+    fs::remove_file("/tmp/link_dst.txt")?;
+
+    println!("test_rename_link() OK!");
+    Ok(())
+}
+
+fn test_symlink() -> Result<()> {
+    println!("create symbolic links in ramfs:");
+    fs::create_dir("/tmp/sym_dir")?;
+    fs::write("/tmp/sym_dir/f1", "symlink")?;
+    fs::symlink("/tmp/sym_dir", "/tmp/abs_link")?;
+    fs::symlink("sym_dir/f1", "/tmp/rel_link")?;
+    fs::symlink("../rel_link", "/tmp/sym_dir/up_link")?;
+    fs::symlink("not_exist", "/tmp/dangling")?;
+    fs::symlink("loop2", "/tmp/loop1")?;
+    fs::symlink("loop1", "/tmp/loop2")?;
+    assert_err!(fs::symlink("f1", "/tmp/rel_link"), AlreadyExists);
+
+    println!("follow symbolic links:");
+    assert_eq!(fs::read_to_string("/tmp/abs_link/f1")?, "symlink");
+    assert_eq!(fs::read_to_string("/tmp/rel_link")?, "symlink");
+    assert_eq!(fs::read_to_string("/tmp/abs_link/up_link")?, "symlink");
+    assert!(fs::metadata("/tmp/abs_link")?.is_dir());
+    assert!(fs::metadata("/tmp/abs_link/")?.is_dir());
+    assert_eq!(
+        fs::canonicalize("/tmp/abs_link/up_link")?,
+        "/tmp/sym_dir/f1"
+    );
+    assert_err!(fs::metadata("/tmp/dangling"), NotFound);
+    assert_err!(fs::metadata("/tmp/loop1"), FilesystemLoop);
+    assert_err!(fs::metadata("/tmp/rel_link/"), NotADirectory);
+
+    println!("do not follow symbolic links:");
+    let md = fs::symlink_metadata("/tmp/abs_link")?;
+    println!("metadata of {:?}: {:?}", "/tmp/abs_link", md);
+    assert!(md.is_symlink());
+    assert_eq!(md.len(), "/tmp/sym_dir".len() as u64);
+    assert!(!fs::symlink_metadata("/tmp/abs_link/f1")?.is_symlink());
+    assert_eq!(fs::read_link("/tmp/abs_link")?, "/tmp/sym_dir");
+    assert_eq!(fs::read_link("/tmp/abs_link/up_link")?, "../rel_link");
+    assert_err!(fs::read_link("/tmp/sym_dir"), InvalidInput);
+    assert_err!(
+        OpenOptions::new()
+            .read(true)
+            .nofollow(true)
+            .open("/tmp/rel_link"),
+        FilesystemLoop
+    );
+    assert_err!(File::create_new("/tmp/dangling"), AlreadyExists);
+
+    println!("create and remove through symbolic links:");
+    fs::write("/tmp/dangling", "created")?;
+    assert_eq!(fs::read_to_string("/tmp/not_exist")?, "created");
+    fs::write("/tmp/abs_link/f2", "f2")?;
+    assert_eq!(fs::read_to_string("/tmp/sym_dir/f2")?, "f2");
+    assert_err!(fs::remove_dir("/tmp/abs_link"), NotADirectory);
+    for fname in ["abs_link", "rel_link", "dangling", "loop1", "loop2"] {
+        fs::remove_file(&format!("/tmp/{fname}"))?;
+    }
+    assert_eq!(fs::read_to_string("/tmp/sym_dir/f1")?, "symlink");
+    for fname in ["sym_dir/f1", "sym_dir/f2", "sym_dir/up_link", "not_exist"] {
+        fs::remove_file(&format!("/tmp/{fname}"))?;
+    }
+    fs::remove_dir("/tmp/sym_dir")?;
+
+    println!("test_symlink() OK!");
+    Ok(())
+}
+
+fn test_mount() -> Result<()> {
+    println!("mount nested filesystems:");
+    fs::mount("ramfs", "/tmp/mnt", "ramfs", Arc::new(RamFileSystem::new()))?;
+    fs::mount(
+        "ramfs",
+        "/tmp/mnt/sub",
+        "ramfs",
+        Arc::new(RamFileSystem::new()),
+    )?;
+    for mount in fs::mounts() {
+        println!("{}", mount);
+    }
+    assert!(fs::mounts().iter().any(|m| m.path == "/tmp/mnt/sub"));
+    fs::write("/tmp/mnt/f1", "mnt")?;
+    fs::write("/tmp/mnt/sub/f2", "sub")?;
+    fs::write("/tmp/f3", "tmp")?;
+    assert_eq!(fs::read_to_string("/tmp/mnt/sub/../f1")?, "mnt");
+    assert_eq!(fs::read_to_string("/tmp/mnt/sub/../../f3")?, "tmp");
+    assert_err!(fs::rename("/tmp/mnt/f1", "/tmp/mnt/sub/f1"), CrossesDevices);
+
+    println!("cross mount points with '..':");
+    fs::set_current_dir("/tmp/mnt/sub")?;
+    assert_eq!(fs::read_to_string("f2")?, "sub");
+    assert_eq!(fs::read_to_string("../../f3")?, "tmp");
+    assert!(fs::metadata("../../../dev")?.is_dir());
+    assert_err!(fs::umount("/tmp/mnt/sub"), ResourceBusy); // current directory
+    fs::set_current_dir("/")?;
+
+    println!("unmount filesystems:");
+    assert_err!(fs::umount("/tmp/mnt"), ResourceBusy); // nested mount point
+    assert_err!(fs::umount("/tmp/mnt/f1"), InvalidInput);
+    fs::umount("/tmp/mnt/sub")?;
+    fs::umount("/tmp/mnt")?;
+    assert_err!(fs::umount("/tmp/mnt"), InvalidInput);
+    assert_err!(fs::metadata("/tmp/mnt/f1"), NotFound);
+    assert!(!fs::mounts().iter().any(|m| m.path.starts_with("/tmp/mnt")));
+    fs::remove_dir("/tmp/mnt")?;
+    fs::remove_file("/tmp/f3")?;
+
+    println!("test_mount() OK!");
+    Ok(())
+}
+
+#[cfg(feature = "procfs")]
+fn test_procfs() -> Result<()> {
+    println!("read /proc files:");
+    let mounts = fs::read_to_string("/proc/mounts")?;
+    print!("{}", mounts);
+    assert!(mounts
+        .lines()
+        .any(|line| line == "procfs /proc procfs rw 0 0"));
+
+    fs::add_proc_file("test/hello", || String::from("Hello, procfs!\n"));
+    assert!(fs::metadata("/proc/test")?.is_dir());
+    assert_eq!(fs::read_to_string("/proc/test/hello")?, "Hello, procfs!\n");
+    assert_err!(fs::write("/proc/test/hello", "test"), PermissionDenied);
+    assert_err!(fs::remove_file("/proc/test/hello"), PermissionDenied);
+    assert_err!(fs::create_dir("/proc/new_dir"), PermissionDenied);
+
+    println!("test_procfs() OK!");
+    Ok(())
+}
+
+fn test_file_lock(fname: &str) -> Result<()> {
+    use fs::LockType::*;
+    println!("test file locks on {:?}:", fname);
+
+    fs::write(fname, "lock")?;
+    let file1 = File::options().read(true).write(true).open(fname)?;
+    let file2 = File::options().read(true).write(true).open(fname)?;
+
+    // whole-file locks
+    file1.lock_shared()?;
+    file2.try_lock_shared()?;
+    assert_err!(file2.try_lock(), WouldBlock);
+    file1.unlock()?;
+    file2.try_lock()?;
+    assert_err!(file1.try_lock_shared(), WouldBlock);
+    drop(file2); // released on close
+    file1.try_lock()?;
+
+    // record locks, independent of whole-file locks
+    let file2 = File::options().read(true).write(true).open(fname)?;
+    file1.lock_range(Some(Exclusive), 0..100, false)?;
+    assert_eq!(
+        file2.test_lock_range(Shared, 50..60)?,
+        Some((Exclusive, 0..100))
+    );
+    assert_eq!(file1.test_lock_range(Exclusive, 50..60)?, None);
+    assert_err!(file2.lock_range(Some(Shared), 99..200, false), WouldBlock);
+    file1.lock_range(None, 40..60, false)?;
+    file2.lock_range(Some(Exclusive), 40..60, false)?;
+    file1.lock_range(Some(Shared), 60..100, false)?;
+    file2.lock_range(Some(Shared), 60..u64::MAX, false)?;
+    assert_eq!(
+        file1.test_lock_range(Shared, 0..u64::MAX)?,
+        Some((Exclusive, 40..60))
+    );
+
+    // a read lock requires the read access
+    let file3 = File::options().write(true).open(fname)?;
+    assert_err!(
+        file3.lock_range(Some(Shared), 0..1, false),
+        PermissionDenied
+    );
+
+    drop(file1);
+    drop(file2);
+    fs::remove_file(fname)?;
+    println!("test_file_lock() OK!");
+    Ok(())
+}
+
+fn test_sync() -> Result<()> {
+    println!("test sync:");
+
+    let fname = "/sync.txt";
+    let contents = "write back by sync";
+    fs::write(fname, contents)?;
+    fs::sync()?;
+    let stats = fs::cache_stats();
+    println!("  block cache: {:?}", stats);
+    assert_eq!(stats.dirty, 0);
+
+    assert_eq!(fs::read_to_string(fname)?, contents);
+    fs::remove_file(fname)?;
+    fs::sync()?;
+    assert_eq!(fs::cache_stats().dirty, 0);
+
+    println!("test_sync() OK!");
+    Ok(())
+}
+
 pub fn test_all() {
     test_read_write_file().expect("test_read_write_file() failed");
     test_read_dir().expect("test_read_dir() failed");
@@ -259,4 +541,14 @@ pub fn test_all() {
     test_create_file_dir().expect("test_create_file_dir() failed");
     test_remove_file_dir().expect("test_remove_file_dir() failed");
     test_devfs_ramfs().expect("test_devfs_ramfs() failed");
+    test_file_times().expect("test_file_times() failed");
+    test_rename_link().expect("test_rename_link() failed");
+    test_symlink().expect("test_symlink() failed");
+    test_mount().expect("test_mount() failed");
+    #[cfg(feature = "procfs")]
+    test_procfs().expect("test_procfs() failed");
+    test_file_lock("/tmp/lock.txt").expect("test_file_lock() on ramfs failed");
+    // fatfs and ext4 create a new node on every lookup
+    test_file_lock("/lock.txt").expect("test_file_lock() on the root filesystem failed");
+    test_sync().expect("test_sync() failed");
 }
@@ -0,0 +1,80 @@
+#![cfg(feature = "ext4fs")]
+
+mod test_common;
+
+use axdriver::AxDeviceContainer;
+use axfs::api::{self as fs, File};
+use axio::{Error, Read, Result, Seek, SeekFrom, Write};
+use driver_block::ramdisk::RamDisk;
+
+const IMG_PATH: &str = "resources/ext2.img";
+
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let data = std::fs::read(path)?;
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+fn test_ext2_features() -> Result<()> {
+    println!("check permissions:");
+    let md = fs::metadata("readonly.txt")?;
+    assert_eq!(md.permissions().bits(), 0o444);
+    assert_eq!(fs::read_to_string("readonly.txt")?, "Rust is cool!\n");
+    assert_eq!(
+        File::options().write(true).open("readonly.txt").err(),
+        Some(Error::PermissionDenied)
+    );
+
+    println!("read symbolic links:");
+    assert_eq!(fs::read_link("fast-link")?, "very/long/path/test.txt");
+    assert_eq!(fs::read_to_string("fast-link")?, "Rust is cool!\n");
+    assert_eq!(
+        fs::read_link("slow-link")?,
+        "very-long-dir-name/../very-long-dir-name/very-long-file-name.txt"
+    );
+    assert_eq!(fs::read_to_string("slow-link")?, "Rust is cool!\n");
+
+    println!("create hard links:");
+    fs::hard_link("short.txt", "very/short-link.txt")?;
+    fs::write("very/short-link.txt", "linked\n")?;
+    assert_eq!(fs::read_to_string("short.txt")?, "linked\n");
+    fs::remove_file("very/short-link.txt")?;
+    fs::write("short.txt", "Rust is cool!\n")?;
+
+    println!("write a large sparse file:");
+    let mut file = File::create_new("large.bin")?;
+    for offset in [70 << 20, 5 << 30] {
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(b"Rust is cool!\n")?;
+    }
+    drop(file);
+    let md = fs::metadata("large.bin")?;
+    assert_eq!(md.len(), (5 << 30) + 14);
+    let mut file = File::open("large.bin")?;
+    file.seek(SeekFrom::Start(5 << 30))?;
+    let mut buf = [0; 14];
+    file.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"Rust is cool!\n");
+    file.seek(SeekFrom::Start(1 << 20))?;
+    file.read_exact(&mut buf)?;
+    assert_eq!(buf, [0; 14]);
+    drop(file);
+    fs::remove_file("large.bin")?;
+
+    println!("test_ext2_features() OK!");
+    Ok(())
+}
+
+#[test]
+fn test_ext2() {
+    println!("Testing ext2 with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    test_common::test_all();
+    test_ext2_features().expect("test_ext2_features() failed");
+}
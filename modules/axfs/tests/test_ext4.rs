@@ -0,0 +1,79 @@
+#![cfg(feature = "ext4fs")]
+
+use axdriver::AxDeviceContainer;
+use axfs::api::{self as fs, File};
+use axio::{Error, Read, Result};
+use driver_block::ramdisk::RamDisk;
+
+const IMG_PATH: &str = "resources/ext4.img";
+
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let data = std::fs::read(path)?;
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+fn test_read_only() -> Result<()> {
+    println!("read files using extents:");
+    let mut contents = String::new();
+    File::open("/long.txt")?.read_to_string(&mut contents)?;
+    assert_eq!(contents, "Rust is cool!\n".repeat(1000));
+    assert_eq!(
+        fs::read_to_string("/very/long/path/test.txt")?,
+        "Rust is cool!\n"
+    );
+    assert_eq!(fs::metadata("/readonly.txt")?.permissions().bits(), 0o444);
+
+    println!("list directories:");
+    let dirents = fs::read_dir("/very-long-dir-name")?
+        .map(|e| e.unwrap().file_name())
+        .collect::<Vec<_>>();
+    println!("dirents = {:?}", dirents);
+    assert!(dirents.contains(&"very-long-file-name.txt".into()));
+
+    println!("read symbolic links:");
+    assert_eq!(fs::read_link("/fast-link")?, "very/long/path/test.txt");
+    assert_eq!(fs::read_to_string("/slow-link")?, "Rust is cool!\n");
+
+    println!("modifications are rejected:");
+    assert_eq!(
+        fs::write("/short.txt", "modified").err(),
+        Some(Error::ReadOnlyFilesystem)
+    );
+    assert_eq!(
+        fs::write("/new.txt", "created").err(),
+        Some(Error::ReadOnlyFilesystem)
+    );
+    assert_eq!(
+        fs::create_dir("/new-dir").err(),
+        Some(Error::ReadOnlyFilesystem)
+    );
+    assert_eq!(
+        fs::remove_file("/short.txt").err(),
+        Some(Error::ReadOnlyFilesystem)
+    );
+    assert_eq!(fs::read_to_string("/short.txt")?, "Rust is cool!\n");
+
+    // other filesystems are still writable
+    fs::write("/tmp/test.txt", "Rust is cool!\n")?;
+    fs::remove_file("/tmp/test.txt")?;
+
+    println!("test_read_only() OK!");
+    Ok(())
+}
+
+#[test]
+fn test_ext4() {
+    println!("Testing read-only ext4 with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    assert!(fs::mounts()
+        .iter()
+        .any(|m| m.path == "/" && m.fs_type == "ext4fs"));
+    test_read_only().expect("test_read_only() failed");
+}
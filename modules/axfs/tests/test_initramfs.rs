@@ -0,0 +1,58 @@
+#![cfg(feature = "initramfs")]
+
+mod test_common;
+
+use std::time::Duration;
+
+use axdriver::AxDeviceContainer;
+use axfs::api as fs;
+use axio::Result;
+
+/// The archive embedded by the build script, see `scripts/make/test.mk`.
+const ARCHIVE_PATH: Option<&str> = option_env!("AX_INITRAMFS");
+
+fn test_initramfs() -> Result<()> {
+    for mount in fs::mounts() {
+        println!("  {}", mount);
+    }
+    assert!(fs::mounts()
+        .iter()
+        .any(|m| m.path == "/" && m.source == "initramfs" && m.fs_type == "ramfs"));
+
+    assert_eq!(fs::metadata("/long.txt")?.len(), 14000);
+    assert_eq!(fs::read_to_string("/etc/hostname")?, "arceos\n");
+    assert_eq!(
+        fs::read_link("/etc/fast-link")?,
+        "../very/long/path/test.txt"
+    );
+    assert_eq!(fs::read_to_string("/etc/fast-link")?, "Rust is cool!\n");
+
+    // modification times are kept, including the ones of directories
+    let time = Duration::from_secs(1_234_567_890);
+    assert_eq!(fs::metadata("/etc/hostname")?.modified(), time);
+    assert_eq!(fs::metadata("/etc")?.modified(), time);
+
+    // hard links share the contents
+    fs::write("/etc/hostname.bak", "arceos-test\n")?;
+    assert_eq!(fs::read_to_string("/etc/hostname")?, "arceos-test\n");
+    fs::remove_file("/etc/hostname.bak")?;
+    assert_eq!(fs::read_to_string("/etc/hostname")?, "arceos-test\n");
+
+    println!("test_initramfs() OK!");
+    Ok(())
+}
+
+#[test]
+fn test_initramfs_root() {
+    let Some(path) = ARCHIVE_PATH else {
+        println!("AX_INITRAMFS is not set, skipped");
+        return;
+    };
+    println!("Testing initramfs from {:?} ...", path);
+
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::default()); // no disk, boot from the initramfs
+
+    test_initramfs().expect("test_initramfs() failed");
+    test_common::test_all();
+}
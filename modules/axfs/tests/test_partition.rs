@@ -0,0 +1,115 @@
+#![cfg(all(feature = "ext4fs", feature = "fatfs", not(feature = "myfs")))]
+
+use axdriver::AxDeviceContainer;
+use axfs::api as fs;
+use axio::{Error, Result};
+use driver_block::ramdisk::RamDisk;
+
+/// A GPT disk with an ext2 root partition, and a FAT data partition listed in
+/// `/etc/fstab` of the root.
+const IMG_PATH: &str = "resources/gpt.img";
+
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let data = std::fs::read(path)?;
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+fn test_partitions() -> Result<()> {
+    let parts = fs::partitions();
+    for part in &parts {
+        println!("  {:?}", part);
+    }
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].name, "vda1");
+    assert_eq!(parts[0].part_label.as_deref(), Some("rootfs"));
+    assert_eq!(parts[0].fs_type, Some("ext4fs"));
+    assert_eq!(parts[0].label.as_deref(), Some("rootfs"));
+    assert_eq!(parts[1].name, "vda2");
+    assert_eq!((parts[1].start, parts[1].num_blocks), (4096, 5000));
+    assert_eq!(
+        parts[1].part_uuid.as_deref(),
+        Some("c0ffee00-0000-4000-8000-000000000002")
+    );
+    assert_eq!(parts[1].fs_type, Some("fatfs"));
+    assert_eq!(parts[1].uuid.as_deref(), Some("1234-5678"));
+
+    println!("test_partitions() OK!");
+    Ok(())
+}
+
+fn test_fstab() -> Result<()> {
+    for mount in fs::mounts() {
+        println!("  {}", mount);
+    }
+    let mounts = fs::mounts();
+    assert!(mounts
+        .iter()
+        .any(|m| m.path == "/" && m.source == "/dev/vda1" && m.fs_type == "ext4fs"));
+    assert!(mounts
+        .iter()
+        .any(|m| m.path == "/data" && m.source == "/dev/vda2" && m.fs_type == "fatfs"));
+    // the same partition cannot be mounted twice, and missing ones are skipped
+    assert!(!mounts.iter().any(|m| m.path == "/data2"));
+    assert!(!mounts.iter().any(|m| m.path == "/backup"));
+
+    assert_eq!(fs::read_to_string("/short.txt")?, "Rust is cool!\n");
+    assert_eq!(
+        fs::read_to_string("/data/very/long/path/test.txt")?,
+        "Rust is cool!\n"
+    );
+
+    println!("test_fstab() OK!");
+    Ok(())
+}
+
+fn test_mount_device() -> Result<()> {
+    assert_eq!(
+        fs::mount_device("UUID=1234-5678", "/mnt", "auto").err(),
+        Some(Error::ResourceBusy)
+    );
+    assert_eq!(
+        fs::mount_device("/dev/vda1", "/mnt", "auto").err(),
+        Some(Error::ResourceBusy)
+    );
+    assert_eq!(
+        fs::mount_device("PARTLABEL=backup", "/mnt", "auto").err(),
+        Some(Error::NotFound)
+    );
+
+    fs::write("/data/new.txt", "written on the data partition\n")?;
+    fs::umount("/data")?;
+    assert!(fs::metadata("/data/new.txt").is_err());
+    fs::mount_device(
+        "PARTUUID=C0FFEE00-0000-4000-8000-000000000002",
+        "/mnt",
+        "vfat",
+    )?;
+    assert_eq!(
+        fs::read_to_string("/mnt/new.txt")?,
+        "written on the data partition\n"
+    );
+    fs::remove_file("/mnt/new.txt")?;
+    fs::umount("/mnt")?;
+    fs::mount_device("vda2", "/data", "fatfs")?;
+    assert!(fs::metadata("/data/new.txt").is_err());
+    fs::sync()?;
+
+    println!("test_mount_device() OK!");
+    Ok(())
+}
+
+#[test]
+fn test_partition() {
+    println!("Testing partitions with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    test_partitions().expect("test_partitions() failed");
+    test_fstab().expect("test_fstab() failed");
+    test_mount_device().expect("test_mount_device() failed");
+}
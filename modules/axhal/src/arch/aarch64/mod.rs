@@ -1,14 +1,20 @@
 mod context;
 pub(crate) mod trap;
 
+#[cfg(feature = "uspace")]
+mod uspace;
+
 use core::arch::asm;
 
-use aarch64_cpu::registers::{DAIF, TTBR1_EL1, VBAR_EL1};
+use aarch64_cpu::registers::{DAIF, TTBR0_EL1, TTBR1_EL1, VBAR_EL1};
 use memory_addr::{PhysAddr, VirtAddr};
 use tock_registers::interfaces::{Readable, Writeable};
 
 pub use self::context::{FpState, TaskContext, TrapFrame};
 
+#[cfg(feature = "uspace")]
+pub use self::uspace::UspaceContext;
+
 /// Allows the current CPU to respond to interrupts.
 #[inline]
 pub fn enable_irqs() {
@@ -66,6 +72,55 @@ pub unsafe fn write_page_table_root(root_paddr: PhysAddr) {
     }
 }
 
+/// The base address bits in `TTBR0_EL1`, excluding the ASID.
+const TTBR_BADDR_MASK: usize = 0x0000_ffff_ffff_fffe;
+
+/// Writes `TTBR0_EL1` to update the page table root of user space.
+///
+/// The kernel page table is in `TTBR1_EL1`, which is not affected.
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the virtual memory address space.
+pub unsafe fn write_page_table_root0(root_paddr: PhysAddr) {
+    let old_root = PhysAddr::from(TTBR0_EL1.get() as usize & TTBR_BADDR_MASK);
+    trace!(
+        "set user page table root: {:#x} => {:#x}",
+        old_root,
+        root_paddr
+    );
+    if old_root != root_paddr {
+        TTBR0_EL1.set(root_paddr.as_usize() as _);
+        flush_tlb(None);
+    }
+}
+
+/// Enables the ASIDs, and returns the maximum usable ASID.
+///
+/// The 8-bit ASIDs in `TTBR0_EL1` (`TCR_EL1.A1 = 0, TCR_EL1.AS = 0`) are
+/// always supported.
+#[cfg(feature = "paging")]
+pub(crate) fn init_asid() -> usize {
+    0xff
+}
+
+/// Writes the page table root and the ASID `asid` to `TTBR0_EL1`.
+///
+/// The TLB entries tagged with `asid` are flushed if `flush` is `true`.
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the virtual memory address space.
+#[cfg(feature = "paging")]
+pub(crate) unsafe fn switch_page_table_root(root_paddr: PhysAddr, asid: usize, flush: bool) {
+    let asid = (asid as u64) << 48;
+    TTBR0_EL1.set(asid | root_paddr.as_usize() as u64);
+    if flush {
+        asm!("tlbi aside1, {}; dsb nsh", in(reg) asid);
+    }
+    asm!("isb");
+}
+
 /// Flushes the TLB.
 ///
 /// If `vaddr` is [`None`], flushes the entire TLB. Otherwise, flushes the TLB
@@ -74,7 +129,8 @@ pub unsafe fn write_page_table_root(root_paddr: PhysAddr) {
 pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     unsafe {
         if let Some(vaddr) = vaddr {
-            asm!("tlbi vaae1is, {}; dsb sy; isb", in(reg) vaddr.as_usize())
+            // the operand is bits [55:12] of the address
+            asm!("tlbi vaae1is, {}; dsb sy; isb", in(reg) vaddr.as_usize() >> 12)
         } else {
             // flush the entire TLB
             asm!("tlbi vmalle1; dsb sy; isb")
@@ -4,6 +4,7 @@ use aarch64_cpu::registers::{ESR_EL1, FAR_EL1};
 use tock_registers::interfaces::Readable;
 
 use super::TrapFrame;
+use crate::trap::MappingFlags;
 
 global_asm!(include_str!("trap.S"));
 
@@ -35,6 +36,46 @@ fn invalid_exception(tf: &mut TrapFrame, kind: TrapKind, source: TrapSource) {
     );
 }
 
+/// Returns the access type of a data abort from its ISS, by the WnR bit.
+fn data_abort_flags(iss: u64) -> MappingFlags {
+    const ISS_WNR: u64 = 1 << 6;
+    if iss & ISS_WNR != 0 {
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    }
+}
+
+fn handle_page_fault(tf: &TrapFrame, access_flags: MappingFlags, is_user: bool) {
+    let vaddr = (FAR_EL1.get() as usize).into();
+    if crate::trap::handle_page_fault(vaddr, access_flags, is_user) {
+        return;
+    }
+    let iss = ESR_EL1.read(ESR_EL1::ISS);
+    if is_user {
+        warn!(
+            "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
+            tf.elr, vaddr, iss
+        );
+    } else {
+        panic!(
+            "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
+            tf.elr, vaddr, iss, tf,
+        );
+    }
+}
+
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame, esr: u64) {
+    warn!("EL0 synchronous exception @ {:#x}: ESR={:#x}", tf.elr, esr);
+    if !crate::trap::handle_user_exception(tf) {
+        panic!(
+            "Unhandled EL0 synchronous exception @ {:#x}: ESR={:#x}:\n{:#x?}",
+            tf.elr, esr, tf
+        );
+    }
+}
+
 #[no_mangle]
 fn handle_sync_exception(tf: &mut TrapFrame) {
     let esr = ESR_EL1.extract();
@@ -44,30 +85,30 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
             debug!("BRK #{:#x} @ {:#x} ", iss, tf.elr);
             tf.elr += 4;
         }
+        #[cfg(feature = "uspace")]
+        Some(ESR_EL1::EC::Value::SVC64) => {
+            super::enable_irqs();
+            tf.r[0] = crate::trap::handle_syscall(tf, tf.r[8] as usize) as u64;
+            super::disable_irqs();
+        }
+        #[cfg(not(feature = "uspace"))]
         Some(ESR_EL1::EC::Value::SVC64) => {
             warn!("No supervisor call is supported currently!");
         }
-        Some(ESR_EL1::EC::Value::DataAbortLowerEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            warn!(
-                "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss
-            );
+        Some(ESR_EL1::EC::Value::DataAbortLowerEL) => {
+            handle_page_fault(tf, data_abort_flags(esr.read(ESR_EL1::ISS)), true)
         }
-        Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            panic!(
-                "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss,
-                tf,
-            );
+        Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => {
+            handle_page_fault(tf, MappingFlags::EXECUTE, true)
+        }
+        Some(ESR_EL1::EC::Value::DataAbortCurrentEL) => {
+            handle_page_fault(tf, data_abort_flags(esr.read(ESR_EL1::ISS)), false)
+        }
+        Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
+            handle_page_fault(tf, MappingFlags::EXECUTE, false)
         }
+        #[cfg(feature = "uspace")]
+        _ if tf.is_user() => handle_user_exception(tf, esr.get()),
         _ => {
             panic!(
                 "Unhandled synchronous exception @ {:#x}: ESR={:#x} (EC {:#08b}, ISS {:#x})",
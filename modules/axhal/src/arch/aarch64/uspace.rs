@@ -0,0 +1,132 @@
+//! Structures and functions for user space.
+
+use aarch64_cpu::registers::TPIDR_EL0;
+use core::arch::asm;
+use memory_addr::VirtAddr;
+use tock_registers::interfaces::{Readable, Writeable};
+
+use super::TrapFrame;
+
+/// Context to enter user space.
+pub struct UspaceContext {
+    tf: TrapFrame,
+    /// Thread pointer for thread-local storage.
+    tpidr_el0: u64,
+}
+
+impl UspaceContext {
+    /// Creates an empty context with all registers set to zero.
+    pub const fn empty() -> Self {
+        unsafe { core::mem::MaybeUninit::zeroed().assume_init() }
+    }
+
+    /// Creates a new context with the given entry point, user stack pointer,
+    /// and the argument.
+    pub fn new(entry: usize, ustack_top: VirtAddr, arg0: usize) -> Self {
+        let mut regs = [0; 31];
+        regs[0] = arg0 as _;
+        Self {
+            tf: TrapFrame {
+                r: regs,
+                usp: ustack_top.as_usize() as _,
+                elr: entry as _,
+                // EL0t, with all exceptions (DAIF) unmasked
+                spsr: 0,
+            },
+            tpidr_el0: 0,
+        }
+    }
+
+    /// Creates a new context from the given [`TrapFrame`], and the current
+    /// `TPIDR_EL0`.
+    ///
+    /// It is usually used to create the context of a new thread (e.g., by
+    /// `clone`) from the trap frame of the current one.
+    pub fn from(tf: &TrapFrame) -> Self {
+        Self {
+            tf: *tf,
+            tpidr_el0: TPIDR_EL0.get(),
+        }
+    }
+
+    /// Gets the instruction pointer.
+    pub const fn get_ip(&self) -> usize {
+        self.tf.elr as _
+    }
+
+    /// Gets the stack pointer.
+    pub const fn get_sp(&self) -> usize {
+        self.tf.usp as _
+    }
+
+    /// Sets the instruction pointer.
+    pub fn set_ip(&mut self, pc: usize) {
+        self.tf.elr = pc as _;
+    }
+
+    /// Sets the stack pointer.
+    pub fn set_sp(&mut self, sp: usize) {
+        self.tf.usp = sp as _;
+    }
+
+    /// Sets the return value register.
+    pub fn set_retval(&mut self, r0: usize) {
+        self.tf.r[0] = r0 as _;
+    }
+
+    /// Sets the thread pointer for thread-local storage, i.e., `TPIDR_EL0`.
+    pub fn set_tls(&mut self, tls: usize) {
+        self.tpidr_el0 = tls as _;
+    }
+
+    /// Enters user space.
+    ///
+    /// It restores the user registers and jumps to the user entry point
+    /// (saved in `ELR_EL1`). When an exception or syscall occurs, the kernel
+    /// stack pointer will be switched to `kstack_top`.
+    ///
+    /// This function never returns.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes processor mode and the
+    /// stack. The contents of the kernel stack are discarded.
+    pub unsafe fn enter_uspace(&self, kstack_top: VirtAddr) -> ! {
+        super::disable_irqs();
+        TPIDR_EL0.set(self.tpidr_el0);
+        // Put the trap frame on the top of the kernel stack, as if the task
+        // just trapped from user space. `self` may be in the same place, so
+        // `ptr::copy` is used. After the registers are restored, `SP_EL1` is
+        // exactly `kstack_top`.
+        let tf_ptr = kstack_top.as_mut_ptr().cast::<TrapFrame>().sub(1);
+        core::ptr::copy(&self.tf, tf_ptr, 1);
+        asm!("
+            mov     sp, {tf}
+            ldp     x10, x11, [sp, 32 * 8]
+            ldp     x30, x9, [sp, 30 * 8]
+            msr     sp_el0, x9
+            msr     elr_el1, x10
+            msr     spsr_el1, x11
+
+            ldp     x28, x29, [sp, 28 * 8]
+            ldp     x26, x27, [sp, 26 * 8]
+            ldp     x24, x25, [sp, 24 * 8]
+            ldp     x22, x23, [sp, 22 * 8]
+            ldp     x20, x21, [sp, 20 * 8]
+            ldp     x18, x19, [sp, 18 * 8]
+            ldp     x16, x17, [sp, 16 * 8]
+            ldp     x14, x15, [sp, 14 * 8]
+            ldp     x12, x13, [sp, 12 * 8]
+            ldp     x10, x11, [sp, 10 * 8]
+            ldp     x8, x9, [sp, 8 * 8]
+            ldp     x6, x7, [sp, 6 * 8]
+            ldp     x4, x5, [sp, 4 * 8]
+            ldp     x2, x3, [sp, 2 * 8]
+            ldp     x0, x1, [sp]
+            add     sp, sp, 34 * 8
+            eret",
+            tf = in(reg) tf_ptr,
+            options(noreturn),
+        )
+    }
+}
@@ -12,3 +12,25 @@ cfg_if::cfg_if! {
         pub use self::aarch64::*;
     }
 }
+
+use memory_addr::{VirtAddr, PAGE_SIZE_4K};
+
+/// Ranges larger than this number of pages are flushed by flushing the entire
+/// TLB in [`flush_tlb_range`], which is cheaper than flushing page by page.
+const TLB_FLUSH_ALL_THRESHOLD: usize = 64;
+
+/// Flushes the TLB entries that map the virtual memory range
+/// `[start, start + size)`.
+///
+/// The range is flushed page by page (huge pages are flushed by any address
+/// inside them), or the entire TLB is flushed if the range is large.
+pub fn flush_tlb_range(start: VirtAddr, size: usize) {
+    if size > TLB_FLUSH_ALL_THRESHOLD * PAGE_SIZE_4K {
+        flush_tlb(None);
+    } else {
+        let start = start.align_down_4k().as_usize();
+        for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+            flush_tlb(Some(vaddr.into()));
+        }
+    }
+}
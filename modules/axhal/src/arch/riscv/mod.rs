@@ -4,12 +4,18 @@ mod macros;
 mod context;
 mod trap;
 
+#[cfg(feature = "uspace")]
+mod uspace;
+
 use memory_addr::{PhysAddr, VirtAddr};
 use riscv::asm;
 use riscv::register::{satp, sstatus, stvec};
 
 pub use self::context::{GeneralRegisters, TaskContext, TrapFrame};
 
+#[cfg(feature = "uspace")]
+pub use self::uspace::UspaceContext;
+
 /// Allows the current CPU to respond to interrupts.
 #[inline]
 pub fn enable_irqs() {
@@ -73,13 +79,47 @@ pub unsafe fn write_page_table_root(root_paddr: PhysAddr) {
 pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     unsafe {
         if let Some(vaddr) = vaddr {
-            asm::sfence_vma(0, vaddr.as_usize())
+            // flush the entries of all ASIDs
+            core::arch::asm!("sfence.vma {}, zero", in(reg) vaddr.as_usize())
         } else {
             asm::sfence_vma_all();
         }
     }
 }
 
+/// Detects the number of supported ASID bits, and returns the maximum usable
+/// ASID (0 if not supported).
+#[cfg(feature = "paging")]
+pub(crate) fn init_asid() -> usize {
+    const SATP_ASID_SHIFT: usize = 44;
+    const SATP_ASID_MASK: usize = 0xffff << SATP_ASID_SHIFT;
+    // the implemented ASID bits are those that remain set after writing all
+    // ones to the `satp.ASID` field.
+    unsafe {
+        let old = satp::read().bits();
+        core::arch::asm!("csrw satp, {}", in(reg) old | SATP_ASID_MASK);
+        let max_asid = satp::read().asid();
+        core::arch::asm!("csrw satp, {}", in(reg) old);
+        asm::sfence_vma_all();
+        max_asid
+    }
+}
+
+/// Writes the page table root and the ASID `asid` to `satp`.
+///
+/// The TLB entries tagged with `asid` are flushed if `flush` is `true`.
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the virtual memory address space.
+#[cfg(feature = "paging")]
+pub(crate) unsafe fn switch_page_table_root(root_paddr: PhysAddr, asid: usize, flush: bool) {
+    satp::set(satp::Mode::Sv39, asid, root_paddr.as_usize() >> 12);
+    if flush {
+        core::arch::asm!("sfence.vma zero, {}", in(reg) asid);
+    }
+}
+
 /// Writes Supervisor Trap Vector Base Address Register (`stvec`).
 #[inline]
 pub fn set_trap_vector_base(stvec: usize) {
@@ -1,6 +1,8 @@
 use riscv::register::scause::{self, Exception as E, Trap};
+use riscv::register::stval;
 
 use super::TrapFrame;
+use crate::trap::MappingFlags;
 
 include_asm_marcos!();
 
@@ -14,12 +16,62 @@ fn handle_breakpoint(sepc: &mut usize) {
     *sepc += 2
 }
 
+#[cfg(feature = "uspace")]
+fn handle_syscall(tf: &mut TrapFrame) {
+    tf.sepc += 4;
+    super::enable_irqs();
+    tf.regs.a0 = crate::trap::handle_syscall(tf, tf.regs.a7) as usize;
+    // `sscratch` will be updated before `sret`, interrupts are not allowed
+    // then.
+    super::disable_irqs();
+}
+
+fn handle_page_fault(tf: &TrapFrame, access_flags: MappingFlags, is_user: bool) {
+    let vaddr = stval::read().into();
+    if !crate::trap::handle_page_fault(vaddr, access_flags, is_user) {
+        panic!(
+            "Unhandled {} Page Fault @ {:#x}, fault_vaddr={:#x} ({:?}):\n{:#x?}",
+            if is_user { "User" } else { "Supervisor" },
+            tf.sepc,
+            vaddr,
+            access_flags,
+            tf,
+        );
+    }
+}
+
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame) {
+    let cause = scause::read().cause();
+    warn!(
+        "User trap {:?} @ {:#x}, stval={:#x}",
+        cause,
+        tf.sepc,
+        stval::read()
+    );
+    if !crate::trap::handle_user_exception(tf) {
+        panic!(
+            "Unhandled user trap {:?} @ {:#x}:\n{:#x?}",
+            cause, tf.sepc, tf
+        );
+    }
+}
+
 #[no_mangle]
-fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
+fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     let scause = scause::read();
     match scause.cause() {
         Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
+        #[cfg(feature = "uspace")]
+        Trap::Exception(E::UserEnvCall) => handle_syscall(tf),
+        Trap::Exception(E::LoadPageFault) => handle_page_fault(tf, MappingFlags::READ, from_user),
+        Trap::Exception(E::StorePageFault) => handle_page_fault(tf, MappingFlags::WRITE, from_user),
+        Trap::Exception(E::InstructionPageFault) => {
+            handle_page_fault(tf, MappingFlags::EXECUTE, from_user)
+        }
         Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
+        #[cfg(feature = "uspace")]
+        _ if from_user => handle_user_exception(tf),
         _ => {
             panic!(
                 "Unhandled trap {:?} @ {:#x}:\n{:#x?}",
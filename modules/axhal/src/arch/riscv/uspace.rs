@@ -0,0 +1,116 @@
+//! Structures and functions for user space.
+
+use core::arch::asm;
+use memory_addr::VirtAddr;
+
+use super::{GeneralRegisters, TrapFrame};
+
+include_asm_marcos!();
+
+/// Context to enter user space.
+pub struct UspaceContext(TrapFrame);
+
+impl UspaceContext {
+    /// Creates an empty context with all registers set to zero.
+    pub const fn empty() -> Self {
+        unsafe { core::mem::MaybeUninit::zeroed().assume_init() }
+    }
+
+    /// Creates a new context with the given entry point, user stack pointer,
+    /// and the argument.
+    pub fn new(entry: usize, ustack_top: VirtAddr, arg0: usize) -> Self {
+        const SPIE: usize = 1 << 5; // enable interrupts after `sret`
+        const SUM: usize = 1 << 18; // allow supervisor to access user pages
+        const FS_INITIAL: usize = 1 << 13;
+        Self(TrapFrame {
+            regs: GeneralRegisters {
+                a0: arg0,
+                sp: ustack_top.as_usize(),
+                ..Default::default()
+            },
+            sepc: entry,
+            sstatus: SPIE | SUM | FS_INITIAL, // SPP is 0, i.e., return to U-mode
+        })
+    }
+
+    /// Creates a new context from the given [`TrapFrame`].
+    ///
+    /// It is usually used to create the context of a new thread (e.g., by
+    /// `clone`) from the trap frame of the current one.
+    pub fn from(tf: &TrapFrame) -> Self {
+        Self(tf.clone())
+    }
+
+    /// Gets the instruction pointer.
+    pub const fn get_ip(&self) -> usize {
+        self.0.sepc
+    }
+
+    /// Gets the stack pointer.
+    pub const fn get_sp(&self) -> usize {
+        self.0.regs.sp
+    }
+
+    /// Sets the instruction pointer.
+    pub fn set_ip(&mut self, pc: usize) {
+        self.0.sepc = pc;
+    }
+
+    /// Sets the stack pointer.
+    pub fn set_sp(&mut self, sp: usize) {
+        self.0.regs.sp = sp;
+    }
+
+    /// Sets the return value register.
+    pub fn set_retval(&mut self, a0: usize) {
+        self.0.regs.a0 = a0;
+    }
+
+    /// Sets the thread pointer for thread-local storage, i.e., the `tp`
+    /// register.
+    pub fn set_tls(&mut self, tls: usize) {
+        self.0.regs.tp = tls;
+    }
+
+    /// Enters user space.
+    ///
+    /// It restores the user registers and jumps to the user entry point
+    /// (saved in `sepc`). When an exception or syscall occurs, the kernel
+    /// stack pointer will be switched to `kstack_top`.
+    ///
+    /// This function never returns.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes processor mode and the
+    /// stack. The contents of the kernel stack are discarded.
+    pub unsafe fn enter_uspace(&self, kstack_top: VirtAddr) -> ! {
+        super::disable_irqs();
+        // Put the trap frame on the top of the kernel stack, as if the task
+        // just trapped from user space. `self` may be in the same place, so
+        // `ptr::copy` is used.
+        let tf_ptr = kstack_top.as_mut_ptr().cast::<TrapFrame>().sub(1);
+        core::ptr::copy(&self.0, tf_ptr, 1);
+        asm!("
+            mv      sp, {tf}
+            csrw    sscratch, {kstack_top}      // put supervisor sp to scratch
+
+            LDR     gp, sp, 2                   // load user gp and tp
+            LDR     t0, sp, 3
+            STR     tp, sp, 3                   // save supervisor tp
+            mv      tp, t0
+
+            LDR     t0, sp, 31
+            LDR     t1, sp, 32
+            csrw    sepc, t0
+            csrw    sstatus, t1
+
+            POP_GENERAL_REGS
+            LDR     sp, sp, 1                   // load user sp
+            sret",
+            tf = in(reg) tf_ptr,
+            kstack_top = in(reg) kstack_top.as_usize(),
+            options(noreturn),
+        )
+    }
+}
@@ -39,6 +39,36 @@ impl TrapFrame {
     pub const fn is_user(&self) -> bool {
         self.cs & 0b11 == 3
     }
+
+    /// Gets the 0th syscall argument.
+    pub const fn arg0(&self) -> usize {
+        self.rdi as _
+    }
+
+    /// Gets the 1st syscall argument.
+    pub const fn arg1(&self) -> usize {
+        self.rsi as _
+    }
+
+    /// Gets the 2nd syscall argument.
+    pub const fn arg2(&self) -> usize {
+        self.rdx as _
+    }
+
+    /// Gets the 3rd syscall argument.
+    pub const fn arg3(&self) -> usize {
+        self.r10 as _
+    }
+
+    /// Gets the 4th syscall argument.
+    pub const fn arg4(&self) -> usize {
+        self.r8 as _
+    }
+
+    /// Gets the 5th syscall argument.
+    pub const fn arg5(&self) -> usize {
+        self.r9 as _
+    }
 }
 
 #[repr(C)]
@@ -117,7 +147,8 @@ impl fmt::Debug for ExtendedState {
 ///
 /// - Callee-saved registers
 /// - Stack pointer register
-/// - Thread pointer register (for thread-local storage, currently unsupported)
+/// - Thread pointer register (for thread-local storage, i.e., the `FS` base,
+///   only saved with the `uspace` feature)
 /// - FP/SIMD registers
 ///
 /// On context switch, current task saves its context from CPU to memory,
@@ -136,6 +167,9 @@ pub struct TaskContext {
     pub kstack_top: VirtAddr,
     /// `RSP` after all callee-saved registers are pushed.
     pub rsp: u64,
+    /// Thread pointer, i.e., the `FS` base.
+    #[cfg(feature = "uspace")]
+    pub fs_base: usize,
     /// Extended states, i.e., FP/SIMD states.
     #[cfg(feature = "fp_simd")]
     pub ext_state: ExtendedState,
@@ -147,6 +181,8 @@ impl TaskContext {
         Self {
             kstack_top: VirtAddr::from(0),
             rsp: 0,
+            #[cfg(feature = "uspace")]
+            fs_base: 0,
             #[cfg(feature = "fp_simd")]
             ext_state: ExtendedState::default(),
         }
@@ -183,10 +219,13 @@ impl TaskContext {
             self.ext_state.save();
             next_ctx.ext_state.restore();
         }
+        #[cfg(feature = "uspace")]
         unsafe {
-            // TODO: swtich tls
-            context_switch(&mut self.rsp, &next_ctx.rsp)
+            self.fs_base = super::read_thread_pointer();
+            super::write_thread_pointer(next_ctx.fs_base);
+            super::uspace::write_kernel_stack_top(next_ctx.kstack_top);
         }
+        unsafe { context_switch(&mut self.rsp, &next_ctx.rsp) }
     }
 }
 
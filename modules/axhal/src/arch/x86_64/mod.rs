@@ -2,21 +2,28 @@ mod context;
 mod gdt;
 mod idt;
 
+#[cfg(feature = "uspace")]
+mod uspace;
+
 // #[cfg(target_os = "none")]
 mod trap;
 
 use core::arch::asm;
 
 use memory_addr::{PhysAddr, VirtAddr};
-use x86::{controlregs, tlb};
+use x86::{controlregs, controlregs::Cr4, tlb};
 use x86_64::instructions::interrupts;
 
 pub use self::context::{ExtendedState, FxsaveArea, TaskContext, TrapFrame};
 pub use self::gdt::GdtStruct;
-pub use self::idt::IdtStruct;
+pub(crate) use self::gdt::TSS;
+pub use self::idt::{IdtStruct, DOUBLE_FAULT_IST_INDEX};
 pub use self::trap::{IRQ_VECTOR_END, IRQ_VECTOR_START};
 pub use x86_64::structures::tss::TaskStateSegment;
 
+#[cfg(feature = "uspace")]
+pub use self::uspace::{init_syscall, read_thread_pointer, write_thread_pointer, UspaceContext};
+
 /// Allows the current CPU to respond to interrupts.
 #[inline]
 pub fn enable_irqs() {
@@ -86,6 +93,52 @@ pub fn flush_tlb(vaddr: Option<VirtAddr>) {
     if let Some(vaddr) = vaddr {
         unsafe { tlb::flush(vaddr.into()) }
     } else {
-        unsafe { tlb::flush_all() }
+        // toggling `CR4.PGE` also flushes the global entries and the entries
+        // of all PCIDs.
+        unsafe {
+            let cr4 = controlregs::cr4();
+            controlregs::cr4_write(cr4 ^ Cr4::CR4_ENABLE_GLOBAL_PAGES);
+            controlregs::cr4_write(cr4);
+        }
+    }
+}
+
+/// Enables the process-context identifiers (PCIDs) if supported, and returns
+/// the maximum usable PCID (0 if not supported).
+#[cfg(feature = "paging")]
+pub(crate) fn init_asid() -> usize {
+    let has_pcid = match raw_cpuid::CpuId::new().get_feature_info() {
+        Some(finfo) => finfo.has_pcid(),
+        None => false,
+    };
+    // `CR4.PCIDE` can only be set when `CR3[11:0]` is 0, which is always true
+    // as we never write a PCID before this.
+    let mut cr4 = unsafe { controlregs::cr4() } | Cr4::CR4_ENABLE_GLOBAL_PAGES;
+    if has_pcid {
+        cr4 |= Cr4::CR4_ENABLE_PCID;
+    }
+    unsafe { controlregs::cr4_write(cr4) };
+    if has_pcid {
+        0xfff
+    } else {
+        0
+    }
+}
+
+/// Writes the page table root and the PCID `asid` to `CR3`.
+///
+/// The TLB entries tagged with `asid` are flushed if `flush` is `true`, and
+/// are kept otherwise (which requires the PCID to be enabled).
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the virtual memory address space.
+#[cfg(feature = "paging")]
+pub(crate) unsafe fn switch_page_table_root(root_paddr: PhysAddr, asid: usize, flush: bool) {
+    const CR3_NOFLUSH: u64 = 1 << 63;
+    let mut cr3 = root_paddr.as_usize() as u64 | asid as u64;
+    if !flush {
+        cr3 |= CR3_NOFLUSH;
     }
+    controlregs::cr3_write(cr3)
 }
@@ -1,6 +1,8 @@
 use x86::{controlregs::cr2, irq::*};
+use x86_64::structures::idt::PageFaultErrorCode;
 
 use super::context::TrapFrame;
+use crate::trap::MappingFlags;
 
 core::arch::global_asm!(include_str!("trap.S"));
 
@@ -9,35 +11,71 @@ pub const IRQ_VECTOR_START: u8 = 0x20;
 /// end value of irq vector
 pub const IRQ_VECTOR_END: u8 = 0xff;
 
+fn err_code_to_flags(err_code: u64) -> MappingFlags {
+    let code = PageFaultErrorCode::from_bits_truncate(err_code);
+    if code.contains(PageFaultErrorCode::INSTRUCTION_FETCH) {
+        MappingFlags::EXECUTE
+    } else if code.contains(PageFaultErrorCode::CAUSED_BY_WRITE) {
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    }
+}
+
+fn handle_page_fault(tf: &TrapFrame) {
+    let access_flags = err_code_to_flags(tf.error_code);
+    let vaddr = unsafe { cr2() }.into();
+    if crate::trap::handle_page_fault(vaddr, access_flags, tf.is_user()) {
+        return;
+    }
+    if tf.is_user() {
+        warn!(
+            "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
+            tf.rip, vaddr, tf.error_code,
+        );
+    } else {
+        panic!(
+            "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
+            tf.rip, vaddr, tf.error_code, tf,
+        );
+    }
+}
+
+#[cfg(feature = "uspace")]
+fn handle_user_exception(tf: &TrapFrame) {
+    warn!(
+        "User exception {} (error_code = {:#x}) @ {:#x}",
+        tf.vector, tf.error_code, tf.rip
+    );
+    if !crate::trap::handle_user_exception(tf) {
+        panic!(
+            "Unhandled user exception {} (error_code = {:#x}) @ {:#x}:\n{:#x?}",
+            tf.vector, tf.error_code, tf.rip, tf
+        );
+    }
+}
+
 #[no_mangle]
 fn x86_trap_handler(tf: &mut TrapFrame) {
     match tf.vector as u8 {
-        PAGE_FAULT_VECTOR => {
-            if tf.is_user() {
-                warn!(
-                    "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                );
-            } else {
-                panic!(
-                    "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                    tf,
-                );
-            }
-        }
+        PAGE_FAULT_VECTOR => handle_page_fault(tf),
         BREAKPOINT_VECTOR => debug!("#BP @ {:#x} ", tf.rip),
+        DOUBLE_FAULT_VECTOR => {
+            // a page fault on the stack guard page cannot push the exception
+            // frame, report it first (e.g., as a stack overflow)
+            let vaddr = unsafe { cr2() }.into();
+            crate::trap::handle_page_fault(vaddr, MappingFlags::WRITE, tf.is_user());
+            panic!("#DF @ {:#x}, fault_vaddr={:#x}:\n{:#x?}", tf.rip, vaddr, tf);
+        }
+        IRQ_VECTOR_START..=IRQ_VECTOR_END => crate::trap::handle_irq_extern(tf.vector as _),
+        #[cfg(feature = "uspace")]
+        _ if tf.is_user() => handle_user_exception(tf),
         GENERAL_PROTECTION_FAULT_VECTOR => {
             panic!(
                 "#GP @ {:#x}, error_code={:#x}:\n{:#x?}",
                 tf.rip, tf.error_code, tf
             );
         }
-        IRQ_VECTOR_START..=IRQ_VECTOR_END => crate::trap::handle_irq_extern(tf.vector as _),
         _ => {
             panic!(
                 "Unhandled exception {} (error_code = {:#x}) @ {:#x}:\n{:#x?}",
@@ -0,0 +1,207 @@
+//! Structures and functions for user space.
+
+use core::arch::asm;
+use memory_addr::VirtAddr;
+use x86_64::registers::model_specific::{Efer, EferFlags, FsBase, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+
+use super::{GdtStruct, TrapFrame, TSS};
+
+/// The kernel stack top of the current task, used by the `syscall` entry.
+#[percpu::def_percpu]
+static KERNEL_RSP: usize = 0;
+
+/// Scratch space to save the user `RSP` on `syscall`.
+#[percpu::def_percpu]
+static USER_RSP: usize = 0;
+
+core::arch::global_asm!(
+    include_str!("syscall.S"),
+    kernel_rsp = sym __PERCPU_KERNEL_RSP,
+    user_rsp = sym __PERCPU_USER_RSP,
+    ucode64 = const GdtStruct::UCODE64_SELECTOR.0,
+    udata = const GdtStruct::UDATA_SELECTOR.0,
+);
+
+/// Context to enter user space.
+pub struct UspaceContext {
+    tf: TrapFrame,
+    /// The `FS` base for thread-local storage.
+    fs_base: u64,
+}
+
+impl UspaceContext {
+    /// Creates an empty context with all registers set to zero.
+    pub const fn empty() -> Self {
+        unsafe { core::mem::MaybeUninit::zeroed().assume_init() }
+    }
+
+    /// Creates a new context with the given entry point, user stack pointer,
+    /// and the argument.
+    pub fn new(entry: usize, ustack_top: VirtAddr, arg0: usize) -> Self {
+        Self {
+            tf: TrapFrame {
+                rdi: arg0 as _,
+                rip: entry as _,
+                cs: GdtStruct::UCODE64_SELECTOR.0 as _,
+                rflags: RFlags::INTERRUPT_FLAG.bits() | 0x2, // bit 1 is reserved as 1
+                rsp: ustack_top.as_usize() as _,
+                ss: GdtStruct::UDATA_SELECTOR.0 as _,
+                ..Default::default()
+            },
+            fs_base: 0,
+        }
+    }
+
+    /// Creates a new context from the given [`TrapFrame`], and the current
+    /// `FS` base.
+    ///
+    /// It is usually used to create the context of a new thread (e.g., by
+    /// `clone`) from the trap frame of the current one.
+    pub fn from(tf: &TrapFrame) -> Self {
+        Self {
+            tf: tf.clone(),
+            fs_base: FsBase::read().as_u64(),
+        }
+    }
+
+    /// Gets the instruction pointer.
+    pub const fn get_ip(&self) -> usize {
+        self.tf.rip as _
+    }
+
+    /// Gets the stack pointer.
+    pub const fn get_sp(&self) -> usize {
+        self.tf.rsp as _
+    }
+
+    /// Sets the instruction pointer.
+    pub fn set_ip(&mut self, rip: usize) {
+        self.tf.rip = rip as _;
+    }
+
+    /// Sets the stack pointer.
+    pub fn set_sp(&mut self, rsp: usize) {
+        self.tf.rsp = rsp as _;
+    }
+
+    /// Sets the return value register.
+    pub fn set_retval(&mut self, rax: usize) {
+        self.tf.rax = rax as _;
+    }
+
+    /// Sets the thread pointer for thread-local storage, i.e., the `FS`
+    /// base.
+    pub fn set_tls(&mut self, tls: usize) {
+        self.fs_base = tls as _;
+    }
+
+    /// Enters user space.
+    ///
+    /// It restores the user registers and jumps to the user entry point
+    /// (saved in `RIP`). When an exception or syscall occurs, the kernel
+    /// stack pointer will be switched to `kstack_top`.
+    ///
+    /// This function never returns.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes processor mode and the
+    /// stack. The contents of the kernel stack are discarded.
+    pub unsafe fn enter_uspace(&self, kstack_top: VirtAddr) -> ! {
+        super::disable_irqs();
+        write_kernel_stack_top(kstack_top);
+        write_thread_pointer(self.fs_base as usize);
+        // Put the trap frame on the top of the kernel stack, as if the task
+        // just trapped from user space. `self` may be in the same place, so
+        // `ptr::copy` is used. After `iretq`, `RSP0` in TSS is exactly
+        // `kstack_top`.
+        let tf_ptr = kstack_top.as_mut_ptr().cast::<TrapFrame>().sub(1);
+        core::ptr::copy(&self.tf, tf_ptr, 1);
+        asm!("
+            mov     rsp, {tf}
+            pop     rax
+            pop     rcx
+            pop     rdx
+            pop     rbx
+            pop     rbp
+            pop     rsi
+            pop     rdi
+            pop     r8
+            pop     r9
+            pop     r10
+            pop     r11
+            pop     r12
+            pop     r13
+            pop     r14
+            pop     r15
+            add     rsp, 16                     // pop vector, error_code
+            swapgs
+            iretq",
+            tf = in(reg) tf_ptr,
+            options(noreturn),
+        )
+    }
+}
+
+/// Sets the kernel stack top of the current CPU, which will be used on traps
+/// and syscalls from user space.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes the per-CPU states. It must be
+/// called with interrupts disabled.
+pub(super) unsafe fn write_kernel_stack_top(kstack_top: VirtAddr) {
+    KERNEL_RSP.write_current_raw(kstack_top.as_usize());
+    TSS.current_ref_mut_raw().privilege_stack_table[0] =
+        x86_64::VirtAddr::new(kstack_top.as_usize() as u64);
+}
+
+/// Reads the thread pointer of the current CPU, i.e., the `FS` base.
+pub fn read_thread_pointer() -> usize {
+    FsBase::read().as_u64() as usize
+}
+
+/// Writes the thread pointer of the current CPU, i.e., the `FS` base.
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the thread-local storage of user
+/// space.
+pub unsafe fn write_thread_pointer(fs_base: usize) {
+    FsBase::write(x86_64::VirtAddr::new_truncate(fs_base as u64))
+}
+
+#[no_mangle]
+fn x86_syscall_handler(tf: &mut TrapFrame) {
+    super::enable_irqs();
+    tf.rax = crate::trap::handle_syscall(tf, tf.rax as usize) as u64;
+    super::disable_irqs();
+}
+
+/// Initializes the `syscall` instruction on the current CPU.
+///
+/// It is called by the platform initialization after the GDT is loaded.
+pub fn init_syscall() {
+    extern "C" {
+        fn syscall_entry();
+    }
+    LStar::write(x86_64::VirtAddr::new(syscall_entry as usize as _));
+    Star::write(
+        GdtStruct::UCODE64_SELECTOR,
+        GdtStruct::UDATA_SELECTOR,
+        GdtStruct::KCODE64_SELECTOR,
+        GdtStruct::KDATA_SELECTOR,
+    )
+    .unwrap();
+    SFMask::write(
+        RFlags::TRAP_FLAG
+            | RFlags::INTERRUPT_FLAG
+            | RFlags::DIRECTION_FLAG
+            | RFlags::IOPL_LOW
+            | RFlags::IOPL_HIGH
+            | RFlags::NESTED_TASK
+            | RFlags::ALIGNMENT_CHECK,
+    );
+    unsafe { Efer::update(|efer| *efer |= EferFlags::SYSTEM_CALL_EXTENSIONS) };
+}
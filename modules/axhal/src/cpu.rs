@@ -81,6 +81,8 @@ pub(crate) fn init_primary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(true);
     }
+    #[cfg(feature = "paging")]
+    crate::tlb::init_percpu();
 }
 
 #[allow(dead_code)]
@@ -90,4 +92,22 @@ pub(crate) fn init_secondary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(false);
     }
+    #[cfg(feature = "paging")]
+    crate::tlb::init_percpu();
+}
+
+#[cfg(feature = "smp")]
+struct SpinLockIfImpl;
+
+#[cfg(feature = "smp")]
+#[crate_interface::impl_interface]
+impl spinlock::SpinLockIf for SpinLockIfImpl {
+    fn relax() {
+        // The lock holder may be waiting for the current CPU to handle its
+        // call (e.g., a TLB shootdown), whose IPI cannot be received now.
+        #[cfg(feature = "irq")]
+        if !crate::arch::irqs_enabled() {
+            crate::irq::handle_pending_call();
+        }
+    }
 }
@@ -6,11 +6,43 @@ use crate::platform::irq::MAX_IRQ_COUNT;
 
 pub use crate::platform::irq::{dispatch_irq, register_handler, set_enable};
 
+#[cfg(feature = "smp")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The IPI number reserved for [`smp_call_function`] and TLB shootdowns.
+///
+/// Sending it to a CPU without pending calls just wakes up the CPU.
+#[cfg(feature = "smp")]
+pub use crate::platform::irq::IPI_IRQ_NUM;
+
 /// The type if an IRQ handler.
 pub type IrqHandler = handler_table::Handler;
 
 static IRQ_HANDLER_TABLE: HandlerTable<MAX_IRQ_COUNT> = HandlerTable::new();
 
+/// The bitmask of all CPUs.
+#[cfg(feature = "smp")]
+#[allow(dead_code)]
+pub(crate) const ALL_CPUS: usize = usize::MAX >> (usize::BITS as usize - axconfig::SMP);
+
+#[cfg(feature = "smp")]
+static_assertions::const_assert!(axconfig::SMP <= usize::BITS as usize);
+
+/// The bitmask of CPUs that can handle IPIs.
+#[cfg(feature = "smp")]
+static IPI_ONLINE_CPUS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the IRQ numbers with registered handlers, and the number of times
+/// each of them has been handled.
+///
+/// IRQs not dispatched through the common handler table (e.g., the timer
+/// interrupt on RISC-V) are not included.
+pub fn irq_counts() -> impl Iterator<Item = (usize, usize)> {
+    (0..MAX_IRQ_COUNT)
+        .filter(|&irq_num| IRQ_HANDLER_TABLE.is_registered(irq_num))
+        .map(|irq_num| (irq_num, IRQ_HANDLER_TABLE.handled_count(irq_num)))
+}
+
 /// Platform-independent IRQ dispatching.
 #[allow(dead_code)]
 pub(crate) fn dispatch_irq_common(irq_num: usize) {
@@ -33,3 +65,147 @@ pub(crate) fn register_handler_common(irq_num: usize, handler: IrqHandler) -> bo
     warn!("register handler for IRQ {} failed", irq_num);
     false
 }
+
+/// Sends the IPI `irq_num` to the given CPU.
+///
+/// The handler registered for `irq_num` by [`register_handler`] will be called
+/// on the target CPU. `irq_num` must be a valid IPI number of the platform: a
+/// local APIC vector on x86_64, an SGI number (0-15) on AArch64, and only
+/// [`IPI_IRQ_NUM`] (the supervisor software interrupt) on RISC-V.
+#[cfg(feature = "smp")]
+pub fn send_ipi(cpu_id: usize, irq_num: usize) {
+    trace!("send IPI {} to CPU {}", irq_num, cpu_id);
+    crate::platform::irq::send_ipi(cpu_id, irq_num);
+}
+
+/// Enables handling IPIs on the current CPU.
+///
+/// It must be called after local IRQs are enabled on the current CPU. Before
+/// that, the current CPU is skipped by [`smp_call_function`], and the TLB
+/// shootdowns for it are deferred until this function is called.
+#[cfg(feature = "smp")]
+pub fn enable_ipi() {
+    if crate::cpu::this_cpu_is_bsp() {
+        register_handler(IPI_IRQ_NUM, handle_ipi);
+    }
+    IPI_ONLINE_CPUS.fetch_or(1 << crate::cpu::this_cpu_id(), Ordering::SeqCst);
+    #[cfg(feature = "paging")]
+    crate::tlb::flush_deferred();
+}
+
+/// Returns the bitmask of CPUs that can handle IPIs.
+#[cfg(feature = "smp")]
+pub(crate) fn ipi_online_cpus() -> usize {
+    IPI_ONLINE_CPUS.load(Ordering::SeqCst)
+}
+
+/// Calls the function `f` on the CPUs in `cpu_mask` (bit `i` for CPU `i`).
+///
+/// `f` is called with local IRQs disabled, directly on the current CPU, or in
+/// the [`IPI_IRQ_NUM`] handler on other CPUs. CPUs on which IPIs have not been
+/// enabled by [`enable_ipi`] are skipped. If `wait` is `true`, it returns after
+/// `f` has completed on all these CPUs.
+///
+/// Other CPUs may also call `f` while spinning for a lock with local IRQs
+/// disabled, so `f` must not acquire any spin lock.
+///
+/// Only one call can be in flight at a time, so it may also wait for the
+/// previous call to be handled by its target CPUs, even if `wait` is `false`.
+#[cfg(feature = "smp")]
+pub fn smp_call_function(cpu_mask: usize, f: &'static (dyn Fn() + Sync), wait: bool) {
+    call_function::call(cpu_mask, f, wait)
+}
+
+/// Like [`smp_call_function`] but always waits for completion, so `f` does not
+/// need to be `'static`.
+#[cfg(feature = "smp")]
+#[allow(dead_code)]
+pub(crate) fn smp_call_function_sync(cpu_mask: usize, f: &(dyn Fn() + Sync)) {
+    // Safety: `f` outlives the call as we wait for all target CPUs to complete.
+    let f = unsafe { core::mem::transmute::<&(dyn Fn() + Sync), &'static (dyn Fn() + Sync)>(f) };
+    call_function::call(cpu_mask, f, true)
+}
+
+#[cfg(feature = "smp")]
+fn handle_ipi() {
+    call_function::handle_pending();
+}
+
+/// Handles the pending call of [`smp_call_function`] on the current CPU
+/// without waiting for its IPI, if any.
+///
+/// Local IRQs must be disabled.
+#[cfg(feature = "smp")]
+pub(crate) fn handle_pending_call() {
+    call_function::handle_pending();
+}
+
+#[cfg(feature = "smp")]
+mod call_function {
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::{ipi_online_cpus, send_ipi, IPI_IRQ_NUM};
+    use crate::cpu::this_cpu_id;
+
+    /// Held by the CPU that is starting a call.
+    static CALL_LOCK: AtomicBool = AtomicBool::new(false);
+
+    /// The bitmask of CPUs that have not completed the current call.
+    static PENDING_CPUS: AtomicUsize = AtomicUsize::new(0);
+
+    /// The function of the current call, only written by the holder of
+    /// `CALL_LOCK` when `PENDING_CPUS` is 0.
+    static mut CALL_FUNC: Option<&'static (dyn Fn() + Sync)> = None;
+
+    /// Calls the current function if it is pending on the current CPU.
+    ///
+    /// Local IRQs must be disabled.
+    pub fn handle_pending() {
+        let mask = 1 << this_cpu_id();
+        if PENDING_CPUS.load(Ordering::Acquire) & mask != 0 {
+            if let Some(f) = unsafe { CALL_FUNC } {
+                f();
+            }
+            PENDING_CPUS.fetch_and(!mask, Ordering::Release);
+        }
+    }
+
+    /// Spins until `cond` becomes `true`.
+    ///
+    /// The calls from other CPUs (e.g., TLB shootdowns) are handled while
+    /// spinning, as their IPIs cannot be received with local IRQs disabled.
+    fn spin_until(cond: impl Fn() -> bool) {
+        while !cond() {
+            handle_pending();
+            core::hint::spin_loop();
+        }
+    }
+
+    pub fn call(cpu_mask: usize, f: &'static (dyn Fn() + Sync), wait: bool) {
+        let _guard = kernel_guard::IrqSave::new();
+        let this_cpu = 1 << this_cpu_id();
+        spin_until(|| {
+            CALL_LOCK
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        });
+        // the previous call may not have been completed if it did not wait
+        spin_until(|| PENDING_CPUS.load(Ordering::Acquire) == 0);
+
+        let targets = cpu_mask & ipi_online_cpus() & !this_cpu;
+        if targets != 0 {
+            unsafe { CALL_FUNC = Some(f) };
+            PENDING_CPUS.store(targets, Ordering::Release);
+            (0..axconfig::SMP)
+                .filter(|cpu_id| targets & (1 << cpu_id) != 0)
+                .for_each(|cpu_id| send_ipi(cpu_id, IPI_IRQ_NUM));
+        }
+        if cpu_mask & this_cpu != 0 {
+            f();
+        }
+        if wait {
+            spin_until(|| PENDING_CPUS.load(Ordering::Acquire) == 0);
+        }
+        CALL_LOCK.store(false, Ordering::Release);
+    }
+}
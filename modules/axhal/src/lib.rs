@@ -18,6 +18,8 @@
 //! - `smp`: Enable SMP (symmetric multiprocessing) support.
 //! - `fp_simd`: Enable floating-point and SIMD support.
 //! - `paging`: Enable page table manipulation.
+//! - `uspace`: Enable user space support, i.e., entering user mode and
+//!   handling system calls from it.
 //! - `irq`: Enable interrupt handling support.
 //! - `platform-pc-x86`: Specify for use on the corresponding platform.
 //! - `platform-qemu-virt-riscv`: Specify for use on the corresponding platform.
@@ -50,6 +52,9 @@ pub mod irq;
 #[cfg(feature = "paging")]
 pub mod paging;
 
+#[cfg(feature = "paging")]
+pub mod tlb;
+
 /// Console input and output.
 pub mod console {
     pub use super::platform::console::*;
@@ -47,6 +47,11 @@ impl PagingIf for PagingIfImpl {
     fn phys_to_virt(paddr: PhysAddr) -> VirtAddr {
         phys_to_virt(paddr)
     }
+
+    fn flush_tlb(vaddr: VirtAddr, size: usize) {
+        // the page table is unknown, flush the range for all of them
+        crate::tlb::flush_tlb_range(None, vaddr, size)
+    }
 }
 
 cfg_if::cfg_if! {
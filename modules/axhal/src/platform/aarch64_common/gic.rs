@@ -1,5 +1,6 @@
 use crate::{irq::IrqHandler, mem::phys_to_virt};
 use arm_gic::gic_v2::{GicCpuInterface, GicDistributor};
+use arm_gic::SGI_RANGE;
 use memory_addr::PhysAddr;
 use spinlock::SpinNoIrq;
 
@@ -9,6 +10,9 @@ pub const MAX_IRQ_COUNT: usize = 1024;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = 30; // physical timer, type=PPI, id=14
 
+/// The IPI IRQ number.
+pub const IPI_IRQ_NUM: usize = 1; // type=SGI, id=1
+
 const GICD_BASE: PhysAddr = PhysAddr::from(axconfig::GICD_PADDR);
 const GICC_BASE: PhysAddr = PhysAddr::from(axconfig::GICC_PADDR);
 
@@ -40,15 +44,31 @@ pub fn dispatch_irq(_unused: usize) {
     GICC.handle_irq(|irq_num| crate::irq::dispatch_irq_common(irq_num as _));
 }
 
+/// Sends the IPI `irq_num` (an SGI number) to the given CPU.
+pub fn send_ipi(cpu_id: usize, irq_num: usize) {
+    GICD.lock().send_sgi(cpu_id, irq_num);
+}
+
+/// Enables all SGIs on the current CPU, which are used as IPIs.
+fn enable_sgis(gicd: &mut GicDistributor) {
+    for irq_num in SGI_RANGE {
+        gicd.set_enable(irq_num, true);
+    }
+}
+
 /// Initializes GICD, GICC on the primary CPU.
 pub(crate) fn init_primary() {
     info!("Initialize GICv2...");
-    GICD.lock().init();
+    let mut gicd = GICD.lock();
+    gicd.init();
+    // SGIs are banked for each CPU
+    enable_sgis(&mut gicd);
     GICC.init();
 }
 
 /// Initializes GICC on secondary CPUs.
 #[cfg(feature = "smp")]
 pub(crate) fn init_secondary() {
+    enable_sgis(&mut GICD.lock());
     GICC.init();
 }
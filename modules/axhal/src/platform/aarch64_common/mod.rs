@@ -2,6 +2,8 @@ mod boot;
 
 pub mod generic_timer;
 pub mod pl011;
+#[cfg(feature = "platform-qemu-virt-aarch64")]
+pub mod pl031;
 pub mod psci;
 
 #[cfg(feature = "irq")]
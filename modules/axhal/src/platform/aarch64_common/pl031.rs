@@ -0,0 +1,26 @@
+//! ARM PrimeCell Real Time Clock (PL031), used to initialize the wall clock.
+//!
+//! The RTC is owned by `axhal` only, there is no RTC driver in `axdriver`.
+
+use driver_rtc::{pl031::Pl031Rtc, RtcDriverOps};
+use memory_addr::PhysAddr;
+
+use crate::mem::phys_to_virt;
+
+const RTC_BASE: PhysAddr = PhysAddr::from(axconfig::RTC_PADDR);
+
+/// Initializes the wall clock from the RTC.
+pub fn init() {
+    let mut rtc = Pl031Rtc::new(phys_to_virt(RTC_BASE).as_mut_ptr());
+    rtc.init();
+    match rtc.get_time() {
+        Ok(now) => {
+            crate::time::set_wall_time(now);
+            info!(
+                "Read wall time from PL031 RTC: {}s since epoch",
+                now.as_secs()
+            );
+        }
+        Err(e) => warn!("Failed to read PL031 RTC: {:?}", e),
+    }
+}
@@ -69,6 +69,9 @@ pub mod irq {
     /// The timer IRQ number.
     pub const TIMER_IRQ_NUM: usize = 0;
 
+    /// The IPI IRQ number.
+    pub const IPI_IRQ_NUM: usize = 1;
+
     /// Enables or disables the given IRQ.
     pub fn set_enable(irq_num: usize, enabled: bool) {}
 
@@ -83,6 +86,9 @@ pub mod irq {
     /// up in the IRQ handler table and calls the corresponding handler. If
     /// necessary, it also acknowledges the interrupt controller after handling.
     pub fn dispatch_irq(irq_num: usize) {}
+
+    /// Sends the IPI `irq_num` to the given CPU.
+    pub fn send_ipi(cpu_id: usize, irq_num: usize) {}
 }
 
 /// Initializes the platform devices for the primary CPU.
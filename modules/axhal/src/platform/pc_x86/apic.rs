@@ -32,6 +32,7 @@ pub(super) mod vectors {
     pub const APIC_TIMER_VECTOR: u8 = 0xf0;
     pub const APIC_SPURIOUS_VECTOR: u8 = 0xf1;
     pub const APIC_ERROR_VECTOR: u8 = 0xf2;
+    pub const APIC_IPI_VECTOR: u8 = 0xf3;
 }
 
 /// The maximum number of IRQs.
@@ -40,6 +41,9 @@ pub const MAX_IRQ_COUNT: usize = 256;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = APIC_TIMER_VECTOR as usize;
 
+/// The IPI IRQ number.
+pub const IPI_IRQ_NUM: usize = APIC_IPI_VECTOR as usize;
+
 const IO_APIC_BASE: PhysAddr = PhysAddr::from(0xFEC0_0000);
 
 static mut LOCAL_APIC: Option<LocalApic> = None;
@@ -99,6 +103,14 @@ pub fn dispatch_irq(vector: usize) {
     unsafe { local_apic().end_of_interrupt() };
 }
 
+/// Sends the IPI `vector` to the given CPU.
+#[cfg(feature = "irq")]
+pub fn send_ipi(cpu_id: usize, vector: usize) {
+    // the ICR must not be written by IRQ handlers in between
+    let _guard = kernel_guard::IrqSave::new();
+    unsafe { local_apic().send_ipi(vector as u8, raw_apic_id(cpu_id as u8)) };
+}
+
 pub(super) fn local_apic<'a>() -> &'a mut LocalApic {
     // It's safe as LAPIC is per-cpu.
     unsafe { LOCAL_APIC.as_mut().unwrap() }
@@ -1,25 +1,34 @@
 //! Description tables (per-CPU GDT, per-CPU ISS, IDT)
 
-use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment};
+use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment, DOUBLE_FAULT_IST_INDEX, TSS};
 use lazy_init::LazyInit;
+use x86_64::VirtAddr;
+
+const DOUBLE_FAULT_STACK_SIZE: usize = 0x4000; // 16K
 
 static IDT: LazyInit<IdtStruct> = LazyInit::new();
 
 #[percpu::def_percpu]
-static TSS: LazyInit<TaskStateSegment> = LazyInit::new();
+static GDT: LazyInit<GdtStruct> = LazyInit::new();
 
 #[percpu::def_percpu]
-static GDT: LazyInit<GdtStruct> = LazyInit::new();
+static DOUBLE_FAULT_STACK: [u8; DOUBLE_FAULT_STACK_SIZE] = [0; DOUBLE_FAULT_STACK_SIZE];
 
 fn init_percpu() {
     unsafe {
         IDT.load();
         let tss = TSS.current_ref_mut_raw();
         let gdt = GDT.current_ref_mut_raw();
-        tss.init_by(TaskStateSegment::new());
+        let mut tss_inner = TaskStateSegment::new();
+        let df_stack = DOUBLE_FAULT_STACK.current_ref_raw();
+        tss_inner.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] =
+            VirtAddr::new(df_stack.as_ptr_range().end as u64);
+        tss.init_by(tss_inner);
         gdt.init_by(GdtStruct::new(tss));
         gdt.load();
         gdt.load_tss();
+        #[cfg(feature = "uspace")]
+        crate::arch::init_syscall();
     }
 }
 
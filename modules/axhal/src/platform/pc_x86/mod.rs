@@ -1,6 +1,7 @@
 mod apic;
 mod boot;
 mod dtables;
+mod rtc;
 mod uart16550;
 
 pub mod mem;
@@ -61,6 +62,7 @@ unsafe extern "C" fn rust_entry_secondary(magic: usize) {
 pub fn platform_init() {
     self::apic::init_primary();
     self::time::init_primary();
+    self::rtc::init();
     #[cfg(feature = "axalloc")]
     self::acpi::init();
 }
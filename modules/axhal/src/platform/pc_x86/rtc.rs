@@ -0,0 +1,20 @@
+//! CMOS real-time clock, used to initialize the wall clock.
+//!
+//! The CMOS index/data ports are owned by `axhal` only, there is no RTC
+//! driver in `axdriver`.
+
+use driver_rtc::{cmos::CmosRtc, RtcDriverOps};
+
+/// Initializes the wall clock from the RTC.
+pub(super) fn init() {
+    match CmosRtc::new().get_time() {
+        Ok(now) => {
+            crate::time::set_wall_time(now);
+            info!(
+                "Read wall time from CMOS RTC: {}s since epoch",
+                now.as_secs()
+            );
+        }
+        Err(e) => warn!("Failed to read CMOS RTC: {:?}", e),
+    }
+}
@@ -8,7 +8,6 @@ use riscv::register::sie;
 pub(super) const INTC_IRQ_BASE: usize = 1 << (usize::BITS - 1);
 
 /// Supervisor software interrupt in `scause`
-#[allow(unused)]
 pub(super) const S_SOFT: usize = INTC_IRQ_BASE + 1;
 
 /// Supervisor timer interrupt in `scause`
@@ -25,10 +24,14 @@ pub const MAX_IRQ_COUNT: usize = 1024;
 /// The timer IRQ number (supervisor timer interrupt in `scause`).
 pub const TIMER_IRQ_NUM: usize = S_TIMER;
 
+/// The IPI IRQ number (supervisor software interrupt in `scause`).
+pub const IPI_IRQ_NUM: usize = S_SOFT;
+
 macro_rules! with_cause {
-    ($cause: expr, @TIMER => $timer_op: expr, @EXT => $ext_op: expr $(,)?) => {
+    ($cause: expr, @TIMER => $timer_op: expr, @SOFT => $soft_op: expr, @EXT => $ext_op: expr $(,)?) => {
         match $cause {
             S_TIMER => $timer_op,
+            S_SOFT => $soft_op,
             S_EXT => $ext_op,
             _ => panic!("invalid trap cause: {:#x}", $cause),
         }
@@ -55,6 +58,7 @@ pub fn register_handler(scause: usize, handler: IrqHandler) -> bool {
         } else {
             false
         },
+        @SOFT => crate::irq::register_handler_common(scause & !INTC_IRQ_BASE, handler),
         @EXT => crate::irq::register_handler_common(scause & !INTC_IRQ_BASE, handler),
     )
 }
@@ -71,10 +75,26 @@ pub fn dispatch_irq(scause: usize) {
             trace!("IRQ: timer");
             TIMER_HANDLER();
         },
+        @SOFT => {
+            // clear the pending bit `sip.SSIP`
+            unsafe { core::arch::asm!("csrc sip, {}", in(reg) 1usize << 1) };
+            crate::irq::dispatch_irq_common(scause & !INTC_IRQ_BASE);
+        },
         @EXT => crate::irq::dispatch_irq_common(0), // TODO: get IRQ number from PLIC
     );
 }
 
+/// Sends the IPI `scause` to the given CPU.
+///
+/// Only the supervisor software interrupt ([`IPI_IRQ_NUM`]) can be sent.
+pub fn send_ipi(cpu_id: usize, scause: usize) {
+    if scause != S_SOFT {
+        warn!("invalid IPI: {:#x}", scause);
+        return;
+    }
+    sbi_rt::send_ipi(1 << cpu_id, 0);
+}
+
 pub(super) fn init_percpu() {
     // enable soft interrupts, timer interrupts, and external interrupts
     unsafe {
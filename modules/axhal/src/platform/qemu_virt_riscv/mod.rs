@@ -1,4 +1,6 @@
 mod boot;
+#[cfg(feature = "paging")]
+mod rtc;
 
 pub mod console;
 pub mod mem;
@@ -39,6 +41,9 @@ pub fn platform_init() {
     #[cfg(feature = "irq")]
     self::irq::init_percpu();
     self::time::init_percpu();
+    // the RTC is not mapped in the boot page table
+    #[cfg(feature = "paging")]
+    self::rtc::init();
 }
 
 /// Initializes the platform devices for secondary CPUs.
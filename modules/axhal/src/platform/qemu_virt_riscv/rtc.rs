@@ -0,0 +1,25 @@
+//! Goldfish RTC, used to initialize the wall clock.
+//!
+//! The RTC is owned by `axhal` only, there is no RTC driver in `axdriver`.
+
+use driver_rtc::{goldfish::GoldfishRtc, RtcDriverOps};
+use memory_addr::PhysAddr;
+
+use crate::mem::phys_to_virt;
+
+const RTC_BASE: PhysAddr = PhysAddr::from(axconfig::RTC_PADDR);
+
+/// Initializes the wall clock from the RTC.
+pub(super) fn init() {
+    let rtc = GoldfishRtc::new(phys_to_virt(RTC_BASE).as_mut_ptr());
+    match rtc.get_time() {
+        Ok(now) => {
+            crate::time::set_wall_time(now);
+            info!(
+                "Read wall time from Goldfish RTC: {}s since epoch",
+                now.as_secs()
+            );
+        }
+        Err(e) => warn!("Failed to read Goldfish RTC: {:?}", e),
+    }
+}
@@ -1,5 +1,7 @@
 //! Time-related operations.
 
+use core::sync::atomic::{AtomicU64, Ordering};
+
 pub use core::time::Duration;
 
 /// A measurement of the system clock.
@@ -35,6 +37,41 @@ pub fn current_time() -> TimeValue {
     TimeValue::from_nanos(current_time_nanos())
 }
 
+/// Offset in nanoseconds from the monotonic clock to the wall clock, i.e.,
+/// the wall time (since the UNIX epoch) when the monotonic clock was zero.
+static EPOCH_OFFSET_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the offset in nanoseconds between the monotonic clock returned by
+/// [`current_time`] and the wall clock.
+///
+/// It is initialized from the real-time clock (RTC) of the platform, or zero
+/// if there is no RTC. The RTC is only accessed by `axhal`, other modules
+/// should read the wall clock through this module instead.
+pub fn epoch_offset_nanos() -> u64 {
+    EPOCH_OFFSET_NANOS.load(Ordering::Relaxed)
+}
+
+/// Returns the current wall time in nanoseconds since the UNIX epoch
+/// (1970-01-01 00:00:00 UTC).
+pub fn wall_time_nanos() -> u64 {
+    current_time_nanos() + epoch_offset_nanos()
+}
+
+/// Returns the current wall time since the UNIX epoch in [`TimeValue`].
+pub fn wall_time() -> TimeValue {
+    TimeValue::from_nanos(wall_time_nanos())
+}
+
+/// Sets the current wall time (since the UNIX epoch).
+///
+/// It only adjusts the offset to the monotonic clock, so [`current_time`] is
+/// not affected. It is used to initialize the wall clock from the RTC, and to
+/// correct it later (e.g. by an SNTP client).
+pub fn set_wall_time(now: TimeValue) {
+    let offset = (now.as_nanos() as u64).saturating_sub(current_time_nanos());
+    EPOCH_OFFSET_NANOS.store(offset, Ordering::Relaxed);
+}
+
 /// Busy waiting for the given duration.
 pub fn busy_wait(dur: Duration) {
     busy_wait_until(current_time() + dur);
@@ -0,0 +1,218 @@
+//! TLB management with address space identifiers (ASIDs) and TLB shootdowns.
+//!
+//! Each CPU tags a few recently used page tables with ASIDs (PCIDs on x86_64),
+//! so that switching between them by [`switch_page_table`] does not flush
+//! the TLB. As a result, stale TLB entries of a page table may remain on any
+//! CPU that has used it. After changing the mappings of a page table,
+//! [`flush_tlb_range`] must be called to flush them on all CPUs, which sends
+//! IPIs to other CPUs (the so-called TLB shootdown) if the `smp` and `irq`
+//! features are enabled.
+
+use memory_addr::{PhysAddr, VirtAddr};
+
+use crate::arch;
+
+/// The maximum number of page tables tagged with ASIDs on each CPU.
+const MAX_ASID_SLOTS: usize = 16;
+
+/// Page tables tagged with ASIDs on the current CPU.
+struct AsidCache {
+    /// The page table root tagged with ASID `i + 1` in slot `i`, or 0 if the
+    /// slot is free.
+    roots: [usize; MAX_ASID_SLOTS],
+    /// The number of usable slots, 0 if ASIDs are not supported.
+    num_slots: usize,
+    /// The next slot to be evicted.
+    next_victim: usize,
+    /// The current page table root, or 0 if it has never been switched by
+    /// [`switch_page_table`].
+    current: usize,
+}
+
+#[percpu::def_percpu]
+static ASID_CACHE: AsidCache = AsidCache::new();
+
+impl AsidCache {
+    const fn new() -> Self {
+        Self {
+            roots: [0; MAX_ASID_SLOTS],
+            num_slots: 0,
+            next_victim: 0,
+            current: 0,
+        }
+    }
+
+    /// Returns the ASID assigned to the page table `root`, and whether the
+    /// TLB entries tagged with it must be flushed, i.e., it is newly assigned.
+    fn assign(&mut self, root: usize) -> (usize, bool) {
+        let slots = &mut self.roots[..self.num_slots];
+        if slots.is_empty() {
+            return (0, true);
+        }
+        if let Some(i) = slots.iter().position(|&r| r == root) {
+            return (i + 1, false);
+        }
+        let i = slots.iter().position(|&r| r == 0).unwrap_or_else(|| {
+            let victim = self.next_victim;
+            self.next_victim = (victim + 1) % slots.len();
+            victim
+        });
+        slots[i] = root;
+        (i + 1, true)
+    }
+
+    /// Drops the ASID assigned to the page table `root`, so the stale TLB
+    /// entries tagged with it will be flushed on the next assignment.
+    fn invalidate(&mut self, root: usize) {
+        for r in self.roots.iter_mut().filter(|r| **r == root) {
+            *r = 0;
+        }
+    }
+
+    /// Drops all assigned ASIDs.
+    #[cfg(all(feature = "smp", feature = "irq"))]
+    fn invalidate_all(&mut self) {
+        self.roots = [0; MAX_ASID_SLOTS];
+    }
+}
+
+/// A request to flush TLB entries on a CPU.
+#[derive(Clone, Copy)]
+struct FlushRequest {
+    /// The page table root, or [`None`] for the kernel mappings shared by all
+    /// page tables.
+    root: Option<PhysAddr>,
+    /// The virtual memory range, or [`None`] for all addresses.
+    range: Option<(VirtAddr, usize)>,
+}
+
+impl FlushRequest {
+    /// Performs the request on the current CPU.
+    ///
+    /// Local IRQs must be disabled.
+    fn flush_local(&self) {
+        let cache = unsafe { ASID_CACHE.current_ref_mut_raw() };
+        match self.root {
+            Some(root) if root.as_usize() != cache.current => cache.invalidate(root.as_usize()),
+            _ => match self.range {
+                Some((start, size)) => arch::flush_tlb_range(start, size),
+                None => arch::flush_tlb(None),
+            },
+        }
+    }
+
+    /// Performs the request on all CPUs.
+    fn flush_all_cpus(&self) {
+        let _guard = kernel_guard::IrqSave::new();
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        shootdown::shootdown(self);
+        #[cfg(not(all(feature = "smp", feature = "irq")))]
+        self.flush_local();
+    }
+}
+
+/// Switches to the given page table on the current CPU (`TTBR0_EL1` is used
+/// on AArch64).
+///
+/// The page table is tagged with an ASID if supported, and the TLB is flushed
+/// only if the ASID is newly assigned.
+///
+/// # Safety
+///
+/// This function is unsafe as it changes the virtual memory address space.
+pub unsafe fn switch_page_table(root_paddr: PhysAddr) {
+    let _guard = kernel_guard::IrqSave::new();
+    let cache = ASID_CACHE.current_ref_mut_raw();
+    let root = root_paddr.as_usize();
+    if cache.current == root {
+        return;
+    }
+    trace!(
+        "switch page table: {:#x} => {:#x}",
+        cache.current,
+        root_paddr
+    );
+    let first_switch = cache.current == 0;
+    let (asid, flush) = cache.assign(root);
+    arch::switch_page_table_root(root_paddr, asid, flush);
+    cache.current = root;
+    if first_switch {
+        // drop the global entries left by the boot page table
+        arch::flush_tlb(None);
+    }
+}
+
+/// Flushes the TLB entries that map the virtual memory range
+/// `[start, start + size)` in the given page table on all CPUs.
+///
+/// If `root_paddr` is [`None`], the range contains kernel mappings which are
+/// shared by all page tables.
+pub fn flush_tlb_range(root_paddr: Option<PhysAddr>, start: VirtAddr, size: usize) {
+    FlushRequest {
+        root: root_paddr,
+        range: Some((start, size)),
+    }
+    .flush_all_cpus();
+}
+
+/// Flushes all TLB entries of the given page table on all CPUs.
+///
+/// If `root_paddr` is [`None`], flushes the entire TLB on all CPUs. It must be
+/// called before the page table is deallocated, so its root address can be
+/// safely reused by a new page table.
+pub fn flush_tlb_all(root_paddr: Option<PhysAddr>) {
+    FlushRequest {
+        root: root_paddr,
+        range: None,
+    }
+    .flush_all_cpus();
+}
+
+/// Initializes the ASIDs on the current CPU.
+pub(crate) fn init_percpu() {
+    let max_asid = arch::init_asid();
+    unsafe { ASID_CACHE.current_ref_mut_raw() }.num_slots = max_asid.min(MAX_ASID_SLOTS);
+}
+
+#[cfg(all(feature = "smp", feature = "irq"))]
+pub(crate) use self::shootdown::flush_deferred;
+
+#[cfg(all(feature = "smp", feature = "irq"))]
+mod shootdown {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{FlushRequest, ASID_CACHE};
+    use crate::cpu::this_cpu_id;
+    use crate::irq::{ipi_online_cpus, smp_call_function_sync, ALL_CPUS};
+
+    /// The bitmask of CPUs that have missed some requests before they can
+    /// handle IPIs, and must flush the entire TLB.
+    static DEFERRED_CPUS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Performs the request on all CPUs, and waits for them to complete.
+    ///
+    /// Requests from other CPUs are performed on the current CPU while
+    /// waiting, so concurrent shootdowns do not wait for each other forever.
+    /// Likewise, CPUs spinning for a lock held by the caller (e.g., the kernel
+    /// address space) perform the request while spinning.
+    ///
+    /// Local IRQs must be disabled.
+    pub fn shootdown(req: &FlushRequest) {
+        // CPUs that cannot handle IPIs yet will flush the entire TLB later, in
+        // `flush_deferred`. Some of them may become online in between, which
+        // are also covered as `smp_call_function_sync` reloads the online CPUs.
+        let this_cpu = 1 << this_cpu_id();
+        DEFERRED_CPUS.fetch_or(ALL_CPUS & !ipi_online_cpus() & !this_cpu, Ordering::SeqCst);
+        smp_call_function_sync(ALL_CPUS, &|| req.flush_local());
+    }
+
+    /// Flushes the entire TLB if the current CPU has missed some requests.
+    pub fn flush_deferred() {
+        let _guard = kernel_guard::IrqSave::new();
+        let mask = 1 << this_cpu_id();
+        if DEFERRED_CPUS.fetch_and(!mask, Ordering::SeqCst) & mask != 0 {
+            unsafe { ASID_CACHE.current_ref_mut_raw() }.invalidate_all();
+            crate::arch::flush_tlb(None);
+        }
+    }
+}
@@ -1,6 +1,12 @@
 //! Trap handling.
 
 use crate_interface::{call_interface, def_interface};
+use memory_addr::VirtAddr;
+
+use crate::arch::TrapFrame;
+
+#[doc(no_inline)]
+pub use page_table_entry::MappingFlags;
 
 /// Trap handler interface.
 ///
@@ -13,7 +19,37 @@ use crate_interface::{call_interface, def_interface};
 pub trait TrapHandler {
     /// Handles interrupt requests for the given IRQ number.
     fn handle_irq(irq_num: usize);
-    // more e.g.: handle_page_fault();
+
+    /// Handles page faults.
+    ///
+    /// `vaddr` is the faulting virtual address, and `access_flags` is the
+    /// type of the access that caused the fault (one of [`READ`], [`WRITE`]
+    /// and [`EXECUTE`]). `is_user` indicates whether the fault occurred in
+    /// user mode.
+    ///
+    /// Returns `true` if the fault has been handled (e.g., the page has been
+    /// mapped), so that the faulting instruction can be retried.
+    ///
+    /// [`READ`]: MappingFlags::READ
+    /// [`WRITE`]: MappingFlags::WRITE
+    /// [`EXECUTE`]: MappingFlags::EXECUTE
+    fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool;
+
+    /// Handles system calls from user space (only used with the `uspace`
+    /// feature).
+    ///
+    /// `tf` holds the user registers, from which the arguments can be read
+    /// by [`TrapFrame::arg0`] to [`TrapFrame::arg5`]. The returned value is
+    /// written to the return value register before going back to user space.
+    fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize;
+
+    /// Handles exceptions from user space other than page faults and system
+    /// calls, e.g., illegal instructions (only used with the `uspace`
+    /// feature).
+    ///
+    /// It usually does not return, e.g., the current process is killed.
+    /// Returns `false` if it cannot be handled, and the kernel panics then.
+    fn handle_user_exception(tf: &TrapFrame) -> bool;
 }
 
 /// Call the external IRQ handler.
@@ -21,3 +57,25 @@ pub trait TrapHandler {
 pub(crate) fn handle_irq_extern(irq_num: usize) {
     call_interface!(TrapHandler::handle_irq, irq_num);
 }
+
+/// Call the external page fault handler.
+#[allow(dead_code)]
+pub(crate) fn handle_page_fault(
+    vaddr: VirtAddr,
+    access_flags: MappingFlags,
+    is_user: bool,
+) -> bool {
+    call_interface!(TrapHandler::handle_page_fault, vaddr, access_flags, is_user)
+}
+
+/// Call the external syscall handler.
+#[cfg(feature = "uspace")]
+pub(crate) fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
+    call_interface!(TrapHandler::handle_syscall, tf, syscall_num)
+}
+
+/// Call the external user exception handler.
+#[cfg(feature = "uspace")]
+pub(crate) fn handle_user_exception(tf: &TrapFrame) -> bool {
+    call_interface!(TrapHandler::handle_user_exception, tf)
+}
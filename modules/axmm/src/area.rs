@@ -0,0 +1,535 @@
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use axerrno::{ax_err, AxError, AxResult};
+use axhal::mem::{phys_to_virt, virt_to_phys, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::{MappingFlags, PageSize, PageTable};
+use core::fmt;
+
+use crate::paging_err_to_ax_err;
+
+/// A file that can be mapped into an address space.
+pub trait MmapFile: Send + Sync {
+    /// Reads the file from `offset` until `buf` is filled or the end of file
+    /// is reached. Returns the number of bytes read.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize>;
+    /// Writes all of `buf` to the file at `offset`.
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult;
+    /// Returns the size of the file in bytes.
+    fn size(&self) -> AxResult<u64>;
+}
+
+/// The source of the pages of a [`MemoryArea`].
+#[derive(Clone)]
+pub enum Backend {
+    /// Linear mapping to contiguous physical memory, i.e.,
+    /// `paddr = vaddr - pa_va_offset`. The physical memory is not owned by
+    /// the area, and huge pages may be used.
+    Linear {
+        /// `vaddr - paddr` of the mapping.
+        pa_va_offset: usize,
+    },
+    /// Pages are allocated from the global allocator and zeroed, when the
+    /// area is mapped if `populate` is true, or on the first access (i.e.,
+    /// the page fault) otherwise.
+    Alloc {
+        /// Whether to allocate all pages when mapping.
+        populate: bool,
+    },
+    /// Pages are allocated and filled with the file contents from `offset`
+    /// by [`FilePages::read`], before the area is mapped. If `shared` is
+    /// true, the contents are written back to the file by [`Writeback`].
+    File {
+        /// The mapped file.
+        file: Arc<dyn MmapFile>,
+        /// The file offset of the area start.
+        offset: u64,
+        /// Whether modifications are written back to the file.
+        shared: bool,
+    },
+}
+
+impl Backend {
+    /// Returns the backend of the part starting from `offset` in the area.
+    fn offset_by(&self, offset: usize) -> Self {
+        match self {
+            Self::File {
+                file,
+                offset: off,
+                shared,
+            } => Self::File {
+                file: file.clone(),
+                offset: off + offset as u64,
+                shared: *shared,
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Linear { pa_va_offset } => f
+                .debug_struct("Linear")
+                .field("pa_va_offset", &format_args!("{:#x}", pa_va_offset))
+                .finish(),
+            Self::Alloc { populate } => {
+                f.debug_struct("Alloc").field("populate", populate).finish()
+            }
+            Self::File { offset, shared, .. } => f
+                .debug_struct("File")
+                .field("offset", &format_args!("{:#x}", offset))
+                .field("shared", shared)
+                .finish(),
+        }
+    }
+}
+
+/// Pages filled with the file contents for a [`Backend::File`] mapping.
+///
+/// They are read before the address space is locked, and then mapped by
+/// [`AddrSpace::map_file_pages`](crate::AddrSpace::map_file_pages), so that
+/// the file is not accessed with the lock held.
+pub struct FilePages {
+    backend: Backend,
+    frames: Vec<PhysAddr>,
+}
+
+impl FilePages {
+    /// Reads `size` bytes from the file of `backend` into newly allocated
+    /// pages. The part beyond the end of file is zeroed.
+    pub fn read(backend: Backend, size: usize) -> AxResult<Self> {
+        let Backend::File { file, offset, .. } = &backend else {
+            return ax_err!(InvalidInput, "not a file mapping");
+        };
+        let (file, offset) = (file.clone(), *offset);
+        let mut pages = Self {
+            backend,
+            frames: Vec::new(),
+        };
+        for page_offset in (0..size).step_by(PAGE_SIZE_4K) {
+            let paddr = alloc_frame()?;
+            pages.frames.push(paddr);
+            file.read_at(offset + page_offset as u64, frame_data(paddr))?;
+        }
+        Ok(pages)
+    }
+
+    /// Returns the backend of the mapping.
+    pub const fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Returns the size of the pages in bytes.
+    pub fn size(&self) -> usize {
+        self.frames.len() * PAGE_SIZE_4K
+    }
+}
+
+impl Drop for FilePages {
+    fn drop(&mut self) {
+        for &paddr in &self.frames {
+            dealloc_frame(paddr);
+        }
+    }
+}
+
+/// Contents of shared file mappings taken out of an address space, to be
+/// written back to the files after the lock of the address space is
+/// released.
+///
+/// Returned by [`AddrSpace::unmap_deferred`] and [`AddrSpace::writeback`].
+/// The pages are freed when it is dropped.
+///
+/// [`AddrSpace::unmap_deferred`]: crate::AddrSpace::unmap_deferred
+/// [`AddrSpace::writeback`]: crate::AddrSpace::writeback
+#[derive(Default)]
+#[must_use]
+pub struct Writeback {
+    areas: Vec<MemoryArea>,
+}
+
+impl Writeback {
+    /// Moves the contents of `other` into `self`.
+    pub fn append(&mut self, mut other: Writeback) {
+        self.areas.append(&mut other.areas);
+    }
+
+    /// Writes the contents back to the files. All areas are written even if
+    /// some of them fail, and the first error is returned.
+    pub fn write(self) -> AxResult {
+        let mut res = Ok(());
+        for area in &self.areas {
+            if let Err(e) = area.sync() {
+                warn!("failed to write back {:?}: {:?}", area, e);
+                res = res.and(Err(e));
+            }
+        }
+        res
+    }
+
+    pub(crate) fn push(&mut self, area: MemoryArea) {
+        self.areas.push(area);
+    }
+}
+
+/// A contiguous virtual memory area with the same mapping flags and backend.
+pub struct MemoryArea {
+    start: VirtAddr,
+    size: usize,
+    flags: MappingFlags,
+    backend: Backend,
+    /// Allocated frames, by the page addresses (unused for linear mappings).
+    frames: BTreeMap<usize, PhysAddr>,
+    /// Whether the area has ever been writable.
+    written: bool,
+}
+
+fn alloc_frame() -> AxResult<PhysAddr> {
+    let vaddr = axalloc::global_allocator()
+        .alloc_pages(1, PAGE_SIZE_4K)
+        .map_err(|_| AxError::NoMemory)?;
+    unsafe { core::ptr::write_bytes(vaddr as *mut u8, 0, PAGE_SIZE_4K) };
+    Ok(virt_to_phys(vaddr.into()))
+}
+
+fn dealloc_frame(paddr: PhysAddr) {
+    axalloc::global_allocator().dealloc_pages(phys_to_virt(paddr).as_usize(), 1);
+}
+
+/// Flushes the TLB entries of `[start, start + size)` in `pt` on all CPUs.
+fn flush_tlb(pt: &PageTable, start: VirtAddr, size: usize) {
+    let root = pt.root_paddr();
+    // the kernel mappings are shared by all page tables
+    let root = (!crate::is_kernel_page_table(root)).then_some(root);
+    axhal::tlb::flush_tlb_range(root, start, size);
+}
+
+fn frame_data(paddr: PhysAddr) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(phys_to_virt(paddr).as_mut_ptr(), PAGE_SIZE_4K) }
+}
+
+impl MemoryArea {
+    pub(crate) fn new(start: VirtAddr, size: usize, flags: MappingFlags, backend: Backend) -> Self {
+        Self {
+            start,
+            size,
+            flags,
+            backend,
+            frames: BTreeMap::new(),
+            written: flags.contains(MappingFlags::WRITE),
+        }
+    }
+
+    /// Returns the start address of the area.
+    pub const fn start(&self) -> VirtAddr {
+        self.start
+    }
+
+    /// Returns the end address (exclusive) of the area.
+    pub fn end(&self) -> VirtAddr {
+        self.start + self.size
+    }
+
+    /// Returns the size of the area in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the mapping flags of the area.
+    pub const fn flags(&self) -> MappingFlags {
+        self.flags
+    }
+
+    /// Returns the backend of the area.
+    pub const fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Whether the area contains `vaddr`.
+    pub fn contains(&self, vaddr: VirtAddr) -> bool {
+        self.start <= vaddr && vaddr < self.end()
+    }
+
+    fn map_frames(&self, pt: &mut PageTable) -> AxResult {
+        self.map_frames_at(pt, self.start)
+    }
+
+    /// Maps the frames of the area as if the area started at `start`.
+    pub(crate) fn map_frames_at(&self, pt: &mut PageTable, start: VirtAddr) -> AxResult {
+        if self.flags.is_empty() {
+            return Ok(());
+        }
+        if let Backend::Linear { pa_va_offset } = self.backend {
+            let paddr = PhysAddr::from(self.start.as_usize().wrapping_sub(pa_va_offset));
+            return pt
+                .map_region(start, paddr, self.size, self.flags, true)
+                .map_err(paging_err_to_ax_err);
+        }
+        let delta = start.as_usize().wrapping_sub(self.start.as_usize());
+        for (&vaddr, &paddr) in &self.frames {
+            pt.map(
+                vaddr.wrapping_add(delta).into(),
+                paddr,
+                PageSize::Size4K,
+                self.flags,
+            )
+            .map_err(paging_err_to_ax_err)?;
+        }
+        Ok(())
+    }
+
+    /// Unmaps the pages that are mapped in the area as if it started at
+    /// `start`, to clean up a partially done [`map_frames_at`].
+    ///
+    /// [`map_frames_at`]: Self::map_frames_at
+    pub(crate) fn clear_frames_at(&self, pt: &mut PageTable, start: VirtAddr) {
+        for vaddr in (start.as_usize()..start.as_usize() + self.size).step_by(PAGE_SIZE_4K) {
+            pt.unmap(vaddr.into()).ok();
+        }
+        flush_tlb(pt, start, self.size);
+    }
+
+    /// Splits the huge pages that are partially covered by the area, so that
+    /// it can then be unmapped or protected without allocating page tables,
+    /// which cannot fail.
+    pub(crate) fn split_huge_pages(&self, pt: &mut PageTable) -> AxResult {
+        if self.flags.is_empty() {
+            return Ok(());
+        }
+        match self.backend {
+            // protecting with the same flags only splits the huge pages
+            Backend::Linear { .. } => pt
+                .protect_region(self.start, self.size, self.flags)
+                .map_err(paging_err_to_ax_err),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn unmap_frames(&self, pt: &mut PageTable) -> AxResult {
+        if self.flags.is_empty() {
+            return Ok(());
+        }
+        if let Backend::Linear { .. } = self.backend {
+            pt.unmap_region(self.start, self.size)
+                .map_err(paging_err_to_ax_err)?;
+        } else {
+            for &vaddr in self.frames.keys() {
+                pt.unmap(vaddr.into()).map_err(paging_err_to_ax_err)?;
+            }
+        }
+        flush_tlb(pt, self.start, self.size);
+        Ok(())
+    }
+
+    fn free_frames(&mut self) {
+        for (_, paddr) in core::mem::take(&mut self.frames) {
+            dealloc_frame(paddr);
+        }
+    }
+
+    /// Takes the frames of `pages` as the frames of the area.
+    pub(crate) fn set_file_pages(&mut self, mut pages: FilePages) {
+        let frames = core::mem::take(&mut pages.frames);
+        let pages = (self.start.as_usize()..self.end().as_usize()).step_by(PAGE_SIZE_4K);
+        self.frames = pages.zip(frames).collect();
+    }
+
+    /// Allocates the frames (if needed) and maps the area.
+    pub(crate) fn map_area(&mut self, pt: &mut PageTable) -> AxResult {
+        let res = self.populate().and_then(|_| self.map_frames(pt));
+        if res.is_err() {
+            // clear the pages that have been mapped before the failure
+            self.clear_frames_at(pt, self.start);
+            self.free_frames();
+        }
+        res
+    }
+
+    fn populate(&mut self) -> AxResult {
+        let pages = (self.start.as_usize()..self.end().as_usize()).step_by(PAGE_SIZE_4K);
+        match &self.backend {
+            // the frames of file mappings are set by `set_file_pages`
+            Backend::Linear { .. } | Backend::Alloc { populate: false } | Backend::File { .. } => {}
+            Backend::Alloc { populate: true } => {
+                for vaddr in pages {
+                    self.frames.insert(vaddr, alloc_frame()?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether it is a shared file mapping that has been writable, whose
+    /// contents need to be written back to the file.
+    fn needs_sync(&self) -> bool {
+        self.written && matches!(self.backend, Backend::File { shared: true, .. })
+    }
+
+    /// Writes the contents back to the file, if [`needs_sync`] is true.
+    ///
+    /// [`needs_sync`]: Self::needs_sync
+    fn sync(&self) -> AxResult {
+        if !self.needs_sync() {
+            return Ok(());
+        }
+        if let Backend::File { file, offset, .. } = &self.backend {
+            // do not extend the file
+            let file_size = file.size()?;
+            for (&vaddr, &paddr) in &self.frames {
+                let file_offset = offset + (vaddr - self.start.as_usize()) as u64;
+                if file_offset < file_size {
+                    let len = PAGE_SIZE_4K.min((file_size - file_offset) as usize);
+                    file.write_at(file_offset, &frame_data(paddr)[..len])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an unmapped copy of the area with the contents copied to new
+    /// frames, if the contents need to be written back to the file.
+    pub(crate) fn snapshot(&self) -> AxResult<Option<Self>> {
+        if !self.needs_sync() {
+            return Ok(None);
+        }
+        let mut copy = Self::new(self.start, self.size, self.flags, self.backend.clone());
+        copy.written = true;
+        for (&vaddr, &paddr) in &self.frames {
+            let new_paddr = alloc_frame()?;
+            copy.frames.insert(vaddr, new_paddr);
+            frame_data(new_paddr).copy_from_slice(frame_data(paddr));
+        }
+        Ok(Some(copy))
+    }
+
+    /// Changes the mapping flags of the area.
+    ///
+    /// It can only fail if the huge pages have not been split by
+    /// [`split_huge_pages`], or an inaccessible area is made accessible, in
+    /// which case the area is kept inaccessible.
+    ///
+    /// [`split_huge_pages`]: Self::split_huge_pages
+    pub(crate) fn protect_area(&mut self, pt: &mut PageTable, flags: MappingFlags) -> AxResult {
+        if self.flags == flags {
+            return Ok(());
+        }
+        if self.flags.is_empty() || flags.is_empty() {
+            // inaccessible areas are not mapped
+            self.unmap_frames(pt)?;
+            let old_flags = core::mem::replace(&mut self.flags, flags);
+            self.written |= flags.contains(MappingFlags::WRITE);
+            if let Err(e) = self.map_frames(pt) {
+                self.clear_frames_at(pt, self.start);
+                self.flags = old_flags;
+                return Err(e);
+            }
+            return Ok(());
+        }
+        if let Backend::Linear { .. } = self.backend {
+            pt.protect_region(self.start, self.size, flags)
+                .map_err(paging_err_to_ax_err)?;
+        } else {
+            for &vaddr in self.frames.keys() {
+                pt.protect_region(vaddr.into(), PAGE_SIZE_4K, flags)
+                    .map_err(paging_err_to_ax_err)?;
+            }
+        }
+        flush_tlb(pt, self.start, self.size);
+        self.flags = flags;
+        self.written |= flags.contains(MappingFlags::WRITE);
+        Ok(())
+    }
+
+    /// Changes the start address of the area to `new_start`, with the
+    /// contents kept. The page table is not changed, so the frames should
+    /// have been mapped at `new_start` by [`map_frames_at`], and unmapped from
+    /// the old start, beforehand.
+    ///
+    /// [`map_frames_at`]: Self::map_frames_at
+    pub(crate) fn relocate(&mut self, new_start: VirtAddr) {
+        let delta = new_start.as_usize().wrapping_sub(self.start.as_usize());
+        self.frames = core::mem::take(&mut self.frames)
+            .into_iter()
+            .map(|(vaddr, paddr)| (vaddr.wrapping_add(delta), paddr))
+            .collect();
+        if let Backend::Linear { pa_va_offset } = &mut self.backend {
+            *pa_va_offset = pa_va_offset.wrapping_add(delta);
+        }
+        self.start = new_start;
+    }
+
+    /// Splits the area at `offset`, and returns the right part.
+    pub(crate) fn split(&mut self, offset: usize) -> Self {
+        let at = self.start + offset;
+        let right = Self {
+            start: at,
+            size: self.size - offset,
+            flags: self.flags,
+            backend: self.backend.offset_by(offset),
+            frames: self.frames.split_off(&at.as_usize()),
+            written: self.written,
+        };
+        self.size = offset;
+        right
+    }
+
+    /// Handles a page fault at `vaddr` in the area, by allocating the page
+    /// if the access is allowed. Returns `true` if it has been handled.
+    pub(crate) fn handle_page_fault(
+        &mut self,
+        pt: &mut PageTable,
+        vaddr: VirtAddr,
+        access_flags: MappingFlags,
+    ) -> bool {
+        if !self.flags.contains(access_flags) {
+            return false;
+        }
+        let vaddr = vaddr.align_down_4k();
+        if self.frames.contains_key(&vaddr.as_usize()) {
+            // the page is mapped (e.g., by another CPU, or the TLB entry was
+            // stale), unless the area is inaccessible
+            return !self.flags.is_empty();
+        }
+        let Backend::Alloc { .. } = self.backend else {
+            return false;
+        };
+        let Ok(paddr) = alloc_frame() else {
+            warn!("no memory for the page fault at {:#x}", vaddr);
+            return false;
+        };
+        if pt.map(vaddr, paddr, PageSize::Size4K, self.flags).is_err() {
+            dealloc_frame(paddr);
+            return false;
+        }
+        self.frames.insert(vaddr.as_usize(), paddr);
+        true
+    }
+}
+
+impl Drop for MemoryArea {
+    fn drop(&mut self) {
+        self.free_frames();
+    }
+}
+
+impl fmt::Debug for MemoryArea {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoryArea")
+            .field(
+                "range",
+                &format_args!("[{:#x}, {:#x})", self.start, self.end()),
+            )
+            .field("flags", &self.flags)
+            .field("backend", &self.backend)
+            .finish()
+    }
+}
+
+/// Returns an error if `vaddr` or `size` is not 4K-aligned.
+pub(crate) fn check_aligned(vaddr: VirtAddr, size: usize) -> AxResult {
+    if vaddr.is_aligned_4k() && size.is_multiple_of(PAGE_SIZE_4K) {
+        Ok(())
+    } else {
+        ax_err!(InvalidInput, "address not aligned")
+    }
+}
@@ -0,0 +1,482 @@
+use alloc::collections::BTreeMap;
+use core::{fmt, ops::Range};
+
+use axerrno::{ax_err, AxResult};
+use axhal::mem::{phys_to_virt, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::{MappingFlags, PageTable};
+
+use crate::area::{check_aligned, Backend, FilePages, MemoryArea, Writeback};
+use crate::paging_err_to_ax_err;
+
+/// A virtual address space, which consists of non-overlapping memory areas
+/// and the page table that maps them.
+pub struct AddrSpace {
+    base: VirtAddr,
+    size: usize,
+    /// Memory areas, by their start addresses.
+    areas: BTreeMap<usize, MemoryArea>,
+    pt: PageTable,
+}
+
+impl AddrSpace {
+    /// Creates a new empty address space covering `[base, base + size)`.
+    pub fn new_empty(base: VirtAddr, size: usize) -> AxResult<Self> {
+        check_aligned(base, size)?;
+        Ok(Self {
+            base,
+            size,
+            areas: BTreeMap::new(),
+            pt: PageTable::try_new().map_err(paging_err_to_ax_err)?,
+        })
+    }
+
+    /// Returns the base address of the address space.
+    pub const fn base(&self) -> VirtAddr {
+        self.base
+    }
+
+    /// Returns the end address (exclusive) of the address space.
+    pub fn end(&self) -> VirtAddr {
+        self.base + self.size
+    }
+
+    /// Returns the size of the address space in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the page table of the address space.
+    pub const fn page_table(&self) -> &PageTable {
+        &self.pt
+    }
+
+    /// Returns the physical address of the root page table.
+    pub const fn page_table_root(&self) -> PhysAddr {
+        self.pt.root_paddr()
+    }
+
+    /// Shares the mappings of `other` with this address space, by copying the
+    /// root-level page table entries that cover `other`.
+    ///
+    /// The two address spaces must not overlap. Only the page table is
+    /// affected, the memory areas of `other` are not copied.
+    pub fn copy_mappings_from(&mut self, other: &AddrSpace) -> AxResult {
+        if self.base < other.end() && other.base < self.end() {
+            return ax_err!(InvalidInput, "address space overlap");
+        }
+        self.pt.copy_from(&other.pt, other.base, other.size);
+        Ok(())
+    }
+
+    /// Allocates all root-level page table entries of the address space, so
+    /// that later mappings are visible to the address spaces that have
+    /// copied them by [`copy_mappings_from`](Self::copy_mappings_from).
+    #[cfg(all(feature = "uspace", not(target_arch = "aarch64")))]
+    pub(crate) fn prealloc_root_entries(&mut self) -> AxResult {
+        self.pt
+            .prealloc_root_entries(self.base, self.size)
+            .map_err(paging_err_to_ax_err)
+    }
+
+    /// Whether `[start, start + size)` is in the address space.
+    pub fn contains_range(&self, start: VirtAddr, size: usize) -> bool {
+        self.base <= start
+            && size <= self.size
+            && start.as_usize() - self.base.as_usize() <= self.size - size
+    }
+
+    /// Returns an iterator over all memory areas, in address order.
+    pub fn areas(&self) -> impl Iterator<Item = &MemoryArea> {
+        self.areas.values()
+    }
+
+    /// Returns the memory area that contains `vaddr`.
+    pub fn find_area(&self, vaddr: VirtAddr) -> Option<&MemoryArea> {
+        self.areas
+            .range(..=vaddr.as_usize())
+            .next_back()
+            .map(|(_, area)| area)
+            .filter(|area| area.contains(vaddr))
+    }
+
+    fn find_area_mut(&mut self, vaddr: VirtAddr) -> Option<&mut MemoryArea> {
+        self.areas
+            .range_mut(..=vaddr.as_usize())
+            .next_back()
+            .map(|(_, area)| area)
+            .filter(|area| area.contains(vaddr))
+    }
+
+    /// Whether no memory area overlaps with `[start, start + size)`.
+    pub fn is_free(&self, start: VirtAddr, size: usize) -> bool {
+        let end = start.as_usize() + size;
+        if let Some((_, area)) = self.areas.range(..end).next_back() {
+            area.end() <= start
+        } else {
+            true
+        }
+    }
+
+    /// Whether `[start, start + size)` is fully covered by memory areas.
+    pub fn is_mapped(&self, start: VirtAddr, size: usize) -> bool {
+        self.can_access_range(start, size, MappingFlags::empty())
+    }
+
+    /// Whether `[start, start + size)` is fully covered by memory areas that
+    /// allow the access type `access_flags` (e.g., [`MappingFlags::WRITE`]).
+    pub fn can_access_range(
+        &self,
+        start: VirtAddr,
+        size: usize,
+        access_flags: MappingFlags,
+    ) -> bool {
+        let end = start + size;
+        let mut vaddr = start;
+        while vaddr < end {
+            match self.find_area(vaddr) {
+                Some(area) if area.flags().contains(access_flags) => vaddr = area.end(),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Finds a free area of `size` bytes in `limit`, at or above `hint` if
+    /// possible. Returns the start address of the area.
+    pub fn find_free_area(
+        &self,
+        hint: VirtAddr,
+        size: usize,
+        limit: Range<VirtAddr>,
+    ) -> Option<VirtAddr> {
+        let limit_end = limit.end.as_usize();
+        let search = |from: usize| -> Option<VirtAddr> {
+            let mut start = from;
+            for area in self.areas.range(..).map(|(_, area)| area) {
+                if area.end().as_usize() <= start {
+                    continue;
+                }
+                if area.start().as_usize() >= start && area.start().as_usize() - start >= size {
+                    break;
+                }
+                start = area.end().as_usize();
+            }
+            (start <= limit_end && limit_end - start >= size).then(|| start.into())
+        };
+        let hint = hint.align_down_4k();
+        if limit.start <= hint && hint < limit.end {
+            if let Some(start) = search(hint.as_usize()) {
+                return Some(start);
+            }
+        }
+        search(limit.start.align_up_4k().as_usize())
+    }
+
+    fn check_free(&self, start: VirtAddr, size: usize) -> AxResult {
+        check_aligned(start, size)?;
+        if size == 0 || !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !self.is_free(start, size) {
+            return ax_err!(AlreadyExists, "address already mapped");
+        }
+        Ok(())
+    }
+
+    fn insert_area(&mut self, mut area: MemoryArea) -> AxResult {
+        area.map_area(&mut self.pt)?;
+        self.areas.insert(area.start().as_usize(), area);
+        Ok(())
+    }
+
+    /// Adds a new memory area `[start, start + size)` with the given flags
+    /// and backend, and maps it in the page table.
+    ///
+    /// Returns [`AlreadyExists`](axerrno::AxError::AlreadyExists) if it
+    /// overlaps with existing areas. File mappings are added by
+    /// [`map_file_pages`](Self::map_file_pages) instead.
+    pub fn map(
+        &mut self,
+        start: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+        backend: Backend,
+    ) -> AxResult {
+        if let Backend::File { .. } = backend {
+            return ax_err!(InvalidInput, "file mappings need the file pages");
+        }
+        self.check_free(start, size)?;
+        self.insert_area(MemoryArea::new(start, size, flags, backend))
+    }
+
+    /// Adds a new file mapping at `start` with the given flags, which maps
+    /// `pages` read by [`FilePages::read`].
+    ///
+    /// Returns [`AlreadyExists`](axerrno::AxError::AlreadyExists) if it
+    /// overlaps with existing areas.
+    pub fn map_file_pages(
+        &mut self,
+        start: VirtAddr,
+        flags: MappingFlags,
+        pages: FilePages,
+    ) -> AxResult {
+        let size = pages.size();
+        self.check_free(start, size)?;
+        let mut area = MemoryArea::new(start, size, flags, pages.backend().clone());
+        area.set_file_pages(pages);
+        self.insert_area(area)
+    }
+
+    /// Maps `[vaddr, vaddr + size)` to contiguous physical memory starting
+    /// from `paddr`.
+    pub fn map_linear(
+        &mut self,
+        vaddr: VirtAddr,
+        paddr: PhysAddr,
+        size: usize,
+        flags: MappingFlags,
+    ) -> AxResult {
+        let pa_va_offset = vaddr.as_usize().wrapping_sub(paddr.as_usize());
+        self.map(vaddr, size, flags, Backend::Linear { pa_va_offset })
+    }
+
+    /// Splits the area that contains `vaddr` (if any), so that there is an
+    /// area boundary at `vaddr`.
+    fn split_at(&mut self, vaddr: VirtAddr) {
+        if let Some(area) = self.find_area_mut(vaddr) {
+            if area.start() != vaddr {
+                let right = area.split(vaddr.as_usize() - area.start().as_usize());
+                self.areas.insert(vaddr.as_usize(), right);
+            }
+        }
+    }
+
+    /// Splits the areas at `start` and `start + size`, and returns the start
+    /// addresses of the areas in `[start, start + size)`.
+    fn split_range(&mut self, start: VirtAddr, size: usize) -> alloc::vec::Vec<usize> {
+        let end = start + size;
+        self.split_at(start);
+        self.split_at(end);
+        self.areas
+            .range(start.as_usize()..end.as_usize())
+            .map(|(&k, _)| k)
+            .collect()
+    }
+
+    /// Splits the huge pages that are partially covered by the areas of
+    /// `keys`, so that they can be unmapped or protected without failures.
+    fn split_huge_pages(&mut self, keys: &[usize]) -> AxResult {
+        for key in keys {
+            self.areas[key].split_huge_pages(&mut self.pt)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the areas (or the parts of them) in `[start, start + size)`,
+    /// and unmaps them from the page table. Holes in the range are skipped.
+    ///
+    /// Shared file mappings are written back to the files, so use
+    /// [`unmap_deferred`](Self::unmap_deferred) if the address space is
+    /// locked.
+    pub fn unmap(&mut self, start: VirtAddr, size: usize) -> AxResult {
+        self.unmap_deferred(start, size)?.write()
+    }
+
+    /// Like [`unmap`](Self::unmap), but returns the removed areas instead of
+    /// writing back the shared file mappings, which is done by
+    /// [`Writeback::write`] later.
+    pub fn unmap_deferred(&mut self, start: VirtAddr, size: usize) -> AxResult<Writeback> {
+        check_aligned(start, size)?;
+        if !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        let keys = self.split_range(start, size);
+        let mut writeback = Writeback::default();
+        for key in keys {
+            let area = self.areas.remove(&key).unwrap();
+            let res = area.unmap_frames(&mut self.pt);
+            writeback.push(area);
+            res?;
+        }
+        Ok(writeback)
+    }
+
+    /// Changes the mapping flags of the areas in `[start, start + size)`.
+    ///
+    /// Like `mprotect`, returns [`NoMemory`](axerrno::AxError::NoMemory) if
+    /// the range is not fully covered by memory areas. If it fails, the flags
+    /// of the areas are not changed.
+    pub fn protect(&mut self, start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult {
+        check_aligned(start, size)?;
+        if !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !self.is_mapped(start, size) {
+            return ax_err!(NoMemory, "range not mapped");
+        }
+        let keys = self.split_range(start, size);
+        self.split_huge_pages(&keys)?;
+        // only mapping the inaccessible areas can fail now, so do it first
+        let mut mapped = alloc::vec::Vec::new();
+        for &key in &keys {
+            let area = self.areas.get_mut(&key).unwrap();
+            if !area.flags().is_empty() || flags.is_empty() {
+                continue;
+            }
+            if let Err(e) = area.protect_area(&mut self.pt, flags) {
+                for key in mapped {
+                    let area = self.areas.get_mut(&key).unwrap();
+                    area.protect_area(&mut self.pt, MappingFlags::empty()).ok();
+                }
+                return Err(e);
+            }
+            mapped.push(key);
+        }
+        for key in keys {
+            let area = self.areas.get_mut(&key).unwrap();
+            area.protect_area(&mut self.pt, flags)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the areas in `[start, start + size)` to `new_start`, with the
+    /// contents kept. The range must be fully mapped, and the destination
+    /// must be free. If it fails, the areas are kept at the source.
+    pub fn move_area(&mut self, start: VirtAddr, size: usize, new_start: VirtAddr) -> AxResult {
+        check_aligned(start, size)?;
+        check_aligned(new_start, 0)?;
+        if !self.contains_range(start, size) || !self.contains_range(new_start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !self.is_mapped(start, size) {
+            return ax_err!(BadAddress, "source range not mapped");
+        }
+        if !self.is_free(new_start, size) {
+            return ax_err!(AlreadyExists, "destination already mapped");
+        }
+        let keys = self.split_range(start, size);
+        self.split_huge_pages(&keys)?;
+        let dst = |key: usize| new_start + (key - start.as_usize());
+        // map the destination first, as unmapping the source cannot fail
+        for (i, &key) in keys.iter().enumerate() {
+            if let Err(e) = self.areas[&key].map_frames_at(&mut self.pt, dst(key)) {
+                for key in &keys[..=i] {
+                    self.areas[key].clear_frames_at(&mut self.pt, dst(*key));
+                }
+                return Err(e);
+            }
+        }
+        let mut res = Ok(());
+        for key in keys {
+            let mut area = self.areas.remove(&key).unwrap();
+            res = res.and(area.unmap_frames(&mut self.pt));
+            area.relocate(dst(key));
+            self.areas.insert(dst(key).as_usize(), area);
+        }
+        res
+    }
+
+    /// Copies the contents of the shared file mappings in
+    /// `[start, start + size)`, to be written back to the files by
+    /// [`Writeback::write`].
+    pub fn writeback(&self, start: VirtAddr, size: usize) -> AxResult<Writeback> {
+        let end = start.as_usize() + size;
+        let first = self
+            .find_area(start)
+            .map_or(start.as_usize(), |a| a.start().as_usize());
+        let mut writeback = Writeback::default();
+        for (_, area) in self.areas.range(first..end) {
+            if let Some(copy) = area.snapshot()? {
+                writeback.push(copy);
+            }
+        }
+        Ok(writeback)
+    }
+
+    /// Translates `vaddr` to the physical address by querying the page table.
+    pub fn translate(&self, vaddr: VirtAddr) -> Option<PhysAddr> {
+        self.pt.query(vaddr).ok().map(|(paddr, _, _)| paddr)
+    }
+
+    /// Writes `data` to the address space starting from `start`.
+    ///
+    /// The data is written through the linear mapping of the target frames,
+    /// so the address space does not need to be active (e.g., when loading a
+    /// user program). Lazily allocated pages are populated on demand, and
+    /// the mapping flags are not checked.
+    pub fn write(&mut self, start: VirtAddr, data: &[u8]) -> AxResult {
+        if !self.contains_range(start, data.len()) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        let mut vaddr = start;
+        let mut written = 0;
+        while written < data.len() {
+            let paddr = match self.translate(vaddr) {
+                Some(paddr) => paddr,
+                None if self.handle_page_fault(vaddr, MappingFlags::empty()) => {
+                    self.translate(vaddr).unwrap()
+                }
+                None => return ax_err!(BadAddress, "address not mapped"),
+            };
+            let n = (PAGE_SIZE_4K - vaddr.align_offset_4k()).min(data.len() - written);
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    data[written..].as_ptr(),
+                    phys_to_virt(paddr).as_mut_ptr(),
+                    n,
+                )
+            };
+            vaddr += n;
+            written += n;
+        }
+        Ok(())
+    }
+
+    /// Handles a page fault at `vaddr` with the access type `access_flags`.
+    ///
+    /// Returns `true` if it has been handled, e.g., a lazily allocated page
+    /// has been mapped.
+    pub fn handle_page_fault(&mut self, vaddr: VirtAddr, access_flags: MappingFlags) -> bool {
+        let area = self
+            .areas
+            .range_mut(..=vaddr.as_usize())
+            .next_back()
+            .map(|(_, area)| area)
+            .filter(|area| area.contains(vaddr));
+        let Some(area) = area else {
+            return false;
+        };
+        area.handle_page_fault(&mut self.pt, vaddr, access_flags)
+    }
+}
+
+impl Drop for AddrSpace {
+    fn drop(&mut self) {
+        let mut writeback = Writeback::default();
+        for (_, area) in core::mem::take(&mut self.areas) {
+            if let Err(e) = area.unmap_frames(&mut self.pt) {
+                warn!("failed to unmap {:?}: {:?}", area, e);
+            }
+            writeback.push(area);
+        }
+        // drop the ASIDs of the page table before it is freed
+        axhal::tlb::flush_tlb_all(Some(self.pt.root_paddr()));
+        writeback.write().ok();
+    }
+}
+
+impl fmt::Debug for AddrSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AddrSpace")
+            .field(
+                "range",
+                &format_args!("[{:#x}, {:#x})", self.base, self.end()),
+            )
+            .field("page_table_root", &self.pt.root_paddr())
+            .field(
+                "areas",
+                &self.areas.values().collect::<alloc::vec::Vec<_>>(),
+            )
+            .finish()
+    }
+}
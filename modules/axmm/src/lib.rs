@@ -0,0 +1,169 @@
+//! [ArceOS](https://github.com/rcore-os/arceos) virtual memory management
+//! module.
+//!
+//! It provides [`AddrSpace`], which manages a virtual address space as a set
+//! of non-overlapping [`MemoryArea`]s. Each area has its own mapping flags
+//! and [`Backend`] (linear mapping, lazily allocated, or file-backed).
+//!
+//! A single kernel address space is created on initialization, which contains
+//! the linear mapping of all physical memory regions. Other kernel regions
+//! (e.g., task stacks, `mmap` areas) are added to it at runtime, and page
+//! faults in it are dispatched to the area that covers the faulting address.
+//!
+//! # Cargo Features
+//!
+//! - `uspace`: Enable user address spaces, which share the kernel mappings
+//!   with the kernel address space.
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+mod area;
+mod aspace;
+
+use axerrno::{AxError, AxResult};
+use axhal::mem::{memory_regions, phys_to_virt, virt_to_phys, PhysAddr, VirtAddr};
+use axhal::paging::{MappingFlags, PagingError};
+use lazy_init::LazyInit;
+use spinlock::SpinNoIrq;
+
+pub use self::area::{Backend, FilePages, MemoryArea, MmapFile, Writeback};
+pub use self::aspace::AddrSpace;
+
+static KERNEL_ASPACE: LazyInit<SpinNoIrq<AddrSpace>> = LazyInit::new();
+static KERNEL_PAGE_TABLE_ROOT: LazyInit<PhysAddr> = LazyInit::new();
+
+pub(crate) fn paging_err_to_ax_err(err: PagingError) -> AxError {
+    warn!("Paging error: {:?}", err);
+    match err {
+        PagingError::NoMemory => AxError::NoMemory,
+        PagingError::AlreadyMapped => AxError::AlreadyExists,
+        PagingError::InUse => AxError::ResourceBusy,
+        _ => AxError::InvalidInput,
+    }
+}
+
+/// Converts the memory protection of `mmap` (`PROT_READ`, `PROT_WRITE` and
+/// `PROT_EXEC`) to mapping flags.
+///
+/// Returns [`InvalidInput`](AxError::InvalidInput) if `prot` has other bits.
+pub fn prot_to_flags(prot: u32) -> AxResult<MappingFlags> {
+    const PROT_READ: u32 = 1;
+    const PROT_WRITE: u32 = 2;
+    const PROT_EXEC: u32 = 4;
+    if prot & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+        return Err(AxError::InvalidInput);
+    }
+    let mut flags = MappingFlags::empty();
+    if prot & PROT_READ != 0 {
+        flags |= MappingFlags::READ;
+    }
+    if prot & PROT_WRITE != 0 {
+        // write-only pages are not supported on some architectures
+        flags |= MappingFlags::READ | MappingFlags::WRITE;
+    }
+    if prot & PROT_EXEC != 0 {
+        flags |= MappingFlags::EXECUTE;
+    }
+    Ok(flags)
+}
+
+/// Creates a new address space for the kernel, with the linear mapping of
+/// all physical memory regions.
+pub fn new_kernel_aspace() -> AxResult<AddrSpace> {
+    let mut aspace = AddrSpace::new_empty(
+        VirtAddr::from(axconfig::KERNEL_ASPACE_BASE),
+        axconfig::KERNEL_ASPACE_SIZE,
+    )?;
+    for r in memory_regions() {
+        aspace.map_linear(phys_to_virt(r.paddr), r.paddr, r.size, r.flags.into())?;
+    }
+    // On AArch64, user page tables are in `TTBR0_EL1` and never contain the
+    // kernel mappings.
+    #[cfg(all(feature = "uspace", not(target_arch = "aarch64")))]
+    aspace.prealloc_root_entries()?;
+    Ok(aspace)
+}
+
+/// Creates a new address space for user processes, covering
+/// `[base, base + size)`.
+///
+/// The kernel mappings are shared with the kernel address space, so that the
+/// kernel keeps running after switching to the user page table.
+#[cfg(feature = "uspace")]
+pub fn new_user_aspace(base: VirtAddr, size: usize) -> AxResult<AddrSpace> {
+    let mut aspace = AddrSpace::new_empty(base, size)?;
+    if !cfg!(target_arch = "aarch64") {
+        aspace.copy_mappings_from(&kernel_aspace().lock())?;
+    }
+    Ok(aspace)
+}
+
+/// Returns the kernel address space.
+///
+/// # Panics
+///
+/// Panics if it has not been initialized by [`init_memory_management`].
+pub fn kernel_aspace() -> &'static SpinNoIrq<AddrSpace> {
+    &KERNEL_ASPACE
+}
+
+/// Returns the physical address of the root page table of the kernel
+/// address space.
+///
+/// Unlike [`kernel_aspace`], it does not need to acquire the lock.
+pub fn kernel_page_table_root() -> PhysAddr {
+    *KERNEL_PAGE_TABLE_ROOT
+}
+
+/// Returns whether `root` is the root page table of the kernel address space.
+pub(crate) fn is_kernel_page_table(root: PhysAddr) -> bool {
+    KERNEL_PAGE_TABLE_ROOT.try_get() == Some(&root)
+}
+
+/// Converts a kernel virtual address to the physical address.
+///
+/// Unlike [`virt_to_phys`], it also works for addresses outside the linear
+/// mapping (e.g., task stacks), by querying the kernel address space.
+pub fn kernel_virt_to_phys(vaddr: VirtAddr) -> PhysAddr {
+    let linear_start = axconfig::PHYS_VIRT_OFFSET + axconfig::PHYS_MEMORY_BASE;
+    let linear_end = linear_start + axconfig::PHYS_MEMORY_SIZE;
+    if !(linear_start..linear_end).contains(&vaddr.as_usize()) {
+        if let Some(paddr) = KERNEL_ASPACE
+            .try_get()
+            .and_then(|aspace| aspace.lock().translate(vaddr))
+        {
+            return paddr;
+        }
+    }
+    virt_to_phys(vaddr)
+}
+
+/// Handles a page fault in the kernel address space.
+///
+/// Returns `true` if it has been handled.
+pub fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags) -> bool {
+    match KERNEL_ASPACE.try_get() {
+        Some(aspace) => aspace.lock().handle_page_fault(vaddr, access_flags),
+        None => false,
+    }
+}
+
+/// Initializes virtual memory management on the primary CPU: creates the
+/// kernel address space, and switches to its page table.
+pub fn init_memory_management() {
+    let aspace = new_kernel_aspace().expect("failed to initialize kernel address space");
+    debug!("kernel address space init OK: {:#x?}", aspace);
+    KERNEL_PAGE_TABLE_ROOT.init_by(aspace.page_table_root());
+    KERNEL_ASPACE.init_by(SpinNoIrq::new(aspace));
+    unsafe { axhal::arch::write_page_table_root(kernel_page_table_root()) };
+}
+
+/// Initializes virtual memory management on secondary CPUs, by switching to
+/// the page table of the kernel address space.
+pub fn init_memory_management_secondary() {
+    unsafe { axhal::arch::write_page_table_root(kernel_page_table_root()) };
+}
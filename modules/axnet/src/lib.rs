@@ -11,6 +11,8 @@
 //! - [`IpAddr`], [`Ipv4Addr`]: IP addresses (either v4 or v6) and IPv4 addresses.
 //! - [`SocketAddr`]: IP address with a port number.
 //! - [`resolve_socket_addr`]: Function for DNS query.
+//! - [`tcp_sockets`]: Function for listing the states of TCP sockets.
+//! - [`sntp_sync`]: Function for correcting the wall clock by an NTP server.
 //!
 //! # Cargo Features
 //!
@@ -33,9 +35,13 @@ cfg_if::cfg_if! {
     }
 }
 
+mod sntp;
+
 pub use self::net_impl::resolve_socket_addr;
 pub use self::net_impl::TcpSocket;
 pub use self::net_impl::UdpSocket;
+pub use self::net_impl::{tcp_sockets, TcpSocketInfo};
+pub use self::sntp::{sntp_query, sntp_sync, NTP_PORT};
 pub use smoltcp::wire::{IpAddress as IpAddr, IpEndpoint as SocketAddr, Ipv4Address as Ipv4Addr};
 
 use axdriver::{prelude::*, AxDeviceContainer};
@@ -3,7 +3,7 @@ mod listen_table;
 mod tcp;
 mod udp;
 
-use alloc::{collections::VecDeque, vec};
+use alloc::{collections::VecDeque, vec, vec::Vec};
 use core::cell::RefCell;
 use core::ops::DerefMut;
 
@@ -19,6 +19,7 @@ use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr};
 
 use self::listen_table::ListenTable;
+use crate::SocketAddr;
 
 pub use self::dns::resolve_socket_addr;
 pub use self::tcp::TcpSocket;
@@ -285,6 +286,55 @@ fn snoop_tcp_packet(buf: &[u8]) -> Result<(), smoltcp::wire::Error> {
     Ok(())
 }
 
+/// Information of a TCP socket, see [`tcp_sockets`].
+#[derive(Debug, Clone)]
+pub struct TcpSocketInfo {
+    /// The local address, `None` if not bound.
+    pub local_addr: Option<SocketAddr>,
+    /// The remote address, `None` if not connected.
+    pub peer_addr: Option<SocketAddr>,
+    /// The TCP state, in the names used by Linux (e.g., `ESTABLISHED`).
+    pub state: &'static str,
+    /// The number of bytes in the receive buffer.
+    pub recv_queue: usize,
+    /// The number of bytes in the send buffer.
+    pub send_queue: usize,
+}
+
+/// Returns the information of all TCP sockets in the socket set.
+///
+/// Listening sockets are managed by the listen table rather than the socket
+/// set, so they are not included.
+pub fn tcp_sockets() -> Vec<TcpSocketInfo> {
+    use socket::tcp::State;
+    let Some(set) = SOCKET_SET.try_get() else {
+        return Vec::new();
+    };
+    let set = set.0.lock();
+    set.iter()
+        .filter_map(|(_, sock)| socket::tcp::Socket::downcast(sock))
+        .map(|sock| TcpSocketInfo {
+            local_addr: sock.local_endpoint(),
+            peer_addr: sock.remote_endpoint(),
+            state: match sock.state() {
+                State::Closed => "CLOSE",
+                State::Listen => "LISTEN",
+                State::SynSent => "SYN_SENT",
+                State::SynReceived => "SYN_RECV",
+                State::Established => "ESTABLISHED",
+                State::FinWait1 => "FIN_WAIT1",
+                State::FinWait2 => "FIN_WAIT2",
+                State::CloseWait => "CLOSE_WAIT",
+                State::Closing => "CLOSING",
+                State::LastAck => "LAST_ACK",
+                State::TimeWait => "TIME_WAIT",
+            },
+            recv_queue: sock.recv_queue(),
+            send_queue: sock.send_queue(),
+        })
+        .collect()
+}
+
 pub(crate) fn init(mut net_dev: AxNetDevice) {
     let pool = NetBufferPool::new(NET_BUF_POOL_SIZE, NET_BUF_LEN).unwrap();
     NET_BUF_POOL.init_by(pool);
@@ -0,0 +1,96 @@
+//! A minimal SNTP (RFC 4330) client, used to correct the wall clock.
+
+use axerrno::{ax_err, AxError, AxResult};
+use axhal::time::{current_time, Duration, NANOS_PER_SEC};
+
+use crate::{SocketAddr, UdpSocket};
+
+/// The well-known UDP port of NTP servers.
+pub const NTP_PORT: u16 = 123;
+
+/// How long to wait for the reply of the server.
+const SNTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Seconds from the NTP epoch (1900-01-01) to the UNIX epoch (1970-01-01).
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+const NTP_PACKET_LEN: usize = 48;
+const NTP_VERSION: u8 = 4;
+const NTP_MODE_CLIENT: u8 = 3;
+const NTP_MODE_SERVER: u8 = 4;
+
+/// Converts a 64-bit NTP timestamp (32.32 fixed point seconds since 1900) at
+/// `buf[offset..]` to the duration since the UNIX epoch.
+fn read_timestamp(buf: &[u8], offset: usize) -> AxResult<Duration> {
+    let secs = u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap()) as u64;
+    let frac = u32::from_be_bytes(buf[offset + 4..offset + 8].try_into().unwrap()) as u64;
+    let Some(secs) = secs.checked_sub(NTP_UNIX_OFFSET_SECS) else {
+        return ax_err!(InvalidData, "SNTP: timestamp before the UNIX epoch");
+    };
+    let nanos = (frac * NANOS_PER_SEC) >> 32;
+    Ok(Duration::new(secs, nanos as u32))
+}
+
+/// Queries the current time from the given NTP server.
+///
+/// Returns the time since the UNIX epoch, already compensated by half of the
+/// round-trip delay. Returns [`Err(TimedOut)`](AxError::TimedOut) if the
+/// server does not reply within 3 seconds.
+pub fn sntp_query(server: SocketAddr) -> AxResult<Duration> {
+    let mut socket = UdpSocket::new();
+    socket.connect(server)?;
+    socket.set_nonblocking(true);
+
+    let mut req = [0u8; NTP_PACKET_LEN];
+    // LI = 0 (no warning), VN = 4, Mode = 3 (client)
+    req[0] = (NTP_VERSION << 3) | NTP_MODE_CLIENT;
+    let t1 = current_time();
+    socket.send(&req)?;
+
+    let deadline = t1 + SNTP_TIMEOUT;
+    let mut resp = [0u8; NTP_PACKET_LEN];
+    loop {
+        match socket.recv(&mut resp) {
+            Ok(len) if len >= NTP_PACKET_LEN => break,
+            Ok(_) => return ax_err!(InvalidData, "SNTP: reply too short"),
+            Err(AxError::WouldBlock) => {
+                if current_time() >= deadline {
+                    return ax_err!(TimedOut, "SNTP: no reply from server");
+                }
+                axtask::yield_now();
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let t4 = current_time();
+
+    if resp[0] & 0x7 != NTP_MODE_SERVER {
+        return ax_err!(InvalidData, "SNTP: not a server reply");
+    }
+    if resp[1] == 0 {
+        // stratum 0 is a "kiss-o'-death" message
+        return ax_err!(ConnectionRefused, "SNTP: kiss-o'-death from server");
+    }
+    let server_recv = read_timestamp(&resp, 32)?;
+    let server_xmit = read_timestamp(&resp, 40)?;
+
+    // round-trip delay, excluding the processing time of the server
+    let delay = (t4 - t1).saturating_sub(server_xmit.saturating_sub(server_recv));
+    let now = server_xmit + delay / 2;
+    debug!(
+        "SNTP: server {} time {:?}, round-trip delay {:?}",
+        server, now, delay
+    );
+    Ok(now + (current_time() - t4))
+}
+
+/// Queries the current time from the given NTP server, and sets the wall
+/// clock to it.
+///
+/// Returns the new wall time since the UNIX epoch.
+pub fn sntp_sync(server: SocketAddr) -> AxResult<Duration> {
+    let now = sntp_query(server)?;
+    axhal::time::set_wall_time(now);
+    info!("Wall clock synchronized with NTP server {}", server);
+    Ok(now)
+}
@@ -0,0 +1,266 @@
+//! A minimal parser of ELF64 executables, which supports static and static
+//! position-independent (PIE) executables of the current architecture.
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use axerrno::{ax_err, AxResult};
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+
+/// Executable file.
+pub const ET_EXEC: u16 = 2;
+/// Shared object file, or position-independent executable.
+pub const ET_DYN: u16 = 3;
+
+/// Loadable segment.
+pub const PT_LOAD: u32 = 1;
+/// Dynamic linking information.
+pub const PT_DYNAMIC: u32 = 2;
+/// Program interpreter (dynamic linker).
+pub const PT_INTERP: u32 = 3;
+/// The program header table itself.
+pub const PT_PHDR: u32 = 6;
+
+/// Executable segment.
+pub const PF_X: u32 = 1;
+/// Writable segment.
+pub const PF_W: u32 = 2;
+/// Readable segment.
+pub const PF_R: u32 = 4;
+
+const DT_NULL: i64 = 0;
+const DT_RELA: i64 = 7;
+const DT_RELASZ: i64 = 8;
+const DT_RELAENT: i64 = 9;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        const EM_CURRENT: u16 = 62; // EM_X86_64
+        const R_RELATIVE: u32 = 8; // R_X86_64_RELATIVE
+    } else if #[cfg(target_arch = "riscv64")] {
+        const EM_CURRENT: u16 = 243; // EM_RISCV
+        const R_RELATIVE: u32 = 3; // R_RISCV_RELATIVE
+    } else if #[cfg(target_arch = "aarch64")] {
+        const EM_CURRENT: u16 = 183; // EM_AARCH64
+        const R_RELATIVE: u32 = 1027; // R_AARCH64_RELATIVE
+    } else {
+        const EM_CURRENT: u16 = 0;
+        const R_RELATIVE: u32 = 0;
+    }
+}
+
+/// ELF64 file header.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Elf64Ehdr {
+    e_ident: [u8; 16],
+    e_type: u16,
+    e_machine: u16,
+    e_version: u32,
+    e_entry: u64,
+    e_phoff: u64,
+    e_shoff: u64,
+    e_flags: u32,
+    e_ehsize: u16,
+    e_phentsize: u16,
+    e_phnum: u16,
+    e_shentsize: u16,
+    e_shnum: u16,
+    e_shstrndx: u16,
+}
+
+/// ELF64 program header.
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Elf64Phdr {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+/// ELF64 dynamic entry.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Elf64Dyn {
+    d_tag: i64,
+    d_val: u64,
+}
+
+/// ELF64 relocation entry with addend.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Elf64Rela {
+    r_offset: u64,
+    r_info: u64,
+    r_addend: i64,
+}
+
+/// Reads a plain structure at `offset` of `data`, which may be unaligned.
+fn read_struct<T: Copy>(data: &[u8], offset: usize) -> AxResult<T> {
+    match offset.checked_add(size_of::<T>()) {
+        Some(end) if end <= data.len() => {
+            Ok(unsafe { core::ptr::read_unaligned(data[offset..].as_ptr().cast()) })
+        }
+        _ => ax_err!(InvalidData, "ELF file truncated"),
+    }
+}
+
+/// A parsed ELF64 executable.
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: Elf64Ehdr,
+    phdrs: Vec<Elf64Phdr>,
+}
+
+impl<'a> ElfFile<'a> {
+    /// Parses the ELF file in `data`, and checks whether it can run on the
+    /// current architecture.
+    pub fn parse(data: &'a [u8]) -> AxResult<Self> {
+        let header: Elf64Ehdr = read_struct(data, 0)?;
+        let ident = &header.e_ident;
+        if ident[..4] != ELF_MAGIC {
+            return ax_err!(InvalidData, "not an ELF file");
+        }
+        if ident[4] != ELFCLASS64 || ident[5] != ELFDATA2LSB {
+            return ax_err!(Unsupported, "not a little-endian ELF64 file");
+        }
+        if header.e_machine != EM_CURRENT {
+            return ax_err!(Unsupported, "ELF file for another architecture");
+        }
+        if header.e_type != ET_EXEC && header.e_type != ET_DYN {
+            return ax_err!(Unsupported, "not an executable ELF file");
+        }
+        if header.e_phentsize as usize != size_of::<Elf64Phdr>() {
+            return ax_err!(InvalidData, "invalid program header size");
+        }
+        let phdrs = (0..header.e_phnum as usize)
+            .map(|i| {
+                let offset = (header.e_phoff as usize).saturating_add(i * size_of::<Elf64Phdr>());
+                read_struct(data, offset)
+            })
+            .collect::<AxResult<Vec<Elf64Phdr>>>()?;
+        for ph in &phdrs {
+            let in_file = ph
+                .p_offset
+                .checked_add(ph.p_filesz)
+                .is_some_and(|end| end <= data.len() as u64);
+            match ph.p_type {
+                PT_LOAD
+                    if !in_file
+                        || ph.p_filesz > ph.p_memsz
+                        || ph.p_vaddr.checked_add(ph.p_memsz).is_none() =>
+                {
+                    return ax_err!(InvalidData, "invalid loadable segment");
+                }
+                PT_DYNAMIC if !in_file => return ax_err!(InvalidData, "invalid dynamic segment"),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            data,
+            header,
+            phdrs,
+        })
+    }
+
+    /// Whether it is a position-independent executable, which can be loaded
+    /// at any address.
+    pub const fn is_pie(&self) -> bool {
+        self.header.e_type == ET_DYN
+    }
+
+    /// Returns the entry point (before relocated).
+    pub const fn entry(&self) -> usize {
+        self.header.e_entry as usize
+    }
+
+    /// Returns all program headers.
+    pub fn program_headers(&self) -> &[Elf64Phdr] {
+        &self.phdrs
+    }
+
+    /// Returns the address of the program header table in memory (before
+    /// relocated), if it is covered by a loadable segment.
+    pub fn phdr_vaddr(&self) -> Option<usize> {
+        if let Some(ph) = self.phdrs.iter().find(|ph| ph.p_type == PT_PHDR) {
+            return Some(ph.p_vaddr as usize);
+        }
+        self.offset_to_vaddr(self.header.e_phoff)
+    }
+
+    /// Returns the contents of the segment in the file, which must be a
+    /// loadable or dynamic segment (checked by [`parse`](Self::parse)).
+    pub fn segment_data(&self, ph: &Elf64Phdr) -> &'a [u8] {
+        &self.data[ph.p_offset as usize..(ph.p_offset + ph.p_filesz) as usize]
+    }
+
+    /// Returns the relative relocations, as `(offset, addend)` pairs.
+    ///
+    /// The value `base + addend` should be written to `base + offset`, where
+    /// `base` is the load bias. Other types of relocations need a dynamic
+    /// linker, which is not supported.
+    pub fn relative_relocations(&self) -> AxResult<Vec<(usize, isize)>> {
+        let Some(dynamic) = self.phdrs.iter().find(|ph| ph.p_type == PT_DYNAMIC) else {
+            return Ok(Vec::new());
+        };
+        let (mut rela, mut relasz, mut relaent) = (None, 0, size_of::<Elf64Rela>());
+        let dynamic = self.segment_data(dynamic);
+        for i in 0..dynamic.len() / size_of::<Elf64Dyn>() {
+            let dyn_entry: Elf64Dyn = read_struct(dynamic, i * size_of::<Elf64Dyn>())?;
+            match dyn_entry.d_tag {
+                DT_NULL => break,
+                DT_RELA => rela = Some(dyn_entry.d_val),
+                DT_RELASZ => relasz = dyn_entry.d_val as usize,
+                DT_RELAENT => relaent = dyn_entry.d_val as usize,
+                _ => {}
+            }
+        }
+        let Some(rela) = rela else {
+            return Ok(Vec::new());
+        };
+        if relaent != size_of::<Elf64Rela>() {
+            return ax_err!(InvalidData, "invalid relocation entry size");
+        }
+        let Some(rela_offset) = self.vaddr_to_offset(rela) else {
+            return ax_err!(InvalidData, "relocation table not in file");
+        };
+        let mut relocs = Vec::new();
+        for i in 0..relasz / relaent {
+            let entry: Elf64Rela = read_struct(self.data, rela_offset.saturating_add(i * relaent))?;
+            match (entry.r_info & 0xffff_ffff) as u32 {
+                0 => {} // R_*_NONE
+                R_RELATIVE => relocs.push((entry.r_offset as usize, entry.r_addend as isize)),
+                ty => {
+                    warn!("unsupported relocation type: {}", ty);
+                    return ax_err!(Unsupported, "dynamic linking is not supported");
+                }
+            }
+        }
+        Ok(relocs)
+    }
+
+    fn vaddr_to_offset(&self, vaddr: u64) -> Option<usize> {
+        self.phdrs
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| ph.p_vaddr <= vaddr && vaddr < ph.p_vaddr + ph.p_filesz)
+            .map(|ph| (vaddr - ph.p_vaddr + ph.p_offset) as usize)
+    }
+
+    fn offset_to_vaddr(&self, offset: u64) -> Option<usize> {
+        self.phdrs
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| ph.p_offset <= offset && offset < ph.p_offset + ph.p_filesz)
+            .map(|ph| (offset - ph.p_offset + ph.p_vaddr) as usize)
+    }
+}
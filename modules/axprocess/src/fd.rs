@@ -0,0 +1,161 @@
+//! File descriptor tables of processes.
+
+use alloc::sync::Arc;
+use core::any::Any;
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axio::SeekFrom;
+use flatten_objects::FlattenObjects;
+
+/// The maximum number of files that can be opened by a process.
+pub const FILE_LIMIT: usize = 1024;
+
+/// The file descriptor table of a process.
+pub(crate) type FdTable = FlattenObjects<Arc<dyn FileLike>, FILE_LIMIT>;
+
+/// The file type bits of `st_mode` for character devices.
+const S_IFCHR: u32 = 0o020000;
+
+/// File status, which is converted to the `struct stat` of the architecture
+/// when returned to user space.
+#[derive(Debug, Default)]
+pub struct Kstat {
+    pub ino: u64,
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub blksize: u32,
+    pub blocks: u64,
+    pub atime: Duration,
+    pub mtime: Duration,
+    pub ctime: Duration,
+}
+
+/// An object that can be referred to by a file descriptor.
+pub trait FileLike: Send + Sync {
+    /// Reads data into `buf`, and returns the number of bytes read.
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize>;
+    /// Writes data from `buf`, and returns the number of bytes written.
+    fn write(&self, buf: &[u8]) -> LinuxResult<usize>;
+    /// Returns the status of the file.
+    fn stat(&self) -> LinuxResult<Kstat>;
+    /// Moves the cursor, and returns the new position.
+    fn seek(&self, _pos: SeekFrom) -> LinuxResult<u64> {
+        Err(LinuxError::ESPIPE)
+    }
+    /// Converts to [`Any`], for downcasting to the concrete type.
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+/// The console input, which blocks until some bytes are available.
+pub(crate) struct Stdin;
+
+/// The console output.
+pub(crate) struct Stdout;
+
+impl FileLike for Stdin {
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let mut read_len = 0;
+            while read_len < buf.len() {
+                match axhal::console::getchar() {
+                    Some(c) => {
+                        buf[read_len] = if c == b'\r' { b'\n' } else { c };
+                        read_len += 1;
+                    }
+                    None => break,
+                }
+            }
+            if read_len > 0 {
+                return Ok(read_len);
+            }
+            axtask::yield_now();
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> LinuxResult<usize> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn stat(&self) -> LinuxResult<Kstat> {
+        Ok(Kstat {
+            ino: 1,
+            mode: S_IFCHR | 0o440, // r--r-----
+            nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+impl FileLike for Stdout {
+    fn read(&self, _buf: &mut [u8]) -> LinuxResult<usize> {
+        Err(LinuxError::EBADF)
+    }
+
+    fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn stat(&self) -> LinuxResult<Kstat> {
+        Ok(Kstat {
+            ino: 1,
+            mode: S_IFCHR | 0o220, // -w--w----
+            nlink: 1,
+            ..Default::default()
+        })
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// Creates a file descriptor table with the standard input, output and
+/// error streams opened.
+pub(crate) fn new_fd_table() -> FdTable {
+    let mut fd_table = FdTable::new();
+    fd_table.add_at(0, Arc::new(Stdin) as _).unwrap(); // stdin
+    fd_table.add_at(1, Arc::new(Stdout) as _).unwrap(); // stdout
+    fd_table.add_at(2, Arc::new(Stdout) as _).unwrap(); // stderr
+    fd_table
+}
+
+/// Returns the file referred to by `fd` in the current process.
+pub(crate) fn get_file_like(fd: i32) -> LinuxResult<Arc<dyn FileLike>> {
+    let process = crate::current().ok_or(LinuxError::EBADF)?;
+    let fd_table = process.fd_table.lock();
+    fd_table.get(fd as usize).cloned().ok_or(LinuxError::EBADF)
+}
+
+/// Adds a file to the file descriptor table of the current process, and
+/// returns the lowest available file descriptor.
+#[cfg(any(feature = "fs", feature = "net"))]
+pub(crate) fn add_file_like(f: Arc<dyn FileLike>) -> LinuxResult<i32> {
+    let process = crate::current().ok_or(LinuxError::EBADF)?;
+    let fd = process.fd_table.lock().add(f).ok_or(LinuxError::EMFILE)?;
+    Ok(fd as i32)
+}
+
+/// Removes `fd` from the file descriptor table of the current process.
+pub(crate) fn close_file_like(fd: i32) -> LinuxResult {
+    let process = crate::current().ok_or(LinuxError::EBADF)?;
+    let f = process
+        .fd_table
+        .lock()
+        .remove(fd as usize)
+        .ok_or(LinuxError::EBADF)?;
+    // The file may be closed here, which must not be done with the lock held.
+    drop(f);
+    Ok(())
+}
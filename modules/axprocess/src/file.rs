@@ -0,0 +1,99 @@
+//! Regular files opened by user processes.
+
+use alloc::sync::Arc;
+use core::any::Any;
+
+use axerrno::{AxResult, LinuxResult};
+use axfs::fops::{self, OpenOptions};
+use axio::SeekFrom;
+use axsync::Mutex;
+
+use crate::fd::{FileLike, Kstat};
+
+/// An opened file of the filesystem.
+pub(crate) struct File(Mutex<fops::File>);
+
+impl File {
+    /// Opens the file at `path` with the given options.
+    pub fn open(path: &str, opts: &OpenOptions) -> LinuxResult<Self> {
+        Ok(Self(Mutex::new(fops::File::open(path, opts)?)))
+    }
+}
+
+impl axmm::MmapFile for File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        let mut file = self.0.lock();
+        let pos = file.seek(SeekFrom::Current(0))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut read_len = 0;
+        let res = loop {
+            match file.read(&mut buf[read_len..]) {
+                Ok(0) => break Ok(read_len),
+                Ok(n) => read_len += n,
+                Err(e) => break Err(e),
+            }
+        };
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult {
+        let mut file = self.0.lock();
+        let pos = file.seek(SeekFrom::Current(0))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut written = 0;
+        let res = loop {
+            if written == buf.len() {
+                break Ok(());
+            }
+            match file.write(&buf[written..]) {
+                Ok(0) => break axerrno::ax_err!(WriteZero),
+                Ok(n) => written += n,
+                Err(e) => break Err(e),
+            }
+        };
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    fn size(&self) -> AxResult<u64> {
+        Ok(self.0.lock().get_attr()?.size())
+    }
+}
+
+impl FileLike for File {
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        Ok(self.0.lock().read(buf)?)
+    }
+
+    fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
+        Ok(self.0.lock().write(buf)?)
+    }
+
+    fn stat(&self) -> LinuxResult<Kstat> {
+        let attr = self.0.lock().get_attr()?;
+        let ty = attr.file_type() as u32;
+        let perm = attr.perm().bits() as u32;
+        Ok(Kstat {
+            ino: 1,
+            mode: (ty << 12) | perm,
+            nlink: 1,
+            uid: 1000,
+            gid: 1000,
+            size: attr.size(),
+            blksize: 512,
+            blocks: attr.blocks(),
+            atime: attr.atime(),
+            mtime: attr.mtime(),
+            ctime: attr.ctime(),
+        })
+    }
+
+    fn seek(&self, pos: SeekFrom) -> LinuxResult<u64> {
+        Ok(self.0.lock().seek(pos)?)
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
@@ -0,0 +1,93 @@
+//! Fast user-space mutexes (futexes).
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::WaitQueue;
+use spinlock::SpinNoIrq;
+
+use crate::syscall::read_user;
+
+/// Tasks waiting on a futex word.
+struct Futex {
+    wq: WaitQueue,
+    /// Increased on every wake-up, so that waiters can tell whether they
+    /// have been woken without reading the user memory.
+    seq: AtomicU64,
+}
+
+/// Futexes of a process, indexed by the user address of their words.
+pub(crate) struct FutexTable(SpinNoIrq<BTreeMap<usize, Arc<Futex>>>);
+
+impl FutexTable {
+    pub const fn new() -> Self {
+        Self(SpinNoIrq::new(BTreeMap::new()))
+    }
+
+    /// Blocks the current task if the futex word at `uaddr` still equals to
+    /// `val`, until it is woken up by [`wake`](Self::wake), or `timeout` has
+    /// elapsed.
+    pub fn wait(&self, uaddr: usize, val: u32, timeout: Option<Duration>) -> LinuxResult {
+        let futex = self
+            .0
+            .lock()
+            .entry(uaddr)
+            .or_insert_with(|| {
+                Arc::new(Futex {
+                    wq: WaitQueue::new(),
+                    seq: AtomicU64::new(0),
+                })
+            })
+            .clone();
+        // Any wake-up after loading `seq` will change it, so it cannot be
+        // missed after the futex word is checked.
+        let seq = futex.seq.load(Ordering::Acquire);
+        let res = match read_user::<u32>(uaddr) {
+            Ok(curr) if curr != val => Err(LinuxError::EAGAIN),
+            Ok(_) => {
+                let woken = || futex.seq.load(Ordering::Acquire) != seq;
+                match timeout {
+                    #[cfg(feature = "irq")]
+                    Some(dur) if futex.wq.wait_timeout_until(dur, woken) => {
+                        Err(LinuxError::ETIMEDOUT)
+                    }
+                    _ => {
+                        futex.wq.wait_until(woken);
+                        Ok(())
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        let mut futexes = self.0.lock();
+        // remove the futex if no one else is using it
+        if Arc::strong_count(&futex) == 2 {
+            futexes.remove(&uaddr);
+        }
+        res
+    }
+
+    /// Wakes up at most `count` tasks waiting on the futex word at `uaddr`.
+    /// Returns the number of tasks woken up.
+    pub fn wake(&self, uaddr: usize, count: usize) -> usize {
+        let Some(futex) = self.0.lock().get(&uaddr).cloned() else {
+            return 0;
+        };
+        futex.seq.fetch_add(1, Ordering::Release);
+        (0..count)
+            .take_while(|_| futex.wq.notify_one(false))
+            .count()
+    }
+
+    /// Wakes up all tasks waiting on any futex.
+    pub fn wake_all(&self) {
+        let futexes: Vec<_> = self.0.lock().values().cloned().collect();
+        for futex in futexes {
+            futex.seq.fetch_add(1, Ordering::Release);
+            futex.wq.notify_all(false);
+        }
+    }
+}
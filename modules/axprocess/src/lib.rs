@@ -0,0 +1,263 @@
+//! [ArceOS](https://github.com/rcore-os/arceos) user process management
+//! module.
+//!
+//! A process runs a statically linked ELF executable in user mode, with its
+//! own address space. The kernel mappings are shared by all address spaces,
+//! so the page table is only switched when another process is scheduled.
+//!
+//! A process has one or more threads, each of which is a task of [`axtask`].
+//! The threads share the address space, the file descriptor table and the
+//! futexes of the process. Page faults in user space are resolved by the
+//! address space of the current process, and the process is killed if that
+//! fails.
+//!
+//! User programs request services through a subset of the Linux system
+//! calls (see [`handle_syscall`]), so that statically linked Linux programs
+//! (e.g., built with musl libc) can run without modification.
+//!
+//! # Cargo Features
+//!
+//! - `fs`: Enable file system support, for opening and mapping files.
+//! - `net`: Enable networking support, for sockets.
+//! - `irq`: Enable timeouts of futex waits.
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+mod elf;
+mod fd;
+#[cfg(feature = "fs")]
+mod file;
+mod futex;
+mod loader;
+#[cfg(feature = "net")]
+mod socket;
+mod syscall;
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axerrno::AxResult;
+use axhal::arch::UspaceContext;
+use axhal::mem::VirtAddr;
+use axhal::paging::MappingFlags;
+use axmm::AddrSpace;
+use axtask::AxTaskRef;
+use lazy_init::LazyInit;
+use memory_addr::align_up_4k;
+use spinlock::SpinNoIrq;
+
+use self::fd::{FdTable, FILE_LIMIT};
+use self::futex::FutexTable;
+
+pub use self::syscall::handle_syscall;
+
+/// User threads indexed by the ID of their tasks.
+static THREADS: SpinNoIrq<BTreeMap<u64, Arc<Thread>>> = SpinNoIrq::new(BTreeMap::new());
+
+/// A user process.
+pub struct Process {
+    name: String,
+    aspace: SpinNoIrq<AddrSpace>,
+    main_task: LazyInit<AxTaskRef>,
+    fd_table: SpinNoIrq<FdTable>,
+    /// The start address of the heap, which is right after the executable.
+    heap_bottom: usize,
+    /// The current end of the heap, which is adjusted by `brk`.
+    heap_top: AtomicUsize,
+    futexes: FutexTable,
+    /// The exit code of the process, set once any thread calls
+    /// [`exit_current`] (i.e., `exit_group`).
+    group_exit_code: SpinNoIrq<Option<i32>>,
+}
+
+/// A thread of a user process.
+struct Thread {
+    process: Arc<Process>,
+    /// The user address of the thread ID, which is cleared and woken up as a
+    /// futex when the thread exits (see `set_tid_address`).
+    clear_child_tid: AtomicUsize,
+}
+
+impl Process {
+    /// Returns the name of the process.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the process ID, which is the ID of its main task.
+    pub fn pid(&self) -> u64 {
+        self.main_task.id().as_u64()
+    }
+
+    /// Returns the address space of the process.
+    pub fn aspace(&self) -> &SpinNoIrq<AddrSpace> {
+        &self.aspace
+    }
+
+    /// Waits for the main thread of the process to exit, and returns the
+    /// exit code.
+    pub fn wait(&self) -> i32 {
+        self.main_task.join().unwrap_or(0)
+    }
+
+    /// Returns the exit code if the process is exiting.
+    fn group_exit_code(&self) -> Option<i32> {
+        *self.group_exit_code.lock()
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // close all files
+        let fd_table = self.fd_table.get_mut();
+        for fd in 0..FILE_LIMIT {
+            fd_table.remove(fd);
+        }
+    }
+}
+
+/// Spawns a new thread of `process`, which starts running in user mode with
+/// the context `ctx`.
+fn spawn_thread(process: &Arc<Process>, ctx: UspaceContext, clear_child_tid: usize) -> AxTaskRef {
+    let thread = Arc::new(Thread {
+        process: process.clone(),
+        clear_child_tid: AtomicUsize::new(clear_child_tid),
+    });
+    let page_table_root = process.aspace.lock().page_table_root();
+    axtask::spawn_user(
+        move || {
+            let curr = axtask::current();
+            let exiting = thread.process.group_exit_code().is_some();
+            THREADS.lock().insert(curr.id().as_u64(), thread);
+            if exiting {
+                exit_thread(0);
+            }
+            info!("enter user space: {} @ {:#x}", curr.id_name(), ctx.get_ip());
+            unsafe { ctx.enter_uspace(curr.kernel_stack_top().unwrap()) }
+        },
+        process.name.clone(),
+        axconfig::TASK_STACK_SIZE,
+        page_table_root,
+    )
+}
+
+/// Creates a new process that runs the ELF executable in `elf_data`, with
+/// the given arguments and environment variables.
+///
+/// The process starts running once it is scheduled.
+pub fn spawn(name: &str, elf_data: &[u8], args: &[&str], envs: &[&str]) -> AxResult<Arc<Process>> {
+    let mut aspace =
+        axmm::new_user_aspace(VirtAddr::from(axconfig::USPACE_BASE), axconfig::USPACE_SIZE)?;
+    let image = loader::load_elf(&mut aspace, elf_data)?;
+    let ustack_top = aspace.end();
+    let ustack_sp = loader::init_user_stack(
+        &mut aspace,
+        ustack_top,
+        axconfig::USER_STACK_SIZE,
+        args,
+        envs,
+        &image,
+    )?;
+    let heap_bottom = align_up_4k(image.end);
+    let process = Arc::new(Process {
+        name: name.into(),
+        aspace: SpinNoIrq::new(aspace),
+        main_task: LazyInit::new(),
+        fd_table: SpinNoIrq::new(fd::new_fd_table()),
+        heap_bottom,
+        heap_top: AtomicUsize::new(heap_bottom),
+        futexes: FutexTable::new(),
+        group_exit_code: SpinNoIrq::new(None),
+    });
+
+    let ctx = UspaceContext::new(image.entry, ustack_sp, 0);
+    let task = spawn_thread(&process, ctx, 0);
+    process.main_task.init_by(task);
+    Ok(process)
+}
+
+/// Returns the thread of the current task, or `None` if it is a kernel task.
+fn current_thread() -> Option<Arc<Thread>> {
+    let curr = axtask::current_may_uninit()?;
+    THREADS.lock().get(&curr.id().as_u64()).cloned()
+}
+
+/// Returns the process of the current task, or `None` if it is a kernel
+/// task.
+pub fn current() -> Option<Arc<Process>> {
+    current_thread().map(|thread| thread.process.clone())
+}
+
+/// Exits the current thread with the exit code, or with that of the process
+/// if the whole process is exiting.
+fn exit_thread(exit_code: i32) -> ! {
+    let curr = axtask::current();
+    let mut exit_code = exit_code;
+    if let Some(thread) = current_thread() {
+        let process = &thread.process;
+        exit_code = process.group_exit_code().unwrap_or(exit_code);
+        let clear_child_tid = thread.clear_child_tid.load(Ordering::Relaxed);
+        if clear_child_tid != 0 && syscall::write_user(clear_child_tid, 0u32).is_ok() {
+            process.futexes.wake(clear_child_tid, 1);
+        }
+    }
+    debug!("thread {} exited with code {}", curr.id_name(), exit_code);
+    curr.set_page_table_root(None);
+    let thread = THREADS.lock().remove(&curr.id().as_u64());
+    // The address space may be freed here, which must not be done with the
+    // lock held.
+    drop(thread);
+    axtask::exit(exit_code)
+}
+
+/// Exits the current process with the exit code.
+///
+/// Other threads of the process exit the next time they enter the kernel.
+pub fn exit_current(exit_code: i32) -> ! {
+    if let Some(process) = current() {
+        process.group_exit_code.lock().get_or_insert(exit_code);
+        // wake up threads blocked on futexes, so that they can exit
+        process.futexes.wake_all();
+    }
+    exit_thread(exit_code)
+}
+
+/// Handles a page fault at `vaddr` in the address space of the current
+/// process.
+///
+/// Returns `true` if it has been handled. If the fault occurred in user
+/// mode and cannot be handled, the process is killed.
+pub fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+    let Some(process) = current() else {
+        return false;
+    };
+    if process.aspace.lock().handle_page_fault(vaddr, access_flags) {
+        return true;
+    }
+    if is_user {
+        warn!(
+            "process {} killed by a page fault @ {:#x}, access_flags={:?}",
+            process.name, vaddr, access_flags
+        );
+        drop(process);
+        exit_current(-1);
+    }
+    false
+}
+
+/// Handles an exception in user mode other than page faults and system
+/// calls, by killing the current process.
+///
+/// Returns `false` if there is no current process.
+pub fn handle_user_exception() -> bool {
+    let Some(process) = current() else {
+        return false;
+    };
+    warn!("process {} killed by an exception", process.name);
+    drop(process);
+    exit_current(-1)
+}
@@ -0,0 +1,223 @@
+//! Loading ELF executables and setting up the initial user stack.
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use axerrno::{ax_err, AxResult};
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::MappingFlags;
+use axmm::{AddrSpace, Backend};
+use memory_addr::{align_down_4k, align_up_4k};
+
+use crate::elf::{Elf64Phdr, ElfFile, PF_R, PF_W, PF_X, PT_INTERP, PT_LOAD};
+
+/// The preferred load address of position-independent executables.
+const PIE_BASE_HINT: usize = 0x1000_0000;
+
+const AT_NULL: usize = 0;
+const AT_PHDR: usize = 3;
+const AT_PHENT: usize = 4;
+const AT_PHNUM: usize = 5;
+const AT_PAGESZ: usize = 6;
+const AT_BASE: usize = 7;
+const AT_ENTRY: usize = 9;
+const AT_RANDOM: usize = 25;
+
+/// Information of a loaded executable, which is passed to the program via
+/// the auxiliary vector.
+pub(crate) struct LoadedImage {
+    pub entry: usize,
+    /// The end address of the loaded segments, where the heap starts.
+    pub end: usize,
+    pub phdr: usize,
+    pub phent: usize,
+    pub phnum: usize,
+}
+
+fn segment_flags(ph: &Elf64Phdr) -> MappingFlags {
+    let mut flags = MappingFlags::USER;
+    if ph.p_flags & PF_R != 0 {
+        flags |= MappingFlags::READ;
+    }
+    if ph.p_flags & PF_W != 0 {
+        flags |= MappingFlags::WRITE;
+    }
+    if ph.p_flags & PF_X != 0 {
+        flags |= MappingFlags::EXECUTE;
+    }
+    flags
+}
+
+/// Loads the ELF executable in `data` into `aspace`.
+///
+/// Position-independent executables are placed at a free area, and their
+/// relative relocations are applied.
+pub(crate) fn load_elf(aspace: &mut AddrSpace, data: &[u8]) -> AxResult<LoadedImage> {
+    let elf = ElfFile::parse(data)?;
+    let phdrs = elf.program_headers();
+    if phdrs.iter().any(|ph| ph.p_type == PT_INTERP) {
+        return ax_err!(
+            Unsupported,
+            "dynamically linked executables are not supported"
+        );
+    }
+    let mut segments: Vec<&Elf64Phdr> = phdrs
+        .iter()
+        .filter(|ph| ph.p_type == PT_LOAD && ph.p_memsz > 0)
+        .collect();
+    segments.sort_by_key(|ph| ph.p_vaddr);
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return ax_err!(InvalidData, "no loadable segments");
+    };
+    let min_vaddr = align_down_4k(first.p_vaddr as usize);
+    let max_vaddr = align_up_4k((last.p_vaddr + last.p_memsz) as usize);
+
+    let bias = if elf.is_pie() {
+        let limit = aspace.base()..aspace.end();
+        match aspace.find_free_area(PIE_BASE_HINT.into(), max_vaddr - min_vaddr, limit) {
+            Some(base) => base.as_usize() - min_vaddr,
+            None => return ax_err!(NoMemory, "no space to load the executable"),
+        }
+    } else {
+        0
+    };
+
+    // The end and flags of the last mapped page, which may be shared with
+    // the next segment.
+    let mut prev: Option<(usize, MappingFlags)> = None;
+    for ph in segments {
+        let flags = segment_flags(ph);
+        let mut start = align_down_4k(bias + ph.p_vaddr as usize);
+        let end = align_up_4k(bias + (ph.p_vaddr + ph.p_memsz) as usize);
+        if let Some((prev_end, prev_flags)) = prev {
+            if start < prev_end {
+                if prev_end - start != PAGE_SIZE_4K {
+                    return ax_err!(InvalidData, "overlapping loadable segments");
+                }
+                aspace.protect(start.into(), PAGE_SIZE_4K, prev_flags | flags)?;
+                start = prev_end;
+            }
+        }
+        if start < end {
+            aspace.map(
+                start.into(),
+                end - start,
+                flags,
+                Backend::Alloc { populate: true },
+            )?;
+        }
+        aspace.write(
+            VirtAddr::from(bias + ph.p_vaddr as usize),
+            elf.segment_data(ph),
+        )?;
+        prev = Some((end, flags));
+    }
+
+    if elf.is_pie() {
+        for (offset, addend) in elf.relative_relocations()? {
+            let value = bias.wrapping_add_signed(addend);
+            aspace.write((bias + offset).into(), &value.to_ne_bytes())?;
+        }
+    }
+
+    Ok(LoadedImage {
+        entry: bias + elf.entry(),
+        end: bias + max_vaddr,
+        phdr: elf.phdr_vaddr().map_or(0, |vaddr| bias + vaddr),
+        phent: size_of::<Elf64Phdr>(),
+        phnum: phdrs.len(),
+    })
+}
+
+/// Builds the initial contents of the user stack from its top.
+struct StackBuilder {
+    top: usize,
+    data: Vec<u8>,
+}
+
+impl StackBuilder {
+    fn new(top: usize) -> Self {
+        Self {
+            top,
+            data: Vec::new(),
+        }
+    }
+
+    /// Returns the current stack pointer.
+    fn sp(&self) -> usize {
+        self.top - self.data.len()
+    }
+
+    /// Pushes `bytes` onto the stack, and returns their address.
+    fn push_bytes(&mut self, bytes: &[u8]) -> usize {
+        self.data.splice(0..0, bytes.iter().copied());
+        self.sp()
+    }
+
+    /// Pushes a NUL-terminated string, and returns its address.
+    fn push_str(&mut self, s: &str) -> usize {
+        self.push_bytes(&[0]);
+        self.push_bytes(s.as_bytes())
+    }
+}
+
+/// Maps the user stack below `ustack_top`, and fills in the arguments, the
+/// environment variables and the auxiliary vector as the System V ABI
+/// specifies.
+///
+/// Returns the initial user stack pointer.
+pub(crate) fn init_user_stack(
+    aspace: &mut AddrSpace,
+    ustack_top: VirtAddr,
+    ustack_size: usize,
+    args: &[&str],
+    envs: &[&str],
+    image: &LoadedImage,
+) -> AxResult<VirtAddr> {
+    aspace.map(
+        ustack_top - ustack_size,
+        ustack_size,
+        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+        Backend::Alloc { populate: false },
+    )?;
+
+    let mut stack = StackBuilder::new(ustack_top.as_usize());
+    let random = axhal::time::current_time_nanos().wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let random_ptr = stack.push_bytes(&[random.to_ne_bytes(), (!random).to_ne_bytes()].concat());
+    let env_ptrs: Vec<usize> = envs.iter().rev().map(|s| stack.push_str(s)).collect();
+    let arg_ptrs: Vec<usize> = args.iter().rev().map(|s| stack.push_str(s)).collect();
+
+    let auxv = [
+        (AT_PHDR, image.phdr),
+        (AT_PHENT, image.phent),
+        (AT_PHNUM, image.phnum),
+        (AT_PAGESZ, PAGE_SIZE_4K),
+        (AT_BASE, 0),
+        (AT_ENTRY, image.entry),
+        (AT_RANDOM, random_ptr),
+        (AT_NULL, 0),
+    ];
+    let mut words = Vec::new();
+    words.push(args.len());
+    words.extend(arg_ptrs.iter().rev());
+    words.push(0);
+    words.extend(env_ptrs.iter().rev());
+    words.push(0);
+    for (key, value) in auxv {
+        words.extend([key, value]);
+    }
+
+    // `argc` must be at a 16-byte aligned address.
+    let words_size = words.len() * size_of::<usize>();
+    let sp = (stack.sp() - words_size) & !0xf;
+    let padding = stack.sp() - sp - words_size;
+    stack.push_bytes(&[0; 16][..padding]);
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+    stack.push_bytes(&bytes);
+
+    if stack.data.len() > ustack_size {
+        return ax_err!(InvalidInput, "arguments too long");
+    }
+    aspace.write(sp.into(), &stack.data)?;
+    Ok(sp.into())
+}
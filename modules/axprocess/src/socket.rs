@@ -0,0 +1,101 @@
+//! Sockets opened by user processes.
+
+use alloc::sync::Arc;
+use core::any::Any;
+
+use axerrno::{LinuxError, LinuxResult};
+use axnet::{SocketAddr, TcpSocket, UdpSocket};
+use axsync::Mutex;
+
+use crate::fd::{FileLike, Kstat};
+
+/// The file type bits of `st_mode` for sockets.
+const S_IFSOCK: u32 = 0o140000;
+
+/// A TCP or UDP socket.
+pub(crate) enum Socket {
+    Udp(Mutex<UdpSocket>),
+    Tcp(Mutex<TcpSocket>),
+}
+
+impl Socket {
+    pub fn bind(&self, addr: SocketAddr) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr)?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr)?),
+        }
+    }
+
+    pub fn connect(&self, addr: SocketAddr) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr)?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr)?),
+        }
+    }
+
+    pub fn listen(&self) -> LinuxResult {
+        match self {
+            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().listen()?),
+        }
+    }
+
+    pub fn accept(&self) -> LinuxResult<TcpSocket> {
+        match self {
+            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().accept()?),
+        }
+    }
+
+    pub fn sendto(&self, buf: &[u8], addr: Option<SocketAddr>) -> LinuxResult<usize> {
+        match (self, addr) {
+            (Socket::Udp(udpsocket), Some(addr)) => Ok(udpsocket.lock().send_to(buf, addr)?),
+            (Socket::Udp(udpsocket), None) => Ok(udpsocket.lock().send(buf)?),
+            (Socket::Tcp(tcpsocket), None) => Ok(tcpsocket.lock().send(buf)?),
+            (Socket::Tcp(_), Some(_)) => Err(LinuxError::EISCONN),
+        }
+    }
+
+    pub fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SocketAddr>)> {
+        match self {
+            Socket::Udp(udpsocket) => {
+                let (len, addr) = udpsocket.lock().recv_from(buf)?;
+                Ok((len, Some(addr)))
+            }
+            Socket::Tcp(tcpsocket) => Ok((tcpsocket.lock().recv(buf)?, None)),
+        }
+    }
+
+    pub fn shutdown(&self) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().shutdown()?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().shutdown()?),
+        }
+    }
+}
+
+impl FileLike for Socket {
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        self.recvfrom(buf).map(|(len, _)| len)
+    }
+
+    fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
+        self.sendto(buf, None)
+    }
+
+    fn stat(&self) -> LinuxResult<Kstat> {
+        Ok(Kstat {
+            ino: 1,
+            mode: S_IFSOCK | 0o777, // rwxrwxrwx
+            nlink: 1,
+            uid: 1000,
+            gid: 1000,
+            blksize: 4096,
+            ..Default::default()
+        })
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
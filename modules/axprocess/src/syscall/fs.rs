@@ -0,0 +1,233 @@
+//! File-related system calls.
+
+use axerrno::{LinuxError, LinuxResult};
+use axio::SeekFrom;
+
+use super::{read_to_user, read_user, write_from_user, write_user};
+use crate::fd::{self, Kstat, Stdin, Stdout};
+
+/// The maximum number of buffers in `readv` and `writev`.
+const IOV_MAX: usize = 1024;
+
+const SEEK_SET: u32 = 0;
+const SEEK_CUR: u32 = 1;
+const SEEK_END: u32 = 2;
+
+/// Get the terminal window size.
+const TIOCGWINSZ: u32 = 0x5413;
+
+/// `struct iovec`
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct IoVec {
+    base: usize,
+    len: usize,
+}
+
+/// `struct winsize`
+#[repr(C)]
+struct WinSize {
+    row: u16,
+    col: u16,
+    xpixel: u16,
+    ypixel: u16,
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        /// `struct stat` of x86_64.
+        #[derive(Default)]
+        #[repr(C)]
+        struct Stat {
+            dev: u64,
+            ino: u64,
+            nlink: u64,
+            mode: u32,
+            uid: u32,
+            gid: u32,
+            _pad0: u32,
+            rdev: u64,
+            size: i64,
+            blksize: i64,
+            blocks: i64,
+            atime: [i64; 2],
+            mtime: [i64; 2],
+            ctime: [i64; 2],
+            _unused: [i64; 3],
+        }
+    } else {
+        /// `struct stat` of riscv64 and aarch64.
+        #[derive(Default)]
+        #[repr(C)]
+        struct Stat {
+            dev: u64,
+            ino: u64,
+            mode: u32,
+            nlink: u32,
+            uid: u32,
+            gid: u32,
+            rdev: u64,
+            _pad1: u64,
+            size: i64,
+            blksize: i32,
+            _pad2: i32,
+            blocks: i64,
+            atime: [i64; 2],
+            mtime: [i64; 2],
+            ctime: [i64; 2],
+            _unused: [u32; 2],
+        }
+    }
+}
+
+impl From<Kstat> for Stat {
+    fn from(st: Kstat) -> Self {
+        let timespec = |t: core::time::Duration| [t.as_secs() as i64, t.subsec_nanos() as i64];
+        Self {
+            ino: st.ino,
+            nlink: st.nlink as _,
+            mode: st.mode,
+            uid: st.uid,
+            gid: st.gid,
+            size: st.size as _,
+            blksize: st.blksize as _,
+            blocks: st.blocks as _,
+            atime: timespec(st.atime),
+            mtime: timespec(st.mtime),
+            ctime: timespec(st.ctime),
+            ..Default::default()
+        }
+    }
+}
+
+pub(super) fn sys_read(fd: i32, buf: usize, count: usize) -> LinuxResult<isize> {
+    let f = fd::get_file_like(fd)?;
+    Ok(read_to_user(buf, count, |buf| f.read(buf))? as isize)
+}
+
+pub(super) fn sys_write(fd: i32, buf: usize, count: usize) -> LinuxResult<isize> {
+    let f = fd::get_file_like(fd)?;
+    Ok(write_from_user(buf, count, |buf| f.write(buf))? as isize)
+}
+
+fn read_iovecs(iov: usize, iovcnt: i32) -> LinuxResult<impl Iterator<Item = LinuxResult<IoVec>>> {
+    if !(0..=IOV_MAX as i32).contains(&iovcnt) {
+        return Err(LinuxError::EINVAL);
+    }
+    let size = core::mem::size_of::<IoVec>();
+    Ok((0..iovcnt as usize).map(move |i| read_user::<IoVec>(iov + i * size)))
+}
+
+pub(super) fn sys_readv(fd: i32, iov: usize, iovcnt: i32) -> LinuxResult<isize> {
+    let f = fd::get_file_like(fd)?;
+    let mut read_len = 0;
+    for iov in read_iovecs(iov, iovcnt)? {
+        let iov = iov?;
+        let len = read_to_user(iov.base, iov.len, |buf| f.read(buf))?;
+        read_len += len;
+        if len < iov.len {
+            break;
+        }
+    }
+    Ok(read_len as isize)
+}
+
+pub(super) fn sys_writev(fd: i32, iov: usize, iovcnt: i32) -> LinuxResult<isize> {
+    let f = fd::get_file_like(fd)?;
+    let mut write_len = 0;
+    for iov in read_iovecs(iov, iovcnt)? {
+        let iov = iov?;
+        let len = write_from_user(iov.base, iov.len, |buf| f.write(buf))?;
+        write_len += len;
+        if len < iov.len {
+            break;
+        }
+    }
+    Ok(write_len as isize)
+}
+
+/// Opens the file at `path`. Paths relative to a directory file descriptor
+/// other than `AT_FDCWD` are not supported, as directories cannot be opened.
+#[cfg(feature = "fs")]
+pub(super) fn sys_openat(dirfd: i32, path: usize, flags: u32) -> LinuxResult<isize> {
+    use alloc::sync::Arc;
+    use axfs::fops::OpenOptions;
+
+    const AT_FDCWD: i32 = -100;
+    const O_ACCMODE: u32 = 0o3;
+    const O_RDONLY: u32 = 0o0;
+    const O_WRONLY: u32 = 0o1;
+    const O_CREAT: u32 = 0o100;
+    const O_EXCL: u32 = 0o200;
+    const O_TRUNC: u32 = 0o1000;
+    const O_APPEND: u32 = 0o2000;
+    #[cfg(target_arch = "aarch64")]
+    const O_NOFOLLOW: u32 = 0o100000;
+    #[cfg(not(target_arch = "aarch64"))]
+    const O_NOFOLLOW: u32 = 0o400000;
+
+    let path = super::user_str(path)?;
+    debug!("sys_openat <= {} {:?} {:#o}", dirfd, path, flags);
+    if dirfd != AT_FDCWD && !path.starts_with('/') {
+        fd::get_file_like(dirfd)?;
+        return Err(LinuxError::ENOTDIR);
+    }
+
+    let mut opts = OpenOptions::new();
+    match flags & O_ACCMODE {
+        O_RDONLY => opts.read(true),
+        O_WRONLY => opts.write(true),
+        _ => {
+            opts.read(true);
+            opts.write(true);
+        }
+    }
+    opts.append(flags & O_APPEND != 0);
+    opts.truncate(flags & O_TRUNC != 0);
+    opts.create(flags & O_CREAT != 0);
+    opts.create_new(flags & O_CREAT != 0 && flags & O_EXCL != 0);
+    opts.nofollow(flags & O_NOFOLLOW != 0);
+
+    let file = crate::file::File::open(&path, &opts)?;
+    Ok(fd::add_file_like(Arc::new(file))? as isize)
+}
+
+pub(super) fn sys_close(fd: i32) -> LinuxResult<isize> {
+    fd::close_file_like(fd)?;
+    Ok(0)
+}
+
+pub(super) fn sys_lseek(fd: i32, offset: i64, whence: u32) -> LinuxResult<isize> {
+    let pos = match whence {
+        SEEK_SET => SeekFrom::Start(offset.try_into().map_err(|_| LinuxError::EINVAL)?),
+        SEEK_CUR => SeekFrom::Current(offset),
+        SEEK_END => SeekFrom::End(offset),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    Ok(fd::get_file_like(fd)?.seek(pos)? as isize)
+}
+
+pub(super) fn sys_fstat(fd: i32, statbuf: usize) -> LinuxResult<isize> {
+    let st = fd::get_file_like(fd)?.stat()?;
+    write_user(statbuf, Stat::from(st))?;
+    Ok(0)
+}
+
+/// Only `TIOCGWINSZ` on the console is supported, which lets programs know
+/// that they are writing to a terminal.
+pub(super) fn sys_ioctl(fd: i32, request: u32, arg: usize) -> LinuxResult<isize> {
+    let f = fd::get_file_like(fd)?.into_any();
+    let is_tty = f.is::<Stdin>() || f.is::<Stdout>();
+    if is_tty && request == TIOCGWINSZ {
+        let ws = WinSize {
+            row: 24,
+            col: 80,
+            xpixel: 0,
+            ypixel: 0,
+        };
+        write_user(arg, ws)?;
+        Ok(0)
+    } else {
+        Err(LinuxError::ENOTTY)
+    }
+}
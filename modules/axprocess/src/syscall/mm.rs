@@ -0,0 +1,182 @@
+//! Memory management system calls.
+
+use core::sync::atomic::Ordering;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::MappingFlags;
+use axmm::{AddrSpace, Backend, FilePages, Writeback};
+use memory_addr::{align_up_4k, is_aligned_4k};
+
+const MAP_SHARED: u32 = 0x01;
+const MAP_PRIVATE: u32 = 0x02;
+const MAP_SHARED_VALIDATE: u32 = 0x03;
+const MAP_TYPE: u32 = 0x0f;
+const MAP_FIXED: u32 = 0x10;
+const MAP_ANONYMOUS: u32 = 0x20;
+
+/// Where to search for free areas for `mmap` without an address hint.
+const MMAP_BASE_HINT: usize = 0x10_0000_0000;
+
+/// Rounds `len` up to a multiple of the page size, or returns `None` if it
+/// overflows.
+fn page_aligned_size(len: usize) -> Option<usize> {
+    Some(len.checked_add(PAGE_SIZE_4K - 1)? & !(PAGE_SIZE_4K - 1))
+}
+
+fn prot_to_flags(prot: u32) -> LinuxResult<MappingFlags> {
+    Ok(axmm::prot_to_flags(prot)? | MappingFlags::USER)
+}
+
+/// Changes the end of the heap to `addr`, and returns the new end.
+///
+/// The heap is not changed if `addr` is invalid or the memory is not
+/// available, and the current end is returned. So `brk(0)` just queries the
+/// current end.
+pub(super) fn sys_brk(addr: usize) -> LinuxResult<isize> {
+    let process = crate::current().ok_or(LinuxError::ENOMEM)?;
+    let mut aspace = process.aspace().lock();
+    let heap_top = process.heap_top.load(Ordering::Acquire);
+    if addr < process.heap_bottom || addr > aspace.end().as_usize() {
+        return Ok(heap_top as isize);
+    }
+    let old_end = align_up_4k(heap_top);
+    let new_end = align_up_4k(addr);
+    let res = if new_end > old_end {
+        aspace.map(
+            old_end.into(),
+            new_end - old_end,
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+            Backend::Alloc { populate: false },
+        )
+    } else if new_end < old_end {
+        aspace.unmap(new_end.into(), old_end - new_end)
+    } else {
+        Ok(())
+    };
+    if res.is_err() {
+        return Ok(heap_top as isize);
+    }
+    process.heap_top.store(addr, Ordering::Release);
+    Ok(addr as isize)
+}
+
+pub(super) fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: u32,
+    flags: u32,
+    fd: i32,
+    offset: usize,
+) -> LinuxResult<isize> {
+    let process = crate::current().ok_or(LinuxError::ENOMEM)?;
+    let map_flags = prot_to_flags(prot)?;
+    let shared = match flags & MAP_TYPE {
+        MAP_SHARED | MAP_SHARED_VALIDATE => true,
+        MAP_PRIVATE => false,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    if len == 0 || !is_aligned_4k(offset) {
+        return Err(LinuxError::EINVAL);
+    }
+    let size = page_aligned_size(len).ok_or(LinuxError::ENOMEM)?;
+
+    // read the file before locking the address space
+    let pages = if flags & MAP_ANONYMOUS != 0 {
+        None
+    } else {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "fs")] {
+                let file = crate::fd::get_file_like(fd)?
+                    .into_any()
+                    .downcast::<crate::file::File>()
+                    .map_err(|_| LinuxError::ENODEV)?;
+                let backend = Backend::File {
+                    file,
+                    offset: offset as u64,
+                    shared,
+                };
+                Some(FilePages::read(backend, size)?)
+            } else {
+                let _ = (fd, shared);
+                return Err(LinuxError::EBADF);
+            }
+        }
+    };
+
+    let mut writeback = Writeback::default();
+    let res = map_locked(
+        &mut process.aspace().lock(),
+        &mut writeback,
+        addr,
+        size,
+        map_flags,
+        flags & MAP_FIXED != 0,
+        pages,
+    );
+    writeback.write()?;
+    res
+}
+
+/// The part of [`sys_mmap`] with the address space locked. The replaced
+/// mappings are moved to `writeback`.
+fn map_locked(
+    aspace: &mut AddrSpace,
+    writeback: &mut Writeback,
+    addr: usize,
+    size: usize,
+    map_flags: MappingFlags,
+    fixed: bool,
+    pages: Option<FilePages>,
+) -> LinuxResult<isize> {
+    let start = if fixed {
+        let start = VirtAddr::from(addr);
+        if !start.is_aligned_4k() || !aspace.contains_range(start, size) {
+            return Err(LinuxError::EINVAL);
+        }
+        writeback.append(aspace.unmap_deferred(start, size)?);
+        start
+    } else {
+        let hint = if addr == 0 { MMAP_BASE_HINT } else { addr };
+        let limit = aspace.base()..aspace.end();
+        aspace
+            .find_free_area(hint.into(), size, limit)
+            .ok_or(LinuxError::ENOMEM)?
+    };
+    match pages {
+        Some(pages) => aspace.map_file_pages(start, map_flags, pages)?,
+        None => aspace.map(start, size, map_flags, Backend::Alloc { populate: false })?,
+    }
+    Ok(start.as_usize() as isize)
+}
+
+pub(super) fn sys_munmap(addr: usize, len: usize) -> LinuxResult<isize> {
+    let process = crate::current().ok_or(LinuxError::EINVAL)?;
+    let start = VirtAddr::from(addr);
+    let size = page_aligned_size(len).ok_or(LinuxError::EINVAL)?;
+    let writeback = {
+        let mut aspace = process.aspace().lock();
+        if !start.is_aligned_4k() || size == 0 || !aspace.contains_range(start, size) {
+            return Err(LinuxError::EINVAL);
+        }
+        aspace.unmap_deferred(start, size)?
+    };
+    writeback.write()?;
+    Ok(0)
+}
+
+pub(super) fn sys_mprotect(addr: usize, len: usize, prot: u32) -> LinuxResult<isize> {
+    let process = crate::current().ok_or(LinuxError::EINVAL)?;
+    let flags = prot_to_flags(prot)?;
+    let start = VirtAddr::from(addr);
+    let size = page_aligned_size(len).ok_or(LinuxError::ENOMEM)?;
+    let mut aspace = process.aspace().lock();
+    if !start.is_aligned_4k() {
+        return Err(LinuxError::EINVAL);
+    }
+    if !aspace.contains_range(start, size) || !aspace.is_mapped(start, size) {
+        return Err(LinuxError::ENOMEM);
+    }
+    aspace.protect(start, size, flags)?;
+    Ok(0)
+}
@@ -0,0 +1,264 @@
+//! Linux system calls for user processes.
+//!
+//! Only the system calls used by common statically linked programs are
+//! implemented. Some of them (e.g., signals) are stubs that do nothing.
+
+mod fs;
+mod mm;
+#[cfg(feature = "net")]
+mod net;
+mod nr;
+mod sys;
+mod task;
+
+use alloc::vec;
+#[cfg(feature = "fs")]
+use alloc::{string::String, vec::Vec};
+use core::mem::size_of;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::arch::TrapFrame;
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::MappingFlags;
+use axmm::AddrSpace;
+use memory_addr::{align_down_4k, align_up_4k};
+
+use self::nr::*;
+
+/// The maximum length of paths, including the terminating NUL.
+#[cfg(feature = "fs")]
+const PATH_MAX: usize = 4096;
+
+/// The maximum size of the kernel buffers that user data is copied through.
+/// Reads are shortened to it, and writes are split into chunks of it.
+const USER_BUF_SIZE: usize = 0x1_0000;
+
+/// Checks whether `[ptr, ptr + len)` is mapped in `aspace` and can be
+/// accessed with `access_flags` by user space, and returns the page-aligned
+/// range that contains it.
+fn check_user_range(
+    aspace: &AddrSpace,
+    ptr: usize,
+    len: usize,
+    access_flags: MappingFlags,
+) -> LinuxResult<(VirtAddr, usize)> {
+    let end = ptr.checked_add(len).ok_or(LinuxError::EFAULT)?;
+    let start = VirtAddr::from(align_down_4k(ptr));
+    let size = align_up_4k(end) - start.as_usize();
+    if !aspace.contains_range(start, size)
+        || !aspace.can_access_range(start, size, access_flags | MappingFlags::USER)
+    {
+        return Err(LinuxError::EFAULT);
+    }
+    Ok((start, size))
+}
+
+/// Calls `f` to access `[ptr, ptr + len)` in the current process, which is
+/// checked to be accessible with `access_flags`.
+///
+/// The pages are populated first, and `f` is called with the lock of the
+/// address space held, so that it does not fault and the range cannot be
+/// unmapped by other threads in the meantime. `f` must not block.
+fn with_user_range<T>(
+    ptr: usize,
+    len: usize,
+    access_flags: MappingFlags,
+    f: impl FnOnce() -> T,
+) -> LinuxResult<T> {
+    let process = crate::current().ok_or(LinuxError::EFAULT)?;
+    let mut aspace = process.aspace().lock();
+    let (start, size) = check_user_range(&aspace, ptr, len, access_flags)?;
+    for vaddr in (start.as_usize()..start.as_usize() + size).step_by(PAGE_SIZE_4K) {
+        let vaddr = VirtAddr::from(vaddr);
+        if aspace.translate(vaddr).is_none() && !aspace.handle_page_fault(vaddr, access_flags) {
+            return Err(LinuxError::EFAULT);
+        }
+    }
+    Ok(f())
+}
+
+/// Copies `buf.len()` bytes at `ptr` in the current process to `buf`.
+fn copy_from_user(ptr: usize, buf: &mut [u8]) -> LinuxResult {
+    if buf.is_empty() {
+        return Ok(());
+    }
+    with_user_range(ptr, buf.len(), MappingFlags::READ, || unsafe {
+        core::ptr::copy_nonoverlapping(ptr as *const u8, buf.as_mut_ptr(), buf.len())
+    })
+}
+
+/// Copies `data` to `ptr` in the current process.
+fn copy_to_user(ptr: usize, data: &[u8]) -> LinuxResult {
+    if data.is_empty() {
+        return Ok(());
+    }
+    with_user_range(ptr, data.len(), MappingFlags::WRITE, || unsafe {
+        core::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len())
+    })
+}
+
+/// Reads data to `[ptr, ptr + len)` in the current process by `read`, which
+/// is called once with a kernel buffer of at most [`USER_BUF_SIZE`] bytes.
+/// Returns the number of bytes read.
+fn read_to_user(
+    ptr: usize,
+    len: usize,
+    read: impl FnOnce(&mut [u8]) -> LinuxResult<usize>,
+) -> LinuxResult<usize> {
+    let mut buf = vec![0; len.min(USER_BUF_SIZE)];
+    // do not consume the data if it cannot be copied
+    if !buf.is_empty() {
+        with_user_range(ptr, buf.len(), MappingFlags::WRITE, || ())?;
+    }
+    let read_len = read(&mut buf)?;
+    copy_to_user(ptr, &buf[..read_len])?;
+    Ok(read_len)
+}
+
+/// Writes the data at `[ptr, ptr + len)` in the current process by `write`,
+/// which is called with kernel buffers of at most [`USER_BUF_SIZE`] bytes
+/// until a short write. Returns the number of bytes written.
+fn write_from_user(
+    ptr: usize,
+    len: usize,
+    mut write: impl FnMut(&[u8]) -> LinuxResult<usize>,
+) -> LinuxResult<usize> {
+    ptr.checked_add(len).ok_or(LinuxError::EFAULT)?;
+    let mut buf = vec![0; len.min(USER_BUF_SIZE)];
+    let mut written = 0;
+    loop {
+        let chunk = &mut buf[..(len - written).min(USER_BUF_SIZE)];
+        copy_from_user(ptr + written, chunk)?;
+        let n = write(chunk)?;
+        written += n;
+        if n < chunk.len() || written == len {
+            return Ok(written);
+        }
+    }
+}
+
+/// Reads a value of type `T` at `ptr` in the current process.
+pub(crate) fn read_user<T: Copy>(ptr: usize) -> LinuxResult<T> {
+    with_user_range(ptr, size_of::<T>(), MappingFlags::READ, || unsafe {
+        (ptr as *const T).read_unaligned()
+    })
+}
+
+/// Writes `value` to `ptr` in the current process.
+pub(crate) fn write_user<T>(ptr: usize, value: T) -> LinuxResult {
+    with_user_range(ptr, size_of::<T>(), MappingFlags::WRITE, || unsafe {
+        (ptr as *mut T).write_unaligned(value)
+    })
+}
+
+/// Reads a NUL-terminated string at `ptr` in the current process.
+#[cfg(feature = "fs")]
+fn user_str(ptr: usize) -> LinuxResult<String> {
+    // copy page by page, as the string may end before an unmapped page
+    let mut bytes = Vec::new();
+    loop {
+        let start = ptr.checked_add(bytes.len()).ok_or(LinuxError::EFAULT)?;
+        let size = align_down_4k(start) + PAGE_SIZE_4K - start;
+        let len = bytes.len();
+        bytes.resize(len + size, 0);
+        copy_from_user(start, &mut bytes[len..])?;
+        if let Some(pos) = bytes[len..].iter().position(|&c| c == 0) {
+            bytes.truncate(len + pos);
+            break;
+        }
+        if bytes.len() >= PATH_MAX {
+            return Err(LinuxError::ENAMETOOLONG);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| LinuxError::EINVAL)
+}
+
+/// Exits the current thread if its process is exiting.
+fn check_group_exit() {
+    let exiting = crate::current().is_some_and(|p| p.group_exit_code().is_some());
+    if exiting {
+        crate::exit_thread(0);
+    }
+}
+
+/// Handles the system call numbered `syscall_num` from the current process,
+/// with arguments in `tf`.
+///
+/// Returns the result, or the negated error number on failure.
+pub fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
+    check_group_exit();
+    let args = [
+        tf.arg0(),
+        tf.arg1(),
+        tf.arg2(),
+        tf.arg3(),
+        tf.arg4(),
+        tf.arg5(),
+    ];
+    trace!("syscall {} <= {:#x?}", syscall_num, args);
+    let ret = match syscall_num {
+        SYS_READ => fs::sys_read(args[0] as _, args[1], args[2]),
+        SYS_WRITE => fs::sys_write(args[0] as _, args[1], args[2]),
+        SYS_READV => fs::sys_readv(args[0] as _, args[1], args[2] as _),
+        SYS_WRITEV => fs::sys_writev(args[0] as _, args[1], args[2] as _),
+        #[cfg(feature = "fs")]
+        SYS_OPENAT => fs::sys_openat(args[0] as _, args[1], args[2] as _),
+        SYS_CLOSE => fs::sys_close(args[0] as _),
+        SYS_LSEEK => fs::sys_lseek(args[0] as _, args[1] as _, args[2] as _),
+        SYS_FSTAT => fs::sys_fstat(args[0] as _, args[1]),
+        SYS_IOCTL => fs::sys_ioctl(args[0] as _, args[1] as _, args[2]),
+        SYS_BRK => mm::sys_brk(args[0]),
+        SYS_MMAP => mm::sys_mmap(
+            args[0],
+            args[1],
+            args[2] as _,
+            args[3] as _,
+            args[4] as _,
+            args[5],
+        ),
+        SYS_MUNMAP => mm::sys_munmap(args[0], args[1]),
+        SYS_MPROTECT => mm::sys_mprotect(args[0], args[1], args[2] as _),
+        SYS_CLONE => task::sys_clone(tf, args[0], args[1], args[2], args[3], args[4]),
+        SYS_EXIT => crate::exit_thread(args[0] as _),
+        SYS_EXIT_GROUP => crate::exit_current(args[0] as _),
+        SYS_GETPID => task::sys_getpid(),
+        SYS_GETTID => task::sys_gettid(),
+        SYS_SET_TID_ADDRESS => task::sys_set_tid_address(args[0]),
+        SYS_SCHED_YIELD => {
+            axtask::yield_now();
+            Ok(0)
+        }
+        SYS_FUTEX => task::sys_futex(args[0], args[1] as _, args[2] as _, args[3]),
+        #[cfg(target_arch = "x86_64")]
+        SYS_ARCH_PRCTL => task::sys_arch_prctl(args[0] as _, args[1]),
+        // signals and robust futex lists are not supported yet
+        SYS_SET_ROBUST_LIST | SYS_RT_SIGACTION | SYS_RT_SIGPROCMASK => Ok(0),
+        SYS_CLOCK_GETTIME => sys::sys_clock_gettime(args[0] as _, args[1]),
+        SYS_UNAME => sys::sys_uname(args[0]),
+        #[cfg(feature = "net")]
+        SYS_SOCKET => net::sys_socket(args[0] as _, args[1] as _, args[2] as _),
+        #[cfg(feature = "net")]
+        SYS_BIND => net::sys_bind(args[0] as _, args[1], args[2] as _),
+        #[cfg(feature = "net")]
+        SYS_CONNECT => net::sys_connect(args[0] as _, args[1], args[2] as _),
+        #[cfg(feature = "net")]
+        SYS_LISTEN => net::sys_listen(args[0] as _),
+        #[cfg(feature = "net")]
+        SYS_ACCEPT | SYS_ACCEPT4 => net::sys_accept(args[0] as _, args[1], args[2]),
+        #[cfg(feature = "net")]
+        SYS_SENDTO => net::sys_sendto(args[0] as _, args[1], args[2], args[4], args[5] as _),
+        #[cfg(feature = "net")]
+        SYS_RECVFROM => net::sys_recvfrom(args[0] as _, args[1], args[2], args[4], args[5]),
+        #[cfg(feature = "net")]
+        SYS_SHUTDOWN => net::sys_shutdown(args[0] as _),
+        #[cfg(feature = "net")]
+        SYS_SETSOCKOPT => Ok(0), // options are ignored
+        _ => {
+            warn!("unsupported syscall: {}", syscall_num);
+            Err(LinuxError::ENOSYS)
+        }
+    };
+    trace!("syscall {} => {:?}", syscall_num, ret);
+    check_group_exit();
+    ret.unwrap_or_else(|e| -(e.code() as isize))
+}
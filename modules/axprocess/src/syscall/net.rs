@@ -0,0 +1,151 @@
+//! Socket system calls. Only IPv4 TCP and UDP sockets are supported.
+
+use alloc::sync::Arc;
+use core::mem::size_of;
+
+use axerrno::{LinuxError, LinuxResult};
+use axnet::{Ipv4Addr, SocketAddr, TcpSocket, UdpSocket};
+use axsync::Mutex;
+
+use super::{copy_to_user, read_to_user, read_user, write_from_user, write_user};
+use crate::fd;
+use crate::socket::Socket;
+
+const AF_INET: u16 = 2;
+const SOCK_STREAM: u32 = 1;
+const SOCK_DGRAM: u32 = 2;
+/// The bits of the socket type that select the type, others are flags (e.g.,
+/// `SOCK_NONBLOCK` and `SOCK_CLOEXEC`).
+const SOCK_TYPE_MASK: u32 = 0xf;
+
+/// `struct sockaddr_in`
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct SockAddrIn {
+    family: u16,
+    /// The port in network byte order.
+    port: u16,
+    addr: [u8; 4],
+    zero: [u8; 8],
+}
+
+fn socket_from_fd(fd: i32) -> LinuxResult<Arc<Socket>> {
+    fd::get_file_like(fd)?
+        .into_any()
+        .downcast::<Socket>()
+        .map_err(|_| LinuxError::ENOTSOCK)
+}
+
+fn read_sockaddr(addr: usize, addrlen: u32) -> LinuxResult<SocketAddr> {
+    if (addrlen as usize) < size_of::<SockAddrIn>() {
+        return Err(LinuxError::EINVAL);
+    }
+    let addr = read_user::<SockAddrIn>(addr)?;
+    if addr.family != AF_INET {
+        return Err(LinuxError::EAFNOSUPPORT);
+    }
+    Ok((Ipv4Addr::from_bytes(&addr.addr), u16::from_be(addr.port)).into())
+}
+
+/// Writes `sockaddr` to `addr` if it is not null, with its length read from
+/// and written to `addrlen`.
+fn write_sockaddr(sockaddr: SocketAddr, addr: usize, addrlen: usize) -> LinuxResult {
+    if addr == 0 {
+        return Ok(());
+    }
+    let sockaddr = SockAddrIn {
+        family: AF_INET,
+        port: sockaddr.port.to_be(),
+        addr: sockaddr
+            .addr
+            .as_bytes()
+            .try_into()
+            .map_err(|_| LinuxError::EAFNOSUPPORT)?,
+        zero: [0; 8],
+    };
+    let bytes = unsafe {
+        core::slice::from_raw_parts(&sockaddr as *const _ as *const u8, size_of::<SockAddrIn>())
+    };
+    // the address is truncated if the buffer is too small
+    let len = (read_user::<u32>(addrlen)? as usize).min(bytes.len());
+    copy_to_user(addr, &bytes[..len])?;
+    write_user(addrlen, bytes.len() as u32)
+}
+
+pub(super) fn sys_socket(domain: u32, ty: u32, _protocol: u32) -> LinuxResult<isize> {
+    if domain != AF_INET as u32 {
+        return Err(LinuxError::EAFNOSUPPORT);
+    }
+    let socket = match ty & SOCK_TYPE_MASK {
+        SOCK_STREAM => Socket::Tcp(Mutex::new(TcpSocket::new())),
+        SOCK_DGRAM => Socket::Udp(Mutex::new(UdpSocket::new())),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    Ok(fd::add_file_like(Arc::new(socket))? as isize)
+}
+
+pub(super) fn sys_bind(fd: i32, addr: usize, addrlen: u32) -> LinuxResult<isize> {
+    let addr = read_sockaddr(addr, addrlen)?;
+    socket_from_fd(fd)?.bind(addr)?;
+    Ok(0)
+}
+
+pub(super) fn sys_connect(fd: i32, addr: usize, addrlen: u32) -> LinuxResult<isize> {
+    let addr = read_sockaddr(addr, addrlen)?;
+    socket_from_fd(fd)?.connect(addr)?;
+    Ok(0)
+}
+
+pub(super) fn sys_listen(fd: i32) -> LinuxResult<isize> {
+    socket_from_fd(fd)?.listen()?;
+    Ok(0)
+}
+
+pub(super) fn sys_accept(fd: i32, addr: usize, addrlen: usize) -> LinuxResult<isize> {
+    let new_socket = socket_from_fd(fd)?.accept()?;
+    let peer_addr = new_socket.peer_addr()?;
+    let new_fd = fd::add_file_like(Arc::new(Socket::Tcp(Mutex::new(new_socket))))?;
+    write_sockaddr(peer_addr, addr, addrlen)?;
+    Ok(new_fd as isize)
+}
+
+pub(super) fn sys_sendto(
+    fd: i32,
+    buf: usize,
+    len: usize,
+    addr: usize,
+    addrlen: u32,
+) -> LinuxResult<isize> {
+    let addr = if addr != 0 {
+        Some(read_sockaddr(addr, addrlen)?)
+    } else {
+        None
+    };
+    let socket = socket_from_fd(fd)?;
+    Ok(write_from_user(buf, len, |buf| socket.sendto(buf, addr))? as isize)
+}
+
+pub(super) fn sys_recvfrom(
+    fd: i32,
+    buf: usize,
+    len: usize,
+    addr: usize,
+    addrlen: usize,
+) -> LinuxResult<isize> {
+    let socket = socket_from_fd(fd)?;
+    let mut peer_addr = None;
+    let len = read_to_user(buf, len, |buf| {
+        let (len, addr) = socket.recvfrom(buf)?;
+        peer_addr = addr;
+        Ok(len)
+    })?;
+    if let Some(peer_addr) = peer_addr {
+        write_sockaddr(peer_addr, addr, addrlen)?;
+    }
+    Ok(len as isize)
+}
+
+pub(super) fn sys_shutdown(fd: i32) -> LinuxResult<isize> {
+    socket_from_fd(fd)?.shutdown()?;
+    Ok(0)
+}
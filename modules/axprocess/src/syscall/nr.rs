@@ -0,0 +1,84 @@
+//! Linux system call numbers of the architectures.
+
+// Some system calls are only available with certain features.
+#![allow(dead_code)]
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        pub const SYS_READ: usize = 0;
+        pub const SYS_WRITE: usize = 1;
+        pub const SYS_CLOSE: usize = 3;
+        pub const SYS_FSTAT: usize = 5;
+        pub const SYS_LSEEK: usize = 8;
+        pub const SYS_MMAP: usize = 9;
+        pub const SYS_MPROTECT: usize = 10;
+        pub const SYS_MUNMAP: usize = 11;
+        pub const SYS_BRK: usize = 12;
+        pub const SYS_RT_SIGACTION: usize = 13;
+        pub const SYS_RT_SIGPROCMASK: usize = 14;
+        pub const SYS_IOCTL: usize = 16;
+        pub const SYS_READV: usize = 19;
+        pub const SYS_WRITEV: usize = 20;
+        pub const SYS_SCHED_YIELD: usize = 24;
+        pub const SYS_GETPID: usize = 39;
+        pub const SYS_SOCKET: usize = 41;
+        pub const SYS_CONNECT: usize = 42;
+        pub const SYS_ACCEPT: usize = 43;
+        pub const SYS_SENDTO: usize = 44;
+        pub const SYS_RECVFROM: usize = 45;
+        pub const SYS_SHUTDOWN: usize = 48;
+        pub const SYS_BIND: usize = 49;
+        pub const SYS_LISTEN: usize = 50;
+        pub const SYS_SETSOCKOPT: usize = 54;
+        pub const SYS_CLONE: usize = 56;
+        pub const SYS_EXIT: usize = 60;
+        pub const SYS_UNAME: usize = 63;
+        pub const SYS_ARCH_PRCTL: usize = 158;
+        pub const SYS_GETTID: usize = 186;
+        pub const SYS_FUTEX: usize = 202;
+        pub const SYS_SET_TID_ADDRESS: usize = 218;
+        pub const SYS_CLOCK_GETTIME: usize = 228;
+        pub const SYS_EXIT_GROUP: usize = 231;
+        pub const SYS_OPENAT: usize = 257;
+        pub const SYS_SET_ROBUST_LIST: usize = 273;
+        pub const SYS_ACCEPT4: usize = 288;
+    } else {
+        // the generic syscall numbers, used by riscv64 and aarch64
+        pub const SYS_IOCTL: usize = 29;
+        pub const SYS_OPENAT: usize = 56;
+        pub const SYS_CLOSE: usize = 57;
+        pub const SYS_LSEEK: usize = 62;
+        pub const SYS_READ: usize = 63;
+        pub const SYS_WRITE: usize = 64;
+        pub const SYS_READV: usize = 65;
+        pub const SYS_WRITEV: usize = 66;
+        pub const SYS_FSTAT: usize = 80;
+        pub const SYS_EXIT: usize = 93;
+        pub const SYS_EXIT_GROUP: usize = 94;
+        pub const SYS_SET_TID_ADDRESS: usize = 96;
+        pub const SYS_FUTEX: usize = 98;
+        pub const SYS_SET_ROBUST_LIST: usize = 99;
+        pub const SYS_CLOCK_GETTIME: usize = 113;
+        pub const SYS_SCHED_YIELD: usize = 124;
+        pub const SYS_RT_SIGACTION: usize = 134;
+        pub const SYS_RT_SIGPROCMASK: usize = 135;
+        pub const SYS_UNAME: usize = 160;
+        pub const SYS_GETPID: usize = 172;
+        pub const SYS_GETTID: usize = 178;
+        pub const SYS_SOCKET: usize = 198;
+        pub const SYS_BIND: usize = 200;
+        pub const SYS_LISTEN: usize = 201;
+        pub const SYS_ACCEPT: usize = 202;
+        pub const SYS_CONNECT: usize = 203;
+        pub const SYS_SENDTO: usize = 206;
+        pub const SYS_RECVFROM: usize = 207;
+        pub const SYS_SETSOCKOPT: usize = 208;
+        pub const SYS_SHUTDOWN: usize = 210;
+        pub const SYS_BRK: usize = 214;
+        pub const SYS_MUNMAP: usize = 215;
+        pub const SYS_CLONE: usize = 220;
+        pub const SYS_MMAP: usize = 222;
+        pub const SYS_MPROTECT: usize = 226;
+        pub const SYS_ACCEPT4: usize = 242;
+    }
+}
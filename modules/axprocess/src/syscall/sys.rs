@@ -0,0 +1,71 @@
+//! System calls for time and system information.
+
+use axerrno::{LinuxError, LinuxResult};
+
+use super::write_user;
+
+const CLOCK_REALTIME: u32 = 0;
+const CLOCK_MONOTONIC: u32 = 1;
+const CLOCK_PROCESS_CPUTIME_ID: u32 = 2;
+const CLOCK_THREAD_CPUTIME_ID: u32 = 3;
+const CLOCK_MONOTONIC_RAW: u32 = 4;
+const CLOCK_REALTIME_COARSE: u32 = 5;
+const CLOCK_MONOTONIC_COARSE: u32 = 6;
+const CLOCK_BOOTTIME: u32 = 7;
+
+/// `struct utsname`
+#[repr(C)]
+struct UtsName {
+    sysname: [u8; 65],
+    nodename: [u8; 65],
+    release: [u8; 65],
+    version: [u8; 65],
+    machine: [u8; 65],
+    domainname: [u8; 65],
+}
+
+/// Returns the time of the clock `clk_id`.
+///
+/// CPU-time clocks are not supported, and the monotonic clock is returned
+/// instead.
+pub(super) fn sys_clock_gettime(clk_id: u32, tp: usize) -> LinuxResult<isize> {
+    let now = match clk_id {
+        CLOCK_REALTIME | CLOCK_REALTIME_COARSE => axhal::time::wall_time(),
+        CLOCK_MONOTONIC
+        | CLOCK_MONOTONIC_RAW
+        | CLOCK_MONOTONIC_COARSE
+        | CLOCK_BOOTTIME
+        | CLOCK_PROCESS_CPUTIME_ID
+        | CLOCK_THREAD_CPUTIME_ID => axhal::time::current_time(),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    write_user(tp, [now.as_secs() as i64, now.subsec_nanos() as i64])?;
+    Ok(0)
+}
+
+/// Returns the system information. The system name is reported as "Linux",
+/// as some programs check it.
+pub(super) fn sys_uname(buf: usize) -> LinuxResult<isize> {
+    fn field(s: &str) -> [u8; 65] {
+        let mut buf = [0; 65];
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+        buf
+    }
+    let machine = if cfg!(target_arch = "x86_64") {
+        "x86_64"
+    } else if cfg!(target_arch = "riscv64") {
+        "riscv64"
+    } else {
+        "aarch64"
+    };
+    let uts = UtsName {
+        sysname: field("Linux"),
+        nodename: field("arceos"),
+        release: field("5.10.0"),
+        version: field(concat!("ArceOS ", env!("CARGO_PKG_VERSION"))),
+        machine: field(machine),
+        domainname: field(""),
+    };
+    write_user(buf, uts)?;
+    Ok(0)
+}
@@ -0,0 +1,161 @@
+//! Thread-related system calls.
+
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::arch::{TrapFrame, UspaceContext};
+
+use super::{read_user, write_user};
+
+const CLONE_VM: usize = 0x100;
+const CLONE_THREAD: usize = 0x10000;
+const CLONE_SETTLS: usize = 0x80000;
+const CLONE_PARENT_SETTID: usize = 0x100000;
+const CLONE_CHILD_CLEARTID: usize = 0x200000;
+const CLONE_CHILD_SETTID: usize = 0x1000000;
+
+const FUTEX_WAIT: u32 = 0;
+const FUTEX_WAKE: u32 = 1;
+const FUTEX_REQUEUE: u32 = 3;
+const FUTEX_CMP_REQUEUE: u32 = 4;
+const FUTEX_WAIT_BITSET: u32 = 9;
+const FUTEX_WAKE_BITSET: u32 = 10;
+/// The bits of the futex operation that select the command.
+const FUTEX_CMD_MASK: u32 = 0x7f;
+/// The timeout of `FUTEX_WAIT_BITSET` is measured by `CLOCK_REALTIME`.
+const FUTEX_CLOCK_REALTIME: u32 = 256;
+
+/// `struct timespec`
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct TimeSpec {
+    tv_sec: i64,
+    tv_nsec: i64,
+}
+
+/// Creates a new thread in the current process.
+///
+/// Only threads sharing the address space (with `CLONE_VM | CLONE_THREAD`)
+/// are supported. The order of `tls` and `child_tid` follows x86_64, and is
+/// swapped on other architectures.
+pub(super) fn sys_clone(
+    tf: &TrapFrame,
+    flags: usize,
+    newsp: usize,
+    parent_tid: usize,
+    arg3: usize,
+    arg4: usize,
+) -> LinuxResult<isize> {
+    #[cfg(target_arch = "x86_64")]
+    let (child_tid, tls) = (arg3, arg4);
+    #[cfg(not(target_arch = "x86_64"))]
+    let (tls, child_tid) = (arg3, arg4);
+
+    if flags & (CLONE_VM | CLONE_THREAD) != CLONE_VM | CLONE_THREAD {
+        warn!("sys_clone: unsupported flags {:#x}", flags);
+        return Err(LinuxError::ENOSYS);
+    }
+    let process = crate::current().ok_or(LinuxError::EINVAL)?;
+
+    let mut ctx = UspaceContext::from(tf);
+    if newsp != 0 {
+        ctx.set_sp(newsp);
+    }
+    if flags & CLONE_SETTLS != 0 {
+        ctx.set_tls(tls);
+    }
+    ctx.set_retval(0);
+    let clear_child_tid = if flags & CLONE_CHILD_CLEARTID != 0 {
+        child_tid
+    } else {
+        0
+    };
+
+    let task = crate::spawn_thread(&process, ctx, clear_child_tid);
+    let tid = task.id().as_u64() as u32;
+    if flags & CLONE_PARENT_SETTID != 0 {
+        write_user(parent_tid, tid)?;
+    }
+    if flags & CLONE_CHILD_SETTID != 0 {
+        write_user(child_tid, tid)?;
+    }
+    Ok(tid as isize)
+}
+
+pub(super) fn sys_getpid() -> LinuxResult<isize> {
+    Ok(crate::current().map_or(0, |p| p.pid()) as isize)
+}
+
+pub(super) fn sys_gettid() -> LinuxResult<isize> {
+    Ok(axtask::current().id().as_u64() as isize)
+}
+
+pub(super) fn sys_set_tid_address(tidptr: usize) -> LinuxResult<isize> {
+    let thread = crate::current_thread().ok_or(LinuxError::EINVAL)?;
+    thread.clear_child_tid.store(tidptr, Ordering::Relaxed);
+    sys_gettid()
+}
+
+/// Waits on or wakes up the futex word at `uaddr`.
+///
+/// The bitsets are ignored, and requeuing wakes up all the waiters to be
+/// requeued instead, as spurious wake-ups are allowed.
+pub(super) fn sys_futex(uaddr: usize, op: u32, val: u32, timeout: usize) -> LinuxResult<isize> {
+    let process = crate::current().ok_or(LinuxError::EINVAL)?;
+    let futexes = &process.futexes;
+    match op & FUTEX_CMD_MASK {
+        cmd @ (FUTEX_WAIT | FUTEX_WAIT_BITSET) => {
+            let timeout = if timeout != 0 {
+                let ts = read_user::<TimeSpec>(timeout)?;
+                if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+                    return Err(LinuxError::EINVAL);
+                }
+                let dur = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+                if cmd == FUTEX_WAIT_BITSET {
+                    // an absolute time
+                    let now = if op & FUTEX_CLOCK_REALTIME != 0 {
+                        axhal::time::wall_time()
+                    } else {
+                        axhal::time::current_time()
+                    };
+                    Some(dur.saturating_sub(now))
+                } else {
+                    Some(dur)
+                }
+            } else {
+                None
+            };
+            futexes.wait(uaddr, val, timeout)?;
+            Ok(0)
+        }
+        FUTEX_WAKE | FUTEX_WAKE_BITSET => Ok(futexes.wake(uaddr, val as usize) as isize),
+        FUTEX_REQUEUE | FUTEX_CMP_REQUEUE => {
+            // `timeout` is the maximum number of waiters to requeue
+            let count = (val as usize).saturating_add(timeout);
+            Ok(futexes.wake(uaddr, count) as isize)
+        }
+        cmd => {
+            warn!("sys_futex: unsupported operation {}", cmd);
+            Err(LinuxError::ENOSYS)
+        }
+    }
+}
+
+/// Sets or gets the FS base, which is the thread pointer on x86_64.
+#[cfg(target_arch = "x86_64")]
+pub(super) fn sys_arch_prctl(code: u32, addr: usize) -> LinuxResult<isize> {
+    const ARCH_SET_FS: u32 = 0x1002;
+    const ARCH_GET_FS: u32 = 0x1003;
+    match code {
+        ARCH_SET_FS => {
+            unsafe { axhal::arch::write_thread_pointer(addr) };
+            Ok(0)
+        }
+        ARCH_GET_FS => {
+            write_user(addr, axhal::arch::read_thread_pointer())?;
+            Ok(0)
+        }
+        _ => Err(LinuxError::EINVAL),
+    }
+}
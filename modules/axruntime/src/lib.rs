@@ -10,7 +10,10 @@
 //! - `irq`: Enable interrupt handling support.
 //! - `multitask`: Enable multi-threading support.
 //! - `smp`: Enable SMP (symmetric multiprocessing) support.
-//! - `fs`: Enable filesystem support.
+//! - `uspace`: Enable user processes, which run ELF executables in user mode
+//!   with their own address spaces.
+//! - `fs`: Enable filesystem support, with runtime information files (e.g.,
+//!   memory usage, tasks) in `/proc`, and `/dev/fb0` if `display` is enabled.
 //! - `net`: Enable networking support.
 //! - `display`: Enable graphics support.
 //!
@@ -22,6 +25,9 @@
 #[macro_use]
 extern crate axlog;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[cfg(all(target_os = "none", not(test)))]
 mod lang_items;
 mod trap;
@@ -29,9 +35,14 @@ mod trap;
 #[cfg(feature = "smp")]
 mod mp;
 
+#[cfg(feature = "fs")]
+mod procfs;
+
 #[cfg(feature = "smp")]
 pub use self::mp::rust_main_secondary;
 
+pub use self::trap::{register_page_fault_handler, PageFaultHandler};
+
 const LOGO: &str = r#"
        d8888                            .d88888b.   .d8888b.
       d88888                           d88P" "Y88b d88P  Y88b
@@ -143,8 +154,8 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
 
     #[cfg(feature = "paging")]
     {
-        info!("Initialize kernel page table...");
-        remap_kernel_memory().expect("remap kernel memoy failed");
+        info!("Initialize virtual memory management...");
+        axmm::init_memory_management();
     }
 
     info!("Initialize platform devices...");
@@ -166,6 +177,22 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
 
         #[cfg(feature = "display")]
         axdisplay::init_display(all_devices.display);
+
+        #[cfg(all(feature = "fs", feature = "display"))]
+        {
+            let info = axdisplay::framebuffer_info();
+            let fb0 = unsafe {
+                axfs_devfs::FrameBufferDev::new(
+                    info.fb_base_vaddr,
+                    info.fb_size,
+                    axdisplay::framebuffer_flush,
+                )
+            };
+            axfs::api::add_device("fb0", alloc::sync::Arc::new(fb0));
+        }
+
+        #[cfg(feature = "fs")]
+        self::procfs::init();
     }
 
     #[cfg(feature = "smp")]
@@ -186,6 +213,9 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
 
     unsafe { main() };
 
+    #[cfg(feature = "fs")]
+    axfs::api::sync().ok(); // write back the block caches before shutdown
+
     #[cfg(feature = "multitask")]
     axtask::exit(0);
     #[cfg(not(feature = "multitask"))]
@@ -221,32 +251,6 @@ fn init_allocator() {
     }
 }
 
-#[cfg(feature = "paging")]
-fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
-    use axhal::mem::{memory_regions, phys_to_virt};
-    use axhal::paging::PageTable;
-    use lazy_init::LazyInit;
-
-    static KERNEL_PAGE_TABLE: LazyInit<PageTable> = LazyInit::new();
-
-    if axhal::cpu::this_cpu_is_bsp() {
-        let mut kernel_page_table = PageTable::try_new()?;
-        for r in memory_regions() {
-            kernel_page_table.map_region(
-                phys_to_virt(r.paddr),
-                r.paddr,
-                r.size,
-                r.flags.into(),
-                true,
-            )?;
-        }
-        KERNEL_PAGE_TABLE.init_by(kernel_page_table);
-    }
-
-    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.root_paddr()) };
-    Ok(())
-}
-
 #[cfg(feature = "irq")]
 fn init_interrupt() {
     use axhal::time::TIMER_IRQ_NUM;
@@ -277,4 +281,6 @@ fn init_interrupt() {
 
     // Enable IRQs before starting app
     axhal::arch::enable_irqs();
+    #[cfg(feature = "smp")]
+    axhal::irq::enable_ipi();
 }
@@ -35,7 +35,7 @@ pub extern "C" fn rust_main_secondary(cpu_id: usize) -> ! {
     info!("Secondary CPU {} started.", cpu_id);
 
     #[cfg(feature = "paging")]
-    super::remap_kernel_memory().unwrap();
+    axmm::init_memory_management_secondary();
 
     axhal::platform_init_secondary();
 
@@ -50,7 +50,10 @@ pub extern "C" fn rust_main_secondary(cpu_id: usize) -> ! {
     }
 
     #[cfg(feature = "irq")]
-    axhal::arch::enable_irqs();
+    {
+        axhal::arch::enable_irqs();
+        axhal::irq::enable_ipi();
+    }
 
     #[cfg(feature = "multitask")]
     axtask::run_idle();
@@ -0,0 +1,64 @@
+//! Registers the runtime information files in `/proc`.
+
+#[allow(unused_imports)]
+use core::fmt::Write;
+
+/// Adds the files of memory, task, network and interrupt information to the
+/// procfs, depending on the enabled features.
+pub(crate) fn init() {
+    #[cfg(feature = "alloc")]
+    axfs::api::add_proc_file("meminfo", || {
+        let allocator = axalloc::global_allocator();
+        alloc::format!(
+            "UsedBytes:      {:>12}\n\
+             AvailableBytes: {:>12}\n\
+             UsedPages:      {:>12}\n\
+             AvailablePages: {:>12}\n",
+            allocator.used_bytes(),
+            allocator.available_bytes(),
+            allocator.used_pages(),
+            allocator.available_pages(),
+        )
+    });
+
+    #[cfg(feature = "multitask")]
+    axfs::api::add_proc_file("tasks", || {
+        let mut s = alloc::string::String::from("ID\tSTATE\tNAME\n");
+        for task in axtask::all_tasks() {
+            let state = alloc::format!("{:?}", task.state());
+            writeln!(s, "{}\t{}\t{}", task.id().as_u64(), state, task.name()).ok();
+        }
+        s
+    });
+
+    #[cfg(feature = "net")]
+    axfs::api::add_proc_file("net/tcp", || {
+        let mut s = alloc::string::String::from("LOCAL\tREMOTE\tSTATE\tRECV-Q\tSEND-Q\n");
+        let addr = |addr: Option<axnet::SocketAddr>| match addr {
+            Some(addr) => alloc::format!("{}", addr),
+            None => alloc::string::String::from("*"),
+        };
+        for sock in axnet::tcp_sockets() {
+            writeln!(
+                s,
+                "{}\t{}\t{}\t{}\t{}",
+                addr(sock.local_addr),
+                addr(sock.peer_addr),
+                sock.state,
+                sock.recv_queue,
+                sock.send_queue
+            )
+            .ok();
+        }
+        s
+    });
+
+    #[cfg(feature = "irq")]
+    axfs::api::add_proc_file("interrupts", || {
+        let mut s = alloc::string::String::from("IRQ\tCOUNT\n");
+        for (irq_num, count) in axhal::irq::irq_counts() {
+            writeln!(s, "{}\t{}", irq_num, count).ok();
+        }
+        s
+    });
+}
@@ -1,3 +1,27 @@
+use axhal::arch::TrapFrame;
+use axhal::mem::VirtAddr;
+use axhal::trap::MappingFlags;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A page fault handler, which receives the faulting virtual address, the
+/// access type, and whether the fault occurred in user mode.
+///
+/// It returns `true` if the fault has been handled (e.g., the page has been
+/// mapped), so that the faulting instruction can be retried.
+pub type PageFaultHandler = fn(VirtAddr, MappingFlags, bool) -> bool;
+
+static PAGE_FAULT_HANDLER: AtomicUsize = AtomicUsize::new(0);
+
+/// Registers the page fault handler, which is used to implement lazy
+/// allocation, stack guards, copy-on-write, etc.
+///
+/// Returns `false` if a handler has already been registered.
+pub fn register_page_fault_handler(handler: PageFaultHandler) -> bool {
+    PAGE_FAULT_HANDLER
+        .compare_exchange(0, handler as usize, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+}
+
 struct TrapHandlerImpl;
 
 #[crate_interface::impl_interface]
@@ -10,4 +34,43 @@ impl axhal::trap::TrapHandler for TrapHandlerImpl {
             drop(guard); // rescheduling may occur when preemption is re-enabled.
         }
     }
+
+    fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+        debug!(
+            "page fault @ {:#x}, access_flags={:?}, is_user={}",
+            vaddr, access_flags, is_user
+        );
+        #[cfg(all(feature = "multitask", feature = "paging"))]
+        if !is_user {
+            axtask::check_stack_overflow(vaddr);
+        }
+        #[cfg(feature = "paging")]
+        if !is_user && axmm::handle_page_fault(vaddr, access_flags) {
+            return true;
+        }
+        #[cfg(feature = "uspace")]
+        if axprocess::handle_page_fault(vaddr, access_flags, is_user) {
+            return true;
+        }
+        let handler = PAGE_FAULT_HANDLER.load(Ordering::Acquire);
+        if handler == 0 {
+            return false;
+        }
+        let handler: PageFaultHandler = unsafe { core::mem::transmute(handler) };
+        handler(vaddr, access_flags, is_user)
+    }
+
+    fn handle_syscall(_tf: &TrapFrame, _syscall_num: usize) -> isize {
+        #[cfg(feature = "uspace")]
+        return axprocess::handle_syscall(_tf, _syscall_num);
+        #[cfg(not(feature = "uspace"))]
+        unreachable!("syscall from user space without `uspace` enabled")
+    }
+
+    fn handle_user_exception(_tf: &TrapFrame) -> bool {
+        #[cfg(feature = "uspace")]
+        return axprocess::handle_user_exception();
+        #[cfg(not(feature = "uspace"))]
+        false
+    }
 }
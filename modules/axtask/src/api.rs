@@ -5,7 +5,7 @@ use alloc::{string::String, sync::Arc};
 pub(crate) use crate::run_queue::{AxRunQueue, RUN_QUEUE};
 
 #[doc(cfg(feature = "multitask"))]
-pub use crate::task::{CurrentTask, TaskId, TaskInner};
+pub use crate::task::{CurrentTask, TaskId, TaskInner, TaskState};
 #[doc(cfg(feature = "multitask"))]
 pub use crate::wait_queue::WaitQueue;
 
@@ -86,6 +86,22 @@ pub fn on_timer_tick() {
     RUN_QUEUE.lock().scheduler_timer_tick();
 }
 
+/// Checks whether the page fault at `vaddr` is caused by a stack overflow of
+/// the current task, i.e., `vaddr` is in the guard page below its stack.
+///
+/// # Panics
+///
+/// Panics with the current task name if it is a stack overflow.
+#[cfg(feature = "paging")]
+#[doc(cfg(feature = "paging"))]
+pub fn check_stack_overflow(vaddr: memory_addr::VirtAddr) {
+    if let Some(curr) = current_may_uninit() {
+        if curr.stack_guard_contains(vaddr) {
+            panic!("stack overflow in task {}", curr.id_name());
+        }
+    }
+}
+
 /// Spawns a new task with the given parameters.
 ///
 /// Returns the task reference.
@@ -98,6 +114,29 @@ where
     task
 }
 
+/// Spawns a new task that will run in user space, with its own page table
+/// whose root is at `page_table_root`.
+///
+/// The task starts in the kernel to run `f`, which usually enters user space
+/// and never returns. The page table is switched when the task is scheduled.
+///
+/// Returns the task reference.
+#[cfg(feature = "uspace")]
+#[doc(cfg(feature = "uspace"))]
+pub fn spawn_user<F>(
+    f: F,
+    name: String,
+    stack_size: usize,
+    page_table_root: memory_addr::PhysAddr,
+) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    let task = TaskInner::new_user(f, name, stack_size, page_table_root);
+    RUN_QUEUE.lock().add_task(task.clone());
+    task
+}
+
 /// Spawns a new task with the default parameters.
 ///
 /// The default task name is an empty string. The default task stack size is
@@ -111,6 +150,12 @@ where
     spawn_raw(f, "".into(), axconfig::TASK_STACK_SIZE)
 }
 
+/// Returns all tasks that have not been dropped (including exited ones not
+/// joined yet), in the order of their IDs.
+pub fn all_tasks() -> alloc::vec::Vec<AxTaskRef> {
+    crate::task::all_tasks()
+}
+
 /// Set the priority for current task.
 ///
 /// The range of the priority is dependent on the underlying scheduler. For
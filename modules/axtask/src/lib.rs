@@ -12,7 +12,14 @@
 //! - `irq`: Interrupts are enabled. If this feature is enabled, timer-based
 //!    APIs can be used, such as [`sleep`], [`sleep_until`], and
 //!    [`WaitQueue::wait_timeout`].
+//! - `smp`: Enable SMP support. With the `irq` feature, idle CPUs are woken
+//!   up by IPIs when tasks become ready.
 //! - `preempt`: Enable preemptive scheduling.
+//! - `paging`: Map task stacks in a dedicated virtual region with guard pages,
+//!   to detect stack overflows. Otherwise, a canary at the bottom of each
+//!   stack is checked on every context switch.
+//! - `uspace`: Tasks can run in user space, each user task has its own page
+//!   table, which is switched on context switches.
 //! - `sched_fifo`: Use the [FIFO cooperative scheduler][1]. It also enables the
 //!   `multitask` feature if it is enabled. This feature is enabled by default.
 //! - `sched_rr`: Use the [Round-robin preemptive scheduler][2]. It also enables
@@ -34,6 +41,7 @@ cfg_if::cfg_if! {
         extern crate log;
         extern crate alloc;
         mod run_queue;
+        mod stack;
         mod task;
         mod wait_queue;
 
@@ -1,5 +1,7 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+#[cfg(all(feature = "smp", feature = "irq"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lazy_init::LazyInit;
 use scheduler::BaseScheduler;
 use spinlock::SpinNoIrq;
@@ -18,6 +20,10 @@ static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
 #[percpu::def_percpu]
 static IDLE_TASK: LazyInit<AxTaskRef> = LazyInit::new();
 
+/// The bitmask of CPUs that are running their idle tasks.
+#[cfg(all(feature = "smp", feature = "irq"))]
+static IDLE_CPUS: AtomicUsize = AtomicUsize::new(0);
+
 pub(crate) struct AxRunQueue {
     scheduler: Scheduler,
 }
@@ -34,6 +40,8 @@ impl AxRunQueue {
         debug!("task spawn: {}", task.id_name());
         assert!(task.is_ready());
         self.scheduler.add_task(task);
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        kick_idle_cpu();
     }
 
     #[cfg(feature = "irq")]
@@ -122,6 +130,8 @@ impl AxRunQueue {
         if task.is_blocked() {
             task.set_state(TaskState::Ready);
             self.scheduler.add_task(task); // TODO: priority
+            #[cfg(all(feature = "smp", feature = "irq"))]
+            kick_idle_cpu();
             if resched {
                 #[cfg(feature = "preempt")]
                 crate::current().set_preempt_pending(true);
@@ -169,9 +179,20 @@ impl AxRunQueue {
             prev_task.id_name(),
             next_task.id_name()
         );
+        #[cfg(not(feature = "paging"))]
+        prev_task.check_stack_canary();
         #[cfg(feature = "preempt")]
         next_task.set_preempt_pending(false);
         next_task.set_state(TaskState::Running);
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        {
+            let this_cpu = 1 << axhal::cpu::this_cpu_id();
+            if next_task.is_idle() {
+                IDLE_CPUS.fetch_or(this_cpu, Ordering::Release);
+            } else {
+                IDLE_CPUS.fetch_and(!this_cpu, Ordering::Release);
+            }
+        }
         if prev_task.ptr_eq(&next_task) {
             return;
         }
@@ -185,12 +206,34 @@ impl AxRunQueue {
             assert!(Arc::strong_count(prev_task.as_task_ref()) > 1);
             assert!(Arc::strong_count(&next_task) >= 1);
 
+            #[cfg(feature = "uspace")]
+            if prev_task.page_table_root() != next_task.page_table_root() {
+                crate::task::switch_page_table(next_task.page_table_root());
+            }
+
             CurrentTask::set_current(prev_task, next_task);
             (*prev_ctx_ptr).switch_to(&*next_ctx_ptr);
         }
     }
 }
 
+/// Sends an IPI to an idle CPU (other than the current one), so that it can
+/// pick the newly ready task without waiting for the next timer tick.
+#[cfg(all(feature = "smp", feature = "irq"))]
+fn kick_idle_cpu() {
+    let this_cpu = 1 << axhal::cpu::this_cpu_id();
+    let mut idle_cpus = IDLE_CPUS.load(Ordering::Acquire) & !this_cpu;
+    while idle_cpus != 0 {
+        let cpu_id = idle_cpus.trailing_zeros() as usize;
+        // clear its bit, so the next ready task will kick another idle CPU
+        if IDLE_CPUS.fetch_and(!(1 << cpu_id), Ordering::AcqRel) & (1 << cpu_id) != 0 {
+            axhal::irq::send_ipi(cpu_id, axhal::irq::IPI_IRQ_NUM);
+            return;
+        }
+        idle_cpus &= !(1 << cpu_id);
+    }
+}
+
 fn gc_entry() {
     loop {
         // Drop all exited tasks and recycle resources.
@@ -0,0 +1,127 @@
+//! Task stacks with stack overflow detection.
+//!
+//! With the `paging` feature, each stack is mapped in a dedicated region
+//! (`TASK_STACK_VADDR_BASE` in the platform config) of the kernel address
+//! space, with an unmapped guard page below it, so that a stack overflow
+//! causes a page fault instead of corrupting other memory. Otherwise, stacks
+//! are allocated from the heap, with a canary at the bottom which is checked
+//! on every context switch.
+
+use memory_addr::VirtAddr;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "paging")] {
+        use axconfig::{TASK_STACK_VADDR_BASE, TASK_STACK_VADDR_SIZE};
+        use axhal::mem::{phys_to_virt, virt_to_phys, PhysAddr, PAGE_SIZE_4K};
+        use axhal::paging::MappingFlags;
+        use axmm::Backend;
+
+        /// Size of the unmapped guard area below each stack.
+        const GUARD_SIZE: usize = PAGE_SIZE_4K;
+
+        /// Size of the mapped area at the beginning of the region.
+        ///
+        /// On some architectures, the trap frame is saved on the current
+        /// stack, so a fault in a guard page leads to nested faults until the
+        /// stack pointer reaches the mapped memory below, where the fault can
+        /// be reported. This area makes sure there is always such memory.
+        const TRAP_AREA_SIZE: usize = 4 * PAGE_SIZE_4K;
+
+        pub(crate) struct TaskStack {
+            base: usize,
+            size: usize,
+            paddr: PhysAddr,
+        }
+
+        impl TaskStack {
+            pub fn alloc(size: usize) -> Self {
+                let region = VirtAddr::from(TASK_STACK_VADDR_BASE)
+                    ..VirtAddr::from(TASK_STACK_VADDR_BASE + TASK_STACK_VADDR_SIZE);
+                let mut aspace = axmm::kernel_aspace().lock();
+                if aspace.find_area(region.start).is_none() {
+                    // never unmapped
+                    aspace
+                        .map(
+                            region.start,
+                            TRAP_AREA_SIZE,
+                            MappingFlags::READ | MappingFlags::WRITE,
+                            Backend::Alloc { populate: true },
+                        )
+                        .expect("failed to map the trap area for task stacks");
+                }
+
+                let start = aspace
+                    .find_free_area(region.start, GUARD_SIZE + size, region)
+                    .expect("no virtual space for task stacks");
+                let base = start + GUARD_SIZE;
+                // physically contiguous, so that `kernel_virt_to_phys` works
+                // for buffers on the stack
+                let frames = axalloc::global_allocator()
+                    .alloc_pages(size / PAGE_SIZE_4K, PAGE_SIZE_4K)
+                    .expect("failed to allocate task stack");
+                let paddr = virt_to_phys(frames.into());
+                aspace
+                    .map_linear(base, paddr, size, MappingFlags::READ | MappingFlags::WRITE)
+                    .expect("failed to map task stack");
+                Self {
+                    base: base.as_usize(),
+                    size,
+                    paddr,
+                }
+            }
+
+            pub fn top(&self) -> VirtAddr {
+                VirtAddr::from(self.base + self.size)
+            }
+
+            /// Whether `vaddr` is in the guard page below the stack.
+            pub fn guard_contains(&self, vaddr: VirtAddr) -> bool {
+                (self.base - GUARD_SIZE..self.base).contains(&vaddr.as_usize())
+            }
+        }
+
+        impl Drop for TaskStack {
+            fn drop(&mut self) {
+                axmm::kernel_aspace()
+                    .lock()
+                    .unmap(self.base.into(), self.size)
+                    .expect("failed to unmap task stack");
+                axalloc::global_allocator()
+                    .dealloc_pages(phys_to_virt(self.paddr).as_usize(), self.size / PAGE_SIZE_4K);
+            }
+        }
+    } else {
+        use core::{alloc::Layout, ptr::NonNull};
+
+        const STACK_CANARY: u64 = 0xdead_beef_cafe_f00d;
+
+        pub(crate) struct TaskStack {
+            ptr: NonNull<u8>,
+            layout: Layout,
+        }
+
+        impl TaskStack {
+            pub fn alloc(size: usize) -> Self {
+                let layout = Layout::from_size_align(size, 16).unwrap();
+                let ptr = NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap();
+                unsafe { (ptr.as_ptr() as *mut u64).write_volatile(STACK_CANARY) };
+                Self { ptr, layout }
+            }
+
+            pub const fn top(&self) -> VirtAddr {
+                unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
+            }
+
+            /// Whether the canary at the bottom of the stack is intact.
+            pub fn canary_intact(&self) -> bool {
+                unsafe { (self.ptr.as_ptr() as *const u64).read_volatile() == STACK_CANARY }
+            }
+        }
+
+        impl Drop for TaskStack {
+            fn drop(&mut self) {
+                unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+            }
+        }
+    }
+}
@@ -1,14 +1,18 @@
-use alloc::{boxed::Box, string::String, sync::Arc};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{boxed::Box, string::String, vec::Vec};
 use core::ops::Deref;
 use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, Ordering};
-use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
+use core::{cell::UnsafeCell, fmt};
 
-#[cfg(feature = "preempt")]
+#[cfg(any(feature = "preempt", feature = "uspace"))]
 use core::sync::atomic::AtomicUsize;
 
 use axhal::arch::TaskContext;
-use memory_addr::{align_up_4k, VirtAddr};
+use memory_addr::align_up_4k;
+use spinlock::SpinNoIrq;
 
+use crate::stack::TaskStack;
 use crate::{AxRunQueue, AxTask, AxTaskRef, WaitQueue};
 
 /// A unique identifier for a thread.
@@ -18,13 +22,20 @@ pub struct TaskId(u64);
 /// The possible states of a task.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub(crate) enum TaskState {
+pub enum TaskState {
+    /// The task is running on a CPU.
     Running = 1,
+    /// The task is ready to run, waiting in the run queue.
     Ready = 2,
+    /// The task is blocked, e.g., in a wait queue or sleeping.
     Blocked = 3,
+    /// The task has exited, but has not been dropped.
     Exited = 4,
 }
 
+/// All tasks that have not been dropped, by their IDs.
+static TASK_LIST: SpinNoIrq<BTreeMap<u64, Weak<AxTask>>> = SpinNoIrq::new(BTreeMap::new());
+
 /// The inner task structure.
 pub struct TaskInner {
     id: TaskId,
@@ -49,6 +60,10 @@ pub struct TaskInner {
 
     kstack: Option<TaskStack>,
     ctx: UnsafeCell<TaskContext>,
+
+    /// Physical address of the page table root, `0` for the kernel one.
+    #[cfg(feature = "uspace")]
+    page_table_root: AtomicUsize,
 }
 
 impl TaskId {
@@ -90,11 +105,50 @@ impl TaskInner {
         self.name.as_str()
     }
 
+    /// Gets the state of the task.
+    #[inline]
+    pub fn state(&self) -> TaskState {
+        self.state.load(Ordering::Acquire).into()
+    }
+
     /// Get a combined string of the task ID and name.
     pub fn id_name(&self) -> alloc::string::String {
         alloc::format!("Task({}, {:?})", self.id.as_u64(), self.name)
     }
 
+    /// Gets the top address of the kernel stack of the task.
+    ///
+    /// Returns [`None`] for the init tasks, which use the boot stacks.
+    pub fn kernel_stack_top(&self) -> Option<memory_addr::VirtAddr> {
+        self.kstack.as_ref().map(|kstack| kstack.top())
+    }
+
+    /// Gets the physical address of the page table root of the task.
+    ///
+    /// Returns [`None`] if the task uses the kernel page table.
+    #[cfg(feature = "uspace")]
+    pub fn page_table_root(&self) -> Option<memory_addr::PhysAddr> {
+        match self.page_table_root.load(Ordering::Acquire) {
+            0 => None,
+            root => Some(root.into()),
+        }
+    }
+
+    /// Sets the page table root of the task, [`None`] for the kernel page
+    /// table.
+    ///
+    /// If it is the current task, the page table is switched immediately.
+    /// Otherwise, it takes effect when the task is scheduled next time.
+    #[cfg(feature = "uspace")]
+    pub fn set_page_table_root(&self, root: Option<memory_addr::PhysAddr>) {
+        let _guard = kernel_guard::NoPreemptIrqSave::new();
+        let root_paddr = root.map_or(0, |root| root.as_usize());
+        self.page_table_root.store(root_paddr, Ordering::Release);
+        if core::ptr::eq(self, crate::current().deref()) {
+            unsafe { switch_page_table(root) };
+        }
+    }
+
     /// Wait for the task to exit, and return the exit code.
     ///
     /// It will return immediately if the task has already exited (but not dropped).
@@ -126,6 +180,8 @@ impl TaskInner {
             wait_for_exit: WaitQueue::new(),
             kstack: None,
             ctx: UnsafeCell::new(TaskContext::new()),
+            #[cfg(feature = "uspace")]
+            page_table_root: AtomicUsize::new(0),
         }
     }
 
@@ -142,7 +198,23 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        t.into_ref()
+    }
+
+    #[cfg(feature = "uspace")]
+    pub(crate) fn new_user<F>(
+        entry: F,
+        name: String,
+        stack_size: usize,
+        page_table_root: memory_addr::PhysAddr,
+    ) -> AxTaskRef
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let task = Self::new(entry, name, stack_size);
+        task.page_table_root
+            .store(page_table_root.as_usize(), Ordering::Release);
+        task
     }
 
     pub(crate) fn new_init(name: String) -> AxTaskRef {
@@ -152,12 +224,33 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        t.into_ref()
     }
 
-    #[inline]
-    pub(crate) fn state(&self) -> TaskState {
-        self.state.load(Ordering::Acquire).into()
+    /// Panics if the canary at the bottom of the stack has been overwritten.
+    #[cfg(not(feature = "paging"))]
+    pub(crate) fn check_stack_canary(&self) {
+        if let Some(kstack) = &self.kstack {
+            if !kstack.canary_intact() {
+                panic!("stack overflow in task {}", self.id_name());
+            }
+        }
+    }
+
+    /// Whether `vaddr` is in the guard page below the stack.
+    #[cfg(feature = "paging")]
+    pub(crate) fn stack_guard_contains(&self, vaddr: memory_addr::VirtAddr) -> bool {
+        self.kstack
+            .as_ref()
+            .map_or(false, |kstack| kstack.guard_contains(vaddr))
+    }
+
+    /// Wraps the task into a reference, and adds it to the task list.
+    fn into_ref(self) -> AxTaskRef {
+        let id = self.id.as_u64();
+        let task = Arc::new(AxTask::new(self));
+        TASK_LIST.lock().insert(id, Arc::downgrade(&task));
+        task
     }
 
     #[inline]
@@ -180,8 +273,10 @@ impl TaskInner {
         matches!(self.state(), TaskState::Blocked)
     }
 
+    /// Whether the task is the initial (main) task, whose exit shuts down the
+    /// system.
     #[inline]
-    pub(crate) const fn is_init(&self) -> bool {
+    pub const fn is_init(&self) -> bool {
         self.is_init
     }
 
@@ -274,32 +369,28 @@ impl fmt::Debug for TaskInner {
 impl Drop for TaskInner {
     fn drop(&mut self) {
         debug!("task drop: {}", self.id_name());
+        TASK_LIST.lock().remove(&self.id.as_u64());
     }
 }
 
-struct TaskStack {
-    ptr: NonNull<u8>,
-    layout: Layout,
+/// Switches to the given page table, [`None`] for the kernel page table.
+///
+/// User page tables are loaded into `TTBR0_EL1` on AArch64, and contain the
+/// kernel mappings on other architectures. Page tables are tagged with ASIDs
+/// to avoid flushing the TLB on each switch.
+#[cfg(feature = "uspace")]
+pub(crate) unsafe fn switch_page_table(root: Option<memory_addr::PhysAddr>) {
+    let root = root.unwrap_or_else(axmm::kernel_page_table_root);
+    axhal::tlb::switch_page_table(root);
 }
 
-impl TaskStack {
-    pub fn alloc(size: usize) -> Self {
-        let layout = Layout::from_size_align(size, 16).unwrap();
-        Self {
-            ptr: NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap(),
-            layout,
-        }
-    }
-
-    pub const fn top(&self) -> VirtAddr {
-        unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
-    }
-}
-
-impl Drop for TaskStack {
-    fn drop(&mut self) {
-        unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
-    }
+/// Returns all tasks that have not been dropped, in the order of their IDs.
+pub(crate) fn all_tasks() -> Vec<AxTaskRef> {
+    TASK_LIST
+        .lock()
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect()
 }
 
 use core::mem::ManuallyDrop;
@@ -128,3 +128,20 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_all_tasks() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let task = axtask::spawn_raw(|| axtask::exit(0), "all_tasks".into(), 0x1000);
+    let id = task.id();
+    let find = || axtask::all_tasks().into_iter().find(|t| t.id() == id);
+    assert!(find().is_some_and(|t| t.name() == "all_tasks"));
+    assert!(axtask::all_tasks().iter().any(|t| t.id() == current().id()));
+
+    assert_eq!(task.join(), Some(0));
+    assert_eq!(find().unwrap().state(), axtask::TaskState::Exited);
+    drop(task);
+    assert!(find().is_none());
+}
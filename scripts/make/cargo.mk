@@ -24,6 +24,10 @@ features-$(FS) += libax/fs
 features-$(NET) += libax/net
 features-$(GRAPHIC) += libax/display
 
+ifneq ($(INITRAMFS),)
+  features-y += libax/initramfs
+endif
+
 ifeq ($(BUS),pci)
   features-y += libax/bus-pci
 endif
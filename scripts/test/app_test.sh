@@ -113,6 +113,7 @@ if [ -z "$1" ]; then
         "apps/task/parallel"
         "apps/task/sleep"
         "apps/task/priority"
+        "apps/task/shootdown"
         "apps/net/httpclient"
         "apps/c/helloworld"
         "apps/c/memtest"
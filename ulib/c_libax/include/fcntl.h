@@ -33,6 +33,9 @@
 #define O_WRONLY  01
 #define O_RDWR    02
 
+#define AT_FDCWD            (-100)
+#define AT_SYMLINK_NOFOLLOW 0x100
+
 #define F_DUPFD 0
 #define F_GETFD 1
 #define F_SETFD 2
@@ -45,6 +45,10 @@ FILE *fopen(const char *filename, const char *mode);
 char *fgets(char *__restrict, int, FILE *__restrict);
 #endif
 
+#ifdef AX_CONFIG_FS
+int rename(const char *oldpath, const char *newpath);
+#endif
+
 int fflush(FILE *);
 
 int getchar();
@@ -42,8 +42,15 @@
 #define MREMAP_FIXED     2
 #define MREMAP_DONTUNMAP 4
 
+/* Flags for msync.  */
+#define MS_ASYNC      1
+#define MS_INVALIDATE 2
+#define MS_SYNC       4
+
 void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off);
 int munmap(void *addr, size_t length);
+int mprotect(void *addr, size_t len, int prot);
+int msync(void *addr, size_t len, int flags);
 void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
              ... /* void *new_address */);
 
@@ -15,15 +15,18 @@ struct stat {
     off_t st_size;            /* total size, in bytes*/
     blksize_t st_blksize;     /* blocksize for filesystem I/O*/
     blkcnt_t st_blocks;       /* number of blocks allocated*/
-    struct timespec st_atime; /* time of last access*/
-    struct timespec st_mtime; /* time of last modification*/
-    struct timespec st_ctime; /* time of last status change*/
+    struct timespec st_atim;  /* time of last access*/
+    struct timespec st_mtim;  /* time of last modification*/
+    struct timespec st_ctim;  /* time of last status change*/
 };
 
 #define st_atime st_atim.tv_sec
 #define st_mtime st_mtim.tv_sec
 #define st_ctime st_ctim.tv_sec
 
+#define UTIME_NOW  ((1l << 30) - 1l)
+#define UTIME_OMIT ((1l << 30) - 2l)
+
 #define S_IFMT 0170000
 
 #define S_IFDIR  0040000
@@ -73,4 +76,7 @@ int fchmod(int fd, mode_t mode);
 
 int mkdir(const char *pathname, mode_t mode);
 
+int utimensat(int dirfd, const char *path, const struct timespec times[2], int flags);
+int futimens(int fd, const struct timespec times[2]);
+
 #endif
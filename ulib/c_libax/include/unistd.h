@@ -14,6 +14,8 @@ ssize_t write(int fd, const void *buf, size_t count);
 ssize_t readlink(const char *path, char *buf, size_t bufsiz);
 int unlink(const char *pathname);
 int rmdir(const char *pathname);
+int link(const char *oldpath, const char *newpath);
+int symlink(const char *target, const char *linkpath);
 int ftruncate(int fd, off_t length);
 
 int access(const char *pathname, int mode);
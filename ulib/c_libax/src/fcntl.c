@@ -1,6 +1,7 @@
 #include <fcntl.h>
 #include <libax.h>
 #include <stdio.h>
+#include <sys/file.h>
 
 int fcntl(int fd, int cmd, ... /* arg */)
 {
@@ -27,4 +28,9 @@ int open(const char *filename, int flags, ...)
 
     return ax_open(filename, flags, mode);
 }
+
+int flock(int fd, int operation)
+{
+    return ax_flock(fd, operation);
+}
 #endif
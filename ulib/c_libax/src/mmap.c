@@ -1,5 +1,47 @@
+#include <libax.h>
+#include <stdarg.h>
 #include <stddef.h>
 #include <stdio.h>
+#include <sys/mman.h>
+
+#ifdef AX_CONFIG_PAGING
+
+void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off)
+{
+    return ax_mmap(addr, len, prot, flags, fildes, off);
+}
+
+int munmap(void *addr, size_t length)
+{
+    return ax_munmap(addr, length);
+}
+
+int mprotect(void *addr, size_t len, int prot)
+{
+    return ax_mprotect(addr, len, prot);
+}
+
+int msync(void *addr, size_t len, int flags)
+{
+    return ax_msync(addr, len, flags);
+}
+
+void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
+             ... /* void *new_address */)
+{
+    void *new_address = NULL;
+
+    if (flags & MREMAP_FIXED) {
+        va_list ap;
+        va_start(ap, flags);
+        new_address = va_arg(ap, void *);
+        va_end(ap);
+    }
+
+    return ax_mremap(old_address, old_size, new_size, flags, new_address);
+}
+
+#else
 
 // TODO:
 void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off)
@@ -22,3 +64,5 @@ void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
     unimplemented();
     return NULL;
 }
+
+#endif
@@ -1,4 +1,7 @@
+#include <fcntl.h>
+#include <libax.h>
 #include <stdio.h>
+#include <sys/stat.h>
 #include <sys/types.h>
 
 // TODO:
@@ -14,3 +17,15 @@ int mkdir(const char *pathname, mode_t mode)
     unimplemented();
     return 0;
 }
+
+#ifdef AX_CONFIG_FS
+int utimensat(int dirfd, const char *path, const struct timespec times[2], int flags)
+{
+    return ax_utimensat(dirfd, path, times, flags);
+}
+
+int futimens(int fd, const struct timespec times[2])
+{
+    return ax_utimensat(fd, NULL, times, 0);
+}
+#endif
@@ -229,3 +229,12 @@ char *fgets(char *restrict s, int n, FILE *restrict f)
 }
 
 #endif
+
+#ifdef AX_CONFIG_FS
+
+int rename(const char *oldpath, const char *newpath)
+{
+    return ax_rename(oldpath, newpath);
+}
+
+#endif
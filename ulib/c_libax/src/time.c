@@ -1,4 +1,5 @@
 #include <errno.h>
+#include <fcntl.h>
 #include <limits.h>
 #include <stddef.h>
 #include <stdio.h>
@@ -142,7 +143,7 @@ struct tm *localtime(const time_t *timep)
 time_t time(time_t *t)
 {
     struct timespec ts;
-    ax_clock_gettime(&ts);
+    ax_clock_gettime(CLOCK_REALTIME, &ts);
     time_t ret = ts.tv_sec;
     if (t)
         *t = ret;
@@ -160,17 +161,26 @@ int gettimeofday(struct timeval *tv, struct timezone *tz)
     return 0;
 }
 
-// TODO:
 int utimes(const char *filename, const struct timeval times[2])
 {
+#ifdef AX_CONFIG_FS
+    struct timespec ts[2];
+    if (!times)
+        return ax_utimensat(AT_FDCWD, filename, NULL, 0);
+    for (int i = 0; i < 2; i++) {
+        ts[i].tv_sec = times[i].tv_sec;
+        ts[i].tv_nsec = times[i].tv_usec * 1000;
+    }
+    return ax_utimensat(AT_FDCWD, filename, ts, 0);
+#else
     unimplemented();
     return 0;
+#endif
 }
 
-// TODO: Should match _clk,
 int clock_gettime(clockid_t _clk, struct timespec *ts)
 {
-    return ax_clock_gettime(ts);
+    return ax_clock_gettime(_clk, ts);
 }
 
 int nanosleep(const struct timespec *req, struct timespec *rem)
@@ -90,11 +90,19 @@ int stat(const char *path, struct stat *buf)
     return ax_stat(path, buf);
 }
 
-// TODO:
 ssize_t readlink(const char *path, char *buf, size_t bufsiz)
 {
-    unimplemented();
-    return 0;
+    return ax_readlink(path, buf, bufsiz);
+}
+
+int link(const char *oldpath, const char *newpath)
+{
+    return ax_link(oldpath, newpath);
+}
+
+int symlink(const char *target, const char *linkpath)
+{
+    return ax_symlink(target, linkpath);
 }
 
 // TODO:
@@ -67,8 +67,10 @@ typedef struct {{
             "jmp_buf",
             "fd.*",
             "timeval",
+            "clockid_t",
             "pthread_.*",
             "epoll_event",
+            "flock",
         ];
         let allow_vars = [
             "O_.*",
@@ -82,6 +84,14 @@ typedef struct {{
             "SOL_.*",
             "EPOLL_CTL_.*",
             "EPOLL.*",
+            "CLOCK_.*",
+            "AT_.*",
+            "UTIME_.*",
+            "LOCK_.*",
+            "PROT_.*",
+            "MAP_.*",
+            "MREMAP_.*",
+            "MS_.*",
         ];
 
         #[derive(Debug)]
@@ -156,7 +156,8 @@ pub unsafe extern "C" fn ax_dup3(old_fd: c_int, new_fd: c_int, flags: c_int) ->
 
 /// Fcntl implementation
 ///
-/// TODO: `SET/GET` command is ignored
+/// TODO: `SET/GET` command is ignored, except the record locks (`F_GETLK`,
+/// `F_SETLK` and `F_SETLKW`) of files.
 #[no_mangle]
 pub unsafe extern "C" fn ax_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
     debug!("ax_fcntl <= fd: {} cmd: {} arg: {}", fd, cmd, arg);
@@ -171,6 +172,10 @@ pub unsafe extern "C" fn ax_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
                 get_file_like(fd)?.set_nonblocking(arg & (ctypes::O_NONBLOCK as usize) > 0)?;
                 Ok(0)
             }
+            #[cfg(feature = "fs")]
+            ctypes::F_GETLK | ctypes::F_SETLK | ctypes::F_SETLKW => {
+                super::file::fcntl_lock(fd, cmd as u32, arg as *mut ctypes::flock)
+            }
             _ => {
                 warn!("unsupported fcntl parameters: cmd {}", cmd);
                 Ok(0)
@@ -1,11 +1,13 @@
 use alloc::sync::Arc;
 use axerrno::{LinuxError, LinuxResult};
-use core::ffi::{c_char, c_int};
+use core::ffi::{c_char, c_int, c_long};
+use core::time::Duration;
 
 use super::{ctypes, fd_ops::FileLike, utils::char_ptr_to_str};
-use crate::fs::OpenOptions;
+use crate::fs::{LockType, OpenOptions};
 use crate::io::{prelude::*, PollState, SeekFrom};
 use crate::sync::Mutex;
+use crate::time::{SystemTime, UNIX_EPOCH};
 
 pub struct File(Mutex<crate::fs::File>);
 
@@ -18,7 +20,7 @@ impl File {
         super::fd_ops::add_file_like(Arc::new(self))
     }
 
-    fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
+    pub(super) fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
         let f = super::fd_ops::get_file_like(fd)?;
         f.into_any()
             .downcast::<Self>()
@@ -26,6 +28,38 @@ impl File {
     }
 }
 
+#[cfg(feature = "paging")]
+impl axmm::MmapFile for File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> axerrno::AxResult<usize> {
+        let mut file = self.0.lock();
+        let pos = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut read_len = 0;
+        let res = loop {
+            match file.read(&mut buf[read_len..]) {
+                Ok(0) => break Ok(read_len),
+                Ok(n) => read_len += n,
+                Err(e) => break Err(e),
+            }
+        };
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> axerrno::AxResult {
+        let mut file = self.0.lock();
+        let pos = file.stream_position()?;
+        file.seek(SeekFrom::Start(offset))?;
+        let res = file.write_all(buf);
+        file.seek(SeekFrom::Start(pos))?;
+        res
+    }
+
+    fn size(&self) -> axerrno::AxResult<u64> {
+        Ok(self.0.lock().metadata()?.len())
+    }
+}
+
 impl FileLike for File {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
         let len = self.0.lock().read(buf)?;
@@ -39,21 +73,7 @@ impl FileLike for File {
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
         let metadata = self.0.lock().metadata()?;
-        let metadata = metadata.raw_metadata();
-        let ty = metadata.file_type() as u8;
-        let perm = metadata.perm().bits() as u32;
-        let st_mode = ((ty as u32) << 12) | perm;
-        Ok(ctypes::stat {
-            st_ino: 1,
-            st_nlink: 1,
-            st_mode,
-            st_uid: 1000,
-            st_gid: 1000,
-            st_size: metadata.size() as _,
-            st_blocks: metadata.blocks() as _,
-            st_blksize: 512,
-            ..Default::default()
-        })
+        Ok(metadata_to_stat(&metadata))
     }
 
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
@@ -72,6 +92,28 @@ impl FileLike for File {
     }
 }
 
+/// Convert file [`Metadata`](crate::fs::Metadata) to `struct stat`.
+fn metadata_to_stat(metadata: &crate::fs::Metadata) -> ctypes::stat {
+    let metadata = metadata.raw_metadata();
+    let ty = metadata.file_type() as u8;
+    let perm = metadata.perm().bits() as u32;
+    let st_mode = ((ty as u32) << 12) | perm;
+    ctypes::stat {
+        st_ino: 1,
+        st_nlink: 1,
+        st_mode,
+        st_uid: 1000,
+        st_gid: 1000,
+        st_size: metadata.size() as _,
+        st_blocks: metadata.blocks() as _,
+        st_blksize: 512,
+        st_atim: metadata.atime().into(),
+        st_mtim: metadata.mtime().into(),
+        st_ctim: metadata.ctime().into(),
+        ..Default::default()
+    }
+}
+
 /// Convert open flags to [`OpenOptions`].
 fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     let flags = flags as u32;
@@ -93,6 +135,9 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     if flags & ctypes::O_EXEC != 0 {
         options.create_new(true);
     }
+    if flags & ctypes::O_NOFOLLOW != 0 {
+        options.nofollow(true);
+    }
     options
 }
 
@@ -157,6 +202,8 @@ pub unsafe extern "C" fn ax_stat(path: *const c_char, buf: *mut ctypes::stat) ->
 
 /// Get the metadata of the symbolic link and write into `buf`.
 ///
+/// Same as `ax_stat` if `path` is not a symbolic link.
+///
 /// Return 0 if success.
 #[no_mangle]
 pub unsafe extern "C" fn ax_lstat(path: *const c_char, buf: *mut ctypes::stat) -> ctypes::ssize_t {
@@ -166,7 +213,146 @@ pub unsafe extern "C" fn ax_lstat(path: *const c_char, buf: *mut ctypes::stat) -
         if buf.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        unsafe { *buf = Default::default() }; // TODO
+        let metadata = crate::fs::symlink_metadata(path?)?;
+        unsafe { *buf = metadata_to_stat(&metadata) };
+        Ok(0)
+    })
+}
+
+/// Read the target of the symbolic link `path` into `buf`, without a
+/// terminating null byte. The target is truncated if `bufsiz` is too small.
+///
+/// Return the number of bytes placed in `buf`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_readlink(
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsiz: usize,
+) -> ctypes::ssize_t {
+    let path = char_ptr_to_str(path);
+    debug!("ax_readlink <= {:?} {:#x} {}", path, buf as usize, bufsiz);
+    ax_call_body!(ax_readlink, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let target = crate::fs::read_link(path?)?;
+        let len = target.len().min(bufsiz);
+        let dst = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, len) };
+        dst.copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len)
+    })
+}
+
+/// Create a symbolic link `linkpath` pointing to `target`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_symlink(target: *const c_char, linkpath: *const c_char) -> c_int {
+    let target = char_ptr_to_str(target);
+    let linkpath = char_ptr_to_str(linkpath);
+    debug!("ax_symlink <= {:?} {:?}", target, linkpath);
+    ax_call_body!(ax_symlink, {
+        crate::fs::symlink(target?, linkpath?)?;
+        Ok(0)
+    })
+}
+
+/// Convert a timestamp given to `utimensat` to the new time, or `None` if it is
+/// `UTIME_OMIT`.
+fn timespec_to_file_time(ts: &ctypes::timespec, now: Duration) -> LinuxResult<Option<Duration>> {
+    match ts.tv_nsec {
+        nsec if nsec == ctypes::UTIME_NOW as c_long => Ok(Some(now)),
+        nsec if nsec == ctypes::UTIME_OMIT as c_long => Ok(None),
+        nsec if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&nsec) => Err(LinuxError::EINVAL),
+        _ => Ok(Some((*ts).into())),
+    }
+}
+
+/// Change the access and modification time of the file at `path`.
+///
+/// `times[0]` is the new access time and `times[1]` is the new modification
+/// time. Both are set to the current time if `times` is NULL. If `path` is
+/// NULL, change the timestamps of the file indicated by `dirfd` (`futimens`).
+/// With `AT_SYMLINK_NOFOLLOW` in `flags`, a symbolic link itself is changed.
+///
+/// Only `AT_FDCWD` or absolute paths are supported currently.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_utimensat(
+    dirfd: c_int,
+    path: *const c_char,
+    times: *const ctypes::timespec,
+    flags: c_int,
+) -> c_int {
+    let path = if path.is_null() {
+        None
+    } else {
+        Some(char_ptr_to_str(path))
+    };
+    debug!(
+        "ax_utimensat <= {} {:?} {:#x} {:#x}",
+        dirfd, path, times as usize, flags
+    );
+    ax_call_body!(ax_utimensat, {
+        if flags as u32 & !ctypes::AT_SYMLINK_NOFOLLOW != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let follow = flags as u32 & ctypes::AT_SYMLINK_NOFOLLOW == 0;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let (atime, mtime) = if times.is_null() {
+            (Some(now), Some(now))
+        } else {
+            let times = unsafe { core::slice::from_raw_parts(times, 2) };
+            (
+                timespec_to_file_time(&times[0], now)?,
+                timespec_to_file_time(&times[1], now)?,
+            )
+        };
+        match path {
+            Some(path) => {
+                let path = path?;
+                if dirfd != ctypes::AT_FDCWD && !path.starts_with('/') {
+                    return Err(LinuxError::EINVAL); // TODO: support `dirfd`
+                }
+                if follow {
+                    crate::fs::set_times(path, atime, mtime)?;
+                } else {
+                    crate::fs::set_symlink_times(path, atime, mtime)?;
+                }
+            }
+            None => File::from_fd(dirfd)?.0.lock().set_times(atime, mtime)?,
+        }
+        Ok(0)
+    })
+}
+
+/// Rename the file or directory at `old` to `new`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_rename(old: *const c_char, new: *const c_char) -> c_int {
+    let old = char_ptr_to_str(old);
+    let new = char_ptr_to_str(new);
+    debug!("ax_rename <= {:?} {:?}", old, new);
+    ax_call_body!(ax_rename, {
+        crate::fs::rename(old?, new?)?;
+        Ok(0)
+    })
+}
+
+/// Create a hard link `new` to the existing file `old`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn ax_link(old: *const c_char, new: *const c_char) -> c_int {
+    let old = char_ptr_to_str(old);
+    let new = char_ptr_to_str(new);
+    debug!("ax_link <= {:?} {:?}", old, new);
+    ax_call_body!(ax_link, {
+        crate::fs::hard_link(old?, new?)?;
         Ok(0)
     })
 }
@@ -191,3 +377,92 @@ pub unsafe extern "C" fn ax_getcwd(buf: *mut c_char, size: usize) -> *mut c_char
         }
     })
 }
+
+/// Apply or remove an advisory lock on the whole file indicated by `fd`.
+///
+/// `operation` is one of `LOCK_SH`, `LOCK_EX` and `LOCK_UN`, optionally with
+/// `LOCK_NB` to return `EWOULDBLOCK` instead of blocking.
+#[no_mangle]
+pub unsafe extern "C" fn ax_flock(fd: c_int, operation: c_int) -> c_int {
+    debug!("ax_flock <= {} {:#x}", fd, operation);
+    ax_call_body!(ax_flock, {
+        let operation = operation as u32;
+        let ty = match operation & !ctypes::LOCK_NB {
+            ctypes::LOCK_SH => Some(LockType::Shared),
+            ctypes::LOCK_EX => Some(LockType::Exclusive),
+            ctypes::LOCK_UN => None,
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let wait = operation & ctypes::LOCK_NB == 0;
+        File::from_fd(fd)?.0.lock().flock(ty, wait)?;
+        Ok(0)
+    })
+}
+
+/// Apply, remove or test an advisory record lock described by `lock`, for
+/// `fcntl` with `F_SETLK`, `F_SETLKW` or `F_GETLK`.
+///
+/// Record locks are owned by the opened file (shared by duplicated fds), like
+/// the open file description locks of Linux.
+pub(super) fn fcntl_lock(fd: c_int, cmd: u32, lock: *mut ctypes::flock) -> LinuxResult<c_int> {
+    if lock.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let lock = unsafe { &mut *lock };
+    let file = File::from_fd(fd)?;
+    let mut file = file.0.lock();
+
+    let base = match lock.l_whence {
+        0 => 0,
+        1 => file.stream_position()? as i64,
+        2 => file.metadata()?.len() as i64,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    let start = base
+        .checked_add(lock.l_start)
+        .ok_or(LinuxError::EOVERFLOW)?;
+    // `None` for the end of file, even if it grows
+    let (start, end) = match lock.l_len {
+        0 => (start, None),
+        len if len > 0 => (
+            start,
+            Some(start.checked_add(len).ok_or(LinuxError::EOVERFLOW)?),
+        ),
+        len => (start + len, Some(start)),
+    };
+    if start < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let range = start as u64..end.map_or(u64::MAX, |end| end as u64);
+
+    let ty = match lock.l_type as u32 {
+        ctypes::F_RDLCK => Some(LockType::Shared),
+        ctypes::F_WRLCK => Some(LockType::Exclusive),
+        ctypes::F_UNLCK => None,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    match cmd {
+        ctypes::F_GETLK => {
+            let ty = ty.ok_or(LinuxError::EINVAL)?;
+            match file.test_lock_range(ty, range)? {
+                Some((ty, range)) => {
+                    lock.l_type = match ty {
+                        LockType::Shared => ctypes::F_RDLCK,
+                        LockType::Exclusive => ctypes::F_WRLCK,
+                    } as _;
+                    lock.l_whence = 0;
+                    lock.l_start = range.start as _;
+                    lock.l_len = if range.end == u64::MAX {
+                        0
+                    } else {
+                        (range.end - range.start) as _
+                    };
+                    lock.l_pid = -1; // not owned by a process
+                }
+                None => lock.l_type = ctypes::F_UNLCK as _,
+            }
+        }
+        _ => file.lock_range(ty, range, cmd == ctypes::F_SETLKW)?,
+    }
+    Ok(0)
+}
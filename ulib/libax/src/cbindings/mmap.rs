@@ -0,0 +1,392 @@
+//! Memory mappings for C programs.
+//!
+//! All mappings are areas of the kernel address space (see [`axmm`]), within
+//! the region `[MMAP_VADDR_BASE, MMAP_VADDR_BASE + MMAP_VADDR_SIZE)` of the
+//! platform. Anonymous mappings are allocated on the first access. File
+//! mappings read the file contents when they are created, and shared ones
+//! write the contents back on `msync` and `munmap`. The files are accessed
+//! without the lock of the address space held.
+
+use axconfig::{MMAP_VADDR_BASE, MMAP_VADDR_SIZE};
+use axerrno::{LinuxError, LinuxResult};
+use axhal::mem::{VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::MappingFlags;
+use axmm::{AddrSpace, Backend, FilePages, Writeback};
+use core::ffi::{c_int, c_void};
+use core::ops::Range;
+
+use super::ctypes;
+
+#[cfg(feature = "fs")]
+use super::file::File;
+
+const MMAP_VADDR_END: usize = MMAP_VADDR_BASE + MMAP_VADDR_SIZE;
+
+fn mmap_region() -> Range<VirtAddr> {
+    VirtAddr::from(MMAP_VADDR_BASE)..VirtAddr::from(MMAP_VADDR_END)
+}
+
+const fn align_up(size: usize) -> usize {
+    (size + PAGE_SIZE_4K - 1) & !(PAGE_SIZE_4K - 1)
+}
+
+const fn is_aligned(addr: usize) -> bool {
+    addr & (PAGE_SIZE_4K - 1) == 0
+}
+
+/// Whether `[start, start + size)` is in the mmap region.
+const fn in_region(start: usize, size: usize) -> bool {
+    start >= MMAP_VADDR_BASE
+        && size <= MMAP_VADDR_SIZE
+        && start - MMAP_VADDR_BASE <= MMAP_VADDR_SIZE - size
+}
+
+/// Returns the length `size` rounded up to whole pages, which must be non-zero
+/// and fit in the mmap area.
+fn aligned_len(size: usize) -> LinuxResult<usize> {
+    if size == 0 || size > MMAP_VADDR_SIZE {
+        Err(LinuxError::EINVAL)
+    } else {
+        Ok(align_up(size))
+    }
+}
+
+fn do_mmap(
+    addr: usize,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    offset: ctypes::off_t,
+) -> LinuxResult<usize> {
+    let size = aligned_len(len)?;
+    let map_flags = axmm::prot_to_flags(prot as u32)?;
+    let flags = flags as u32;
+    let shared = match flags & ctypes::MAP_TYPE {
+        ctypes::MAP_SHARED | ctypes::MAP_SHARED_VALIDATE => true,
+        ctypes::MAP_PRIVATE => false,
+        _ => return Err(LinuxError::EINVAL),
+    };
+    if offset < 0 || !is_aligned(offset as usize) {
+        return Err(LinuxError::EINVAL);
+    }
+
+    let pages = if flags & ctypes::MAP_ANONYMOUS != 0 {
+        None
+    } else {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "fs")] {
+                let backend = Backend::File {
+                    file: File::from_fd(fd)?,
+                    offset: offset as u64,
+                    shared,
+                };
+                Some(FilePages::read(backend, size)?)
+            } else {
+                let _ = (fd, shared);
+                return Err(LinuxError::EBADF);
+            }
+        }
+    };
+
+    let mut writeback = Writeback::default();
+    let res = map_locked(
+        &mut axmm::kernel_aspace().lock(),
+        &mut writeback,
+        addr,
+        size,
+        map_flags,
+        flags & ctypes::MAP_FIXED != 0,
+        pages,
+    );
+    writeback.write()?;
+    res
+}
+
+/// The part of [`do_mmap`] with the address space locked. The replaced
+/// mappings are moved to `writeback`.
+fn map_locked(
+    aspace: &mut AddrSpace,
+    writeback: &mut Writeback,
+    addr: usize,
+    size: usize,
+    map_flags: MappingFlags,
+    fixed: bool,
+    pages: Option<FilePages>,
+) -> LinuxResult<usize> {
+    let start = if fixed {
+        if !is_aligned(addr) || !in_region(addr, size) {
+            return Err(LinuxError::EINVAL);
+        }
+        writeback.append(aspace.unmap_deferred(addr.into(), size)?);
+        addr.into()
+    } else {
+        aspace
+            .find_free_area(addr.into(), size, mmap_region())
+            .ok_or(LinuxError::ENOMEM)?
+    };
+    match pages {
+        Some(pages) => aspace.map_file_pages(start, map_flags, pages)?,
+        None => aspace.map(start, size, map_flags, Backend::Alloc { populate: false })?,
+    }
+    Ok(start.as_usize())
+}
+
+/// Returns the mapping flags of the mapping that contains `[start, end)`, and
+/// the backend for the part after `end`.
+fn mapping_at(
+    aspace: &AddrSpace,
+    start: usize,
+    end: usize,
+) -> LinuxResult<(MappingFlags, Backend)> {
+    match aspace.find_area(start.into()) {
+        Some(area) if area.end().as_usize() >= end => {
+            let backend = match area.backend() {
+                Backend::File {
+                    file,
+                    offset,
+                    shared,
+                } => Backend::File {
+                    file: file.clone(),
+                    offset: offset + (end - area.start().as_usize()) as u64,
+                    shared: *shared,
+                },
+                backend => backend.clone(),
+            };
+            Ok((area.flags(), backend))
+        }
+        _ => Err(LinuxError::EFAULT),
+    }
+}
+
+fn do_mremap(
+    old_addr: usize,
+    old_size: usize,
+    new_size: usize,
+    flags: c_int,
+    new_addr: usize,
+) -> LinuxResult<usize> {
+    let flags = flags as u32;
+    if flags & !(ctypes::MREMAP_MAYMOVE | ctypes::MREMAP_FIXED) != 0
+        || (flags & ctypes::MREMAP_FIXED != 0 && flags & ctypes::MREMAP_MAYMOVE == 0)
+        || !is_aligned(old_addr)
+    {
+        return Err(LinuxError::EINVAL);
+    }
+    let old_size = aligned_len(old_size)?;
+    let new_size = aligned_len(new_size)?;
+    if !in_region(old_addr, old_size) {
+        return Err(LinuxError::EFAULT);
+    }
+
+    // read the file contents of the expanded part before locking the address
+    // space. The pages are mapped with the backend they are read from, even
+    // if the mapping has been changed in the meantime.
+    let old_end = old_addr + old_size;
+    let pages = if new_size > old_size {
+        let (_, backend) = mapping_at(&axmm::kernel_aspace().lock(), old_addr, old_end)?;
+        match backend {
+            Backend::File { .. } => Some(FilePages::read(backend, new_size - old_size)?),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let mut writeback = Writeback::default();
+    let res = remap_locked(
+        &mut axmm::kernel_aspace().lock(),
+        &mut writeback,
+        (old_addr, old_size),
+        new_size,
+        flags,
+        new_addr,
+        pages,
+    );
+    writeback.write()?;
+    res
+}
+
+/// The part of [`do_mremap`] with the address space locked. The removed
+/// mappings are moved to `writeback`.
+fn remap_locked(
+    aspace: &mut AddrSpace,
+    writeback: &mut Writeback,
+    (old_addr, old_size): (usize, usize),
+    new_size: usize,
+    flags: u32,
+    new_addr: usize,
+    pages: Option<FilePages>,
+) -> LinuxResult<usize> {
+    let old_end = old_addr + old_size;
+    // the range must be in a single mapping
+    let (map_flags, backend) = mapping_at(aspace, old_addr, old_end)?;
+
+    let target = if flags & ctypes::MREMAP_FIXED != 0 {
+        if !is_aligned(new_addr)
+            || !in_region(new_addr, new_size)
+            || (new_addr < old_end && old_addr < new_addr + new_size)
+        {
+            return Err(LinuxError::EINVAL);
+        }
+        writeback.append(aspace.unmap_deferred(new_addr.into(), new_size)?);
+        new_addr
+    } else if new_size <= old_size {
+        writeback.append(aspace.unmap_deferred((old_addr + new_size).into(), old_size - new_size)?);
+        return Ok(old_addr);
+    } else if in_region(old_end, new_size - old_size)
+        && aspace.is_free(old_end.into(), new_size - old_size)
+    {
+        old_addr
+    } else if flags & ctypes::MREMAP_MAYMOVE != 0 {
+        aspace
+            .find_free_area(mmap_region().start, new_size, mmap_region())
+            .ok_or(LinuxError::ENOMEM)?
+            .as_usize()
+    } else {
+        return Err(LinuxError::ENOMEM);
+    };
+
+    let keep_size = old_size.min(new_size);
+    if keep_size < old_size {
+        writeback
+            .append(aspace.unmap_deferred((old_addr + keep_size).into(), old_size - keep_size)?);
+    }
+    if target != old_addr {
+        aspace.move_area(old_addr.into(), keep_size, target.into())?;
+    }
+    if new_size > keep_size {
+        let start = (target + keep_size).into();
+        match pages {
+            Some(pages) => aspace.map_file_pages(start, map_flags, pages)?,
+            None => aspace.map(start, new_size - keep_size, map_flags, backend)?,
+        }
+    }
+    Ok(target)
+}
+
+/// Creates a new mapping of `len` bytes, and returns its start address.
+///
+/// Anonymous mappings are filled with zeros, and file mappings are filled
+/// with the contents of the file `fd` starting from `off`. `MAP_SHARED`
+/// file mappings write the contents back to the file on `msync` and
+/// `munmap`.
+///
+/// Returns `MAP_FAILED` on failure.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mmap(
+    addr: *mut c_void,
+    len: ctypes::size_t,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    debug!(
+        "ax_mmap <= addr: {:#x}, len: {:#x}, prot: {:#x}, flags: {:#x}, fd: {}, off: {:#x}",
+        addr as usize, len, prot, flags, fd, off
+    );
+    ax_call_body!(ax_mmap, {
+        do_mmap(addr as usize, len as usize, prot, flags, fd, off)
+    })
+}
+
+/// Deletes the mappings in the range `[addr, addr + len)`.
+#[no_mangle]
+pub unsafe extern "C" fn ax_munmap(addr: *mut c_void, len: ctypes::size_t) -> c_int {
+    debug!("ax_munmap <= addr: {:#x}, len: {:#x}", addr as usize, len);
+    ax_call_body!(ax_munmap, {
+        let (addr, size) = (addr as usize, aligned_len(len as usize)?);
+        if !is_aligned(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        // only the mappings in the mmap region can be deleted
+        let start = addr.max(MMAP_VADDR_BASE);
+        let end = addr.saturating_add(size).min(MMAP_VADDR_END);
+        if start < end {
+            let writeback = axmm::kernel_aspace()
+                .lock()
+                .unmap_deferred(start.into(), end - start)?;
+            writeback.write()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Changes the access protections of the mappings in the range
+/// `[addr, addr + len)`, which must be fully mapped.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mprotect(addr: *mut c_void, len: ctypes::size_t, prot: c_int) -> c_int {
+    debug!(
+        "ax_mprotect <= addr: {:#x}, len: {:#x}, prot: {:#x}",
+        addr as usize, len, prot
+    );
+    ax_call_body!(ax_mprotect, {
+        let (addr, size) = (addr as usize, aligned_len(len as usize)?);
+        let flags = axmm::prot_to_flags(prot as u32)?;
+        if !is_aligned(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut aspace = axmm::kernel_aspace().lock();
+        if !in_region(addr, size) || !aspace.is_mapped(addr.into(), size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        aspace.protect(addr.into(), size, flags)?;
+        Ok(0)
+    })
+}
+
+/// Expands (or shrinks) the mapping at `old_addr`, possibly moving it if
+/// `MREMAP_MAYMOVE` is specified, and returns the new start address.
+///
+/// `new_addr` is only used if `MREMAP_FIXED` is specified.
+///
+/// Returns `MAP_FAILED` on failure.
+#[no_mangle]
+pub unsafe extern "C" fn ax_mremap(
+    old_addr: *mut c_void,
+    old_size: ctypes::size_t,
+    new_size: ctypes::size_t,
+    flags: c_int,
+    new_addr: *mut c_void,
+) -> *mut c_void {
+    debug!(
+        "ax_mremap <= old_addr: {:#x}, old_size: {:#x}, new_size: {:#x}, flags: {:#x}",
+        old_addr as usize, old_size, new_size, flags
+    );
+    ax_call_body!(ax_mremap, {
+        do_mremap(
+            old_addr as usize,
+            old_size as usize,
+            new_size as usize,
+            flags,
+            new_addr as usize,
+        )
+    })
+}
+
+/// Writes the shared file mappings in the range `[addr, addr + len)` back to
+/// the files.
+#[no_mangle]
+pub unsafe extern "C" fn ax_msync(addr: *mut c_void, len: ctypes::size_t, flags: c_int) -> c_int {
+    debug!(
+        "ax_msync <= addr: {:#x}, len: {:#x}, flags: {:#x}",
+        addr as usize, len, flags
+    );
+    ax_call_body!(ax_msync, {
+        let (addr, size) = (addr as usize, align_up(len as usize));
+        if !is_aligned(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        let writeback = {
+            let aspace = axmm::kernel_aspace().lock();
+            if !in_region(addr, size) || !aspace.is_mapped(addr.into(), size) {
+                return Err(LinuxError::ENOMEM);
+            }
+            // also write back the parts out of the range, instead of splitting
+            aspace.writeback(addr.into(), size)?
+        };
+        writeback.write()?;
+        Ok(0)
+    })
+}
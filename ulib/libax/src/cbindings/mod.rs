@@ -19,6 +19,8 @@ mod file;
 mod io_mpx;
 #[cfg(feature = "alloc")]
 mod malloc;
+#[cfg(feature = "paging")]
+mod mmap;
 #[cfg(feature = "pipe")]
 mod pipe;
 #[cfg(feature = "multitask")]
@@ -64,8 +66,14 @@ pub use self::malloc::{ax_free, ax_malloc};
 #[cfg(feature = "alloc")]
 pub use self::fd_ops::{ax_close, ax_dup, ax_dup3, ax_fcntl, ax_fstat, ax_read, ax_write};
 
+#[cfg(feature = "paging")]
+pub use self::mmap::{ax_mmap, ax_mprotect, ax_mremap, ax_msync, ax_munmap};
+
 #[cfg(feature = "fs")]
-pub use self::file::{ax_getcwd, ax_lseek, ax_lstat, ax_open, ax_stat};
+pub use self::file::{
+    ax_getcwd, ax_link, ax_lseek, ax_lstat, ax_open, ax_readlink, ax_rename, ax_stat, ax_symlink,
+    ax_utimensat,
+};
 
 #[cfg(feature = "net")]
 pub use self::socket::{
@@ -1,4 +1,4 @@
-use crate::time::Instant;
+use crate::time::{Instant, SystemTime, UNIX_EPOCH};
 use axerrno::LinuxError;
 use core::ffi::{c_int, c_long};
 use core::time::Duration;
@@ -35,14 +35,27 @@ impl From<Duration> for ctypes::timeval {
     }
 }
 
-/// Get clock time since booting
+/// Get the time of the specified clock.
+///
+/// `CLOCK_REALTIME` is the wall time since the UNIX epoch, and
+/// `CLOCK_MONOTONIC` is the time since booting.
 #[no_mangle]
-pub unsafe extern "C" fn ax_clock_gettime(ts: *mut ctypes::timespec) -> c_int {
+pub unsafe extern "C" fn ax_clock_gettime(
+    clk: ctypes::clockid_t,
+    ts: *mut ctypes::timespec,
+) -> c_int {
     ax_call_body!(ax_clock_gettime, {
         if ts.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        let now = Instant::now().as_duration().into();
+        let now = match clk as u32 {
+            ctypes::CLOCK_REALTIME => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            ctypes::CLOCK_MONOTONIC => Instant::now().as_duration(),
+            _ => return Err(LinuxError::EINVAL),
+        }
+        .into();
         unsafe { *ts = now };
         debug!("ax_clock_gettime: {}.{:09}s", now.tv_sec, now.tv_nsec);
         Ok(0)
@@ -1,5 +1,10 @@
 //! Filesystem manipulation operations.
 
+pub use axfs::api::LockType;
+pub use axfs::api::{cache_stats, sync, CacheStats};
 pub use axfs::api::{canonicalize, metadata, read, read_to_string, remove_file, write};
 pub use axfs::api::{create_dir, create_dir_all, read_dir, remove_dir};
+pub use axfs::api::{hard_link, read_link, rename, symlink, symlink_metadata};
+pub use axfs::api::{mount, mount_device, mounts, partitions, umount, MountInfo, PartitionInfo};
+pub use axfs::api::{set_symlink_times, set_times};
 pub use axfs::api::{DirEntry, File, FileType, Metadata, OpenOptions, Permissions, ReadDir};
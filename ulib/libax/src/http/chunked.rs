@@ -0,0 +1,106 @@
+use axerrno::{ax_err, ax_err_type};
+
+use crate::io::{self, prelude::*};
+
+/// A reader that decodes a body sent with `Transfer-Encoding: chunked`.
+///
+/// It returns EOF after the last (zero-sized) chunk and the trailer section
+/// have been consumed, leaving the underlying reader at the start of the next
+/// message.
+pub struct ChunkedReader<'a, R: BufRead> {
+    inner: &'a mut R,
+    remaining: usize,
+    finished: bool,
+}
+
+impl<'a, R: BufRead> ChunkedReader<'a, R> {
+    /// Creates a new decoder reading from `inner`.
+    pub fn new(inner: &'a mut R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            finished: false,
+        }
+    }
+
+    fn read_chunk_size(&mut self) -> io::Result<usize> {
+        let line = super::read_line(self.inner)?
+            .ok_or_else(|| ax_err_type!(UnexpectedEof, "http: missing chunk size"))?;
+        // ignore chunk extensions
+        let size = line.split(';').next().unwrap_or("").trim();
+        usize::from_str_radix(size, 16)
+            .map_err(|_| ax_err_type!(InvalidData, "http: invalid chunk size"))
+    }
+
+    fn read_crlf(&mut self) -> io::Result {
+        match super::read_line(self.inner)? {
+            Some(line) if line.is_empty() => Ok(()),
+            _ => ax_err!(InvalidData, "http: missing CRLF after chunk"),
+        }
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.finished || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            self.remaining = self.read_chunk_size()?;
+            if self.remaining == 0 {
+                // skip the trailer section
+                super::read_headers(self.inner)?;
+                self.finished = true;
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            return ax_err!(UnexpectedEof, "http: truncated chunk");
+        }
+        self.remaining -= n;
+        if self.remaining == 0 {
+            self.read_crlf()?;
+        }
+        Ok(n)
+    }
+}
+
+/// A writer that encodes everything written to it as chunks of
+/// `Transfer-Encoding: chunked`.
+///
+/// [`finish`](ChunkedWriter::finish) must be called to emit the last chunk.
+pub struct ChunkedWriter<'a, W: Write> {
+    inner: &'a mut W,
+}
+
+impl<'a, W: Write> ChunkedWriter<'a, W> {
+    /// Creates a new encoder writing to `inner`.
+    pub fn new(inner: &'a mut W) -> Self {
+        Self { inner }
+    }
+
+    /// Writes the terminating zero-sized chunk.
+    pub fn finish(self) -> io::Result {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // an empty chunk would terminate the body
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:x}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result {
+        self.inner.flush()
+    }
+}
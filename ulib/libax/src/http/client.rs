@@ -0,0 +1,197 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::{ax_err, ax_err_type};
+
+use super::{Method, Request, Response};
+use crate::io::{self, BufReader};
+use crate::net::{TcpStream, ToSocketAddrs};
+
+/// Components of an `http://` URL.
+struct Url<'a> {
+    host: &'a str,
+    port: u16,
+    target: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> io::Result<Self> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| ax_err_type!(Unsupported, "http: only http:// URLs are supported"))?;
+        let (authority, target) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| ax_err_type!(InvalidInput, "http: invalid port in URL"))?;
+                (host, port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return ax_err!(InvalidInput, "http: missing host in URL");
+        }
+        Ok(Self { host, port, target })
+    }
+
+    /// Value of the `Host` header.
+    fn authority(&self) -> String {
+        if self.port == 80 {
+            self.host.to_string()
+        } else {
+            alloc::format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// An open connection kept by [`Client`] for reuse.
+struct Connection {
+    host: String,
+    port: u16,
+    reader: BufReader<TcpStream>,
+}
+
+/// A blocking HTTP/1.1 client.
+///
+/// It keeps the connection to the last server open, and reuses it for the
+/// following requests to the same host and port if the server allows.
+#[derive(Default)]
+pub struct Client {
+    conn: Option<Connection>,
+}
+
+impl Client {
+    /// Creates a new client.
+    pub const fn new() -> Self {
+        Self { conn: None }
+    }
+
+    /// Starts building a request to the given URL.
+    pub fn request<'a>(&'a mut self, method: Method, url: &str) -> RequestBuilder<'a> {
+        RequestBuilder {
+            client: self,
+            url: url.to_string(),
+            req: Request::new(method, "/"),
+        }
+    }
+
+    /// Sends a `GET` request to the given URL.
+    pub fn get(&mut self, url: &str) -> io::Result<Response> {
+        self.request(Method::Get, url).send()
+    }
+
+    /// Sends a `POST` request to the given URL, with the given content type
+    /// and body.
+    pub fn post(
+        &mut self,
+        url: &str,
+        content_type: &str,
+        body: impl Into<Vec<u8>>,
+    ) -> io::Result<Response> {
+        self.request(Method::Post, url)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+    }
+
+    fn connect(&mut self, url: &Url) -> io::Result<&mut Connection> {
+        let reusable = matches!(&self.conn, Some(c) if c.host == url.host && c.port == url.port);
+        if !reusable {
+            self.conn = None;
+            let addr = (url.host, url.port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| ax_err_type!(NotFound, "http: host not found"))?;
+            debug!("http: connecting to {} ({})", url.host, addr);
+            self.conn = Some(Connection {
+                host: url.host.to_string(),
+                port: url.port,
+                reader: BufReader::new(TcpStream::connect(addr)?),
+            });
+        }
+        Ok(self.conn.as_mut().unwrap())
+    }
+
+    fn send(&mut self, url: &str, mut req: Request) -> io::Result<Response> {
+        let url = Url::parse(url)?;
+        req.set_target(url.target);
+        if !req.headers().contains("Host") {
+            req.headers_mut().insert("Host", &url.authority());
+        }
+
+        let reused = matches!(&self.conn, Some(c) if c.host == url.host && c.port == url.port);
+        let resp = match self.exchange(&url, &req) {
+            // the server may have closed an idle persistent connection
+            Err(_) if reused => {
+                self.conn = None;
+                self.exchange(&url, &req)
+            }
+            res => res,
+        };
+        match resp {
+            Ok(resp) => {
+                if !resp.keep_alive() || !req.keep_alive() {
+                    self.conn = None;
+                }
+                Ok(resp)
+            }
+            Err(e) => {
+                self.conn = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn exchange(&mut self, url: &Url, req: &Request) -> io::Result<Response> {
+        let conn = self.connect(url)?;
+        req.write_to(conn.reader.get_mut())?;
+        Response::read_from(&mut conn.reader, req.method())
+    }
+}
+
+/// A builder of a request sent by [`Client`].
+pub struct RequestBuilder<'a> {
+    client: &'a mut Client,
+    url: String,
+    req: Request,
+}
+
+impl RequestBuilder<'_> {
+    /// Sets a header field, replacing any existing field with the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.req.headers_mut().insert(name, value);
+        self
+    }
+
+    /// Sets the request body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.req.set_body(body.into());
+        self
+    }
+
+    /// Sends the request and waits for the response.
+    pub fn send(self) -> io::Result<Response> {
+        self.client.send(&self.url, self.req)
+    }
+}
+
+/// Sends a `GET` request to the given URL on a new connection.
+pub fn get(url: &str) -> io::Result<Response> {
+    Client::new()
+        .request(Method::Get, url)
+        .header("Connection", "close")
+        .send()
+}
+
+/// Sends a `POST` request to the given URL on a new connection.
+pub fn post(url: &str, content_type: &str, body: impl Into<Vec<u8>>) -> io::Result<Response> {
+    Client::new()
+        .request(Method::Post, url)
+        .header("Content-Type", content_type)
+        .header("Connection", "close")
+        .body(body)
+        .send()
+}
@@ -0,0 +1,94 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::ax_err_type;
+
+use crate::io;
+
+/// A list of HTTP header fields.
+///
+/// Field names are matched case-insensitively, and the insertion order is
+/// preserved when the headers are written out.
+#[derive(Clone, Debug, Default)]
+pub struct Headers {
+    fields: Vec<(String, String)>,
+}
+
+impl Headers {
+    /// Creates an empty header list.
+    pub const fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Returns the number of header fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether the header list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns the value of the first field with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether a field with the given name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets a field, replacing all existing fields with the same name.
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.remove(name);
+        self.append(name, value);
+    }
+
+    /// Appends a field, keeping existing fields with the same name.
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.fields.push((name.to_string(), value.to_string()));
+    }
+
+    /// Removes all fields with the given name.
+    pub fn remove(&mut self, name: &str) {
+        self.fields.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    }
+
+    /// Returns an iterator over `(name, value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Returns the parsed `Content-Length` field, if present.
+    pub fn content_length(&self) -> io::Result<Option<usize>> {
+        match self.get("Content-Length") {
+            Some(len) => len
+                .parse()
+                .map(Some)
+                .map_err(|_| ax_err_type!(InvalidData, "http: invalid Content-Length")),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the body uses chunked transfer encoding.
+    pub fn is_chunked(&self) -> bool {
+        self.get("Transfer-Encoding").map_or(false, |te| {
+            te.rsplit(',')
+                .next()
+                .map_or(false, |last| last.trim().eq_ignore_ascii_case("chunked"))
+        })
+    }
+
+    /// Whether the `Connection` field contains the given token (e.g. `close`
+    /// or `keep-alive`).
+    pub(super) fn connection_has(&self, token: &str) -> bool {
+        self.get("Connection").map_or(false, |conn| {
+            conn.split(',')
+                .any(|t| t.trim().eq_ignore_ascii_case(token))
+        })
+    }
+}
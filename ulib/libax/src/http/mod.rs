@@ -0,0 +1,270 @@
+//! A minimal HTTP/1.1 server and client library.
+//!
+//! It is built on top of [`TcpStream`](crate::net::TcpStream) and supports:
+//!
+//! - Parsing requests and responses (request line, status line, headers and
+//!   bodies delimited by `Content-Length` or chunked transfer encoding).
+//! - Building responses and requests with a builder-style API.
+//! - Persistent connections (keep-alive) on both the server and client sides.
+//! - Dispatching requests to handlers with a simple [`Router`].
+//! - A small blocking [`Client`] with [`get`] and [`post`] shortcuts.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use libax::http::{Method, Response, Router, Server, StatusCode};
+//!
+//! let mut router = Router::new();
+//! router.route(Method::Get, "/", |_req| {
+//!     Response::new(StatusCode::OK)
+//!         .header("Content-Type", "text/plain")
+//!         .body("Hello, ArceOS!")
+//! });
+//! Server::bind("10.0.2.15:5555").unwrap().serve(router).unwrap();
+//! ```
+
+mod chunked;
+mod client;
+mod headers;
+mod request;
+mod response;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+use core::fmt;
+
+use crate::io::{self, prelude::*};
+use alloc::{string::String, vec::Vec};
+use axerrno::{ax_err, ax_err_type};
+
+pub use self::chunked::{ChunkedReader, ChunkedWriter};
+pub use self::client::{get, post, Client, RequestBuilder};
+pub use self::headers::Headers;
+pub use self::request::Request;
+pub use self::response::{Response, StatusCode};
+pub use self::server::{serve_connection, Handler, Router, Server};
+
+/// Maximum length of a request line, a status line, or a single header line.
+const MAX_LINE_LEN: usize = 8 * 1024;
+/// Maximum number of header fields in a message.
+const MAX_HEADERS: usize = 64;
+/// Maximum size of a message body that will be read into memory.
+const MAX_BODY_LEN: usize = 16 * 1024 * 1024;
+
+/// HTTP request methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `HEAD`
+    Head,
+    /// `POST`
+    Post,
+    /// `PUT`
+    Put,
+    /// `DELETE`
+    Delete,
+    /// `OPTIONS`
+    Options,
+    /// `PATCH`
+    Patch,
+}
+
+impl Method {
+    /// Returns the method name as it appears in the request line.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Options => "OPTIONS",
+            Self::Patch => "PATCH",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "GET" => Self::Get,
+            "HEAD" => Self::Head,
+            "POST" => Self::Post,
+            "PUT" => Self::Put,
+            "DELETE" => Self::Delete,
+            "OPTIONS" => Self::Options,
+            "PATCH" => Self::Patch,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// HTTP protocol versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    /// `HTTP/1.0`
+    Http10,
+    /// `HTTP/1.1`
+    Http11,
+}
+
+impl Version {
+    /// Returns the version string, e.g. `HTTP/1.1`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http10 => "HTTP/1.0",
+            Self::Http11 => "HTTP/1.1",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "HTTP/1.0" => Some(Self::Http10),
+            "HTTP/1.1" => Some(Self::Http11),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Reads a line terminated by `\n` from `reader`, with the trailing `\r\n` or
+/// `\n` stripped.
+///
+/// Returns `Ok(None)` if EOF is reached before any byte is read.
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    loop {
+        let (done, used) = {
+            let available = match reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(io::Error::WouldBlock) => {
+                    crate::thread::yield_now();
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                return ax_err!(UnexpectedEof, "http: unterminated line");
+            }
+            match available.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    line.extend_from_slice(&available[..i]);
+                    (true, i + 1)
+                }
+                None => {
+                    line.extend_from_slice(available);
+                    (false, available.len())
+                }
+            }
+        };
+        reader.consume(used);
+        if line.len() > MAX_LINE_LEN {
+            return ax_err!(InvalidData, "http: line too long");
+        }
+        if done {
+            break;
+        }
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| ax_err_type!(InvalidData, "http: line is not valid UTF-8"))
+}
+
+/// Reads header lines until an empty line is encountered.
+fn read_headers<R: BufRead>(reader: &mut R) -> io::Result<Headers> {
+    let mut headers = Headers::new();
+    loop {
+        let line = read_line(reader)?
+            .ok_or_else(|| ax_err_type!(UnexpectedEof, "http: incomplete headers"))?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        if headers.len() >= MAX_HEADERS {
+            return ax_err!(InvalidData, "http: too many headers");
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| ax_err_type!(InvalidData, "http: malformed header"))?;
+        headers.append(name.trim(), value.trim());
+    }
+}
+
+/// Reads a message body according to the framing described by `headers`.
+///
+/// If neither `Transfer-Encoding: chunked` nor `Content-Length` is present,
+/// the body is read until EOF when `read_to_eof` is set, or is empty otherwise.
+///
+/// Bodies larger than [`MAX_BODY_LEN`] are rejected before they are fully
+/// read.
+fn read_body<R: BufRead>(
+    reader: &mut R,
+    headers: &Headers,
+    read_to_eof: bool,
+) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    if headers.is_chunked() {
+        read_to_end_limited(&mut ChunkedReader::new(reader), &mut body)?;
+    } else if let Some(len) = headers.content_length()? {
+        if len > MAX_BODY_LEN {
+            return ax_err!(InvalidData, "http: body too large");
+        }
+        body.resize(len, 0);
+        reader.read_exact(&mut body)?;
+    } else if read_to_eof {
+        read_to_end_limited(reader, &mut body)?;
+    }
+    Ok(body)
+}
+
+/// Reads all bytes until EOF into `body`, failing as soon as it grows larger
+/// than [`MAX_BODY_LEN`].
+fn read_to_end_limited<R: Read>(reader: &mut R, body: &mut Vec<u8>) -> io::Result {
+    let mut buf = [0; 4096];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        if body.len() + n > MAX_BODY_LEN {
+            return ax_err!(InvalidData, "http: body too large");
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+}
+
+/// Writes the header fields and the terminating empty line.
+fn write_headers<W: Write>(writer: &mut W, headers: &Headers) -> io::Result {
+    for (name, value) in headers.iter() {
+        write!(writer, "{}: {}\r\n", name, value)?;
+    }
+    writer.write_all(b"\r\n")
+}
+
+/// Writes `body`, using chunked encoding if `chunked` is set.
+fn write_body<W: Write>(writer: &mut W, body: &[u8], chunked: bool) -> io::Result {
+    if chunked {
+        let mut w = ChunkedWriter::new(writer);
+        w.write_all(body)?;
+        w.finish()
+    } else {
+        writer.write_all(body)
+    }
+}
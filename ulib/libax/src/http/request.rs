@@ -0,0 +1,157 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::ax_err_type;
+
+use super::{Headers, Method, Version};
+use crate::io::{self, prelude::*};
+
+/// An HTTP request.
+#[derive(Clone, Debug)]
+pub struct Request {
+    method: Method,
+    target: String,
+    version: Version,
+    headers: Headers,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Creates a new `HTTP/1.1` request with an empty body.
+    ///
+    /// `target` is the request target in origin form, e.g. `/index.html?a=b`.
+    pub fn new(method: Method, target: &str) -> Self {
+        Self {
+            method,
+            target: target.to_string(),
+            version: Version::Http11,
+            headers: Headers::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Returns the request method.
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Returns the full request target, including the query string.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Returns the path component of the request target.
+    pub fn path(&self) -> &str {
+        self.target.split_once('?').map_or(&self.target, |(p, _)| p)
+    }
+
+    /// Returns the query string (without the leading `?`), if any.
+    pub fn query(&self) -> Option<&str> {
+        self.target.split_once('?').map(|(_, q)| q)
+    }
+
+    pub(super) fn set_target(&mut self, target: &str) {
+        self.target = target.to_string();
+    }
+
+    /// Returns the protocol version.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the header fields.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the mutable header fields.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Returns the value of the given header field.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// Returns the request body.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the request body as a string, if it is valid UTF-8.
+    pub fn body_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.body).ok()
+    }
+
+    /// Sets the request body.
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    /// Whether the connection should be kept open after this request.
+    ///
+    /// `HTTP/1.1` connections are persistent unless `Connection: close` is
+    /// given, while `HTTP/1.0` requires an explicit `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        match self.version {
+            Version::Http11 => !self.headers.connection_has("close"),
+            Version::Http10 => self.headers.connection_has("keep-alive"),
+        }
+    }
+
+    /// Reads and parses a request from `reader`.
+    ///
+    /// Returns `Ok(None)` if the peer closed the connection before sending
+    /// anything, which is the normal end of a persistent connection.
+    pub fn read_from<R: BufRead>(reader: &mut R) -> io::Result<Option<Self>> {
+        let line = loop {
+            match super::read_line(reader)? {
+                // RFC 9112 allows ignoring empty lines before the request line
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Ok(None),
+            }
+        };
+
+        let mut parts = line.split_ascii_whitespace();
+        let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(m), Some(t), Some(v)) if parts.next().is_none() => (m, t, v),
+            _ => return Err(ax_err_type!(InvalidData, "http: malformed request line")),
+        };
+        let method = Method::parse(method)
+            .ok_or_else(|| ax_err_type!(Unsupported, "http: unknown method"))?;
+        let version = Version::parse(version)
+            .ok_or_else(|| ax_err_type!(Unsupported, "http: unsupported version"))?;
+
+        let headers = super::read_headers(reader)?;
+        let body = super::read_body(reader, &headers, false)?;
+        Ok(Some(Self {
+            method,
+            target: target.to_string(),
+            version,
+            headers,
+            body,
+        }))
+    }
+
+    /// Serializes the request to `writer`.
+    ///
+    /// `Content-Length` is filled in automatically unless the body is sent
+    /// with chunked transfer encoding.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result {
+        let chunked = self.headers.is_chunked();
+        write!(
+            writer,
+            "{} {} {}\r\n",
+            self.method, self.target, self.version
+        )?;
+        let mut headers = self.headers.clone();
+        let has_body = matches!(self.method, Method::Post | Method::Put | Method::Patch);
+        if !chunked && (has_body || !self.body.is_empty()) {
+            headers.insert("Content-Length", &self.body.len().to_string());
+        }
+        super::write_headers(writer, &headers)?;
+        super::write_body(writer, &self.body, chunked)?;
+        writer.flush()
+    }
+}
@@ -0,0 +1,271 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::ax_err_type;
+use core::fmt;
+
+use super::{Headers, Method, Version};
+use crate::io::{self, prelude::*};
+
+/// An HTTP status code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatusCode(pub u16);
+
+impl StatusCode {
+    /// 200 OK
+    pub const OK: Self = Self(200);
+    /// 201 Created
+    pub const CREATED: Self = Self(201);
+    /// 204 No Content
+    pub const NO_CONTENT: Self = Self(204);
+    /// 301 Moved Permanently
+    pub const MOVED_PERMANENTLY: Self = Self(301);
+    /// 302 Found
+    pub const FOUND: Self = Self(302);
+    /// 304 Not Modified
+    pub const NOT_MODIFIED: Self = Self(304);
+    /// 400 Bad Request
+    pub const BAD_REQUEST: Self = Self(400);
+    /// 403 Forbidden
+    pub const FORBIDDEN: Self = Self(403);
+    /// 404 Not Found
+    pub const NOT_FOUND: Self = Self(404);
+    /// 405 Method Not Allowed
+    pub const METHOD_NOT_ALLOWED: Self = Self(405);
+    /// 413 Content Too Large
+    pub const PAYLOAD_TOO_LARGE: Self = Self(413);
+    /// 500 Internal Server Error
+    pub const INTERNAL_SERVER_ERROR: Self = Self(500);
+    /// 501 Not Implemented
+    pub const NOT_IMPLEMENTED: Self = Self(501);
+    /// 505 HTTP Version Not Supported
+    pub const HTTP_VERSION_NOT_SUPPORTED: Self = Self(505);
+
+    /// Returns the standard reason phrase of the status code, or an empty
+    /// string if it is unknown.
+    pub const fn reason(&self) -> &'static str {
+        match self.0 {
+            100 => "Continue",
+            101 => "Switching Protocols",
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            204 => "No Content",
+            206 => "Partial Content",
+            301 => "Moved Permanently",
+            302 => "Found",
+            303 => "See Other",
+            304 => "Not Modified",
+            307 => "Temporary Redirect",
+            308 => "Permanent Redirect",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Content Too Large",
+            414 => "URI Too Long",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            505 => "HTTP Version Not Supported",
+            _ => "",
+        }
+    }
+
+    /// Whether the status code is in the range 200-299.
+    pub const fn is_success(&self) -> bool {
+        self.0 >= 200 && self.0 < 300
+    }
+
+    /// Whether a response with this status code never carries a body.
+    const fn is_bodiless(&self) -> bool {
+        (self.0 >= 100 && self.0 < 200) || self.0 == 204 || self.0 == 304
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, self.reason())
+    }
+}
+
+/// An HTTP response.
+///
+/// It is built with a builder-style API:
+///
+/// ```no_run
+/// # use libax::http::{Response, StatusCode};
+/// let resp = Response::new(StatusCode::OK)
+///     .header("Content-Type", "text/html")
+///     .body("<h1>Hello</h1>");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Response {
+    status: StatusCode,
+    version: Version,
+    reason: String,
+    headers: Headers,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Creates a new `HTTP/1.1` response with the given status and an empty
+    /// body.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            version: Version::Http11,
+            reason: status.reason().to_string(),
+            headers: Headers::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Sets a header field, replacing any existing field with the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the response body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sends the body with `Transfer-Encoding: chunked` instead of
+    /// `Content-Length`.
+    pub fn chunked(self) -> Self {
+        self.header("Transfer-Encoding", "chunked")
+    }
+
+    /// Returns the status code.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the reason phrase from the status line.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Returns the protocol version.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the header fields.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the mutable header fields.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Returns the value of the given header field.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// Returns the response body.
+    pub fn body_bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the response body as a string, if it is valid UTF-8.
+    pub fn body_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.body).ok()
+    }
+
+    /// Consumes the response and returns its body.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// Whether the connection can be reused after this response.
+    pub fn keep_alive(&self) -> bool {
+        match self.version {
+            Version::Http11 => !self.headers.connection_has("close"),
+            Version::Http10 => self.headers.connection_has("keep-alive"),
+        }
+    }
+
+    /// Reads and parses a response from `reader`.
+    ///
+    /// `method` is the method of the corresponding request, since responses
+    /// to `HEAD` requests never carry a body.
+    pub fn read_from<R: BufRead>(reader: &mut R, method: Method) -> io::Result<Self> {
+        let line = super::read_line(reader)?
+            .ok_or_else(|| ax_err_type!(UnexpectedEof, "http: connection closed"))?;
+        let (version, rest) = line
+            .split_once(' ')
+            .ok_or_else(|| ax_err_type!(InvalidData, "http: malformed status line"))?;
+        let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+        let version = Version::parse(version)
+            .ok_or_else(|| ax_err_type!(Unsupported, "http: unsupported version"))?;
+        let status = code
+            .parse()
+            .map(StatusCode)
+            .map_err(|_| ax_err_type!(InvalidData, "http: invalid status code"))?;
+
+        let headers = super::read_headers(reader)?;
+        let body = if method == Method::Head || status.is_bodiless() {
+            Vec::new()
+        } else {
+            // without explicit framing, the body is delimited by closing the connection
+            super::read_body(reader, &headers, true)?
+        };
+        Ok(Self {
+            status,
+            version,
+            reason: reason.to_string(),
+            headers,
+            body,
+        })
+    }
+
+    /// Serializes the response to `writer`.
+    ///
+    /// `Content-Length` is filled in automatically unless the body is sent
+    /// with chunked transfer encoding. The `Connection` header is set according
+    /// to `keep_alive`. The body is omitted if
+    /// `with_body` is false (e.g. in response to a `HEAD` request).
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        keep_alive: bool,
+        with_body: bool,
+    ) -> io::Result {
+        let with_body = with_body && !self.status.is_bodiless();
+        let chunked = self.headers.is_chunked() && with_body;
+        write!(
+            writer,
+            "{} {} {}\r\n",
+            self.version, self.status.0, self.reason
+        )?;
+
+        let mut headers = self.headers.clone();
+        if !chunked {
+            headers.remove("Transfer-Encoding");
+            if !self.status.is_bodiless() {
+                headers.insert("Content-Length", &self.body.len().to_string());
+            }
+        }
+        // be explicit, as `HTTP/1.0` clients assume `close` by default
+        if keep_alive {
+            headers.insert("Connection", "keep-alive");
+        } else {
+            headers.insert("Connection", "close");
+        }
+        super::write_headers(writer, &headers)?;
+        if with_body {
+            super::write_body(writer, &self.body, chunked)?;
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,211 @@
+use alloc::string::String;
+use alloc::{boxed::Box, vec::Vec};
+use axerrno::ax_err;
+
+use super::{Method, Request, Response, StatusCode};
+use crate::io::{self, BufReader};
+use crate::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+/// A type that produces a [`Response`] for each [`Request`].
+///
+/// It is implemented for all `Fn(&Request) -> Response` closures.
+pub trait Handler: Send + Sync {
+    /// Handles a request.
+    fn handle(&self, req: &Request) -> Response;
+}
+
+impl<F> Handler for F
+where
+    F: Fn(&Request) -> Response + Send + Sync,
+{
+    fn handle(&self, req: &Request) -> Response {
+        self(req)
+    }
+}
+
+struct Route {
+    method: Method,
+    pattern: String,
+    handler: Box<dyn Handler>,
+}
+
+impl Route {
+    /// A pattern matches the path exactly, or if it ends with `/*`, matches
+    /// every path under that prefix.
+    fn matches_path(&self, path: &str) -> bool {
+        match self.pattern.strip_suffix("/*") {
+            Some(prefix) => {
+                path == prefix
+                    || (path.starts_with(prefix) && path[prefix.len()..].starts_with('/'))
+            }
+            None => path == self.pattern,
+        }
+    }
+}
+
+/// A request router that dispatches requests to handlers by method and path.
+///
+/// Routes are matched in the order they are added. A `HEAD` request is served
+/// by the `GET` handler of the same path if no `HEAD` handler is registered.
+/// Requests that match no route get `404 Not Found`, and requests that only
+/// match the path get `405 Method Not Allowed`.
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Option<Box<dyn Handler>>,
+}
+
+impl Router {
+    /// Creates an empty router.
+    pub const fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Adds a route.
+    ///
+    /// `pattern` is either an exact path such as `/index.html`, or a prefix
+    /// ending with `/*` such as `/static/*`.
+    pub fn route<H: Handler + 'static>(
+        &mut self,
+        method: Method,
+        pattern: &str,
+        handler: H,
+    ) -> &mut Self {
+        self.routes.push(Route {
+            method,
+            pattern: pattern.into(),
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Sets the handler for requests that match no route, instead of replying
+    /// with `404 Not Found`.
+    pub fn fallback<H: Handler + 'static>(&mut self, handler: H) -> &mut Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    fn find(&self, method: Method, path: &str) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|r| r.method == method && r.matches_path(path))
+    }
+}
+
+impl Handler for Router {
+    fn handle(&self, req: &Request) -> Response {
+        let path = req.path();
+        let route = self.find(req.method(), path).or_else(|| {
+            if req.method() == Method::Head {
+                self.find(Method::Get, path)
+            } else {
+                None
+            }
+        });
+        if let Some(route) = route {
+            return route.handler.handle(req);
+        }
+
+        let allowed = self
+            .routes
+            .iter()
+            .filter(|r| r.matches_path(path))
+            .map(|r| r.method.as_str())
+            .collect::<Vec<_>>();
+        if !allowed.is_empty() {
+            Response::new(StatusCode::METHOD_NOT_ALLOWED).header("Allow", &allowed.join(", "))
+        } else if let Some(fallback) = &self.fallback {
+            fallback.handle(req)
+        } else {
+            Response::new(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// Serves requests on an accepted connection until the peer closes it, or
+/// either side asks for `Connection: close`.
+///
+/// A malformed request is answered with `400 Bad Request` before the
+/// connection is closed.
+pub fn serve_connection<H: Handler + ?Sized>(stream: TcpStream, handler: &H) -> io::Result {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let req = match Request::read_from(&mut reader) {
+            Ok(Some(req)) => req,
+            Ok(None) => break,
+            Err(e) => {
+                let status = match e {
+                    io::Error::Unsupported => StatusCode::NOT_IMPLEMENTED,
+                    _ => StatusCode::BAD_REQUEST,
+                };
+                Response::new(status).write_to(reader.get_mut(), false, true)?;
+                return Err(e);
+            }
+        };
+        debug!("http: {} {}", req.method(), req.target());
+
+        let resp = handler.handle(&req);
+        let keep_alive = req.keep_alive() && resp.keep_alive();
+        resp.write_to(reader.get_mut(), keep_alive, req.method() != Method::Head)?;
+        if !keep_alive {
+            break;
+        }
+    }
+    reader.get_ref().shutdown()
+}
+
+/// An HTTP server listening on a TCP port.
+pub struct Server {
+    listener: TcpListener,
+}
+
+impl Server {
+    /// Creates a server listening on the given address.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addr = match addr.to_socket_addrs()?.next() {
+            Some(addr) => addr,
+            None => return ax_err!(InvalidInput, "http: no address to bind"),
+        };
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    /// Returns the local address that this server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections forever, serving each of them with `handler`.
+    ///
+    /// With the `multitask` feature, each connection is served in a new
+    /// thread. Otherwise connections are served one by one.
+    ///
+    /// It only returns if accepting a connection fails.
+    pub fn serve<H: Handler + 'static>(mut self, handler: H) -> io::Result {
+        #[cfg(feature = "multitask")]
+        let handler = alloc::sync::Arc::new(handler);
+        loop {
+            let (stream, addr) = self.listener.accept()?;
+            debug!("http: new connection from {}", addr);
+
+            #[cfg(feature = "multitask")]
+            {
+                let handler = handler.clone();
+                crate::thread::spawn(move || {
+                    if let Err(e) = serve_connection(stream, handler.as_ref()) {
+                        warn!("http: connection from {} failed: {:?}", addr, e);
+                    }
+                });
+            }
+            #[cfg(not(feature = "multitask"))]
+            if let Err(e) = serve_connection(stream, &handler) {
+                warn!("http: connection from {} failed: {:?}", addr, e);
+            }
+        }
+    }
+}
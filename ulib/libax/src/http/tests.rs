@@ -0,0 +1,194 @@
+use alloc::vec::Vec;
+
+use super::{Method, Request, Response, StatusCode, Version, MAX_BODY_LEN};
+use crate::io::{self, BufReader, Read};
+
+fn parse_request(data: &[u8]) -> io::Result<Option<Request>> {
+    Request::read_from(&mut BufReader::new(data))
+}
+
+/// Sends `head` followed by an endless stream of `a`.
+struct EndlessBody<'a> {
+    head: &'a [u8],
+}
+
+impl Read for EndlessBody<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.head.is_empty() {
+            return self.head.read(buf);
+        }
+        buf.fill(b'a');
+        Ok(buf.len())
+    }
+}
+
+#[test]
+fn test_request_line() {
+    let req = parse_request(b"\r\nGET /index.html?a=b HTTP/1.1\r\n\r\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(req.method(), Method::Get);
+    assert_eq!(req.target(), "/index.html?a=b");
+    assert_eq!(req.path(), "/index.html");
+    assert_eq!(req.query(), Some("a=b"));
+    assert_eq!(req.version(), Version::Http11);
+    assert!(req.body().is_empty());
+
+    assert!(parse_request(b"").unwrap().is_none());
+    assert_eq!(
+        parse_request(b"GET /\r\n\r\n").unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"GET / HTTP/1.1 extra\r\n\r\n").unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"BREW / HTTP/1.1\r\n\r\n").unwrap_err(),
+        io::Error::Unsupported
+    );
+    assert_eq!(
+        parse_request(b"GET / HTTP/2\r\n\r\n").unwrap_err(),
+        io::Error::Unsupported
+    );
+}
+
+#[test]
+fn test_headers() {
+    let req = parse_request(
+        b"GET / HTTP/1.0\r\nHost: example.com\r\nX-A:  1 \r\nx-a: 2\r\nConnection: Keep-Alive\r\n\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(req.headers().len(), 4);
+    assert_eq!(req.header("host"), Some("example.com"));
+    assert_eq!(req.header("X-A"), Some("1"));
+    assert!(req.keep_alive());
+
+    assert_eq!(
+        parse_request(b"GET / HTTP/1.1\r\nno colon\r\n\r\n").unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"GET / HTTP/1.1\r\nHost: x\r\n").unwrap_err(),
+        io::Error::UnexpectedEof
+    );
+
+    let mut many = Vec::from(&b"GET / HTTP/1.1\r\n"[..]);
+    for _ in 0..=super::MAX_HEADERS {
+        many.extend_from_slice(b"A: b\r\n");
+    }
+    many.extend_from_slice(b"\r\n");
+    assert_eq!(parse_request(&many).unwrap_err(), io::Error::InvalidData);
+
+    let mut long = Vec::from(&b"GET / HTTP/1.1\r\nA: "[..]);
+    long.resize(long.len() + super::MAX_LINE_LEN, b'b');
+    long.extend_from_slice(b"\r\n\r\n");
+    assert_eq!(parse_request(&long).unwrap_err(), io::Error::InvalidData);
+}
+
+#[test]
+fn test_content_length_body() {
+    let mut reader = BufReader::new(
+        &b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /b HTTP/1.1\r\n\r\n"[..],
+    );
+    let req = Request::read_from(&mut reader).unwrap().unwrap();
+    assert_eq!(req.body_str(), Some("hello"));
+    // the next request on the same connection is still intact
+    let req = Request::read_from(&mut reader).unwrap().unwrap();
+    assert_eq!(req.path(), "/b");
+    assert!(Request::read_from(&mut reader).unwrap().is_none());
+
+    assert_eq!(
+        parse_request(b"POST / HTTP/1.1\r\nContent-Length: abc\r\n\r\n").unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").unwrap_err(),
+        io::Error::UnexpectedEof
+    );
+}
+
+#[test]
+fn test_chunked_body() {
+    let req = parse_request(
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+          5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\nTrailer: x\r\n\r\n",
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(req.body_str(), Some("hello world"));
+
+    assert_eq!(
+        parse_request(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabcd\r\n")
+            .unwrap_err(),
+        io::Error::InvalidData
+    );
+    assert_eq!(
+        parse_request(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nab").unwrap_err(),
+        io::Error::UnexpectedEof
+    );
+}
+
+#[test]
+fn test_oversized_body() {
+    let head = alloc::format!(
+        "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+        MAX_BODY_LEN + 1
+    );
+    assert_eq!(
+        parse_request(head.as_bytes()).unwrap_err(),
+        io::Error::InvalidData
+    );
+
+    // a chunked body is rejected while it is being read, not after EOF
+    let mut reader = BufReader::new(EndlessBody {
+        head: b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffff\r\n",
+    });
+    assert_eq!(
+        Request::read_from(&mut reader).unwrap_err(),
+        io::Error::InvalidData
+    );
+
+    // so is a response body delimited by EOF
+    let mut reader = BufReader::new(EndlessBody {
+        head: b"HTTP/1.1 200 OK\r\n\r\n",
+    });
+    assert_eq!(
+        Response::read_from(&mut reader, Method::Get).unwrap_err(),
+        io::Error::InvalidData
+    );
+}
+
+#[test]
+fn test_status_line() {
+    let resp = Response::read_from(
+        &mut BufReader::new(&b"HTTP/1.1 404 Not Found\r\nContent-Length: 3\r\n\r\nabc"[..]),
+        Method::Get,
+    )
+    .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.reason(), "Not Found");
+    assert_eq!(resp.body_bytes(), b"abc");
+
+    // responses to HEAD requests never have a body
+    let resp = Response::read_from(
+        &mut BufReader::new(&b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n"[..]),
+        Method::Head,
+    )
+    .unwrap();
+    assert!(resp.body_bytes().is_empty());
+
+    assert_eq!(
+        Response::read_from(
+            &mut BufReader::new(&b"HTTP/1.1 abc OK\r\n\r\n"[..]),
+            Method::Get
+        )
+        .unwrap_err(),
+        io::Error::InvalidData
+    );
+}
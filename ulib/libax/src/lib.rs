@@ -9,7 +9,9 @@
 //!     - `fp_simd`: Enable floating point and SIMD support.
 //! - Memory
 //!     - `alloc`: Enable dynamic memory allocation.
-//!     - `paging`: Enable page table manipulation.
+//!     - `page-alloc-buddy`: Use the buddy page allocator, which supports more
+//!       than 4 GB memory.
+//!     - `paging`: Enable page table manipulation, and `mmap` in C bindings.
 //! - Interrupts:
 //!     - `irq`: Enable interrupt handling support. This feature is required for
 //!       some multitask operations, such as [`sync::WaitQueue::wait_timeout`] and
@@ -18,8 +20,14 @@
 //!     - `multitask`: Enable multi-threading support.
 //!     - `sched_fifo`: Use the FIFO cooperative scheduler.
 //!     - `sched_rr`: Use the Round-robin preemptive scheduler.
+//!     - `uspace`: Enable user processes that run ELF executables in user
+//!       mode.
 //! - Device and upperlayer stack
 //!     - `fs`: Enable file system support.
+//!     - `ext4fs`: Use ext2 (or read-only ext4) instead of FAT as the root
+//!       file system.
+//!     - `initramfs`: Unpack the archive specified by `AX_INITRAMFS` at build
+//!       time into a RAM file system, mounted on `/` if there is no disk.
 //!     - `net`: Enable networking support.
 //!     - `display`: Enable graphics support.
 //!     - `bus-mmio`: Use device tree to probe all MMIO devices.
@@ -75,8 +83,14 @@ pub mod fs;
 #[cfg(feature = "net")]
 pub mod net;
 
+#[cfg(feature = "net")]
+pub mod http;
+
 #[cfg(feature = "display")]
 pub mod display;
 
+#[cfg(feature = "uspace")]
+pub mod process;
+
 #[cfg(feature = "cbindings")]
 pub mod cbindings;
@@ -0,0 +1,4 @@
+//! User processes, which run statically linked ELF executables in user mode
+//! with their own address spaces.
+
+pub use axprocess::{current, spawn, Process};
@@ -106,6 +106,11 @@ pub fn yield_now() {
 /// For single-threaded configuration (`multitask` feature is disabled),
 /// it directly terminates the main thread and shutdown.
 pub fn exit(exit_code: i32) -> ! {
+    // the system shuts down if it's the main thread
+    #[cfg(feature = "fs")]
+    if axtask::current().is_init() {
+        axfs::api::sync().ok();
+    }
     axtask::exit(exit_code);
 }
 
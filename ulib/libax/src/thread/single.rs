@@ -19,6 +19,8 @@ pub fn yield_now() {
 /// it directly terminates the main thread and shutdown.
 pub fn exit(exit_code: i32) -> ! {
     axlog::debug!("main task exited: exit_code={}", exit_code);
+    #[cfg(feature = "fs")]
+    axfs::api::sync().ok();
     axhal::misc::terminate()
 }
 
@@ -40,3 +40,121 @@ impl Instant {
         Instant::now().0 - self.0
     }
 }
+
+/// A measurement of the system clock (wall clock), useful for talking to
+/// external entities like the file system or other processes.
+///
+/// It is initialized from the real-time clock of the platform, and may be
+/// corrected later (e.g. by [`sync_with_ntp`]). So unlike [`Instant`], it is
+/// not guaranteed to be monotonic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemTime(Duration);
+
+/// An anchor in time, defined as "1970-01-01 00:00:00 UTC".
+pub const UNIX_EPOCH: SystemTime = SystemTime(Duration::ZERO);
+
+/// An error returned from the `duration_since` and `elapsed` methods on
+/// [`SystemTime`], used to learn how far in the opposite direction a system
+/// time lies.
+#[derive(Clone, Debug)]
+pub struct SystemTimeError(Duration);
+
+impl SystemTimeError {
+    /// Returns the positive duration which represents how far forward the
+    /// second system time was from the first.
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl core::fmt::Display for SystemTimeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "second time provided was later than self")
+    }
+}
+
+impl SystemTime {
+    /// An anchor in time, defined as "1970-01-01 00:00:00 UTC".
+    pub const UNIX_EPOCH: SystemTime = UNIX_EPOCH;
+
+    /// Returns the system time corresponding to "now".
+    pub fn now() -> SystemTime {
+        SystemTime(axhal::time::wall_time())
+    }
+
+    /// Returns the amount of time elapsed from an earlier point in time.
+    ///
+    /// Returns an [`Err`] if `earlier` is later than `self`, and the error
+    /// contains how far from `self` the time is.
+    pub fn duration_since(&self, earlier: SystemTime) -> Result<Duration, SystemTimeError> {
+        self.0
+            .checked_sub(earlier.0)
+            .ok_or_else(|| SystemTimeError(earlier.0 - self.0))
+    }
+
+    /// Returns the difference between the clock time when this system time
+    /// was created, and the current clock time.
+    pub fn elapsed(&self) -> Result<Duration, SystemTimeError> {
+        SystemTime::now().duration_since(*self)
+    }
+
+    /// Returns `Some(t)` where `t` is the time `self + duration` if `t` can be
+    /// represented, `None` otherwise.
+    pub fn checked_add(&self, duration: Duration) -> Option<SystemTime> {
+        self.0.checked_add(duration).map(SystemTime)
+    }
+
+    /// Returns `Some(t)` where `t` is the time `self - duration` if `t` can be
+    /// represented (i.e., not before the UNIX epoch), `None` otherwise.
+    pub fn checked_sub(&self, duration: Duration) -> Option<SystemTime> {
+        self.0.checked_sub(duration).map(SystemTime)
+    }
+}
+
+impl core::ops::Add<Duration> for SystemTime {
+    type Output = SystemTime;
+
+    fn add(self, dur: Duration) -> SystemTime {
+        self.checked_add(dur)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl core::ops::AddAssign<Duration> for SystemTime {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+impl core::ops::Sub<Duration> for SystemTime {
+    type Output = SystemTime;
+
+    fn sub(self, dur: Duration) -> SystemTime {
+        self.checked_sub(dur)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl core::ops::SubAssign<Duration> for SystemTime {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+/// Corrects the system time by querying the given NTP server.
+///
+/// `server` can be a domain name or an IP address, with an optional port
+/// (123 by default). Returns the new system time.
+#[cfg(feature = "net")]
+pub fn sync_with_ntp(server: &str) -> crate::io::Result<SystemTime> {
+    use crate::net::ToSocketAddrs;
+    let mut addrs = if server.contains(':') {
+        server.to_socket_addrs()?
+    } else {
+        (server, axnet::NTP_PORT).to_socket_addrs()?
+    };
+    let addr = addrs
+        .next()
+        .ok_or_else(|| axerrno::ax_err_type!(NotFound, "NTP server not found"))?;
+    axnet::sntp_sync(addr).map(SystemTime)
+}